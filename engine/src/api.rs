@@ -10,13 +10,22 @@
 use wasm_bindgen::prelude::*;
 use web_sys::HtmlCanvasElement;
 
-use crate::gpu::device::init_device;
+use crate::chunk::{parse_chunk, MycosChunk};
+use crate::cpu_ref::CpuEngine;
+use crate::link::{parse_links, validate_links, Link};
+use crate::runner::MAX_ROUNDS;
 
-/// Handle to the engine. Internally stores the WebGPU `Device` and `Queue`.
+/// Handle to the engine, backed by the CPU reference executor.
+///
+/// The wavefront kernels only run batch evaluation
+/// ([`GpuEvaluator`](crate::gpu::eval::GpuEvaluator)), so the handle steps
+/// its system on the CPU even when WebGPU is available.
 #[wasm_bindgen]
 pub struct MycosHandle {
-    device: wgpu::Device,
-    queue: wgpu::Queue,
+    /// The loaded system; `None` until chunks are loaded.
+    engine: Option<CpuEngine>,
+    chunks: Vec<MycosChunk>,
+    links: Vec<Link>,
 }
 
 /// Execution metrics returned from `tick`.
@@ -41,48 +50,107 @@ impl Metrics {
     }
 }
 
-/// Initialize WebGPU and create a new [`MycosHandle`].
+/// Create a new [`MycosHandle`].
 ///
 /// Named `init_engine` instead of `init` to avoid clashing with the
 /// wasm-bindgen generated module initializer which is also called `init`.
 /// The prior name caused the function to be dropped from the JS exports,
 /// leaving the web wrapper unable to obtain a handle at runtime.
+///
+/// The handle runs on the CPU reference executor whether or not the browser
+/// has WebGPU.
 #[wasm_bindgen]
 pub async fn init_engine(_canvas: Option<HtmlCanvasElement>) -> Result<MycosHandle, JsValue> {
     // For now the canvas is unused as the engine only performs compute work.
-    let (device, queue) = init_device().await?;
-    Ok(MycosHandle { device, queue })
+    Ok(MycosHandle {
+        engine: None,
+        chunks: Vec::new(),
+        links: Vec::new(),
+    })
+}
+
+fn js_error(e: impl std::fmt::Display) -> JsValue {
+    JsValue::from_str(&e.to_string())
 }
 
 #[wasm_bindgen]
 impl MycosHandle {
-    /// Load chunk binaries into the engine.
-    pub fn load_chunks(&mut self, _chunks: js_sys::Array) {
-        // Placeholder: real implementation will parse and upload chunk data.
+    /// Load chunk binaries into the engine, replacing the loaded system and
+    /// dropping its links.
+    pub fn load_chunks(&mut self, chunks: js_sys::Array) -> Result<(), JsValue> {
+        self.chunks = chunks
+            .iter()
+            .map(|buffer| parse_chunk(&js_sys::Uint8Array::new(&buffer).to_vec()))
+            .collect::<Result<_, _>>()
+            .map_err(js_error)?;
+        self.links.clear();
+        self.reload()
     }
 
-    /// Load link graph binary describing inter-chunk connections.
-    pub fn load_links(&mut self, _links: js_sys::ArrayBuffer) {
-        // Placeholder for future implementation.
+    /// Load link graph binary describing inter-chunk connections between the
+    /// loaded chunks.
+    pub fn load_links(&mut self, links: js_sys::ArrayBuffer) -> Result<(), JsValue> {
+        let links = parse_links(&js_sys::Uint8Array::new(&links).to_vec()).map_err(js_error)?;
+        validate_links(&links, &self.chunks).map_err(js_error)?;
+        self.links = links;
+        self.reload()
     }
 
     /// Set input words for a given chunk.
     ///
     /// `words` is a view into WebAssembly memory, avoiding an extra copy.
-    pub fn set_inputs(&mut self, _chunk_id: u32, _words: js_sys::Uint32Array) {}
+    pub fn set_inputs(&mut self, chunk_id: u32, words: js_sys::Uint32Array) -> Result<(), JsValue> {
+        self.check_chunk(chunk_id)?;
+        if let Some(engine) = &mut self.engine {
+            engine.set_inputs(chunk_id, &words.to_vec());
+        }
+        Ok(())
+    }
 
     /// Execute the engine for up to `max_rounds` wavefront rounds.
-    pub fn tick(&mut self, _max_rounds: Option<u32>) -> Metrics {
-        // Stub metrics; real values will be produced by the GPU pipeline.
-        Metrics {
-            rounds: 0,
-            effects: 0,
+    pub fn tick(&mut self, max_rounds: Option<u32>) -> Metrics {
+        match &mut self.engine {
+            Some(engine) => {
+                let stats = engine.tick(max_rounds.unwrap_or(MAX_ROUNDS));
+                Metrics {
+                    rounds: stats.rounds,
+                    effects: stats.effects,
+                }
+            }
+            // Nothing loaded yet.
+            None => Metrics {
+                rounds: 0,
+                effects: 0,
+            },
         }
     }
 
-    /// Read output words for a given chunk into `out`.
-    pub fn get_outputs(&self, _chunk_id: u32, _out: js_sys::Uint32Array) {}
+    /// Read output words for a given chunk into `out`, as many as fit.
+    pub fn get_outputs(&self, chunk_id: u32, out: js_sys::Uint32Array) -> Result<(), JsValue> {
+        self.check_chunk(chunk_id)?;
+        if let Some(engine) = &self.engine {
+            let words = engine.outputs(chunk_id).unwrap_or_default();
+            let n = words.len().min(out.length() as usize);
+            out.subarray(0, n as u32).copy_from(&words[..n]);
+        }
+        Ok(())
+    }
 
     /// Select the oscillation handling policy.
     pub fn set_policy(&mut self, _mode: &str) {}
 }
+
+impl MycosHandle {
+    /// Rebuild the CPU system from the loaded chunks and links.
+    fn reload(&mut self) -> Result<(), JsValue> {
+        self.engine = Some(CpuEngine::new(self.chunks.clone(), &self.links).map_err(js_error)?);
+        Ok(())
+    }
+
+    fn check_chunk(&self, chunk_id: u32) -> Result<(), JsValue> {
+        match (chunk_id as usize) < self.chunks.len() {
+            true => Ok(()),
+            false => Err(JsValue::from_str(&format!("unknown chunk {chunk_id}"))),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::TaskIdentity;
+use crate::hash::genome_hash;
+use crate::map_elites::EliteArchive;
+use crate::{Genome, HallOfFame, ValidationError};
+
+/// A genome kept by a [`GenomeArchive`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub genome: Genome,
+    /// [`genome_hash`] of `genome`.
+    pub hash: [u8; 32],
+    pub fitness: f32,
+    /// Generation in which the genome reached this fitness.
+    pub generation: u32,
+    /// Task the fitness was measured on, if known.
+    pub task: Option<TaskIdentity>,
+}
+
+/// Interesting genomes collected across runs, deduplicated by structure.
+///
+/// Entries are keyed by [`genome_hash`], so genomes differing only in meta,
+/// innovation numbers or gene order share one entry. That entry keeps the
+/// metadata of the fittest copy offered. On disk an archive is a directory
+/// holding one `<hash>.json` per entry, with the hash in lowercase hex, so
+/// several runs can add to the same directory.
+#[derive(Clone, Default)]
+pub struct GenomeArchive {
+    entries: BTreeMap<[u8; 32], ArchiveEntry>,
+}
+
+impl GenomeArchive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, hash: &[u8; 32]) -> Option<&ArchiveEntry> {
+        self.entries.get(hash)
+    }
+
+    /// Entries in hash order.
+    pub fn entries(&self) -> impl Iterator<Item = &ArchiveEntry> {
+        self.entries.values()
+    }
+
+    /// The fittest entry; the first in hash order among equals.
+    pub fn best(&self) -> Option<&ArchiveEntry> {
+        self.entries()
+            .reduce(|best, e| if e.fitness > best.fitness { e } else { best })
+    }
+
+    /// Add a genome, or update its entry when `fitness` beats the stored one.
+    /// Returns whether anything changed. Fails if the genome does not
+    /// validate, since only valid genomes have a structural hash.
+    pub fn insert(
+        &mut self,
+        genome: &Genome,
+        fitness: f32,
+        generation: u32,
+        task: Option<&TaskIdentity>,
+    ) -> Result<bool, ValidationError> {
+        let hash = genome_hash(genome)?;
+        if self
+            .entries
+            .get(&hash)
+            .is_some_and(|e| e.fitness >= fitness)
+        {
+            return Ok(false);
+        }
+        self.entries.insert(
+            hash,
+            ArchiveEntry {
+                genome: genome.clone(),
+                hash,
+                fitness,
+                generation,
+                task: task.cloned(),
+            },
+        );
+        Ok(true)
+    }
+
+    /// [`insert`](Self::insert) every hall of fame entry, returning how many
+    /// changed the archive.
+    pub fn add_hall_of_fame(
+        &mut self,
+        hof: &HallOfFame,
+        task: Option<&TaskIdentity>,
+    ) -> Result<usize, ValidationError> {
+        let mut changed = 0;
+        for entry in hof.entries() {
+            changed += self.insert(&entry.genome, entry.fitness, entry.generation, task)? as usize;
+        }
+        Ok(changed)
+    }
+
+    /// [`insert`](Self::insert) every MAP-Elites elite, recorded at
+    /// `generation`, returning how many changed the archive.
+    pub fn add_elites(
+        &mut self,
+        elites: &EliteArchive,
+        generation: u32,
+        task: Option<&TaskIdentity>,
+    ) -> Result<usize, ValidationError> {
+        let mut changed = 0;
+        for elite in elites.elites() {
+            changed += self.insert(&elite.genome, elite.fitness, generation, task)? as usize;
+        }
+        Ok(changed)
+    }
+
+    /// Read every `*.json` entry in `dir`; other files are ignored. Fails
+    /// with `InvalidData` when an entry's genome does not validate or does
+    /// not match its stored hash.
+    pub fn load(dir: &Path) -> std::io::Result<Self> {
+        let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        let mut archive = Self::new();
+        for file in fs::read_dir(dir)? {
+            let path = file?.path();
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let entry: ArchiveEntry = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            let hash = genome_hash(&entry.genome)
+                .map_err(|e| invalid(format!("{}: {e}", path.display())))?;
+            if hash != entry.hash {
+                return Err(invalid(format!("{}: hash mismatch", path.display())));
+            }
+            archive.entries.insert(hash, entry);
+        }
+        Ok(archive)
+    }
+
+    /// Write every entry to `dir/<hash>.json`, creating `dir` if needed and
+    /// replacing files already there. [`load`](Self::load) the directory
+    /// first to merge with what other runs stored.
+    pub fn save(&self, dir: &Path) -> std::io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (hash, entry) in &self.entries {
+            fs::write(
+                dir.join(format!("{}.json", hex(hash))),
+                serde_json::to_string(entry)?,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::{ChunkGene, GenomeMeta};
+    use bitvec::prelude::*;
+
+    fn genome(nn: u32, seed: u64) -> Genome {
+        let chunk = ChunkGene::new(
+            0,
+            0,
+            nn,
+            BitVec::new(),
+            BitVec::new(),
+            bitvec![u8, Lsb0; 0; nn as usize],
+            Vec::new(),
+        );
+        Genome::new(vec![chunk], Vec::new(), GenomeMeta::new(seed, "".into())).unwrap()
+    }
+
+    #[test]
+    fn dedups_and_round_trips_through_a_directory() {
+        let task = TaskIdentity::of(&crate::tasks::t00_wire_echo());
+        let mut archive = GenomeArchive::new();
+        assert!(archive.insert(&genome(1, 0), 0.5, 3, Some(&task)).unwrap());
+        // same structure, different meta
+        assert!(!archive.insert(&genome(1, 9), 0.4, 4, None).unwrap());
+        assert!(archive.insert(&genome(1, 9), 0.6, 5, None).unwrap());
+        assert_eq!(archive.len(), 1);
+        assert_eq!(archive.best().unwrap().generation, 5);
+
+        let mut hof = HallOfFame::new(2);
+        hof.offer(&genome(1, 1), 0.7, 6);
+        hof.offer(&genome(2, 0), 0.2, 6);
+        assert_eq!(archive.add_hall_of_fame(&hof, Some(&task)).unwrap(), 2);
+        assert_eq!(archive.len(), 2);
+
+        let mut bad = genome(1, 0);
+        bad.chunks[0].internals_init.clear();
+        assert!(archive.insert(&bad, 1.0, 0, None).is_err());
+
+        let dir = std::env::temp_dir().join("mycos_archive_test");
+        let _ = fs::remove_dir_all(&dir);
+        archive.save(&dir).unwrap();
+        fs::write(dir.join("README"), "not an entry").unwrap();
+        let loaded = GenomeArchive::load(&dir).unwrap();
+        let best = loaded.best().unwrap();
+        assert_eq!(
+            (loaded.len(), best.fitness, best.task.as_ref()),
+            (2, 0.7, Some(&task))
+        );
+
+        let path = dir.join(format!("{}.json", hex(&best.hash)));
+        let mut entry = best.clone();
+        entry.genome = genome(3, 0);
+        fs::write(&path, serde_json::to_string(&entry).unwrap()).unwrap();
+        let err = GenomeArchive::load(&dir).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
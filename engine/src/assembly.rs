@@ -0,0 +1,518 @@
+//! `.myca` text assembly: a line-oriented, reviewable form of a
+//! [`Container`] that compiles to the same chunks, links and embeds as the
+//! binaries.
+//!
+//! One statement per line; `#` starts a comment. Chunk statements apply to
+//! the most recent `chunk`, and chunks are numbered in order from 0:
+//!
+//! ```text
+//! chunk relay                     # optional name
+//! inputs 1
+//! internals 1
+//! outputs 1
+//! set internal 0                  # initially set bits, any number of indices
+//! note free text to the end of the line
+//! hash 00ff…                      # build hash as hex
+//! port input 0 clk
+//! conn input 0 internal 0 on enable [tag 0]
+//! link 0 0 1 0 on enable [tag 0] [delay 2]
+//! embed 0 1 alias gate internal 0 [in 0:0 …] [out 0:1 …]
+//! ```
+//!
+//! Sections are `input`, `internal` and `output`; triggers `on`, `off` and
+//! `toggle`; actions `enable`, `disable` and `toggle`. A `conn` without a tag
+//! takes the next free tag of its source bit and a `link` the next free tag
+//! of its source output, the same as [`ChunkBuilder`]. `link` reads
+//! `from_chunk from_out to_chunk to_in`; `embed` reads `parent child mode`,
+//! then `in parent:child` and `out child:parent` pairs. Names and ports must
+//! not contain whitespace, and notes cannot contain `#`.
+
+use std::fmt::Write;
+
+use crate::builder::ChunkBuilder;
+use crate::chunk::{Action, Connection, Section, Trigger};
+use crate::container::{validate_container, Container, ContainerError};
+use crate::embed::{Embed, IoMode};
+use crate::link::Link;
+
+#[derive(Debug)]
+pub enum AssemblyError {
+    /// Line `line` (1-based) does not parse.
+    Syntax { line: usize, message: String },
+    /// The assembled system fails validation.
+    Invalid(ContainerError),
+}
+
+impl std::fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssemblyError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            AssemblyError::Invalid(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+impl From<ContainerError> for AssemblyError {
+    fn from(e: ContainerError) -> Self {
+        AssemblyError::Invalid(e)
+    }
+}
+
+/// Parse `.myca` text into a validated container.
+pub fn parse_assembly(text: &str) -> Result<Container, AssemblyError> {
+    let mut chunks: Vec<(ChunkBuilder, Option<Vec<u8>>)> = Vec::new();
+    let mut links: Vec<Link> = Vec::new();
+    let mut embeds: Vec<Embed> = Vec::new();
+    for (n, raw) in text.lines().enumerate() {
+        let line = n + 1;
+        let syntax = |message: String| AssemblyError::Syntax { line, message };
+        let code = raw.split('#').next().unwrap_or("");
+        let mut words = code.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let rest: Vec<&str> = words.collect();
+        if keyword == "chunk" {
+            let builder = match rest.as_slice() {
+                [] => ChunkBuilder::new(),
+                [name] => ChunkBuilder::new().name(name),
+                _ => return Err(syntax("expected `chunk [name]`".into())),
+            };
+            chunks.push((builder, None));
+            continue;
+        }
+        if keyword == "link" {
+            links.push(parse_link(&rest, &links).map_err(syntax)?);
+            continue;
+        }
+        if keyword == "embed" {
+            embeds.push(parse_embed(&rest).map_err(syntax)?);
+            continue;
+        }
+        if keyword == "note" {
+            // the note keeps its inner spacing
+            let note = code.trim().strip_prefix("note").unwrap().trim();
+            let (builder, _) = chunks
+                .last_mut()
+                .ok_or_else(|| syntax("`note` outside a chunk".into()))?;
+            *builder = std::mem::take(builder).note(note);
+            continue;
+        }
+        let (builder, hash) = chunks
+            .last_mut()
+            .ok_or_else(|| syntax(format!("`{keyword}` outside a chunk")))?;
+        let b = std::mem::take(builder);
+        *builder = match (keyword, rest.as_slice()) {
+            ("inputs", [n]) => b.inputs(number(n).map_err(syntax)?),
+            ("internals", [n]) => b.internal(number(n).map_err(syntax)?),
+            ("outputs", [n]) => b.outputs(number(n).map_err(syntax)?),
+            ("set", [section, indices @ ..]) => {
+                let section = section_of(section).map_err(syntax)?;
+                let mut b = b;
+                for index in indices {
+                    b = b.set(section, number(index).map_err(syntax)?);
+                }
+                b
+            }
+            ("hash", [hex]) => {
+                *hash = Some(from_hex(hex).map_err(syntax)?);
+                b
+            }
+            ("port", [section, index, name]) => b.port(
+                section_of(section).map_err(syntax)?,
+                number(index).map_err(syntax)?,
+                name,
+            ),
+            ("conn", [fs, fi, ts, ti, trigger, action, tail @ ..]) => {
+                let from = (section_of(fs).map_err(syntax)?, number(fi).map_err(syntax)?);
+                let to = (section_of(ts).map_err(syntax)?, number(ti).map_err(syntax)?);
+                let trigger = trigger_of(trigger).map_err(syntax)?;
+                let action = action_of(action).map_err(syntax)?;
+                match tail {
+                    [] => b.conn(from, to, trigger, action),
+                    ["tag", tag] => {
+                        b.conn_tagged(from, to, trigger, action, number(tag).map_err(syntax)?)
+                    }
+                    _ => return Err(syntax("expected `tag N` after the action".into())),
+                }
+            }
+            _ => return Err(syntax(format!("cannot parse `{}`", code.trim()))),
+        };
+    }
+
+    let mut container = Container {
+        chunks: Vec::with_capacity(chunks.len()),
+        links,
+        embeds,
+    };
+    for (i, (builder, hash)) in chunks.into_iter().enumerate() {
+        let mut chunk = builder.build().map_err(|error| ContainerError::Chunk {
+            index: i as u32,
+            error,
+        })?;
+        chunk.build_hash = hash;
+        container.chunks.push(chunk);
+    }
+    validate_container(&container)?;
+    Ok(container)
+}
+
+/// Render a container as `.myca` text; [`parse_assembly`] reads it back to
+/// the same container. Tags are only written where they differ from the
+/// default the parser would assign.
+pub fn emit_assembly(container: &Container) -> String {
+    let mut out = String::new();
+    for (i, chunk) in container.chunks.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        match &chunk.name {
+            Some(name) => writeln!(out, "chunk {name}"),
+            None => writeln!(out, "chunk"),
+        }
+        .unwrap();
+        writeln!(out, "inputs {}", chunk.input_count).unwrap();
+        writeln!(out, "internals {}", chunk.internal_count).unwrap();
+        writeln!(out, "outputs {}", chunk.output_count).unwrap();
+        for (section, bits, count) in [
+            (Section::Input, &chunk.input_bits, chunk.input_count),
+            (
+                Section::Internal,
+                &chunk.internal_bits,
+                chunk.internal_count,
+            ),
+            (Section::Output, &chunk.output_bits, chunk.output_count),
+        ] {
+            let set: Vec<String> = (0..count)
+                .filter(|&i| bits[(i / 8) as usize] >> (i % 8) & 1 == 1)
+                .map(|i| i.to_string())
+                .collect();
+            if !set.is_empty() {
+                writeln!(out, "set {} {}", section_name(section), set.join(" ")).unwrap();
+            }
+        }
+        if let Some(note) = &chunk.note {
+            writeln!(out, "note {note}").unwrap();
+        }
+        if let Some(hash) = &chunk.build_hash {
+            let hex: String = hash.iter().map(|b| format!("{b:02x}")).collect();
+            writeln!(out, "hash {hex}").unwrap();
+        }
+        for port in &chunk.ports {
+            let section = section_name(port.section);
+            writeln!(out, "port {section} {} {}", port.index, port.name).unwrap();
+        }
+        for (c, conn) in chunk.connections.iter().enumerate() {
+            let default_tag = chunk.connections[..c]
+                .iter()
+                .filter(|p| (p.from_section, p.from_index) == (conn.from_section, conn.from_index))
+                .count() as u32;
+            out.push_str(&conn_statement(conn));
+            if conn.order_tag != default_tag {
+                write!(out, " tag {}", conn.order_tag).unwrap();
+            }
+            out.push('\n');
+        }
+    }
+    if !container.links.is_empty() {
+        out.push('\n');
+    }
+    for (i, link) in container.links.iter().enumerate() {
+        write!(
+            out,
+            "link {} {} {} {} {} {}",
+            link.from_chunk,
+            link.from_out_idx,
+            link.to_chunk,
+            link.to_in_idx,
+            trigger_name(link.trigger),
+            action_name(link.action),
+        )
+        .unwrap();
+        if link.order_tag != next_link_tag(&container.links[..i], link) {
+            write!(out, " tag {}", link.order_tag).unwrap();
+        }
+        if link.delay > 0 {
+            write!(out, " delay {}", link.delay).unwrap();
+        }
+        out.push('\n');
+    }
+    if !container.embeds.is_empty() {
+        out.push('\n');
+    }
+    for embed in &container.embeds {
+        let mode = match embed.io_mode {
+            IoMode::Alias => "alias",
+            IoMode::Copy => "copy",
+        };
+        write!(
+            out,
+            "embed {} {} {mode} gate {} {}",
+            embed.parent_chunk,
+            embed.child_chunk,
+            section_name(embed.gate_section),
+            embed.gate_bit,
+        )
+        .unwrap();
+        for (p, c) in &embed.map_in {
+            write!(out, " in {p}:{c}").unwrap();
+        }
+        for (c, p) in &embed.map_out {
+            write!(out, " out {c}:{p}").unwrap();
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The `conn` statement for `conn`, without its tag.
+pub(crate) fn conn_statement(conn: &Connection) -> String {
+    format!(
+        "conn {} {} {} {} {} {}",
+        section_name(conn.from_section),
+        conn.from_index,
+        section_name(conn.to_section),
+        conn.to_index,
+        trigger_name(conn.trigger),
+        action_name(conn.action),
+    )
+}
+
+fn parse_link(words: &[&str], earlier: &[Link]) -> Result<Link, String> {
+    let [from_chunk, from_out, to_chunk, to_in, trigger, action, tail @ ..] = words else {
+        return Err("expected `link from_chunk from_out to_chunk to_in trigger action`".into());
+    };
+    let mut link = Link {
+        from_chunk: number(from_chunk)?,
+        from_out_idx: number(from_out)?,
+        trigger: trigger_of(trigger)?,
+        action: action_of(action)?,
+        to_chunk: number(to_chunk)?,
+        to_in_idx: number(to_in)?,
+        order_tag: 0,
+        delay: 0,
+    };
+    link.order_tag = next_link_tag(earlier, &link);
+    let mut tail = tail;
+    while let [key, value, more @ ..] = tail {
+        match *key {
+            "tag" => link.order_tag = number(value)?,
+            "delay" => {
+                link.delay = value
+                    .parse()
+                    .map_err(|_| format!("invalid delay `{value}`"))?
+            }
+            _ => return Err(format!("unknown link option `{key}`")),
+        }
+        tail = more;
+    }
+    if !tail.is_empty() {
+        return Err(format!("`{}` is missing a value", tail[0]));
+    }
+    Ok(link)
+}
+
+fn parse_embed(words: &[&str]) -> Result<Embed, String> {
+    let [parent, child, mode, "gate", section, bit, tail @ ..] = words else {
+        return Err("expected `embed parent child mode gate section bit`".into());
+    };
+    let io_mode = match *mode {
+        "alias" => IoMode::Alias,
+        "copy" => IoMode::Copy,
+        _ => return Err(format!("unknown io mode `{mode}`")),
+    };
+    let mut embed = Embed {
+        parent_chunk: number(parent)?,
+        child_chunk: number(child)?,
+        gate_bit: number(bit)?,
+        gate_section: section_of(section)?,
+        io_mode,
+        map_in: Vec::new(),
+        map_out: Vec::new(),
+        gate_prev: false,
+    };
+    let mut tail = tail;
+    while let [key, pair, more @ ..] = tail {
+        let (a, b) = pair
+            .split_once(':')
+            .ok_or_else(|| format!("expected `a:b`, found `{pair}`"))?;
+        let pair = (number(a)?, number(b)?);
+        match *key {
+            "in" => embed.map_in.push(pair),
+            "out" => embed.map_out.push(pair),
+            _ => return Err(format!("unknown embed option `{key}`")),
+        }
+        tail = more;
+    }
+    if !tail.is_empty() {
+        return Err(format!("`{}` is missing a value", tail[0]));
+    }
+    Ok(embed)
+}
+
+/// Default tag of `link`: the number of `earlier` links from the same output.
+fn next_link_tag(earlier: &[Link], link: &Link) -> u32 {
+    earlier
+        .iter()
+        .filter(|l| (l.from_chunk, l.from_out_idx) == (link.from_chunk, link.from_out_idx))
+        .count() as u32
+}
+
+fn number(word: &str) -> Result<u32, String> {
+    word.parse().map_err(|_| format!("invalid number `{word}`"))
+}
+
+fn from_hex(word: &str) -> Result<Vec<u8>, String> {
+    if !word.len().is_multiple_of(2) || !word.is_ascii() {
+        return Err(format!("invalid hex `{word}`"));
+    }
+    (0..word.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&word[i..i + 2], 16).map_err(|_| format!("invalid hex `{word}`"))
+        })
+        .collect()
+}
+
+fn section_of(word: &str) -> Result<Section, String> {
+    match word {
+        "input" => Ok(Section::Input),
+        "internal" => Ok(Section::Internal),
+        "output" => Ok(Section::Output),
+        _ => Err(format!("unknown section `{word}`")),
+    }
+}
+
+pub(crate) fn section_name(section: Section) -> &'static str {
+    match section {
+        Section::Input => "input",
+        Section::Internal => "internal",
+        Section::Output => "output",
+    }
+}
+
+fn trigger_of(word: &str) -> Result<Trigger, String> {
+    match word {
+        "on" => Ok(Trigger::On),
+        "off" => Ok(Trigger::Off),
+        "toggle" => Ok(Trigger::Toggle),
+        _ => Err(format!("unknown trigger `{word}`")),
+    }
+}
+
+pub(crate) fn trigger_name(trigger: Trigger) -> &'static str {
+    match trigger {
+        Trigger::On => "on",
+        Trigger::Off => "off",
+        Trigger::Toggle => "toggle",
+    }
+}
+
+fn action_of(word: &str) -> Result<Action, String> {
+    match word {
+        "enable" => Ok(Action::Enable),
+        "disable" => Ok(Action::Disable),
+        "toggle" => Ok(Action::Toggle),
+        _ => Err(format!("unknown action `{word}`")),
+    }
+}
+
+pub(crate) fn action_name(action: Action) -> &'static str {
+    match action {
+        Action::Enable => "enable",
+        Action::Disable => "disable",
+        Action::Toggle => "toggle",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{encode_chunk, parse_chunk};
+    use crate::container::encode_container;
+    use std::path::PathBuf;
+
+    fn fixtures() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join("fixtures")
+    }
+
+    #[test]
+    fn text_fixture_compiles_to_binary() {
+        let text = std::fs::read_to_string(fixtures().join("tiny_toggle.myca")).unwrap();
+        let assembled = parse_assembly(&text).unwrap();
+        let binary = parse_chunk(&std::fs::read(fixtures().join("tiny_toggle.myc")).unwrap());
+        assert_eq!(
+            encode_chunk(&assembled.chunks[0]),
+            encode_chunk(&binary.unwrap())
+        );
+    }
+
+    #[test]
+    fn system_round_trips() {
+        let text = "\
+chunk parent  # gate owner
+inputs 1
+internals 2
+outputs 1
+set internal 1
+note two  spaces
+hash 0aff
+port input 0 en
+conn input 0 internal 0 on enable
+conn input 0 internal 0 off disable tag 3
+conn internal 1 output 0 toggle toggle
+
+chunk
+inputs 1
+outputs 1
+internals 1
+conn input 0 internal 0 toggle toggle
+conn internal 0 output 0 toggle toggle
+
+link 0 0 1 0 on enable delay 2
+link 0 0 1 0 off disable
+embed 0 1 alias gate input 0 in 1:0 out 0:0
+";
+        let container = parse_assembly(text).unwrap();
+        let parent = &container.chunks[0];
+        assert_eq!(parent.note.as_deref(), Some("two  spaces"));
+        assert_eq!(parent.build_hash.as_deref(), Some(&[0x0a, 0xff][..]));
+        assert_eq!(parent.internal_bits, vec![0b10]);
+        let tags: Vec<u32> = parent.connections.iter().map(|c| c.order_tag).collect();
+        assert_eq!(tags, vec![0, 3, 0]);
+        assert_eq!(
+            container
+                .links
+                .iter()
+                .map(|l| l.order_tag)
+                .collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(container.embeds[0].gate_section, Section::Input);
+
+        let emitted = emit_assembly(&container);
+        let again = parse_assembly(&emitted).unwrap();
+        assert_eq!(encode_container(&again), encode_container(&container));
+        assert_eq!(emit_assembly(&again), emitted);
+
+        assert!(matches!(
+            parse_assembly("inputs 1"),
+            Err(AssemblyError::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse_assembly("chunk\ninputs 1\nconn input 0 output 0 on enable"),
+            Err(AssemblyError::Invalid(ContainerError::Chunk {
+                index: 0,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            parse_assembly("chunk\noutputs 1\nlink 0 0 1 0 on enable"),
+            Err(AssemblyError::Invalid(ContainerError::Link(_)))
+        ));
+    }
+}
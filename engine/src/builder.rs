@@ -0,0 +1,218 @@
+use crate::chunk::{validate_chunk, Action, Connection, Error, MycosChunk, Port, Section, Trigger};
+
+/// Fluent construction of a [`MycosChunk`].
+///
+/// Connections keep the order they are added in, and each one's `order_tag`
+/// is the number of connections already added from the same source bit.
+/// [`build`](Self::build) runs [`validate_chunk`] on the result.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkBuilder {
+    counts: [u32; 3],
+    set: Vec<(Section, u32)>,
+    connections: Vec<Connection>,
+    name: Option<String>,
+    note: Option<String>,
+    ports: Vec<Port>,
+}
+
+impl ChunkBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of Input bits.
+    pub fn inputs(mut self, n: u32) -> Self {
+        self.counts[Section::Input as usize] = n;
+        self
+    }
+
+    /// Number of Internal bits.
+    pub fn internal(mut self, n: u32) -> Self {
+        self.counts[Section::Internal as usize] = n;
+        self
+    }
+
+    /// Number of Output bits.
+    pub fn outputs(mut self, n: u32) -> Self {
+        self.counts[Section::Output as usize] = n;
+        self
+    }
+
+    /// Start with bit `index` of `section` set; bits are clear otherwise.
+    pub fn set(mut self, section: Section, index: u32) -> Self {
+        self.set.push((section, index));
+        self
+    }
+
+    /// Add a connection with the next free order tag of its source.
+    pub fn conn(
+        self,
+        from: (Section, u32),
+        to: (Section, u32),
+        trigger: Trigger,
+        action: Action,
+    ) -> Self {
+        let order_tag = self
+            .connections
+            .iter()
+            .filter(|c| (c.from_section, c.from_index) == from)
+            .count() as u32;
+        self.conn_tagged(from, to, trigger, action, order_tag)
+    }
+
+    /// Add a connection with an explicit order tag.
+    pub fn conn_tagged(
+        mut self,
+        from: (Section, u32),
+        to: (Section, u32),
+        trigger: Trigger,
+        action: Action,
+        order_tag: u32,
+    ) -> Self {
+        self.connections.push(Connection {
+            from_section: from.0,
+            to_section: to.0,
+            trigger,
+            action,
+            from_index: from.1,
+            to_index: to.1,
+            order_tag,
+        });
+        self
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn note(mut self, note: &str) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Name Input or Output bit `index` of `section`.
+    pub fn port(mut self, section: Section, index: u32, name: &str) -> Self {
+        self.ports.push(Port {
+            section,
+            index,
+            name: name.into(),
+        });
+        self
+    }
+
+    /// The finished chunk, or the first [`validate_chunk`] error. An initial
+    /// bit outside its section fails with `BitOutOfRange`.
+    pub fn build(self) -> Result<MycosChunk, Error> {
+        let [ni, nn, no] = self.counts;
+        let mut bits = [ni, nn, no].map(|n| vec![0u8; n.div_ceil(8) as usize]);
+        for (section, index) in self.set {
+            if index >= self.counts[section as usize] {
+                return Err(Error::BitOutOfRange { section, index });
+            }
+            bits[section as usize][(index / 8) as usize] |= 1 << (index % 8);
+        }
+        let [input_bits, internal_bits, output_bits] = bits;
+        let chunk = MycosChunk {
+            input_bits,
+            output_bits,
+            internal_bits,
+            input_count: ni,
+            output_count: no,
+            internal_count: nn,
+            connections: self.connections,
+            name: self.name,
+            note: self.note,
+            build_hash: None,
+            ports: self.ports,
+        };
+        validate_chunk(&chunk)?;
+        Ok(chunk)
+    }
+}
+
+/// Chunks and links shared by tests across modules.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::ChunkBuilder;
+    use crate::chunk::{Action, MycosChunk, Section, Trigger};
+    use crate::genome::Genome;
+    use crate::link::Link;
+
+    /// Input 0 copied to Output 0 through Internal 0.
+    pub(crate) fn wire() -> MycosChunk {
+        use Section::{Input, Internal, Output};
+        ChunkBuilder::new()
+            .inputs(1)
+            .internal(1)
+            .outputs(1)
+            .conn((Input, 0), (Internal, 0), Trigger::On, Action::Enable)
+            .conn((Input, 0), (Internal, 0), Trigger::Off, Action::Disable)
+            .conn((Internal, 0), (Output, 0), Trigger::On, Action::Enable)
+            .conn((Internal, 0), (Output, 0), Trigger::Off, Action::Disable)
+            .build()
+            .unwrap()
+    }
+
+    /// [`wire`] as a single-chunk genome.
+    pub(crate) fn wire_genome() -> Genome {
+        Genome::from_chunks(&[wire()], &[]).unwrap()
+    }
+
+    /// Undelayed link from Output 0 of chunk 0 to Input 0 of chunk 1.
+    pub(crate) fn relay(trigger: Trigger, action: Action, order_tag: u32) -> Link {
+        Link {
+            from_chunk: 0,
+            from_out_idx: 0,
+            trigger,
+            action,
+            to_chunk: 1,
+            to_in_idx: 0,
+            order_tag,
+            delay: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Section::{Input, Internal, Output};
+
+    #[test]
+    fn builds_tags_and_validates() {
+        let chunk = ChunkBuilder::new()
+            .inputs(2)
+            .internal(9)
+            .outputs(1)
+            .set(Internal, 8)
+            .conn((Input, 0), (Internal, 0), Trigger::On, Action::Enable)
+            .conn((Input, 0), (Internal, 1), Trigger::Off, Action::Disable)
+            .conn((Input, 1), (Internal, 0), Trigger::On, Action::Toggle)
+            .conn((Internal, 0), (Output, 0), Trigger::Toggle, Action::Toggle)
+            .name("demo")
+            .port(Output, 0, "q")
+            .build()
+            .unwrap();
+        assert_eq!(
+            (
+                chunk.input_bits.len(),
+                &chunk.internal_bits[..],
+                &chunk.output_bits[..]
+            ),
+            (1, &[0, 1][..], &[0][..])
+        );
+        let tags: Vec<u32> = chunk.connections.iter().map(|c| c.order_tag).collect();
+        assert_eq!(tags, vec![0, 1, 0, 0]);
+        assert_eq!(chunk.port(Output, "q"), Some(0));
+        assert_eq!(chunk.name.as_deref(), Some("demo"));
+
+        let bad = ChunkBuilder::new()
+            .inputs(1)
+            .conn((Input, 0), (Output, 0), Trigger::On, Action::Enable)
+            .build();
+        assert!(matches!(bad, Err(Error::InvalidConnectionEdge { .. })));
+        let bad = ChunkBuilder::new().inputs(1).set(Input, 1).build();
+        assert!(matches!(bad, Err(Error::BitOutOfRange { .. })));
+    }
+}
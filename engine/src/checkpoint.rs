@@ -1,10 +1,10 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::Genome;
+use crate::{chunk::encode_chunk, link::encode_links, Genome, HallOfFame, Task};
 
 /// Evolution checkpoint allowing training to resume deterministically.
 #[derive(Clone, Serialize, Deserialize)]
@@ -17,6 +17,67 @@ pub struct Checkpoint {
     pub fitness: Vec<f32>,
     /// RNG state for the evolution loop.
     pub rng: ChaCha8Rng,
+    /// Best genomes seen so far; empty in checkpoints that predate it.
+    #[serde(default)]
+    pub hall_of_fame: HallOfFame,
+    /// [`EvoConfig::digest`](crate::EvoConfig::digest) of the run; 0 in
+    /// checkpoints that predate it.
+    #[serde(default)]
+    pub config_digest: u64,
+    /// The run's [`EvoConfig`](crate::EvoConfig) as JSON, without
+    /// `checkpoint_path`; `None` in checkpoints that predate it.
+    #[serde(default)]
+    pub config: Option<serde_json::Value>,
+    /// Task being trained when the checkpoint was taken; `None` in
+    /// checkpoints that predate it.
+    #[serde(default)]
+    pub task: Option<TaskIdentity>,
+    /// Curriculum stage being trained: 0 for the config's `task`.
+    #[serde(default)]
+    pub stage: usize,
+}
+
+/// Name and [`Task::digest`] of a task.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskIdentity {
+    pub name: String,
+    pub digest: u64,
+}
+
+impl TaskIdentity {
+    pub fn of(task: &Task) -> Self {
+        Self {
+            name: task.name.to_string(),
+            digest: task.digest(),
+        }
+    }
+}
+
+impl std::fmt::Display for TaskIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:016x})", self.name, self.digest)
+    }
+}
+
+/// Summary of a checkpoint file, read by [`peek`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckpointInfo {
+    pub generation: u32,
+    /// Number of genomes in the population.
+    pub population: usize,
+    /// Highest recorded fitness; `None` for an empty population.
+    pub best_fitness: Option<f32>,
+    pub config_digest: u64,
+}
+
+/// The fields of a checkpoint that [`peek`] reads; everything else is
+/// skipped without being built.
+#[derive(Deserialize)]
+struct Header {
+    generation: u32,
+    fitness: Vec<f32>,
+    #[serde(default)]
+    config_digest: u64,
 }
 
 /// Save a checkpoint to the given path as JSON.
@@ -32,6 +93,44 @@ pub fn load(path: &Path) -> std::io::Result<Checkpoint> {
     Ok(cp)
 }
 
+/// Read a checkpoint's metadata without deserializing its genomes, RNG or
+/// hall of fame. The population size is the length of its fitness list.
+pub fn peek(path: &Path) -> std::io::Result<CheckpointInfo> {
+    let json = fs::read_to_string(path)?;
+    let header: Header = serde_json::from_str(&json)?;
+    Ok(CheckpointInfo {
+        generation: header.generation,
+        population: header.fitness.len(),
+        best_fitness: header.fitness.iter().copied().reduce(f32::max),
+        config_digest: header.config_digest,
+    })
+}
+
+/// Write `genome` as binaries into `dir`: `best_gen_XXXX.myc` (or
+/// `best_gen_XXXX_N.myc` per chunk when there are several) and
+/// `best_gen_XXXX.links.bin`, where `XXXX` is the zero-padded generation.
+/// Returns the paths written, chunks first.
+pub fn export_best(dir: &Path, generation: u32, genome: &Genome) -> std::io::Result<Vec<PathBuf>> {
+    let invalid = |e| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+    let chunks = genome.to_chunks().map_err(invalid)?;
+    let links = genome.to_links().map_err(invalid)?;
+    let stem = format!("best_gen_{generation:04}");
+    let mut written = Vec::with_capacity(chunks.len() + 1);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let path = if chunks.len() == 1 {
+            dir.join(format!("{stem}.myc"))
+        } else {
+            dir.join(format!("{stem}_{i}.myc"))
+        };
+        fs::write(&path, encode_chunk(chunk))?;
+        written.push(path);
+    }
+    let path = dir.join(format!("{stem}.links.bin"));
+    fs::write(&path, encode_links(&links))?;
+    written.push(path);
+    Ok(written)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,15 +158,32 @@ mod tests {
             genomes: vec![genome],
             fitness: vec![1.23],
             rng: rng.clone(),
+            hall_of_fame: HallOfFame::new(1),
+            config_digest: 99,
+            config: None,
+            task: Some(TaskIdentity::of(&crate::tasks::t00_wire_echo())),
+            stage: 0,
         };
         let path = std::env::temp_dir().join("mycos_checkpoint_test.json");
         save(&path, &cp).unwrap();
         let loaded = load(&path).unwrap();
+        let info = peek(&path).unwrap();
         fs::remove_file(path).ok();
 
+        assert_eq!(
+            info,
+            CheckpointInfo {
+                generation: 3,
+                population: 1,
+                best_fitness: Some(1.23),
+                config_digest: 99,
+            }
+        );
+
         assert_eq!(loaded.generation, cp.generation);
         assert_eq!(loaded.genomes.len(), cp.genomes.len());
         assert_eq!(loaded.fitness, cp.fitness);
+        assert_eq!(loaded.task, cp.task);
         let mut r1 = cp.rng.clone();
         let mut r2 = loaded.rng.clone();
         let v1: u64 = r1.gen();
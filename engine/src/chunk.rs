@@ -1,6 +1,11 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
+use std::hash::Hash;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Section {
     Input = 0,
     Internal = 1,
@@ -19,7 +24,7 @@ impl TryFrom<u8> for Section {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Trigger {
     On = 0,
     Off = 1,
@@ -38,7 +43,7 @@ impl TryFrom<u8> for Trigger {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Action {
     Enable = 0,
     Disable = 1,
@@ -57,7 +62,7 @@ impl TryFrom<u8> for Action {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Connection {
     pub from_section: Section,
     pub to_section: Section,
@@ -68,7 +73,9 @@ pub struct Connection {
     pub order_tag: u32,
 }
 
-#[derive(Debug, Clone)]
+/// A chunk in memory. Also serializes to JSON (see
+/// [`to_json`](Self::to_json)), where the trailer fields may be left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MycosChunk {
     pub input_bits: Vec<u8>,
     pub output_bits: Vec<u8>,
@@ -77,23 +84,182 @@ pub struct MycosChunk {
     pub output_count: u32,
     pub internal_count: u32,
     pub connections: Vec<Connection>,
+    #[serde(default)]
     pub name: Option<String>,
+    #[serde(default)]
     pub note: Option<String>,
+    #[serde(default)]
     pub build_hash: Option<Vec<u8>>,
+    /// Named Input and Output bits, one `0x0004` TLV record each.
+    #[serde(default)]
+    pub ports: Vec<Port>,
+}
+
+/// A name for one Input or Output bit of a chunk.
+///
+/// Stored as TLV `0x0004` with value `u8 section, u8 reserved[3], u32 index`
+/// followed by the UTF-8 name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Port {
+    pub section: Section,
+    pub index: u32,
+    pub name: String,
+}
+
+impl MycosChunk {
+    /// Index of the bit named `name` in `section`.
+    pub fn port(&self, section: Section, name: &str) -> Option<u32> {
+        self.ports
+            .iter()
+            .find(|p| p.section == section && p.name == name)
+            .map(|p| p.index)
+    }
+
+    /// Parse a chunk from JSON and check it with [`validate_chunk`].
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let chunk: Self = serde_json::from_str(json).map_err(Error::Json)?;
+        validate_chunk(&chunk)?;
+        Ok(chunk)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("chunk serializes to JSON")
+    }
+}
+
+/// Current `.myc` version written by [`encode_chunk`]; [`parse_chunk`] also
+/// reads version 1.
+pub const CHUNK_VERSION: u16 = 2;
+
+/// Version 2 header flag bits, each set exactly when its section is non-empty.
+pub const FLAG_INPUTS: u16 = 1 << 0;
+pub const FLAG_OUTPUTS: u16 = 1 << 1;
+pub const FLAG_INTERNALS: u16 = 1 << 2;
+pub const FLAG_CONNECTIONS: u16 = 1 << 3;
+pub const FLAG_TRAILER: u16 = 1 << 4;
+
+/// Upper bounds enforced while parsing untrusted chunk, link and embed
+/// binaries. Header counts are checked before anything is read or allocated,
+/// so a crafted file fails with a [`Limit`] error instead of exhausting memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Total size of the parsed byte slice.
+    pub max_bytes: usize,
+    /// Bits per chunk section.
+    pub max_bits: u32,
+    /// Connections per chunk.
+    pub max_connections: u32,
+    /// Records in a link table.
+    pub max_links: usize,
+    /// `map_in` or `map_out` entries per embed record.
+    pub max_map_entries: u32,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 256 << 20,
+            max_bits: 1 << 26,
+            max_connections: 1 << 24,
+            max_links: 1 << 24,
+            max_map_entries: 1 << 20,
+        }
+    }
+}
+
+/// The [`ParseLimits`] bound a binary exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Bytes,
+    Bits,
+    Connections,
+    Links,
+    MapEntries,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Limit::Bytes => "bytes",
+            Limit::Bits => "section bits",
+            Limit::Connections => "connections",
+            Limit::Links => "links",
+            Limit::MapEntries => "map entries",
+        };
+        f.write_str(name)
+    }
 }
 
+const HEADER_V1: usize = 32;
+const HEADER_V2: usize = 40;
+
 #[derive(Debug)]
 pub enum Error {
     InvalidMagic,
     UnsupportedVersion(u16),
     UnexpectedEof,
+    /// Version 2 header flags disagree with the sections actually present.
+    InvalidFlags {
+        expected: u16,
+        actual: u16,
+    },
+    /// Version 2 payload CRC32 mismatch.
+    ChecksumMismatch {
+        expected: u32,
+        actual: u32,
+    },
+    LimitExceeded {
+        limit: Limit,
+        value: u64,
+        max: u64,
+    },
     InvalidSection(u8),
     InvalidTrigger(u8),
     InvalidAction(u8),
-    InvalidConnectionEdge { from: Section, to: Section },
-    FromIndexOutOfRange { section: Section, index: u32 },
-    ToIndexOutOfRange { section: Section, index: u32 },
+    InvalidConnectionEdge {
+        from: Section,
+        to: Section,
+    },
+    FromIndexOutOfRange {
+        section: Section,
+        index: u32,
+    },
+    ToIndexOutOfRange {
+        section: Section,
+        index: u32,
+    },
+    /// An initial bit set through `ChunkBuilder` lies outside its section.
+    BitOutOfRange {
+        section: Section,
+        index: u32,
+    },
+    /// A bit section holds fewer bytes than its count needs.
+    SectionTooShort {
+        section: Section,
+        bytes: u32,
+    },
     InvalidUtf8,
+    InvalidPortSection(Section),
+    PortIndexOutOfRange {
+        section: Section,
+        index: u32,
+    },
+    DuplicatePort {
+        section: Section,
+        name: String,
+    },
+    /// Connection `second` repeats the source, target and trigger of `first`.
+    DuplicateConnection {
+        first: u32,
+        second: u32,
+    },
+    /// Two connections from the same source bit share an order tag.
+    OrderTagCollision {
+        section: Section,
+        index: u32,
+        order_tag: u32,
+    },
+    Json(serde_json::Error),
 }
 
 impl std::fmt::Display for Error {
@@ -102,6 +268,15 @@ impl std::fmt::Display for Error {
             Error::InvalidMagic => write!(f, "invalid magic"),
             Error::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
             Error::UnexpectedEof => write!(f, "unexpected eof"),
+            Error::InvalidFlags { expected, actual } => {
+                write!(f, "section flags {actual:#06x}, expected {expected:#06x}")
+            }
+            Error::ChecksumMismatch { expected, actual } => {
+                write!(f, "payload crc32 {actual:#010x}, expected {expected:#010x}")
+            }
+            Error::LimitExceeded { limit, value, max } => {
+                write!(f, "{value} {limit} exceeds the limit of {max}")
+            }
             Error::InvalidSection(v) => write!(f, "invalid section {v}"),
             Error::InvalidTrigger(v) => write!(f, "invalid trigger {v}"),
             Error::InvalidAction(v) => write!(f, "invalid action {v}"),
@@ -114,7 +289,35 @@ impl std::fmt::Display for Error {
             Error::ToIndexOutOfRange { section, index } => {
                 write!(f, "to index {index} out of range for {:?}", section)
             }
+            Error::BitOutOfRange { section, index } => {
+                write!(f, "bit index {index} out of range for {:?}", section)
+            }
+            Error::SectionTooShort { section, bytes } => {
+                write!(f, "{:?} section has only {bytes} bytes", section)
+            }
             Error::InvalidUtf8 => write!(f, "invalid utf8"),
+            Error::InvalidPortSection(section) => {
+                write!(f, "ports must name Input or Output bits, not {:?}", section)
+            }
+            Error::PortIndexOutOfRange { section, index } => {
+                write!(f, "port index {index} out of range for {:?}", section)
+            }
+            Error::DuplicatePort { section, name } => {
+                write!(f, "duplicate {:?} port name {name:?}", section)
+            }
+            Error::DuplicateConnection { first, second } => {
+                write!(f, "connection {second} duplicates connection {first}")
+            }
+            Error::OrderTagCollision {
+                section,
+                index,
+                order_tag,
+            } => write!(
+                f,
+                "order tag {order_tag} used twice by connections from {:?} {index}",
+                section
+            ),
+            Error::Json(e) => write!(f, "invalid JSON chunk: {e}"),
         }
     }
 }
@@ -152,142 +355,305 @@ fn write_u32(out: &mut Vec<u8>, v: u32) {
     out.extend_from_slice(&v.to_le_bytes());
 }
 
-pub fn parse_chunk(bytes: &[u8]) -> Result<MycosChunk, Error> {
-    if bytes.len() < 32 {
-        return Err(Error::UnexpectedEof);
-    }
-    if &bytes[0..8] != b"MYCOSCH0" {
-        return Err(Error::InvalidMagic);
-    }
-    let mut cursor = 8;
-    let version = read_u16(bytes, &mut cursor)?;
-    if version != 1 {
-        return Err(Error::UnsupportedVersion(version));
-    }
-    let _flags = read_u16(bytes, &mut cursor)?;
-    let input_count = read_u32(bytes, &mut cursor)?;
-    let output_count = read_u32(bytes, &mut cursor)?;
-    let internal_count = read_u32(bytes, &mut cursor)?;
-    let connection_count = read_u32(bytes, &mut cursor)? as usize;
-    let _reserved = read_u32(bytes, &mut cursor)?;
-
-    let input_bytes = input_count.div_ceil(8) as usize;
-    let output_bytes = output_count.div_ceil(8) as usize;
-    let internal_bytes = internal_count.div_ceil(8) as usize;
-    let bits_total = input_bytes + output_bytes + internal_bytes;
-    if cursor + bits_total > bytes.len() {
-        return Err(Error::UnexpectedEof);
-    }
-    let input_bits = bytes[cursor..cursor + input_bytes].to_vec();
-    cursor += input_bytes;
-    let output_bits = bytes[cursor..cursor + output_bytes].to_vec();
-    cursor += output_bytes;
-    let internal_bits = bytes[cursor..cursor + internal_bytes].to_vec();
-    cursor += internal_bytes;
-    let pad = (4 - (bits_total % 4)) % 4;
-    if cursor + pad > bytes.len() {
-        return Err(Error::UnexpectedEof);
-    }
-    cursor += pad;
+/// CRC-32 (IEEE 802.3, reflected, as used by zlib and PNG).
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| {
+        (0..8).fold(crc ^ b as u32, |c, _| {
+            (c >> 1) ^ (0xEDB8_8320 & (c & 1).wrapping_neg())
+        })
+    })
+}
 
-    let mut connections = Vec::with_capacity(connection_count);
-    for _ in 0..connection_count {
-        if cursor + 16 > bytes.len() {
-            return Err(Error::UnexpectedEof);
-        }
-        let from_section = Section::try_from(bytes[cursor])?;
-        let to_section = Section::try_from(bytes[cursor + 1])?;
-        let trigger = Trigger::try_from(bytes[cursor + 2])?;
-        let action = Action::try_from(bytes[cursor + 3])?;
-        let from_index = u32::from_le_bytes([
-            bytes[cursor + 4],
-            bytes[cursor + 5],
-            bytes[cursor + 6],
-            bytes[cursor + 7],
-        ]);
-        let to_index = u32::from_le_bytes([
-            bytes[cursor + 8],
-            bytes[cursor + 9],
-            bytes[cursor + 10],
-            bytes[cursor + 11],
-        ]);
-        let order_tag = u32::from_le_bytes([
-            bytes[cursor + 12],
-            bytes[cursor + 13],
-            bytes[cursor + 14],
-            bytes[cursor + 15],
-        ]);
-        connections.push(Connection {
-            from_section,
-            to_section,
-            trigger,
-            action,
-            from_index,
-            to_index,
-            order_tag,
-        });
-        cursor += 16;
+/// Section flags a version 2 header must carry for these counts.
+fn section_flags(ni: u32, no: u32, nn: u32, nc: usize, trailer: bool) -> u16 {
+    [
+        (ni > 0, FLAG_INPUTS),
+        (no > 0, FLAG_OUTPUTS),
+        (nn > 0, FLAG_INTERNALS),
+        (nc > 0, FLAG_CONNECTIONS),
+        (trailer, FLAG_TRAILER),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .fold(0, |flags, (_, flag)| flags | flag)
+}
+
+/// Zero-copy view of a `.myc` chunk binary.
+///
+/// [`ChunkView::parse`] checks the header, the version 2 CRC32 and section
+/// flags and the section bounds, then borrows the bit sections, connection
+/// table and trailer straight from the input. Connections are decoded on the
+/// fly; use [`ChunkView::to_chunk`] (or [`parse_chunk`]) for an owned chunk.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkView<'a> {
+    pub version: u16,
+    pub input_count: u32,
+    pub output_count: u32,
+    pub internal_count: u32,
+    input_bits: &'a [u8],
+    output_bits: &'a [u8],
+    internal_bits: &'a [u8],
+    connections: &'a [u8],
+    trailer: &'a [u8],
+}
+
+impl<'a> ChunkView<'a> {
+    /// Parse a `.myc` chunk binary of version 1 or 2.
+    ///
+    /// Version 2 payloads are checked against the header CRC32 before anything
+    /// else is read, and the section flags must match the decoded sections.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        Self::parse_with_limits(bytes, &ParseLimits::default())
     }
 
-    let mut name = None;
-    let mut note = None;
-    let mut build_hash = None;
-    while cursor < bytes.len() {
-        if cursor + 4 > bytes.len() {
+    /// [`ChunkView::parse`] with explicit size limits.
+    pub fn parse_with_limits(bytes: &'a [u8], limits: &ParseLimits) -> Result<Self, Error> {
+        let check = |limit, value: u64, max: u64| {
+            if value > max {
+                return Err(Error::LimitExceeded { limit, value, max });
+            }
+            Ok(())
+        };
+        check(Limit::Bytes, bytes.len() as u64, limits.max_bytes as u64)?;
+        if bytes.len() < HEADER_V1 {
             return Err(Error::UnexpectedEof);
         }
-        let t = read_u16(bytes, &mut cursor)?;
-        let len = read_u16(bytes, &mut cursor)? as usize;
-        if cursor + len > bytes.len() {
-            return Err(Error::UnexpectedEof);
+        if &bytes[0..8] != b"MYCOSCH0" {
+            return Err(Error::InvalidMagic);
         }
-        let value = bytes[cursor..cursor + len].to_vec();
-        cursor += len;
-        let pad = (4 - (len % 4)) % 4;
-        if cursor + pad > bytes.len() {
-            return Err(Error::UnexpectedEof);
+        let mut cursor = 8;
+        let version = read_u16(bytes, &mut cursor)?;
+        if !(1..=CHUNK_VERSION).contains(&version) {
+            return Err(Error::UnsupportedVersion(version));
         }
-        cursor += pad;
-        match t {
-            0x0001 => {
-                let s = String::from_utf8(value).map_err(|_| Error::InvalidUtf8)?;
-                name = Some(s);
+        let flags = read_u16(bytes, &mut cursor)?;
+        let input_count = read_u32(bytes, &mut cursor)?;
+        let output_count = read_u32(bytes, &mut cursor)?;
+        let internal_count = read_u32(bytes, &mut cursor)?;
+        let connection_count = read_u32(bytes, &mut cursor)? as usize;
+        let crc = read_u32(bytes, &mut cursor)?;
+        for count in [input_count, output_count, internal_count] {
+            check(Limit::Bits, count as u64, limits.max_bits as u64)?;
+        }
+        check(
+            Limit::Connections,
+            connection_count as u64,
+            limits.max_connections as u64,
+        )?;
+        if version >= 2 {
+            if bytes.len() < HEADER_V2 {
+                return Err(Error::UnexpectedEof);
+            }
+            // 64-bit reserved region
+            cursor = HEADER_V2;
+            let actual = crc32(&bytes[HEADER_V2..]);
+            if actual != crc {
+                return Err(Error::ChecksumMismatch {
+                    expected: crc,
+                    actual,
+                });
+            }
+        }
+
+        let mut take = |len: usize| -> Result<&'a [u8], Error> {
+            let end = cursor.checked_add(len).ok_or(Error::UnexpectedEof)?;
+            let section = bytes.get(cursor..end).ok_or(Error::UnexpectedEof)?;
+            cursor = end;
+            Ok(section)
+        };
+        let input_bits = take(input_count.div_ceil(8) as usize)?;
+        let output_bits = take(output_count.div_ceil(8) as usize)?;
+        let internal_bits = take(internal_count.div_ceil(8) as usize)?;
+        let bits_total = input_bits.len() + output_bits.len() + internal_bits.len();
+        take((4 - (bits_total % 4)) % 4)?;
+        let connections = take(
+            connection_count
+                .checked_mul(16)
+                .ok_or(Error::UnexpectedEof)?,
+        )?;
+        let trailer = &bytes[cursor..];
+
+        if version >= 2 {
+            let expected = section_flags(
+                input_count,
+                output_count,
+                internal_count,
+                connection_count,
+                !trailer.is_empty(),
+            );
+            if flags != expected {
+                return Err(Error::InvalidFlags {
+                    expected,
+                    actual: flags,
+                });
+            }
+        }
+
+        Ok(Self {
+            version,
+            input_count,
+            output_count,
+            internal_count,
+            input_bits,
+            output_bits,
+            internal_bits,
+            connections,
+            trailer,
+        })
+    }
+
+    pub fn input_bits(&self) -> &'a [u8] {
+        self.input_bits
+    }
+
+    pub fn output_bits(&self) -> &'a [u8] {
+        self.output_bits
+    }
+
+    pub fn internal_bits(&self) -> &'a [u8] {
+        self.internal_bits
+    }
+
+    /// Input bits packed into little-endian `u32` words, as the executors
+    /// store them.
+    pub fn input_words(&self) -> impl Iterator<Item = u32> + 'a {
+        words(self.input_bits)
+    }
+
+    pub fn output_words(&self) -> impl Iterator<Item = u32> + 'a {
+        words(self.output_bits)
+    }
+
+    pub fn internal_words(&self) -> impl Iterator<Item = u32> + 'a {
+        words(self.internal_bits)
+    }
+
+    pub fn connection_count(&self) -> usize {
+        self.connections.len() / 16
+    }
+
+    /// Decode the connection table record by record.
+    pub fn connections(&self) -> impl Iterator<Item = Result<Connection, Error>> + 'a {
+        self.connections.chunks_exact(16).map(|r| {
+            let word = |at: usize| u32::from_le_bytes(r[at..at + 4].try_into().unwrap());
+            Ok(Connection {
+                from_section: Section::try_from(r[0])?,
+                to_section: Section::try_from(r[1])?,
+                trigger: Trigger::try_from(r[2])?,
+                action: Action::try_from(r[3])?,
+                from_index: word(4),
+                to_index: word(8),
+                order_tag: word(12),
+            })
+        })
+    }
+
+    /// Copy the view into an owned chunk, decoding the TLV trailer.
+    pub fn to_chunk(&self) -> Result<MycosChunk, Error> {
+        let connections = self.connections().collect::<Result<_, _>>()?;
+        let bytes = self.trailer;
+        let mut cursor = 0;
+        let mut name = None;
+        let mut note = None;
+        let mut build_hash = None;
+        let mut ports = Vec::new();
+        while cursor < bytes.len() {
+            // version 2 widens the length to u32 after a reserved u16
+            let t = read_u16(bytes, &mut cursor)?;
+            let len = if self.version >= 2 {
+                read_u16(bytes, &mut cursor)?;
+                read_u32(bytes, &mut cursor)? as usize
+            } else {
+                read_u16(bytes, &mut cursor)? as usize
+            };
+            if len > bytes.len() - cursor {
+                return Err(Error::UnexpectedEof);
             }
-            0x0002 => {
-                let s = String::from_utf8(value).map_err(|_| Error::InvalidUtf8)?;
-                note = Some(s);
+            let value = bytes[cursor..cursor + len].to_vec();
+            cursor += len;
+            let pad = (4 - (len % 4)) % 4;
+            if cursor + pad > bytes.len() {
+                return Err(Error::UnexpectedEof);
             }
-            0x0003 => {
-                build_hash = Some(value);
+            cursor += pad;
+            match t {
+                0x0001 => {
+                    let s = String::from_utf8(value).map_err(|_| Error::InvalidUtf8)?;
+                    name = Some(s);
+                }
+                0x0002 => {
+                    let s = String::from_utf8(value).map_err(|_| Error::InvalidUtf8)?;
+                    note = Some(s);
+                }
+                0x0003 => {
+                    build_hash = Some(value);
+                }
+                0x0004 => {
+                    if value.len() < 8 {
+                        return Err(Error::UnexpectedEof);
+                    }
+                    let section = Section::try_from(value[0])?;
+                    let index = u32::from_le_bytes(value[4..8].try_into().unwrap());
+                    let name =
+                        String::from_utf8(value[8..].to_vec()).map_err(|_| Error::InvalidUtf8)?;
+                    ports.push(Port {
+                        section,
+                        index,
+                        name,
+                    });
+                }
+                _ => {}
             }
-            _ => {}
         }
+
+        Ok(MycosChunk {
+            input_bits: self.input_bits.to_vec(),
+            output_bits: self.output_bits.to_vec(),
+            internal_bits: self.internal_bits.to_vec(),
+            input_count: self.input_count,
+            output_count: self.output_count,
+            internal_count: self.internal_count,
+            connections,
+            name,
+            note,
+            build_hash,
+            ports,
+        })
     }
+}
 
-    Ok(MycosChunk {
-        input_bits,
-        output_bits,
-        internal_bits,
-        input_count,
-        output_count,
-        internal_count,
-        connections,
-        name,
-        note,
-        build_hash,
+/// Little-endian `u32` words over packed bytes, zero-padding the last one.
+fn words(bytes: &[u8]) -> impl Iterator<Item = u32> + '_ {
+    bytes.chunks(4).map(|w| {
+        w.iter()
+            .enumerate()
+            .fold(0u32, |word, (i, &b)| word | (b as u32) << (8 * i))
     })
 }
 
+/// Parse a `.myc` chunk binary of version 1 or 2 into an owned chunk; see
+/// [`ChunkView::parse`].
+pub fn parse_chunk(bytes: &[u8]) -> Result<MycosChunk, Error> {
+    ChunkView::parse(bytes)?.to_chunk()
+}
+
+/// [`parse_chunk`] with explicit size limits.
+pub fn parse_chunk_with_limits(bytes: &[u8], limits: &ParseLimits) -> Result<MycosChunk, Error> {
+    ChunkView::parse_with_limits(bytes, limits)?.to_chunk()
+}
+
+/// Encode a chunk as a version 2 `.myc` binary.
 pub fn encode_chunk(chunk: &MycosChunk) -> Vec<u8> {
     let mut out = Vec::new();
     out.extend_from_slice(b"MYCOSCH0");
-    write_u16(&mut out, 1); // version
-    write_u16(&mut out, 0); // flags
+    write_u16(&mut out, CHUNK_VERSION);
+    write_u16(&mut out, 0); // flags, filled in below
     write_u32(&mut out, chunk.input_count);
     write_u32(&mut out, chunk.output_count);
     write_u32(&mut out, chunk.internal_count);
     write_u32(&mut out, chunk.connections.len() as u32);
-    write_u32(&mut out, 0); // reserved
+    write_u32(&mut out, 0); // crc32, filled in below
+    out.extend_from_slice(&[0; 8]); // reserved
 
     out.extend_from_slice(&chunk.input_bits);
     out.extend_from_slice(&chunk.output_bits);
@@ -315,80 +681,327 @@ pub fn encode_chunk(chunk: &MycosChunk) -> Vec<u8> {
     if let Some(hash) = &chunk.build_hash {
         encode_tlv(&mut out, 0x0003, hash);
     }
+    for port in &chunk.ports {
+        let mut value = vec![port.section as u8, 0, 0, 0];
+        value.extend_from_slice(&port.index.to_le_bytes());
+        value.extend_from_slice(port.name.as_bytes());
+        encode_tlv(&mut out, 0x0004, &value);
+    }
 
+    let trailer = out.len() > HEADER_V2 + bits_total + pad + 16 * chunk.connections.len();
+    let flags = section_flags(
+        chunk.input_count,
+        chunk.output_count,
+        chunk.internal_count,
+        chunk.connections.len(),
+        trailer,
+    );
+    out[0x0A..0x0C].copy_from_slice(&flags.to_le_bytes());
+    let crc = crc32(&out[HEADER_V2..]);
+    out[0x1C..0x20].copy_from_slice(&crc.to_le_bytes());
     out
 }
 
 fn encode_tlv(out: &mut Vec<u8>, t: u16, value: &[u8]) {
     write_u16(out, t);
-    write_u16(out, value.len() as u16);
+    write_u16(out, 0); // reserved
+    write_u32(out, value.len() as u32);
     out.extend_from_slice(value);
     let pad = (4 - (value.len() % 4)) % 4;
     out.extend(std::iter::repeat_n(0, pad));
 }
 
 pub fn validate_chunk(chunk: &MycosChunk) -> Result<(), Error> {
+    validate_sections(chunk)?;
     for conn in &chunk.connections {
-        match (conn.from_section, conn.to_section) {
-            (Section::Input | Section::Internal, Section::Internal)
-            | (Section::Internal, Section::Output) => {}
-            _ => {
-                return Err(Error::InvalidConnectionEdge {
-                    from: conn.from_section,
-                    to: conn.to_section,
-                })
+        validate_connection(chunk, conn)?;
+    }
+    if let Some(error) = duplicate_connections(chunk).into_iter().next() {
+        return Err(error);
+    }
+    for i in 0..chunk.ports.len() {
+        validate_port(chunk, i)?;
+    }
+    Ok(())
+}
+
+/// Every violation in `chunk` instead of only the first: one error per
+/// invalid connection, repeated connection, reused order tag or invalid
+/// port.
+pub fn validate_chunk_all(chunk: &MycosChunk) -> Vec<Error> {
+    let mut errors: Vec<Error> = validate_sections(chunk).err().into_iter().collect();
+    errors.extend(
+        chunk
+            .connections
+            .iter()
+            .filter_map(|conn| validate_connection(chunk, conn).err()),
+    );
+    errors.extend(duplicate_connections(chunk));
+    errors.extend((0..chunk.ports.len()).filter_map(|i| validate_port(chunk, i).err()));
+    errors
+}
+
+/// Errors for [`duplicate_edges`] among the chunk's connections.
+fn duplicate_connections(chunk: &MycosChunk) -> Vec<Error> {
+    duplicate_edges(
+        &chunk.connections,
+        |c| {
+            let from = (c.from_section as u8, c.from_index);
+            (
+                from,
+                (c.to_section as u8, c.to_index, c.trigger as u8),
+                c.order_tag,
+            )
+        },
+        |first, second| Error::DuplicateConnection { first, second },
+        |c| Error::OrderTagCollision {
+            section: c.from_section,
+            index: c.from_index,
+            order_tag: c.order_tag,
+        },
+    )
+}
+
+/// Edges repeating the `(from, to, trigger)` of an earlier one, and order
+/// tags reused by edges from the same source. Either would make the CSR count
+/// one edge twice or leave the firing order ambiguous.
+///
+/// `key` splits an edge into its source, the rest of its `(to, trigger)` key
+/// and its order tag. Repeats are reported through `duplicate(first, second)`
+/// with both indices, and reused tags through `collision(edge)`.
+pub(crate) fn duplicate_edges<T, S, K, O, E>(
+    edges: &[T],
+    key: impl Fn(&T) -> (S, K, O),
+    duplicate: impl Fn(u32, u32) -> E,
+    collision: impl Fn(&T) -> E,
+) -> Vec<E>
+where
+    S: Copy + Eq + Hash,
+    K: Eq + Hash,
+    O: Eq + Hash,
+{
+    let mut errors = Vec::new();
+    let mut seen = HashMap::new();
+    let mut tags = HashSet::new();
+    for (i, edge) in edges.iter().enumerate() {
+        let (from, to, order_tag) = key(edge);
+        match seen.entry((from, to)) {
+            Entry::Occupied(first) => errors.push(duplicate(*first.get(), i as u32)),
+            Entry::Vacant(slot) => {
+                slot.insert(i as u32);
             }
         }
-        match conn.from_section {
-            Section::Input => {
-                if conn.from_index >= chunk.input_count {
-                    return Err(Error::FromIndexOutOfRange {
-                        section: conn.from_section,
-                        index: conn.from_index,
-                    });
-                }
-            }
-            Section::Internal => {
-                if conn.from_index >= chunk.internal_count {
-                    return Err(Error::FromIndexOutOfRange {
-                        section: conn.from_section,
-                        index: conn.from_index,
-                    });
-                }
+        if !tags.insert((from, order_tag)) {
+            errors.push(collision(edge));
+        }
+    }
+    errors
+}
+
+/// Every bit section has room for its count.
+fn validate_sections(chunk: &MycosChunk) -> Result<(), Error> {
+    for (section, bits, count) in [
+        (Section::Input, &chunk.input_bits, chunk.input_count),
+        (Section::Output, &chunk.output_bits, chunk.output_count),
+        (
+            Section::Internal,
+            &chunk.internal_bits,
+            chunk.internal_count,
+        ),
+    ] {
+        if bits.len() < count.div_ceil(8) as usize {
+            return Err(Error::SectionTooShort {
+                section,
+                bytes: bits.len() as u32,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn validate_connection(chunk: &MycosChunk, conn: &Connection) -> Result<(), Error> {
+    match (conn.from_section, conn.to_section) {
+        (Section::Input | Section::Internal, Section::Internal)
+        | (Section::Internal, Section::Output) => {}
+        _ => {
+            return Err(Error::InvalidConnectionEdge {
+                from: conn.from_section,
+                to: conn.to_section,
+            })
+        }
+    }
+    match conn.from_section {
+        Section::Input => {
+            if conn.from_index >= chunk.input_count {
+                return Err(Error::FromIndexOutOfRange {
+                    section: conn.from_section,
+                    index: conn.from_index,
+                });
             }
-            Section::Output => {
-                return Err(Error::InvalidConnectionEdge {
-                    from: conn.from_section,
-                    to: conn.to_section,
-                })
+        }
+        Section::Internal => {
+            if conn.from_index >= chunk.internal_count {
+                return Err(Error::FromIndexOutOfRange {
+                    section: conn.from_section,
+                    index: conn.from_index,
+                });
             }
         }
-        match conn.to_section {
-            Section::Internal => {
-                if conn.to_index >= chunk.internal_count {
-                    return Err(Error::ToIndexOutOfRange {
-                        section: conn.to_section,
-                        index: conn.to_index,
-                    });
-                }
+        Section::Output => {
+            return Err(Error::InvalidConnectionEdge {
+                from: conn.from_section,
+                to: conn.to_section,
+            })
+        }
+    }
+    match conn.to_section {
+        Section::Internal => {
+            if conn.to_index >= chunk.internal_count {
+                return Err(Error::ToIndexOutOfRange {
+                    section: conn.to_section,
+                    index: conn.to_index,
+                });
             }
-            Section::Output => {
-                if conn.to_index >= chunk.output_count {
-                    return Err(Error::ToIndexOutOfRange {
-                        section: conn.to_section,
-                        index: conn.to_index,
-                    });
-                }
+        }
+        Section::Output => {
+            if conn.to_index >= chunk.output_count {
+                return Err(Error::ToIndexOutOfRange {
+                    section: conn.to_section,
+                    index: conn.to_index,
+                });
             }
-            Section::Input => {
-                return Err(Error::InvalidConnectionEdge {
-                    from: conn.from_section,
-                    to: conn.to_section,
+        }
+        Section::Input => {
+            return Err(Error::InvalidConnectionEdge {
+                from: conn.from_section,
+                to: conn.to_section,
+            })
+        }
+    }
+    Ok(())
+}
+
+fn validate_port(chunk: &MycosChunk, i: usize) -> Result<(), Error> {
+    let port = &chunk.ports[i];
+    let count = match port.section {
+        Section::Input => chunk.input_count,
+        Section::Output => chunk.output_count,
+        Section::Internal => return Err(Error::InvalidPortSection(port.section)),
+    };
+    if port.index >= count {
+        return Err(Error::PortIndexOutOfRange {
+            section: port.section,
+            index: port.index,
+        });
+    }
+    let earlier = &chunk.ports[..i];
+    if earlier
+        .iter()
+        .any(|p| p.section == port.section && p.name == port.name)
+    {
+        return Err(Error::DuplicatePort {
+            section: port.section,
+            name: port.name.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Cut out the cone of logic feeding `bits`: those bits plus every bit that
+/// reaches one of them through connections.
+///
+/// Kept bits stay in their original order within each section and are
+/// re-indexed densely; connections, initial bits and ports come along for the
+/// kept bits only. The name and note are kept and the build hash is cleared.
+///
+/// Panics if a bit in `bits` is out of range.
+pub fn extract_subchunk(chunk: &MycosChunk, bits: &[(Section, u32)]) -> MycosChunk {
+    let counts = [chunk.input_count, chunk.internal_count, chunk.output_count];
+    let mut incoming: [Vec<Vec<&Connection>>; 3] =
+        counts.map(|n| (0..n).map(|_| Vec::new()).collect());
+    for conn in &chunk.connections {
+        incoming[conn.to_section as usize][conn.to_index as usize].push(conn);
+    }
+
+    let mut kept: [Vec<bool>; 3] = counts.map(|n| vec![false; n as usize]);
+    let mut stack: Vec<(Section, u32)> = Vec::new();
+    for &(section, index) in bits {
+        assert!(
+            index < counts[section as usize],
+            "{section:?} bit {index} out of range"
+        );
+        stack.push((section, index));
+    }
+    while let Some((section, index)) = stack.pop() {
+        let seen = &mut kept[section as usize][index as usize];
+        if *seen {
+            continue;
+        }
+        *seen = true;
+        stack.extend(
+            incoming[section as usize][index as usize]
+                .iter()
+                .map(|c| (c.from_section, c.from_index)),
+        );
+    }
+
+    // new index of every kept bit
+    let remap: [Vec<Option<u32>>; 3] = kept.clone().map(|keep| {
+        let mut next = 0;
+        keep.into_iter()
+            .map(|k| {
+                k.then(|| {
+                    next += 1;
+                    next - 1
                 })
+            })
+            .collect()
+    });
+    let at = |section: Section, index: u32| remap[section as usize][index as usize];
+    let bit = |bytes: &[u8], i: usize| bytes[i / 8] >> (i % 8) & 1 == 1;
+    let pack = |bytes: &[u8], keep: &[bool]| {
+        let mut out = vec![0u8; keep.iter().filter(|&&k| k).count().div_ceil(8)];
+        for (n, i) in (0..keep.len()).filter(|&i| keep[i]).enumerate() {
+            if bit(bytes, i) {
+                out[n / 8] |= 1 << (n % 8);
             }
         }
+        out
+    };
+    let count = |section: Section| kept[section as usize].iter().filter(|&&k| k).count() as u32;
+
+    MycosChunk {
+        input_bits: pack(&chunk.input_bits, &kept[Section::Input as usize]),
+        output_bits: pack(&chunk.output_bits, &kept[Section::Output as usize]),
+        internal_bits: pack(&chunk.internal_bits, &kept[Section::Internal as usize]),
+        input_count: count(Section::Input),
+        output_count: count(Section::Output),
+        internal_count: count(Section::Internal),
+        connections: chunk
+            .connections
+            .iter()
+            .filter_map(|conn| {
+                Some(Connection {
+                    from_index: at(conn.from_section, conn.from_index)?,
+                    to_index: at(conn.to_section, conn.to_index)?,
+                    ..conn.clone()
+                })
+            })
+            .collect(),
+        name: chunk.name.clone(),
+        note: chunk.note.clone(),
+        build_hash: None,
+        ports: chunk
+            .ports
+            .iter()
+            .filter_map(|port| {
+                Some(Port {
+                    index: at(port.section, port.index)?,
+                    ..port.clone()
+                })
+            })
+            .collect(),
     }
-    Ok(())
 }
 
 #[cfg(test)]
@@ -437,6 +1050,41 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn validate_all_collects_every_error() {
+        let path = fixtures().join("tiny_toggle.myc");
+        let mut chunk = parse_chunk(&fs::read(path).unwrap()).unwrap();
+        assert!(validate_chunk_all(&chunk).is_empty());
+        let conn = chunk.connections[0].clone();
+        chunk.connections.push(conn.clone());
+        assert!(matches!(
+            validate_chunk(&chunk),
+            Err(Error::DuplicateConnection { first: 0, .. })
+        ));
+        chunk.connections.push(Connection {
+            from_index: 9,
+            order_tag: 7,
+            ..conn.clone()
+        });
+        chunk.connections.push(Connection {
+            to_section: Section::Input,
+            order_tag: 8,
+            ..conn
+        });
+        let errors = validate_chunk_all(&chunk);
+        assert_eq!(errors.len(), 4, "{errors:?}");
+        assert!(matches!(
+            errors[0],
+            Error::FromIndexOutOfRange { index: 9, .. }
+        ));
+        assert!(matches!(errors[1], Error::InvalidConnectionEdge { .. }));
+        assert!(matches!(
+            errors[2],
+            Error::DuplicateConnection { first: 0, .. }
+        ));
+        assert!(matches!(errors[3], Error::OrderTagCollision { .. }));
+    }
+
     #[test]
     fn invalid_from_index() {
         let path = fixtures().join("tiny_toggle.myc");
@@ -450,6 +1098,111 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn crafted_headers_hit_limits() {
+        // a bare header claiming billions of connections and bits
+        let mut data = b"MYCOSCH0".to_vec();
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        for count in [1, 0, 0, u32::MAX, 0] {
+            data.extend_from_slice(&u32::to_le_bytes(count));
+        }
+        data.resize(100, 0);
+        assert!(matches!(
+            parse_chunk(&data),
+            Err(Error::LimitExceeded {
+                limit: Limit::Connections,
+                ..
+            })
+        ));
+        data[0x0C..0x10].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            parse_chunk(&data),
+            Err(Error::LimitExceeded {
+                limit: Limit::Bits,
+                ..
+            })
+        ));
+        // within the limits the header is still checked against the data
+        data[0x0C..0x10].copy_from_slice(&(1u32 << 20).to_le_bytes());
+        data[0x18..0x1C].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(parse_chunk(&data), Err(Error::UnexpectedEof)));
+
+        let tiny = ParseLimits {
+            max_bytes: 64,
+            ..ParseLimits::default()
+        };
+        assert!(matches!(
+            parse_chunk_with_limits(&data, &tiny),
+            Err(Error::LimitExceeded {
+                limit: Limit::Bytes,
+                value: 100,
+                max: 64
+            })
+        ));
+    }
+
+    #[test]
+    fn view_borrows_sections() {
+        let data = fs::read(fixtures().join("fanout_1_to_1024.myc")).unwrap();
+        let view = ChunkView::parse(&data).unwrap();
+        let owned = parse_chunk(&data).unwrap();
+        let range = data.as_ptr_range();
+        assert!(range.contains(&view.internal_bits().as_ptr()));
+        assert_eq!(view.internal_bits(), &owned.internal_bits[..]);
+        assert_eq!(
+            view.internal_words().count(),
+            owned.internal_count.div_ceil(32) as usize
+        );
+        assert_eq!(view.connection_count(), owned.connections.len());
+        for (a, b) in view.connections().zip(&owned.connections) {
+            let a = a.unwrap();
+            assert_eq!(
+                (a.from_index, a.to_index, a.order_tag),
+                (b.from_index, b.to_index, b.order_tag)
+            );
+            assert_eq!(
+                (a.from_section, a.to_section),
+                (b.from_section, b.to_section)
+            );
+        }
+        let words: Vec<u32> = words(&[1, 0, 0, 0x80, 2]).collect();
+        assert_eq!(words, vec![0x8000_0001, 2]);
+    }
+
+    #[test]
+    fn version_two_reads_back_and_checks_payload() {
+        let v1 = fs::read(fixtures().join("tiny_toggle.myc")).unwrap();
+        let mut chunk = parse_chunk(&v1).unwrap();
+        chunk.note = Some("x".repeat(70_000)); // past the v1 u16 TLV length
+        let data = encode_chunk(&chunk);
+        assert_eq!(&data[8..10], &CHUNK_VERSION.to_le_bytes());
+        let flags = u16::from_le_bytes([data[10], data[11]]);
+        assert_eq!(
+            flags,
+            FLAG_INPUTS | FLAG_OUTPUTS | FLAG_INTERNALS | FLAG_CONNECTIONS | FLAG_TRAILER
+        );
+        let parsed = parse_chunk(&data).unwrap();
+        assert_eq!(encode_chunk(&parsed), data);
+        assert_eq!(parsed.note.as_ref().map(String::len), Some(70_000));
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let mut bad = data.clone();
+        bad[HEADER_V2] ^= 1;
+        assert!(matches!(
+            parse_chunk(&bad),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        let mut bad = data.clone();
+        bad[10] &= !(FLAG_TRAILER as u8);
+        assert!(matches!(parse_chunk(&bad), Err(Error::InvalidFlags { .. })));
+        bad[8] = 3;
+        assert!(matches!(
+            parse_chunk(&bad),
+            Err(Error::UnsupportedVersion(3))
+        ));
+    }
+
     #[test]
     fn tlv_round_trip() {
         let chunk = MycosChunk {
@@ -463,11 +1216,122 @@ mod tests {
             name: Some("demo".to_string()),
             note: Some("note".to_string()),
             build_hash: Some(vec![1, 2, 3, 4]),
+            ports: vec![Port {
+                section: Section::Input,
+                index: 0,
+                name: "clk".to_string(),
+            }],
         };
         let data = encode_chunk(&chunk);
         let parsed = parse_chunk(&data).unwrap();
         assert_eq!(parsed.name.as_deref(), Some("demo"));
         assert_eq!(parsed.note.as_deref(), Some("note"));
         assert_eq!(parsed.build_hash.as_deref(), Some(&[1, 2, 3, 4][..]));
+        assert_eq!(parsed.ports, chunk.ports);
+        assert_eq!(parsed.port(Section::Input, "clk"), Some(0));
+        assert_eq!(parsed.port(Section::Output, "clk"), None);
+        validate_chunk(&parsed).unwrap();
+
+        let mut bad = parsed.clone();
+        bad.ports.push(bad.ports[0].clone());
+        assert!(matches!(
+            validate_chunk(&bad),
+            Err(Error::DuplicatePort { .. })
+        ));
+        bad.ports[1].index = 1;
+        assert!(matches!(
+            validate_chunk(&bad),
+            Err(Error::PortIndexOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn extract_keeps_only_the_cone() {
+        use Section::{Input, Internal, Output};
+        let conn = |from_section, from_index, to_section, to_index| Connection {
+            from_section,
+            to_section,
+            trigger: Trigger::On,
+            action: Action::Enable,
+            from_index,
+            to_index,
+            order_tag: 0,
+        };
+        // two independent wires plus a stray internal
+        let chunk = MycosChunk {
+            input_bits: vec![0b10],
+            output_bits: vec![0],
+            internal_bits: vec![0b110],
+            input_count: 2,
+            output_count: 2,
+            internal_count: 3,
+            connections: vec![
+                conn(Input, 0, Internal, 0),
+                conn(Internal, 0, Output, 0),
+                conn(Input, 1, Internal, 1),
+                conn(Internal, 1, Output, 1),
+                conn(Internal, 1, Internal, 2),
+            ],
+            name: Some("pair".into()),
+            note: None,
+            build_hash: Some(vec![0; 32]),
+            ports: vec![
+                Port {
+                    section: Output,
+                    index: 0,
+                    name: "a".into(),
+                },
+                Port {
+                    section: Output,
+                    index: 1,
+                    name: "b".into(),
+                },
+            ],
+        };
+        let sub = extract_subchunk(&chunk, &[(Output, 1)]);
+        validate_chunk(&sub).unwrap();
+        assert_eq!(
+            (sub.input_count, sub.internal_count, sub.output_count),
+            (1, 1, 1)
+        );
+        assert_eq!((sub.input_bits[0], sub.internal_bits[0]), (1, 1));
+        let ends = |c: &MycosChunk| -> Vec<_> {
+            c.connections
+                .iter()
+                .map(|c| (c.from_section, c.from_index, c.to_section, c.to_index))
+                .collect()
+        };
+        assert_eq!(
+            ends(&sub),
+            vec![(Input, 0, Internal, 0), (Internal, 0, Output, 0)]
+        );
+        assert_eq!(sub.port(Output, "b"), Some(0));
+        assert_eq!(sub.port(Output, "a"), None);
+        assert_eq!(sub.build_hash, None);
+
+        let all = extract_subchunk(&chunk, &[(Output, 0), (Output, 1), (Internal, 2)]);
+        assert_eq!(ends(&all), ends(&chunk));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let data = fs::read(fixtures().join("oscillator_2cycle.myc")).unwrap();
+        let chunk = parse_chunk(&data).unwrap();
+        let back = MycosChunk::from_json(&chunk.to_json()).unwrap();
+        assert_eq!(encode_chunk(&back), encode_chunk(&chunk));
+
+        // trailer fields may be omitted, but sections must fit their counts
+        let authored = r#"{"input_bits": [], "output_bits": [0], "internal_bits": [1],
+            "input_count": 0, "output_count": 1, "internal_count": 9, "connections": []}"#;
+        assert!(matches!(
+            MycosChunk::from_json(authored),
+            Err(Error::SectionTooShort {
+                section: Section::Internal,
+                bytes: 1
+            })
+        ));
+        let authored = authored.replace("[1]", "[1, 0]");
+        assert_eq!(MycosChunk::from_json(&authored).unwrap().name, None);
+        assert!(matches!(MycosChunk::from_json("[]"), Err(Error::Json(_))));
     }
 }
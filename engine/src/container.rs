@@ -0,0 +1,367 @@
+//! `.mycb` container bundling several chunk binaries, the link table and embed
+//! records into a single artifact.
+//!
+//! Layout (little-endian):
+//!
+//! ```text
+//! 0x00  8  Magic = "MYCOSCB0"
+//! 0x08  2  Version = 0x0003
+//! 0x0A  2  Flags (0)
+//! 0x0C  4  EntryCount
+//! 0x10     EntryCount × 12-byte TOC entries {u16 kind, u16 reserved, u32 offset, u32 len}
+//!          followed by the entry payloads, each 4-byte aligned
+//! ```
+//!
+//! Entry kinds: `0` = chunk binary (`.myc`), `1` = link table, `2` = embed
+//! records. Chunks are numbered in TOC order; at most one link table and one
+//! embed table may be present.
+//!
+//! Version 2 stores each link's `delay` in the formerly reserved bytes 10..12
+//! of its record. Version 1 containers still load, with every delay 0.
+//! Version 3 stores each embed's gate section in the formerly reserved byte
+//! after `io_mode`; older containers load with every gate internal.
+
+use crate::chunk::{encode_chunk, parse_chunk, validate_chunk, MycosChunk, Section};
+use crate::embed::{encode_embeds, parse_embeds, validate_embeds, Embed, EmbedError};
+use crate::link::{encode_links, parse_links, validate_links, Link, LinkError};
+
+const MAGIC: &[u8; 8] = b"MYCOSCB0";
+const VERSION: u16 = 3;
+const HEADER_BYTES: usize = 16;
+const ENTRY_BYTES: usize = 12;
+
+const KIND_CHUNK: u16 = 0;
+const KIND_LINKS: u16 = 1;
+const KIND_EMBEDS: u16 = 2;
+
+/// A complete system: chunks plus the links and embeds wiring them together.
+#[derive(Debug, Clone, Default)]
+pub struct Container {
+    pub chunks: Vec<MycosChunk>,
+    pub links: Vec<Link>,
+    pub embeds: Vec<Embed>,
+}
+
+#[derive(Debug)]
+pub enum ContainerError {
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    UnexpectedEof,
+    InvalidEntryKind(u16),
+    DuplicateEntry(u16),
+    EntryOutOfBounds {
+        entry: u32,
+    },
+    Chunk {
+        index: u32,
+        error: crate::chunk::Error,
+    },
+    Link(LinkError),
+    Embed(EmbedError),
+}
+
+impl std::fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContainerError::InvalidMagic => write!(f, "invalid magic"),
+            ContainerError::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
+            ContainerError::UnexpectedEof => write!(f, "unexpected eof"),
+            ContainerError::InvalidEntryKind(k) => write!(f, "invalid entry kind {k}"),
+            ContainerError::DuplicateEntry(k) => write!(f, "duplicate entry of kind {k}"),
+            ContainerError::EntryOutOfBounds { entry } => {
+                write!(f, "entry {entry} out of bounds")
+            }
+            ContainerError::Chunk { index, error } => write!(f, "chunk {index}: {error}"),
+            ContainerError::Link(e) => write!(f, "links: {e}"),
+            ContainerError::Embed(e) => write!(f, "embeds: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ContainerError {}
+
+impl From<LinkError> for ContainerError {
+    fn from(e: LinkError) -> Self {
+        ContainerError::Link(e)
+    }
+}
+
+impl From<EmbedError> for ContainerError {
+    fn from(e: EmbedError) -> Self {
+        ContainerError::Embed(e)
+    }
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Result<u16, ContainerError> {
+    bytes
+        .get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(ContainerError::UnexpectedEof)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, ContainerError> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(ContainerError::UnexpectedEof)
+}
+
+/// Parse and validate a `.mycb` container.
+///
+/// Every chunk is validated individually, links are validated against the
+/// chunk I/O counts, and embeds are checked with
+/// [`validate_embeds`](crate::validate_embeds).
+pub fn parse_container(bytes: &[u8]) -> Result<Container, ContainerError> {
+    if bytes.len() < HEADER_BYTES {
+        return Err(ContainerError::UnexpectedEof);
+    }
+    if &bytes[0..8] != MAGIC {
+        return Err(ContainerError::InvalidMagic);
+    }
+    let version = read_u16(bytes, 8)?;
+    if !(1..=VERSION).contains(&version) {
+        return Err(ContainerError::UnsupportedVersion(version));
+    }
+    let _flags = read_u16(bytes, 10)?;
+    let entry_count = read_u32(bytes, 12)?;
+    let toc_end = (entry_count as usize)
+        .checked_mul(ENTRY_BYTES)
+        .and_then(|n| n.checked_add(HEADER_BYTES))
+        .ok_or(ContainerError::UnexpectedEof)?;
+    if toc_end > bytes.len() {
+        return Err(ContainerError::UnexpectedEof);
+    }
+
+    let mut container = Container::default();
+    let mut seen_links = false;
+    let mut seen_embeds = false;
+    for entry in 0..entry_count {
+        let at = HEADER_BYTES + entry as usize * ENTRY_BYTES;
+        let kind = read_u16(bytes, at)?;
+        let offset = read_u32(bytes, at + 4)? as usize;
+        let len = read_u32(bytes, at + 8)? as usize;
+        let payload = offset
+            .checked_add(len)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or(ContainerError::EntryOutOfBounds { entry })?;
+        match kind {
+            KIND_CHUNK => {
+                let index = container.chunks.len() as u32;
+                let chunk =
+                    parse_chunk(payload).map_err(|error| ContainerError::Chunk { index, error })?;
+                container.chunks.push(chunk);
+            }
+            KIND_LINKS => {
+                if seen_links {
+                    return Err(ContainerError::DuplicateEntry(kind));
+                }
+                seen_links = true;
+                container.links = parse_links(payload)?;
+                if version < 2 {
+                    // bytes 10..12 were reserved before delays existed
+                    for link in &mut container.links {
+                        link.delay = 0;
+                    }
+                }
+            }
+            KIND_EMBEDS => {
+                if seen_embeds {
+                    return Err(ContainerError::DuplicateEntry(kind));
+                }
+                seen_embeds = true;
+                container.embeds = parse_embeds(payload)?;
+                if version < 3 {
+                    // the gate section byte was reserved before version 3
+                    for embed in &mut container.embeds {
+                        embed.gate_section = Section::Internal;
+                    }
+                }
+            }
+            _ => return Err(ContainerError::InvalidEntryKind(kind)),
+        }
+    }
+
+    validate_container(&container)?;
+    Ok(container)
+}
+
+/// Check chunks, links and embeds of a container for cross-reference errors.
+pub fn validate_container(container: &Container) -> Result<(), ContainerError> {
+    for (i, chunk) in container.chunks.iter().enumerate() {
+        validate_chunk(chunk).map_err(|error| ContainerError::Chunk {
+            index: i as u32,
+            error,
+        })?;
+    }
+    validate_links(&container.links, &container.chunks)?;
+    validate_embeds(&container.embeds, &container.chunks)?;
+    Ok(())
+}
+
+/// Encode a container into its `.mycb` byte representation.
+///
+/// The link and embed tables are only written when non-empty.
+pub fn encode_container(container: &Container) -> Vec<u8> {
+    let mut entries: Vec<(u16, Vec<u8>)> = container
+        .chunks
+        .iter()
+        .map(|c| (KIND_CHUNK, encode_chunk(c)))
+        .collect();
+    if !container.links.is_empty() {
+        entries.push((KIND_LINKS, encode_links(&container.links)));
+    }
+    if !container.embeds.is_empty() {
+        entries.push((KIND_EMBEDS, encode_embeds(&container.embeds)));
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // flags
+    out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+
+    let mut offset = HEADER_BYTES + entries.len() * ENTRY_BYTES;
+    for (kind, payload) in &entries {
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // reserved
+        out.extend_from_slice(&(offset as u32).to_le_bytes());
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        offset += payload.len().next_multiple_of(4);
+    }
+    for (_, payload) in &entries {
+        out.extend_from_slice(payload);
+        let pad = payload.len().next_multiple_of(4) - payload.len();
+        out.extend(std::iter::repeat_n(0, pad));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{Action, Trigger};
+    use crate::embed::IoMode;
+    use std::path::PathBuf;
+
+    fn fixtures() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join("fixtures")
+    }
+
+    fn fixture(name: &str) -> MycosChunk {
+        let data = std::fs::read(fixtures().join(name)).unwrap();
+        parse_chunk(&data).unwrap()
+    }
+
+    fn sample() -> Container {
+        Container {
+            chunks: vec![
+                fixture("parent_with_gate.myc"),
+                fixture("gated_child.myc"),
+                fixture("noop.myc"),
+            ],
+            links: vec![Link {
+                from_chunk: 0,
+                from_out_idx: 0,
+                trigger: Trigger::On,
+                action: Action::Enable,
+                to_chunk: 2,
+                to_in_idx: 1,
+                order_tag: 3,
+                delay: 0,
+            }],
+            embeds: vec![Embed {
+                parent_chunk: 0,
+                child_chunk: 1,
+                gate_bit: 0,
+                gate_section: Section::Internal,
+                io_mode: IoMode::Copy,
+                map_in: vec![(1, 0)],
+                map_out: vec![(0, 0)],
+                gate_prev: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let container = sample();
+        let data = encode_container(&container);
+        assert_eq!(data.len() % 4, 0);
+        let parsed = parse_container(&data).unwrap();
+        assert_eq!(parsed.chunks.len(), 3);
+        for (a, b) in parsed.chunks.iter().zip(&container.chunks) {
+            assert_eq!(encode_chunk(a), encode_chunk(b));
+        }
+        assert_eq!(parsed.links, container.links);
+        assert_eq!(parsed.embeds.len(), 1);
+        assert_eq!(parsed.embeds[0].io_mode, IoMode::Copy);
+        assert_eq!(parsed.embeds[0].map_in, vec![(1, 0)]);
+        assert_eq!(parsed.embeds[0].map_out, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn version_one_links_have_no_delay() {
+        let mut container = sample();
+        container.links[0].delay = 3;
+        let mut data = encode_container(&container);
+        assert_eq!(parse_container(&data).unwrap().links[0].delay, 3);
+        // a version 1 reader never looked at the delay bytes
+        data[8..10].copy_from_slice(&1u16.to_le_bytes());
+        assert_eq!(parse_container(&data).unwrap().links[0].delay, 0);
+        data[8..10].copy_from_slice(&4u16.to_le_bytes());
+        assert!(matches!(
+            parse_container(&data),
+            Err(ContainerError::UnsupportedVersion(4))
+        ));
+    }
+
+    #[test]
+    fn version_two_embeds_are_internally_gated() {
+        let mut container = sample();
+        container.embeds[0].gate_section = Section::Input;
+        let mut data = encode_container(&container);
+        assert_eq!(
+            parse_container(&data).unwrap().embeds[0].gate_section,
+            Section::Input
+        );
+        // the zero byte was reserved padding before version 3
+        data[8..10].copy_from_slice(&2u16.to_le_bytes());
+        assert_eq!(
+            parse_container(&data).unwrap().embeds[0].gate_section,
+            Section::Internal
+        );
+    }
+
+    #[test]
+    fn rejects_dangling_link() {
+        let mut container = sample();
+        container.links[0].to_chunk = 7;
+        let data = encode_container(&container);
+        assert!(matches!(
+            parse_container(&data),
+            Err(ContainerError::Link(LinkError::ToChunkOutOfRange(7)))
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_embed_and_truncation() {
+        let mut container = sample();
+        container.embeds[0].child_chunk = 9;
+        let data = encode_container(&container);
+        assert!(matches!(
+            parse_container(&data),
+            Err(ContainerError::Embed(EmbedError::ChunkOutOfRange {
+                embed: 0,
+                chunk: 9
+            }))
+        ));
+
+        let data = encode_container(&sample());
+        assert!(matches!(
+            parse_container(&data[..data.len() - 4]),
+            Err(ContainerError::EntryOutOfBounds { .. })
+        ));
+    }
+}
@@ -1,6 +1,18 @@
 use crate::chunk::{Action, MycosChunk, Section, Trigger};
+use crate::csr::{build_csr, Effect, CSR};
 use crate::layout::{bit_to_word, clr_bit, set_bit, xor_bit};
-use std::collections::VecDeque;
+use crate::link::{build_link_csr, validate_links, GlobalBitMap, Link, LinkError};
+use crate::policy::{
+    clamp_commutative, freeze_last_stable, parity_quench, stochastic_quench, CycleDetector,
+    DetectorConfig, ExecutionResult, Policy, PolicyConfig,
+};
+use crate::runner::TickStats;
+use crate::scc::scc_ids_and_topo_levels;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::Range;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Edge {
@@ -11,6 +23,7 @@ enum Edge {
 
 #[derive(Clone, Copy, Debug)]
 struct Event {
+    chunk: u32,
     section: Section,
     index: u32,
     edge: Edge,
@@ -55,114 +68,986 @@ fn words_to_bytes(words: &[u32], bit_count: u32) -> Vec<u8> {
     out
 }
 
+/// Resolved write to `(to_section, to_index)` with its `(action, order_tag)`.
+type Proposal = ((Section, u32), (Action, u32));
+
+/// Seed the queue with initially set Input and Internal bits (On + Toggle).
+fn seed_events(
+    q: &mut impl Extend<Event>,
+    chunk_id: u32,
+    chunk: &MycosChunk,
+    input: &[u32],
+    internal: &[u32],
+) {
+    for i in 0..chunk.input_count {
+        if get_bit(input, i) {
+            push_edge(q, chunk_id, Section::Input, i, true);
+        }
+    }
+    for i in 0..chunk.internal_count {
+        if get_bit(internal, i) {
+            push_edge(q, chunk_id, Section::Internal, i, true);
+        }
+    }
+}
+
+/// Enqueue the level edge for a bit change followed by its Toggle edge.
+fn push_edge(q: &mut impl Extend<Event>, chunk: u32, section: Section, index: u32, high: bool) {
+    let edge = if high { Edge::On } else { Edge::Off };
+    q.extend([
+        Event {
+            chunk,
+            section,
+            index,
+            edge,
+        },
+        Event {
+            chunk,
+            section,
+            index,
+            edge: Edge::Toggle,
+        },
+    ]);
+}
+
+/// Record a proposal, keeping the highest `order_tag` per target; ties go to
+/// the later proposal.
+fn propose(proposals: &mut Vec<Proposal>, key: (Section, u32), action: Action, order_tag: u32) {
+    if let Some((_, (act, tag))) = proposals.iter_mut().find(|(k, _)| *k == key) {
+        if order_tag >= *tag {
+            *act = action;
+            *tag = order_tag;
+        }
+    } else {
+        proposals.push((key, (action, order_tag)));
+    }
+}
+
+/// Best write to one bit proposed so far in a round.
+#[derive(Clone, Copy)]
+struct Winner<R = usize> {
+    action: Action,
+    order_tag: u32,
+    /// Breaks `order_tag` ties: the CSR index of the effect, the index of a
+    /// delayed link, or the `(trigger, source, connection)` of [`execute`].
+    rank: R,
+}
+
+/// Keep `write` for `key` if no better write is there yet: the highest
+/// `order_tag` wins and ties go to the highest rank.
+fn resolve<K: Ord, R: Ord + Copy>(winners: &mut BTreeMap<K, Winner<R>>, key: K, write: Winner<R>) {
+    let best = winners.entry(key).or_insert(write);
+    if (write.order_tag, write.rank) > (best.order_tag, best.rank) {
+        *best = write;
+    }
+}
+
+/// Apply a round's winners, keyed by `(Section as u8, index)`, to a single
+/// chunk, returning the edges of the Internal bits that changed.
+fn apply_winners<R>(
+    winners: &BTreeMap<(u8, u32), Winner<R>>,
+    output: &mut [u32],
+    internal: &mut [u32],
+) -> Vec<Event> {
+    let mut next = Vec::new();
+    for (&(section, index), w) in winners {
+        if section == Section::Output as u8 {
+            set_bit_action(output, index, w.action);
+            continue;
+        }
+        let before = get_bit(internal, index);
+        set_bit_action(internal, index, w.action);
+        let after = get_bit(internal, index);
+        if before != after {
+            push_edge(&mut next, 0, Section::Internal, index, after);
+        }
+    }
+    next
+}
+
+const MAX_EFFECTS: usize = 5_000_000;
+
 /// Execute the given chunk on the CPU until quiescence.
 /// Returns final Input, Output, Internal bit vectors (as bytes).
+///
+/// Runs the wavefront of the spec straight from the connection list: each
+/// round expands every pending edge against the state the round began with,
+/// every target bit takes the proposal with the highest `order_tag`, ties
+/// going to the connection [`build_csr`] stores later (On before Off before
+/// Toggle triggers, then by source bit, then by connection index), and the
+/// winners are applied together. [`execute_system`] runs the same rounds
+/// from the CSR.
 pub fn execute(chunk: &MycosChunk) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    execute_gated(chunk, (Section::Internal, 0), 0..0)
+}
+
+/// [`execute`] where the connections at indices in `gated` only fire while
+/// the `gate` bit is set, checked per round like the gated expansion kernels.
+pub(crate) fn execute_gated(
+    chunk: &MycosChunk,
+    gate: (Section, u32),
+    gated: Range<usize>,
+) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
     let input = bytes_to_words(&chunk.input_bits, chunk.input_count);
     let mut output = bytes_to_words(&chunk.output_bits, chunk.output_count);
     let mut internal = bytes_to_words(&chunk.internal_bits, chunk.internal_count);
 
-    let mut q = VecDeque::new();
-    // seed queue with initial set bits (On + Toggle)
-    for i in 0..chunk.input_count {
-        if get_bit(&input, i) {
-            q.push_back(Event {
-                section: Section::Input,
-                index: i,
-                edge: Edge::On,
-            });
-            q.push_back(Event {
-                section: Section::Input,
-                index: i,
-                edge: Edge::Toggle,
-            });
+    let mut frontier = Vec::new();
+    seed_events(&mut frontier, 0, chunk, &input, &internal);
+    let mut effects_applied = 0usize;
+
+    while !frontier.is_empty() && effects_applied < MAX_EFFECTS {
+        let gate_open = gated.is_empty()
+            || match gate.0 {
+                Section::Input => get_bit(&input, gate.1),
+                Section::Internal => get_bit(&internal, gate.1),
+                Section::Output => get_bit(&output, gate.1),
+            };
+        let mut winners = BTreeMap::new();
+        for ev in &frontier {
+            let src = match ev.section {
+                Section::Input => ev.index,
+                Section::Internal => chunk.input_count + ev.index,
+                Section::Output => continue,
+            };
+            for (i, conn) in chunk.connections.iter().enumerate() {
+                if conn.from_section != ev.section || conn.from_index != ev.index {
+                    continue;
+                }
+                if !gate_open && gated.contains(&i) {
+                    continue;
+                }
+                let trigger_match = matches!(
+                    (ev.edge, conn.trigger),
+                    (Edge::On, Trigger::On)
+                        | (Edge::Off, Trigger::Off)
+                        | (Edge::Toggle, Trigger::Toggle)
+                );
+                if !trigger_match || conn.to_section == Section::Input {
+                    continue;
+                }
+                let write = Winner {
+                    action: conn.action,
+                    order_tag: conn.order_tag,
+                    rank: (conn.trigger as u8, src, i),
+                };
+                resolve(&mut winners, (conn.to_section as u8, conn.to_index), write);
+            }
         }
+        effects_applied += winners.len();
+        frontier = apply_winners(&winners, &mut output, &mut internal);
     }
-    for i in 0..chunk.internal_count {
-        if get_bit(&internal, i) {
-            q.push_back(Event {
-                section: Section::Internal,
-                index: i,
-                edge: Edge::On,
-            });
-            q.push_back(Event {
-                section: Section::Internal,
-                index: i,
-                edge: Edge::Toggle,
-            });
+
+    (
+        words_to_bytes(&input, chunk.input_count),
+        words_to_bytes(&output, chunk.output_count),
+        words_to_bytes(&internal, chunk.internal_count),
+    )
+}
+
+/// Resolve the proposals for `ev` from a chunk's CSR slice.
+fn csr_proposals(chunk: &MycosChunk, csr: &CSR, ev: &Event) -> Vec<Proposal> {
+    let src = match ev.section {
+        Section::Input => ev.index,
+        Section::Internal => chunk.input_count + ev.index,
+        Section::Output => return Vec::new(),
+    } as usize;
+    let slice = csr_slice(csr, src, ev.edge);
+    let mut proposals = Vec::with_capacity(slice.len());
+    for eff in slice {
+        let section = if eff.to_is_internal {
+            Section::Internal
+        } else {
+            Section::Output
+        };
+        propose(
+            &mut proposals,
+            (section, eff.to_bit),
+            eff.action,
+            eff.order_tag,
+        );
+    }
+    proposals
+}
+
+fn csr_slice(csr: &CSR, src: usize, edge: Edge) -> &[Effect] {
+    &csr.effects[csr_range(csr, src, edge)]
+}
+
+/// Indices into `csr.effects` of the effects `edge` of source `src` fires.
+fn csr_range(csr: &CSR, src: usize, edge: Edge) -> Range<usize> {
+    let offs = match edge {
+        Edge::On => &csr.offs_on,
+        Edge::Off => &csr.offs_off,
+        Edge::Toggle => &csr.offs_tog,
+    };
+    offs[src] as usize..offs[src + 1] as usize
+}
+
+/// Execute the given chunk using a prebuilt [`CSR`] from `build_csr`.
+///
+/// Semantics match [`execute`]; effect lookup per edge is a CSR slice
+/// instead of a scan over every connection, and ties go to the higher CSR
+/// index.
+pub fn execute_csr(chunk: &MycosChunk, csr: &CSR) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    let input = bytes_to_words(&chunk.input_bits, chunk.input_count);
+    let mut output = bytes_to_words(&chunk.output_bits, chunk.output_count);
+    let mut internal = bytes_to_words(&chunk.internal_bits, chunk.internal_count);
+
+    let mut frontier = Vec::new();
+    seed_events(&mut frontier, 0, chunk, &input, &internal);
+    let mut effects_applied = 0usize;
+
+    while !frontier.is_empty() && effects_applied < MAX_EFFECTS {
+        let mut winners = BTreeMap::new();
+        for ev in &frontier {
+            let src = match ev.section {
+                Section::Input => ev.index,
+                Section::Internal => chunk.input_count + ev.index,
+                Section::Output => continue,
+            };
+            for rank in csr_range(csr, src as usize, ev.edge) {
+                let eff = &csr.effects[rank];
+                let section = if eff.to_is_internal {
+                    Section::Internal
+                } else {
+                    Section::Output
+                };
+                let write = Winner {
+                    action: eff.action,
+                    order_tag: eff.order_tag,
+                    rank,
+                };
+                resolve(&mut winners, (section as u8, eff.to_bit), write);
+            }
         }
+        effects_applied += winners.len();
+        frontier = apply_winners(&winners, &mut output, &mut internal);
     }
 
-    const MAX_EFFECTS: usize = 5_000_000;
+    (
+        words_to_bytes(&input, chunk.input_count),
+        words_to_bytes(&output, chunk.output_count),
+        words_to_bytes(&internal, chunk.internal_count),
+    )
+}
+
+/// Execute the given chunk on the CPU until quiescence, one topological
+/// level at a time.
+///
+/// Events are grouped by the level of their source bit: Inputs first, then
+/// Internal bits by the level of their SCC from [`scc_ids_and_topo_levels`].
+/// The lowest pending level runs as one batch, like a GPU wavefront: the
+/// proposals of all its events are resolved per target (highest `order_tag`
+/// wins) and applied word by word as set, clear and toggle masks. Changed
+/// Internal bits enqueue their edges at their own level, which is never
+/// below the batch's, so a level only runs once the levels feeding it are
+/// quiet.
+///
+/// Results match [`execute`] when no two events of a batch write the same
+/// bit; otherwise only the resolved write of each target takes effect.
+pub fn execute_levels(chunk: &MycosChunk) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    let csr = build_csr(chunk);
+    let (scc_ids, topo) = scc_ids_and_topo_levels(chunk);
+    let level = |ev: &Event| match ev.section {
+        Section::Internal => topo[scc_ids[ev.index as usize]] + 1,
+        _ => 0,
+    };
+    let input = bytes_to_words(&chunk.input_bits, chunk.input_count);
+    let mut output = bytes_to_words(&chunk.output_bits, chunk.output_count);
+    let mut internal = bytes_to_words(&chunk.internal_bits, chunk.internal_count);
+
+    let mut q = VecDeque::new();
+    seed_events(&mut q, 0, chunk, &input, &internal);
+    let mut pending: BTreeMap<usize, VecDeque<Event>> = BTreeMap::new();
+    for ev in q.drain(..) {
+        pending.entry(level(&ev)).or_default().push_back(ev);
+    }
     let mut effects_applied = 0usize;
 
-    while let Some(ev) = q.pop_front() {
+    while let Some((_, batch)) = pending.pop_first() {
         if effects_applied >= MAX_EFFECTS {
             break;
         }
-        // gather proposals
-        let mut proposals: Vec<((Section, u32), (Action, u32))> = Vec::new();
-        for conn in &chunk.connections {
-            if conn.from_section != ev.section || conn.from_index != ev.index {
-                continue;
+        let mut proposals = Vec::new();
+        for ev in &batch {
+            for (key, (action, tag)) in csr_proposals(chunk, &csr, ev) {
+                propose(&mut proposals, key, action, tag);
+            }
+        }
+        effects_applied += proposals.len();
+        let writes = |section: Section| {
+            proposals
+                .iter()
+                .filter(move |((s, _), _)| *s == section)
+                .map(|&((_, index), (action, _))| (index, action))
+        };
+        apply_masks(&mut output, writes(Section::Output));
+        let changed = apply_masks(&mut internal, writes(Section::Internal));
+        for (w, &mask) in changed.iter().enumerate() {
+            for b in 0..32 {
+                if mask & (1 << b) != 0 {
+                    let index = w as u32 * 32 + b;
+                    push_edge(
+                        &mut q,
+                        0,
+                        Section::Internal,
+                        index,
+                        get_bit(&internal, index),
+                    );
+                }
+            }
+        }
+        for ev in q.drain(..) {
+            pending.entry(level(&ev)).or_default().push_back(ev);
+        }
+    }
+
+    (
+        words_to_bytes(&input, chunk.input_count),
+        words_to_bytes(&output, chunk.output_count),
+        words_to_bytes(&internal, chunk.internal_count),
+    )
+}
+
+/// Apply at most one write per bit as whole-word set, clear and toggle
+/// masks, returning the mask of bits that changed in each word.
+fn apply_masks(words: &mut [u32], writes: impl Iterator<Item = (u32, Action)>) -> Vec<u32> {
+    let mut set = vec![0u32; words.len()];
+    let mut clear = vec![0u32; words.len()];
+    let mut toggle = vec![0u32; words.len()];
+    for (index, action) in writes {
+        let (w, m) = bit_to_word(index);
+        let masks = match action {
+            Action::Enable => &mut set,
+            Action::Disable => &mut clear,
+            Action::Toggle => &mut toggle,
+        };
+        masks[w as usize] |= m;
+    }
+    words
+        .iter_mut()
+        .enumerate()
+        .map(|(w, word)| {
+            let before = *word;
+            *word = ((before | set[w]) & !clear[w]) ^ toggle[w];
+            before ^ *word
+        })
+        .collect()
+}
+
+/// Per-chunk word buffers plus intra- and inter-chunk adjacency.
+///
+/// Shared by [`execute_system`] and the tick-driven `runner`. Like the
+/// device it keeps the words every section had when the last round began:
+/// the bits that differ from them are the edges the next round expands.
+pub(crate) struct System<'a> {
+    chunks: Cow<'a, [MycosChunk]>,
+    csrs: Vec<CSR>,
+    link_csr: CSR,
+    /// Links with a delay, expanded by scanning instead of through `link_csr`.
+    delayed_links: Vec<Link>,
+    /// Delayed link effects waiting for their tick.
+    in_flight: Vec<InFlight>,
+    bits: GlobalBitMap,
+    input: Vec<Vec<u32>>,
+    output: Vec<Vec<u32>>,
+    internal: Vec<Vec<u32>>,
+    /// Input, Internal and Output words as of the last round, indexed by
+    /// `Section as usize`.
+    prev: [Vec<Vec<u32>>; 3],
+}
+
+/// Work done by a single [`System::settle`] call.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SettleStats {
+    pub rounds: u32,
+    pub effects: u32,
+    /// A guard stopped the loop before the frontier emptied.
+    pub oscillator: bool,
+    /// Last cycle period seen by the detector, 0 if none.
+    pub period: u32,
+    /// A detected cycle was quenched with the chunks' policies.
+    pub quenched: bool,
+}
+
+impl<'a> System<'a> {
+    /// Load the initial state of every chunk without enqueueing any events.
+    pub(crate) fn new(chunks: &'a [MycosChunk], links: &[Link]) -> Self {
+        Self::load(Cow::Borrowed(chunks), links)
+    }
+
+    fn load(chunks: Cow<'a, [MycosChunk]>, links: &[Link]) -> Self {
+        let (delayed_links, immediate): (Vec<Link>, Vec<Link>) =
+            links.iter().cloned().partition(|l| l.delay > 0);
+        let input: Vec<Vec<u32>> = chunks
+            .iter()
+            .map(|c| bytes_to_words(&c.input_bits, c.input_count))
+            .collect();
+        let output: Vec<Vec<u32>> = chunks
+            .iter()
+            .map(|c| bytes_to_words(&c.output_bits, c.output_count))
+            .collect();
+        let internal: Vec<Vec<u32>> = chunks
+            .iter()
+            .map(|c| bytes_to_words(&c.internal_bits, c.internal_count))
+            .collect();
+        Self {
+            csrs: chunks.iter().map(build_csr).collect(),
+            link_csr: build_link_csr(&immediate, &chunks),
+            delayed_links,
+            in_flight: Vec::new(),
+            bits: GlobalBitMap::new(&chunks),
+            prev: [input.clone(), internal.clone(), output.clone()],
+            input,
+            output,
+            internal,
+            chunks,
+        }
+    }
+
+    /// Clear Prev of every Input and Internal bit, so the first round fires
+    /// the On and Toggle edges of those initially set.
+    fn seed(&mut self) {
+        for section in [Section::Input, Section::Internal] {
+            for words in &mut self.prev[section as usize] {
+                words.fill(0);
+            }
+        }
+    }
+
+    /// Drive an Input bit; a change fires its edges in the next round. Bits
+    /// outside the chunk set are ignored.
+    pub(crate) fn write_input(&mut self, chunk: u32, bit: u32, value: bool) {
+        self.store_input(chunk, bit, value, false);
+    }
+
+    /// Set an Input bit as part of the initial state without firing edges.
+    pub(crate) fn preset_input(&mut self, chunk: u32, bit: u32, value: bool) {
+        self.store_input(chunk, bit, value, true);
+    }
+
+    /// Store an Input bit in Curr, and in Prev too for a `preset`.
+    fn store_input(&mut self, chunk: u32, bit: u32, value: bool, preset: bool) {
+        let c = chunk as usize;
+        if self.chunks.get(c).is_none_or(|ch| bit >= ch.input_count) {
+            return;
+        }
+        let action = if value {
+            Action::Enable
+        } else {
+            Action::Disable
+        };
+        set_bit_action(&mut self.input[c], bit, action);
+        if preset {
+            set_bit_action(&mut self.prev[Section::Input as usize][c], bit, action);
+        }
+    }
+
+    /// Read an Output bit; bits outside the chunk set read as `false`.
+    pub(crate) fn read_output(&self, chunk: u32, bit: u32) -> bool {
+        match self.chunks.get(chunk as usize) {
+            Some(c) if bit < c.output_count => get_bit(&self.output[chunk as usize], bit),
+            _ => false,
+        }
+    }
+
+    /// Read an Internal bit; bits outside the chunk set read as `false`.
+    pub(crate) fn read_internal(&self, chunk: u32, bit: u32) -> bool {
+        match self.chunks.get(chunk as usize) {
+            Some(c) if bit < c.internal_count => get_bit(&self.internal[chunk as usize], bit),
+            _ => false,
+        }
+    }
+
+    /// Current words of `section`, one vector per chunk.
+    fn curr(&self, section: Section) -> &[Vec<u32>] {
+        match section {
+            Section::Input => &self.input,
+            Section::Internal => &self.internal,
+            Section::Output => &self.output,
+        }
+    }
+
+    /// Words of `section` over every chunk, laid out by the system's
+    /// [`GlobalBitMap`] like the section's device buffer.
+    pub(crate) fn global_words(&self, section: Section) -> Vec<u32> {
+        let mut out = vec![0u32; self.bits.total(section).div_ceil(32) as usize];
+        for (c, words) in self.curr(section).iter().enumerate() {
+            let extent = self.bits.extent(c as u32, section).unwrap();
+            for (w, &word) in words.iter().enumerate() {
+                let first = w as u32 * 32;
+                let len = extent.len() as u32 - first;
+                let word = if len < 32 {
+                    word & ((1 << len) - 1)
+                } else {
+                    word
+                };
+                let (at, shift) = ((extent.start + first) / 32, (extent.start + first) % 32);
+                out[at as usize] |= word << shift;
+                if shift > 0 && word >> (32 - shift) != 0 {
+                    out[at as usize + 1] |= word >> (32 - shift);
+                }
             }
-            let trigger_match = matches!(
-                (ev.edge, conn.trigger),
+        }
+        out
+    }
+
+    /// Level and Toggle edges of every bit that differs from Prev, Inputs
+    /// first, then Internals, then Outputs: the work of the next round.
+    fn frontier(&self) -> Vec<Event> {
+        let mut events = Vec::new();
+        for section in [Section::Input, Section::Internal, Section::Output] {
+            let prev = &self.prev[section as usize];
+            for (c, (curr, prev)) in self.curr(section).iter().zip(prev).enumerate() {
+                for (w, (&cur, &old)) in curr.iter().zip(prev).enumerate() {
+                    let mut flips = cur ^ old;
+                    while flips != 0 {
+                        let b = flips.trailing_zeros();
+                        flips &= flips - 1;
+                        let index = w as u32 * 32 + b;
+                        push_edge(&mut events, c as u32, section, index, cur >> b & 1 == 1);
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    /// Catch Prev up with Curr, dropping any edges not yet expanded.
+    fn catch_up(&mut self) {
+        self.prev[Section::Input as usize].clone_from(&self.input);
+        self.prev[Section::Internal as usize].clone_from(&self.internal);
+        self.prev[Section::Output as usize].clone_from(&self.output);
+    }
+
+    /// State before the latest round: the Internal words it replaced
+    /// (`internal`, concatenated) and the current Output words.
+    fn snapshot_before(&self, internal: &[u32]) -> Snapshot {
+        let mut rest = internal;
+        let internal = self
+            .internal
+            .iter()
+            .map(|words| {
+                let (head, tail) = rest.split_at(words.len());
+                rest = tail;
+                head.to_vec()
+            })
+            .collect();
+        Snapshot {
+            internal,
+            output: self.output.clone(),
+        }
+    }
+
+    /// Apply each chunk's policy from `policies` to a cycle of `period`
+    /// rounds whose states precede the current one in `history`, returning
+    /// the effects applied.
+    fn quench(
+        &mut self,
+        policies: &PolicyConfig,
+        period: u32,
+        history: &VecDeque<Snapshot>,
+    ) -> usize {
+        let cycle_start = history.len().saturating_sub(period as usize);
+        let pending = self.frontier();
+        let mut applied = 0;
+        for c in 0..self.chunks.len() {
+            match policies.for_chunk(c as u32) {
+                Policy::FreezeLastStable => {
+                    // the last state before the cycle, or the oldest one kept
+                    let stable = &history[cycle_start.saturating_sub(1)];
+                    freeze_last_stable(&mut self.internal[c], &stable.internal[c]);
+                    freeze_last_stable(&mut self.output[c], &stable.output[c]);
+                }
+                Policy::ClampCommutative => {
+                    let mut targets: Vec<((Section, u32), Vec<Action>)> = Vec::new();
+                    for ev in pending.iter().filter(|ev| ev.chunk as usize == c) {
+                        for (key, (action, _)) in csr_proposals(&self.chunks[c], &self.csrs[c], ev)
+                        {
+                            match targets.iter_mut().find(|(k, _)| *k == key) {
+                                Some((_, actions)) => actions.push(action),
+                                None => targets.push((key, vec![action])),
+                            }
+                        }
+                    }
+                    for ((section, bit), actions) in targets {
+                        let Some(action) = clamp_commutative(&actions) else {
+                            continue;
+                        };
+                        let words = match section {
+                            Section::Internal => &mut self.internal[c],
+                            _ => &mut self.output[c],
+                        };
+                        set_bit_action(words, bit, action);
+                        applied += 1;
+                    }
+                }
+                Policy::ParityQuench => {
+                    let flipped = self.flipped_in_cycle(c, history, cycle_start);
+                    let words = &mut self.internal[c];
+                    let mut toggled = words.clone();
+                    parity_quench(&mut toggled, period);
+                    for ((word, toggled), m) in words.iter_mut().zip(toggled).zip(flipped) {
+                        *word = (*word & !m) | (toggled & m);
+                    }
+                }
+                Policy::StochasticQuench { fraction, seed } => {
+                    let flipped = self.flipped_in_cycle(c, history, cycle_start);
+                    let stable = &history[cycle_start.saturating_sub(1)];
+                    let mut rng = ChaCha8Rng::seed_from_u64(seed.wrapping_add(c as u64));
+                    stochastic_quench(
+                        &mut self.internal[c],
+                        &stable.internal[c],
+                        &flipped,
+                        fraction,
+                        &mut rng,
+                    );
+                }
+            }
+        }
+        applied
+    }
+
+    /// Internal bits of chunk `c` that changed at some point in the cycle
+    /// starting at `history[cycle_start]` and closing at the current state.
+    fn flipped_in_cycle(
+        &self,
+        c: usize,
+        history: &VecDeque<Snapshot>,
+        cycle_start: usize,
+    ) -> Vec<u32> {
+        let states: Vec<&[u32]> = history
+            .range(cycle_start..)
+            .map(|s| &s.internal[c][..])
+            .chain(std::iter::once(&self.internal[c][..]))
+            .collect();
+        let mut flipped = vec![0u32; self.internal[c].len()];
+        for pair in states.windows(2) {
+            for (w, m) in flipped.iter_mut().enumerate() {
+                *m |= pair[0][w] ^ pair[1][w];
+            }
+        }
+        flipped
+    }
+
+    /// Run wavefront rounds until no bit differs from Prev or a guard trips,
+    /// then catch Prev up with Curr.
+    ///
+    /// A round expands every pending edge at once (see [`round`](Self::round)).
+    /// The Internal state, in [`global_words`](Self::global_words) layout, is
+    /// hashed after every round that changes it; when a guard stops the loop
+    /// the last detected period is reported.
+    pub(crate) fn settle(&mut self, max_rounds: u32) -> SettleStats {
+        self.settle_with(max_rounds, None)
+    }
+
+    /// Like [`settle`](Self::settle), but when `policies` is set the first
+    /// detected cycle is quenched, each chunk with its own policy, and
+    /// processing stops there.
+    pub(crate) fn settle_with(
+        &mut self,
+        max_rounds: u32,
+        policies: Option<&PolicyConfig>,
+    ) -> SettleStats {
+        let mut stats = SettleStats::default();
+        let mut effects_applied = 0usize;
+        let config = policies.map_or_else(DetectorConfig::default, |p| p.detector);
+        let mut detector = CycleDetector::with_config(config);
+        let mut last_state = self.internal.concat();
+        // Internal and Output words before each state change, newest last.
+        let mut history: VecDeque<Snapshot> = VecDeque::new();
+        let mut frontier = self.frontier();
+        while !frontier.is_empty() && stats.rounds < max_rounds && effects_applied < MAX_EFFECTS {
+            stats.rounds += 1;
+            effects_applied += self.round(&frontier);
+            let state = self.internal.concat();
+            if state != last_state {
+                if history.len() > config.window {
+                    history.pop_front();
+                }
+                history.push_back(self.snapshot_before(&last_state));
+                if let Some(p) = detector.observe(&self.global_words(Section::Internal)) {
+                    stats.period = if p == 0 { config.window as u32 } else { p };
+                    if let Some(policies) = policies {
+                        effects_applied += self.quench(policies, stats.period, &history);
+                        stats.quenched = true;
+                        break;
+                    }
+                }
+                last_state = state;
+            }
+            frontier = self.frontier();
+        }
+        stats.oscillator = !frontier.is_empty() || stats.quenched;
+        if !stats.oscillator {
+            stats.period = 0;
+        }
+        self.catch_up();
+        stats.effects = effects_applied as u32;
+        stats
+    }
+
+    /// Expand every edge of `frontier` at once, returning the effects
+    /// applied.
+    ///
+    /// All edges see the state the round began with. Each proposes the CSR
+    /// effects of its trigger, Output edges through the links; every target
+    /// bit takes the proposal with the highest `order_tag`, ties going to the
+    /// effect stored later in its CSR, and the winners are applied together.
+    /// Output edges also put their delayed links in flight.
+    fn round(&mut self, frontier: &[Event]) -> usize {
+        self.catch_up();
+        for ev in frontier.iter().filter(|ev| ev.section == Section::Output) {
+            self.fire_delayed(ev);
+        }
+        // keyed by (section, chunk, bit)
+        let mut winners: BTreeMap<(u8, u32, u32), Winner> = BTreeMap::new();
+        for ev in frontier {
+            let c = ev.chunk as usize;
+            let (csr, src) = match ev.section {
+                Section::Input => (&self.csrs[c], ev.index),
+                Section::Internal => (&self.csrs[c], self.chunks[c].input_count + ev.index),
+                Section::Output => (&self.link_csr, self.bits.offsets()[c].output + ev.index),
+            };
+            for rank in csr_range(csr, src as usize, ev.edge) {
+                let eff = &csr.effects[rank];
+                let key = match ev.section {
+                    Section::Output => {
+                        let (t, local) = self.bits.locate(Section::Input, eff.to_bit).unwrap();
+                        (Section::Input as u8, t, local)
+                    }
+                    _ if eff.to_is_internal => (Section::Internal as u8, ev.chunk, eff.to_bit),
+                    _ => (Section::Output as u8, ev.chunk, eff.to_bit),
+                };
+                let write = Winner {
+                    action: eff.action,
+                    order_tag: eff.order_tag,
+                    rank,
+                };
+                resolve(&mut winners, key, write);
+            }
+        }
+        for (&(section, c, bit), w) in &winners {
+            let words = match Section::try_from(section).expect("section code") {
+                Section::Input => &mut self.input[c as usize],
+                Section::Internal => &mut self.internal[c as usize],
+                Section::Output => &mut self.output[c as usize],
+            };
+            set_bit_action(words, bit, w.action);
+        }
+        winners.len()
+    }
+
+    /// Put the effects of the delayed links fired by Output edge `ev` in
+    /// flight. A link carries at most one effect per tick, like the bit per
+    /// tick of its device delay line, so firing again within the tick adds
+    /// nothing.
+    fn fire_delayed(&mut self, ev: &Event) {
+        for (index, link) in self.delayed_links.iter().enumerate() {
+            let fires = matches!(
+                (ev.edge, link.trigger),
                 (Edge::On, Trigger::On)
                     | (Edge::Off, Trigger::Off)
                     | (Edge::Toggle, Trigger::Toggle)
             );
-            if !trigger_match {
+            if !fires || link.from_chunk != ev.chunk || link.from_out_idx != ev.index {
                 continue;
             }
-            let key = (conn.to_section, conn.to_index);
-            if let Some((_, (act, tag))) = proposals.iter_mut().find(|(k, _)| *k == key) {
-                if conn.order_tag >= *tag {
-                    *act = conn.action;
-                    *tag = conn.order_tag;
-                }
-            } else {
-                proposals.push((key, (conn.action, conn.order_tag)));
+            let queued = self
+                .in_flight
+                .iter()
+                .any(|e| e.link == index && e.ticks_left == link.delay);
+            if !queued {
+                self.in_flight.push(InFlight {
+                    ticks_left: link.delay,
+                    link: index,
+                    to_bit: self.bits.offsets()[link.to_chunk as usize].input + link.to_in_idx,
+                    action: link.action,
+                    order_tag: link.order_tag,
+                });
             }
         }
+    }
 
-        for ((to_section, to_index), (action, _tag)) in proposals {
-            let words = match to_section {
-                Section::Internal => &mut internal,
-                Section::Output => &mut output,
-                Section::Input => continue, // invalid target
-            };
-            let before = get_bit(words, to_index);
-            set_bit_action(words, to_index, action);
-            let after = get_bit(words, to_index);
-            effects_applied += 1;
-            if before != after && matches!(to_section, Section::Internal) {
-                let edge = if after { Edge::On } else { Edge::Off };
-                q.push_back(Event {
-                    section: Section::Internal,
-                    index: to_index,
-                    edge,
-                });
-                q.push_back(Event {
-                    section: Section::Internal,
-                    index: to_index,
-                    edge: Edge::Toggle,
-                });
+    /// Move delayed link effects one tick on and write those that arrive to
+    /// their Input bits, the highest `order_tag` winning per bit and later
+    /// links breaking ties. Their edges fire in the next round. Call at the
+    /// start of every tick after the first.
+    pub(crate) fn advance_tick(&mut self) {
+        let mut winners: BTreeMap<u32, Winner> = BTreeMap::new();
+        self.in_flight.retain_mut(|e| {
+            e.ticks_left -= 1;
+            if e.ticks_left > 0 {
+                return true;
             }
+            let write = Winner {
+                action: e.action,
+                order_tag: e.order_tag,
+                rank: e.link,
+            };
+            resolve(&mut winners, e.to_bit, write);
+            false
+        });
+        for (bit, w) in winners {
+            let (t, local) = self.bits.locate(Section::Input, bit).unwrap();
+            set_bit_action(&mut self.input[t as usize], local, w.action);
         }
     }
 
-    (
-        words_to_bytes(&input, chunk.input_count),
-        words_to_bytes(&output, chunk.output_count),
-        words_to_bytes(&internal, chunk.internal_count),
-    )
+    fn into_bytes(self) -> Vec<(Vec<u8>, Vec<u8>, Vec<u8>)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                (
+                    words_to_bytes(&self.input[i], c.input_count),
+                    words_to_bytes(&self.output[i], c.output_count),
+                    words_to_bytes(&self.internal[i], c.internal_count),
+                )
+            })
+            .collect()
+    }
+}
+
+/// A delayed link effect on the global Input bit `to_bit`.
+struct InFlight {
+    ticks_left: u16,
+    /// Index of the link in `System::delayed_links`.
+    link: usize,
+    to_bit: u32,
+    action: Action,
+    order_tag: u32,
+}
+
+/// Internal and Output words of every chunk at one point of a settle.
+struct Snapshot {
+    internal: Vec<Vec<u32>>,
+    output: Vec<Vec<u32>>,
+}
+/// Execute a chunk like [`execute`] under the oscillation guards: at most
+/// `max_rounds` wavefront rounds, and the first cycle the [`CycleDetector`]
+/// finds in the Internal state is quenched with `policy`, which ends the
+/// run. `policy` in the result is `None` when no cycle was quenched.
+pub fn execute_with_policy(chunk: &MycosChunk, policy: Policy, max_rounds: u32) -> ExecutionResult {
+    let chunks = std::slice::from_ref(chunk);
+    let mut results = execute_system_with_policy(chunks, &[], &policy.into(), max_rounds);
+    results.swap_remove(0)
+}
+
+/// [`execute_with_policy`] for linked chunks, with the policy of each chunk
+/// taken from `policies`. Cycles are detected on the Internal state of the
+/// whole system; a detected cycle is quenched in every chunk with that
+/// chunk's policy. Returns one result per chunk, sharing rounds, effects and
+/// period.
+pub fn execute_system_with_policy(
+    chunks: &[MycosChunk],
+    links: &[Link],
+    policies: &PolicyConfig,
+    max_rounds: u32,
+) -> Vec<ExecutionResult> {
+    let mut system = System::new(chunks, links);
+    system.seed();
+    let stats = system.settle_with(max_rounds, Some(policies));
+    system
+        .internal
+        .into_iter()
+        .zip(system.output)
+        .enumerate()
+        .map(|(c, (internals, outputs))| ExecutionResult {
+            rounds: stats.rounds,
+            effects_applied: stats.effects as u64,
+            oscillator: stats.oscillator,
+            period: stats.period,
+            policy: stats.quenched.then(|| policies.for_chunk(c as u32)),
+            internals,
+            outputs,
+        })
+        .collect()
+}
+
+/// Execute several chunks joined by inter-chunk links until quiescence.
+///
+/// Output bit changes propagate over `links` to the Inputs of other chunks,
+/// which then fire like any other Input edge. Links must already pass
+/// `validate_links`. Returns final Input, Output, Internal bytes per chunk.
+///
+/// Execution follows the device wavefront: each round expands every pending
+/// edge against the same state and resolves all of its proposals per target
+/// bit, the highest `order_tag` winning and ties going to the effect stored
+/// later in the CSR. Without two writes to one bit in a round this matches
+/// [`execute`] on each chunk alone. This is a single tick, so links with a
+/// `delay` never deliver here; the tick-driven `EpisodeRunner` honours them.
+pub fn execute_system(chunks: &[MycosChunk], links: &[Link]) -> Vec<(Vec<u8>, Vec<u8>, Vec<u8>)> {
+    let mut system = System::new(chunks, links);
+    system.seed();
+    system.settle(u32::MAX);
+    system.into_bytes()
+}
+
+/// Linked chunks stepped tick by tick from the host, like the
+/// `EpisodeRunner` but driven through raw Input words; the engine behind
+/// the wasm handle when WebGPU is unavailable.
+///
+/// Inputs written between ticks fire their edges on the next
+/// [`tick`](Self::tick). After settling, a tick moves delayed link effects
+/// on and lands those due next tick on their Inputs, as the device does when
+/// it finalizes a tick.
+pub struct CpuEngine {
+    system: System<'static>,
+}
+
+impl CpuEngine {
+    /// Load `chunks` in their initial state, without firing them, joined by
+    /// `links`.
+    pub fn new(chunks: Vec<MycosChunk>, links: &[Link]) -> Result<Self, LinkError> {
+        validate_links(links, &chunks)?;
+        Ok(Self {
+            system: System::load(Cow::Owned(chunks), links),
+        })
+    }
+
+    /// Number of chunks loaded.
+    pub fn chunks(&self) -> u32 {
+        self.system.chunks.len() as u32
+    }
+
+    /// Drive the Inputs of `chunk` from `words`, LSB first. Bits past the
+    /// chunk's Inputs, and chunks past the last, are ignored.
+    pub fn set_inputs(&mut self, chunk: u32, words: &[u32]) {
+        let Some(c) = self.system.chunks.get(chunk as usize) else {
+            return;
+        };
+        for bit in 0..c.input_count.min(words.len() as u32 * 32) {
+            let high = words[(bit / 32) as usize] >> (bit % 32) & 1 == 1;
+            self.system.write_input(chunk, bit, high);
+        }
+    }
+
+    /// Settle the pending edges under a cap of `max_rounds` rounds.
+    pub fn tick(&mut self, max_rounds: u32) -> TickStats {
+        let stats = self.system.settle(max_rounds);
+        self.system.advance_tick();
+        TickStats {
+            rounds: stats.rounds,
+            effects: stats.effects,
+            oscillator: stats.oscillator,
+            period: stats.period,
+        }
+    }
+
+    /// Output words of `chunk`; `None` past the last chunk.
+    pub fn outputs(&self, chunk: u32) -> Option<&[u32]> {
+        self.system.output.get(chunk as usize).map(Vec::as_slice)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::chunk::parse_chunk;
+    use crate::chunk::{parse_chunk, Connection};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
     use std::fs;
     use std::path::PathBuf;
 
@@ -184,4 +1069,388 @@ mod tests {
         assert_eq!(n[0], 1);
         assert_eq!(o[0], 1);
     }
+
+    fn random_chunk(rng: &mut ChaCha8Rng) -> MycosChunk {
+        let input_count = rng.gen_range(1..8u32);
+        let output_count = rng.gen_range(1..8u32);
+        let internal_count = rng.gen_range(1..40u32);
+        let bytes = |rng: &mut ChaCha8Rng, n: u32| -> Vec<u8> {
+            let mut v: Vec<u8> = (0..n.div_ceil(8)).map(|_| rng.gen()).collect();
+            if !n.is_multiple_of(8) {
+                *v.last_mut().unwrap() &= (1u8 << (n % 8)) - 1;
+            }
+            v
+        };
+        let connections = (0..rng.gen_range(0..120))
+            .map(|_| {
+                let from_input = rng.gen_bool(0.3);
+                let (from_section, from_index) = if from_input {
+                    (Section::Input, rng.gen_range(0..input_count))
+                } else {
+                    (Section::Internal, rng.gen_range(0..internal_count))
+                };
+                // Internal edges only point forward so execution always settles.
+                let lo = if from_input { 0 } else { from_index + 1 };
+                let (to_section, to_index) = if lo < internal_count && rng.gen_bool(0.7) {
+                    (Section::Internal, rng.gen_range(lo..internal_count))
+                } else {
+                    (Section::Output, rng.gen_range(0..output_count))
+                };
+                Connection {
+                    from_section,
+                    to_section,
+                    trigger: Trigger::try_from(rng.gen_range(0..3u8)).unwrap(),
+                    action: Action::try_from(rng.gen_range(0..3u8)).unwrap(),
+                    from_index,
+                    to_index,
+                    order_tag: rng.gen_range(0..4),
+                }
+            })
+            .collect();
+        MycosChunk {
+            input_bits: bytes(rng, input_count),
+            output_bits: bytes(rng, output_count),
+            internal_bits: bytes(rng, internal_count),
+            input_count,
+            output_count,
+            internal_count,
+            connections,
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn csr_matches_reference() {
+        for entry in fs::read_dir(fixtures()).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|s| s.to_str()) != Some("myc") {
+                continue;
+            }
+            let mut chunk = parse_chunk(&fs::read(&path).unwrap()).unwrap();
+            // drive every input high
+            let all = vec![0xFF; chunk.input_bits.len()];
+            chunk.input_bits =
+                words_to_bytes(&bytes_to_words(&all, chunk.input_count), chunk.input_count);
+            let csr = build_csr(&chunk);
+            assert_eq!(execute_csr(&chunk, &csr), execute(&chunk), "{path:?}");
+        }
+
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        for _ in 0..200 {
+            let chunk = random_chunk(&mut rng);
+            let csr = build_csr(&chunk);
+            assert_eq!(execute_csr(&chunk, &csr), execute(&chunk));
+        }
+    }
+
+    #[test]
+    fn rounds_resolve_ties_by_csr_rank() {
+        let conn = |from_section, from_index, to_section, to_index, action| Connection {
+            from_section,
+            to_section,
+            trigger: Trigger::On,
+            action,
+            from_index,
+            to_index,
+            order_tag: 0,
+        };
+        // Input 0 raises both internal bits in one round; in the next their
+        // equal-tag writes to output 0 meet, and internal 1's Enable, stored
+        // later in the CSR, wins whatever the connection order.
+        let chunk = MycosChunk {
+            input_bits: vec![1],
+            output_bits: vec![0],
+            internal_bits: vec![0],
+            input_count: 1,
+            output_count: 1,
+            internal_count: 2,
+            connections: vec![
+                conn(Section::Input, 0, Section::Internal, 1, Action::Enable),
+                conn(Section::Input, 0, Section::Internal, 0, Action::Enable),
+                conn(Section::Internal, 1, Section::Output, 0, Action::Enable),
+                conn(Section::Internal, 0, Section::Output, 0, Action::Disable),
+            ],
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        };
+        let (_, out, internal) = execute(&chunk);
+        assert_eq!((out[0], internal[0]), (1, 0b11));
+        assert_eq!(execute_csr(&chunk, &build_csr(&chunk)), execute(&chunk));
+        let system = execute_system(std::slice::from_ref(&chunk), &[]);
+        assert_eq!(system[0], execute(&chunk));
+    }
+
+    #[test]
+    fn level_batches_resolve_like_a_wavefront() {
+        for name in ["tiny_toggle.myc", "fanout_1_to_1024.myc", "noop.myc"] {
+            let mut chunk = parse_chunk(&fs::read(fixtures().join(name)).unwrap()).unwrap();
+            let all = vec![0xFF; chunk.input_bits.len()];
+            chunk.input_bits =
+                words_to_bytes(&bytes_to_words(&all, chunk.input_count), chunk.input_count);
+            assert_eq!(execute_levels(&chunk), execute(&chunk), "{name}");
+        }
+
+        // both inputs write internal 0 in the first round and only the
+        // higher tag, a Disable, lands
+        let conn = |from_section, from_index, action, to_index, order_tag| Connection {
+            from_section,
+            to_section: Section::Internal,
+            trigger: Trigger::On,
+            action,
+            from_index,
+            to_index,
+            order_tag,
+        };
+        let chunk = MycosChunk {
+            input_bits: vec![0b11],
+            output_bits: vec![],
+            internal_bits: vec![0],
+            input_count: 2,
+            output_count: 0,
+            internal_count: 2,
+            connections: vec![
+                conn(Section::Input, 0, Action::Enable, 0, 0),
+                conn(Section::Input, 1, Action::Disable, 0, 1),
+                conn(Section::Internal, 0, Action::Enable, 1, 0),
+            ],
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        };
+        assert_eq!(execute(&chunk).2, vec![0b00]);
+        assert_eq!(execute_levels(&chunk).2, vec![0b00]);
+        let system = execute_system(std::slice::from_ref(&chunk), &[]);
+        assert_eq!(system[0], execute(&chunk));
+
+        // equal tags: the effect stored later in the CSR wins, here the
+        // Toggle-triggered Disable over the On-triggered Enable
+        let mut tied = chunk.clone();
+        tied.connections[1].order_tag = 0;
+        tied.connections[1].trigger = Trigger::Toggle;
+        assert_eq!(execute(&tied).2, vec![0b00]);
+        let system = execute_system(std::slice::from_ref(&tied), &[]);
+        assert_eq!(system[0], execute(&tied));
+        // within a trigger the later source wins, whatever the table order:
+        // input 1 toggles internal 0 on, input 0's Disable loses
+        tied.connections[1].action = Action::Toggle;
+        tied.connections[0].trigger = Trigger::Toggle;
+        tied.connections[0].action = Action::Disable;
+        tied.connections.swap(0, 1);
+        assert_eq!(execute(&tied).2, vec![0b11]);
+        let system = execute_system(std::slice::from_ref(&tied), &[]);
+        assert_eq!(system[0], execute(&tied));
+    }
+
+    #[test]
+    fn system_without_links_matches_reference() {
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        let chunks: Vec<MycosChunk> = (0..20).map(|_| random_chunk(&mut rng)).collect();
+        let expected: Vec<_> = chunks.iter().map(execute).collect();
+        assert_eq!(execute_system(&chunks, &[]), expected);
+    }
+
+    #[test]
+    fn system_relays_across_link() {
+        let data = fs::read(fixtures().join("tiny_toggle.myc")).unwrap();
+        let mut first = parse_chunk(&data).unwrap();
+        first.input_bits[0] = 1;
+        let second = parse_chunk(&data).unwrap();
+        let links = [Link {
+            from_chunk: 0,
+            from_out_idx: 0,
+            trigger: Trigger::On,
+            action: Action::Enable,
+            to_chunk: 1,
+            to_in_idx: 0,
+            order_tag: 0,
+            delay: 0,
+        }];
+        let chunks = [first, second];
+        crate::link::validate_links(&links, &chunks).unwrap();
+        let result = execute_system(&chunks, &links);
+        assert_eq!(result[0].1[0], 1);
+        assert_eq!(result[1].0[0], 1);
+        assert_eq!(result[1].1[0], 1);
+
+        // without the link the second chunk stays idle
+        assert_eq!(execute_system(&chunks, &[])[1].1[0], 0);
+    }
+
+    #[test]
+    fn delayed_link_lands_ticks_later() {
+        let data = fs::read(fixtures().join("tiny_toggle.myc")).unwrap();
+        let chunk = parse_chunk(&data).unwrap();
+        let chunks = [chunk.clone(), chunk];
+        let links = [Link {
+            from_chunk: 0,
+            from_out_idx: 0,
+            trigger: Trigger::On,
+            action: Action::Enable,
+            to_chunk: 1,
+            to_in_idx: 0,
+            order_tag: 0,
+            delay: 2,
+        }];
+        let mut system = System::new(&chunks, &links);
+        system.write_input(0, 0, true);
+        system.settle(64);
+        assert!(system.read_output(0, 0));
+        assert!(!system.read_output(1, 0));
+        system.advance_tick();
+        system.settle(64);
+        assert!(!system.read_output(1, 0));
+        system.advance_tick();
+        system.settle(64);
+        assert!(system.read_output(1, 0));
+        assert!(system.in_flight.is_empty());
+        assert_eq!(execute_system(&chunks, &links)[1].1[0], 0);
+    }
+
+    #[test]
+    fn engine_carries_delayed_links_across_ticks() {
+        let data = fs::read(fixtures().join("tiny_toggle.myc")).unwrap();
+        let chunk = parse_chunk(&data).unwrap();
+        let mut link = Link {
+            from_chunk: 0,
+            from_out_idx: 0,
+            trigger: Trigger::On,
+            action: Action::Enable,
+            to_chunk: 1,
+            to_in_idx: 0,
+            order_tag: 0,
+            delay: 1,
+        };
+        let mut engine =
+            CpuEngine::new(vec![chunk.clone(), chunk.clone()], &[link.clone()]).unwrap();
+        assert_eq!(engine.chunks(), 2);
+        engine.set_inputs(0, &[1]);
+        assert!(engine.tick(64).rounds > 0);
+        assert_eq!(engine.outputs(0), Some(&[1][..]));
+        assert_eq!(engine.outputs(1), Some(&[0][..]));
+        // the delayed effect is already on the Input and fires next tick
+        assert!(engine.tick(64).rounds > 0);
+        assert_eq!(engine.outputs(1), Some(&[1][..]));
+        assert_eq!(engine.tick(64), TickStats::default());
+        assert_eq!(engine.outputs(2), None);
+
+        link.to_chunk = 2;
+        assert!(CpuEngine::new(vec![chunk], &[link]).is_err());
+    }
+
+    #[test]
+    fn settle_flags_oscillator() {
+        // two internals that toggle each other forever
+        let ring = |from_index, to_index| Connection {
+            from_section: Section::Internal,
+            to_section: Section::Internal,
+            trigger: Trigger::Toggle,
+            action: Action::Toggle,
+            from_index,
+            to_index,
+            order_tag: 0,
+        };
+        let chunks = [MycosChunk {
+            input_bits: vec![],
+            output_bits: vec![],
+            internal_bits: vec![0b01],
+            input_count: 0,
+            output_count: 0,
+            internal_count: 2,
+            connections: vec![ring(0, 1), ring(1, 0)],
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        }];
+        let mut system = System::new(&chunks, &[]);
+        system.seed();
+        let stats = system.settle(64);
+        assert!(stats.oscillator);
+        assert_eq!(stats.rounds, 64);
+        assert!(stats.period > 0);
+
+        let data = fs::read(fixtures().join("tiny_toggle.myc")).unwrap();
+        let chunks = [parse_chunk(&data).unwrap()];
+        let mut system = System::new(&chunks, &[]);
+        system.write_input(0, 0, true);
+        let stats = system.settle(64);
+        assert!(!stats.oscillator);
+        assert_eq!(stats.period, 0);
+    }
+
+    #[test]
+    fn policies_quench_detected_cycles() {
+        // internals 01 -> 11 -> 10 -> 00 -> 01 -> 11: period 4
+        let ring = |from_index, to_index| Connection {
+            from_section: Section::Internal,
+            to_section: Section::Internal,
+            trigger: Trigger::Toggle,
+            action: Action::Toggle,
+            from_index,
+            to_index,
+            order_tag: 0,
+        };
+        let chunk = MycosChunk {
+            input_bits: vec![],
+            output_bits: vec![],
+            internal_bits: vec![0b01],
+            input_count: 0,
+            output_count: 0,
+            internal_count: 2,
+            connections: vec![ring(0, 1), ring(1, 0)],
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        };
+        let frozen = execute_with_policy(&chunk, Policy::FreezeLastStable, 64);
+        assert!(frozen.oscillator);
+        assert_eq!(frozen.policy, Some(Policy::FreezeLastStable));
+        assert_eq!((frozen.period, frozen.rounds), (4, 5));
+        assert_eq!(frozen.internals, vec![0b01]);
+        // even period: parity leaves the state where the cycle closed
+        let parity = execute_with_policy(&chunk, Policy::ParityQuench, 64);
+        assert_eq!(parity.internals, vec![0b11]);
+        // the pending toggle of bit 1 fires once more, onto bit 0
+        let clamped = execute_with_policy(&chunk, Policy::ClampCommutative, 64);
+        assert_eq!(clamped.internals, vec![0b10]);
+
+        let policies = PolicyConfig {
+            default: Policy::FreezeLastStable,
+            overrides: vec![(1, Policy::ClampCommutative), (1, Policy::ParityQuench)],
+            detector: DetectorConfig::default(),
+        };
+        let pair = [chunk.clone(), chunk.clone()];
+        let results = execute_system_with_policy(&pair, &[], &policies, 64);
+        assert_eq!(results[0].policy, Some(Policy::FreezeLastStable));
+        assert_eq!(results[0].internals, vec![0b01]);
+        assert_eq!(results[1].policy, Some(Policy::ParityQuench));
+        assert_eq!(results[1].internals, vec![0b11]);
+        assert_eq!(policies.for_chunk(2), Policy::FreezeLastStable);
+
+        // suppressing every toggled bit restores the pre-cycle state, none
+        // leaves the state where the cycle closed
+        let damped = |fraction| {
+            let policy = Policy::StochasticQuench { fraction, seed: 3 };
+            execute_with_policy(&chunk, policy, 64).internals
+        };
+        assert_eq!(damped(1.0), frozen.internals);
+        assert_eq!(damped(0.0), parity.internals);
+
+        let data = fs::read(fixtures().join("tiny_toggle.myc")).unwrap();
+        let mut quiet = parse_chunk(&data).unwrap();
+        quiet.input_bits[0] = 1;
+        let result = execute_with_policy(&quiet, Policy::FreezeLastStable, 64);
+        assert!(!result.oscillator);
+        assert_eq!(result.policy, None);
+        let (_, outputs, internals) = execute(&quiet);
+        assert_eq!(result.outputs[0], outputs[0] as u32);
+        assert_eq!(result.internals[0], internals[0] as u32);
+    }
 }
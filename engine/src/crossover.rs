@@ -1,112 +1,271 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use bitvec::prelude::*;
-use rand::RngCore;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
 
-use crate::genome::{ChunkGene, ConnGene, Genome, GenomeMeta, LinkGene};
+use crate::genome::{
+    ChunkGene, ConnGene, EmbedGene, Genome, GenomeMeta, LinkGene, ValidationError,
+};
+use crate::innovation::{conn_id, conn_key, link_id, link_key, ConnKey, GeneId, LinkKey};
 
-type ConnKey = (u8, u32, u8, u32);
-type LinkKey = (u32, u32, u32, u32);
 type ConnPair<'a> = (Option<&'a ConnGene>, Option<&'a ConnGene>);
 type LinkPair<'a> = (Option<&'a LinkGene>, Option<&'a LinkGene>);
+type EmbedKey = (u32, u32, u32);
+type EmbedPair<'a> = (Option<&'a EmbedGene>, Option<&'a EmbedGene>);
 
 const MAX_CHUNKS: usize = 64;
 const MAX_CONNS_PER_CHUNK: usize = 256;
 const MAX_LINKS: usize = 256;
 const MAX_NN_PER_CHUNK: u32 = 256;
 
-pub fn crossover(a: &Genome, b: &Genome, rng: &mut dyn RngCore) -> Genome {
+/// How aligned genes are shared out between the two parents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrossoverStrategy {
+    /// Each field of a shared gene comes from either parent with p=0.5 and
+    /// every unshared gene is kept.
+    #[default]
+    Uniform,
+    /// One cut point per chunk (and one across links and embeds): aligned
+    /// genes before the cut come from the first parent, the rest from the
+    /// second.
+    OnePointPerChunk,
+    /// Shared genes come from the first (fitter) parent with p=0.75 and
+    /// unshared genes only from the first parent.
+    FitterParentBiased,
+}
+
+/// Which parent supplies each gene of one aligned gene list.
+struct Picker {
+    strategy: CrossoverStrategy,
+    cut: usize,
+}
+
+impl Picker {
+    fn new(strategy: CrossoverStrategy, len: usize, rng: &mut dyn RngCore) -> Self {
+        let cut = match strategy {
+            CrossoverStrategy::OnePointPerChunk => rng.next_u32() as usize % (len + 1),
+            _ => 0,
+        };
+        Self { strategy, cut }
+    }
+
+    /// Whether the gene at `pos`, present in both parents, comes from `a`.
+    fn take_a(&self, pos: usize, rng: &mut dyn RngCore) -> bool {
+        match self.strategy {
+            CrossoverStrategy::Uniform => rng.next_u32() & 1 == 0,
+            CrossoverStrategy::OnePointPerChunk => pos < self.cut,
+            CrossoverStrategy::FitterParentBiased => !rng.next_u32().is_multiple_of(4),
+        }
+    }
+
+    /// Whether a gene at `pos` present only in `a` (or only in `b`) is kept.
+    fn keep_unshared(&self, pos: usize, in_a: bool) -> bool {
+        match self.strategy {
+            CrossoverStrategy::Uniform => true,
+            CrossoverStrategy::OnePointPerChunk => (pos < self.cut) == in_a,
+            CrossoverStrategy::FitterParentBiased => in_a,
+        }
+    }
+}
+
+/// Uniform crossover; see [`crossover_with`].
+pub fn crossover(a: &Genome, b: &Genome, rng: &mut dyn RngCore) -> Result<Genome, ValidationError> {
+    crossover_with(a, b, rng, CrossoverStrategy::Uniform)
+}
+
+/// Recombine two parents with the given strategy. Chunks are paired by
+/// [`align_chunks`] and genes within them by their structural key. For
+/// [`CrossoverStrategy::FitterParentBiased`] `a` must be the fitter parent.
+///
+/// Fails if the recombined genome does not validate, which can only happen
+/// when a parent was itself invalid.
+pub fn crossover_with(
+    a: &Genome,
+    b: &Genome,
+    rng: &mut dyn RngCore,
+    strategy: CrossoverStrategy,
+) -> Result<Genome, ValidationError> {
+    // Renumber b's chunks so each sits in the slot of its best match in a.
+    let slot_of_b = align_chunks(a, b);
+    let mut b_slots: Vec<Option<&ChunkGene>> = vec![None; a.chunks.len().max(b.chunks.len())];
+    for (j, &slot) in slot_of_b.iter().enumerate() {
+        b_slots[slot as usize] = Some(&b.chunks[j]);
+    }
+    let b_links: Vec<LinkGene> = b
+        .links
+        .iter()
+        .map(|l| LinkGene {
+            from_chunk: slot_of_b[l.from_chunk as usize],
+            to_chunk: slot_of_b[l.to_chunk as usize],
+            ..l.clone()
+        })
+        .collect();
+    let b_embeds: Vec<EmbedGene> = b
+        .embeds
+        .iter()
+        .map(|e| EmbedGene {
+            parent_chunk: slot_of_b[e.parent_chunk as usize],
+            child_chunk: slot_of_b[e.child_chunk as usize],
+            ..e.clone()
+        })
+        .collect();
+
     let mut chunks: Vec<ChunkGene> = Vec::new();
-    let max_chunk_len = a.chunks.len().max(b.chunks.len()).min(MAX_CHUNKS);
-    for i in 0..max_chunk_len {
-        match (a.chunks.get(i), b.chunks.get(i)) {
+    let max_chunk_len = b_slots.len().min(MAX_CHUNKS);
+    for (i, &b_chunk) in b_slots.iter().enumerate().take(max_chunk_len) {
+        match (a.chunks.get(i), b_chunk) {
             (Some(ca), Some(cb)) => {
-                chunks.push(crossover_chunk(ca, cb, rng));
+                chunks.push(crossover_chunk(ca, cb, rng, strategy));
             }
             (Some(ca), None) => {
                 chunks.push(ca.clone());
             }
             (None, Some(cb)) => {
+                if strategy == CrossoverStrategy::FitterParentBiased {
+                    break;
+                }
                 chunks.push(cb.clone());
             }
             (None, None) => break,
         }
     }
 
-    let mut links = crossover_links(&a.links, &b.links, &chunks, rng);
+    let mut links = crossover_links(&a.links, &b_links, &chunks, rng, strategy);
     fix_link_order_tags(&mut links);
     if links.len() > MAX_LINKS {
         links.truncate(MAX_LINKS);
         fix_link_order_tags(&mut links);
     }
 
+    let embeds = crossover_embeds(&a.embeds, &b_embeds, &chunks, rng, strategy);
+
     Genome::new(
         chunks,
         links,
         GenomeMeta::new(a.meta.seed, a.meta.tag.clone()),
     )
-    .expect("crossover produced invalid genome")
+    .and_then(|g| g.with_embeds(embeds))
+}
+
+/// Similarity in `[0, 1]` of two chunks: the mean of their I/O shape
+/// agreement (per-section min/max size ratio) and the Jaccard overlap of their
+/// connection keys.
+pub fn chunk_similarity(a: &ChunkGene, b: &ChunkGene) -> f32 {
+    let ratio = |x: u32, y: u32| {
+        if x == y {
+            1.0
+        } else {
+            x.min(y) as f32 / x.max(y) as f32
+        }
+    };
+    let shape = (ratio(a.ni, b.ni) + ratio(a.no, b.no) + ratio(a.nn, b.nn)) / 3.0;
+
+    let keys_a: BTreeSet<ConnKey> = a.conns.iter().map(conn_key).collect();
+    let keys_b: BTreeSet<ConnKey> = b.conns.iter().map(conn_key).collect();
+    let union = keys_a.union(&keys_b).count();
+    let overlap = if union == 0 {
+        1.0
+    } else {
+        keys_a.intersection(&keys_b).count() as f32 / union as f32
+    };
+    (shape + overlap) / 2.0
+}
+
+/// Match every chunk of `b` to a slot in the child, returning the slot for
+/// each `b` chunk. Pairs are taken greedily by descending
+/// [`chunk_similarity`], ties going to the pair closest in index, so
+/// identically ordered parents align positionally. Chunks of `b` left over
+/// once `a` is exhausted take the slots after `a`'s chunks in their original
+/// order.
+pub fn align_chunks(a: &Genome, b: &Genome) -> Vec<u32> {
+    let mut pairs: Vec<(f32, usize, usize)> = Vec::new();
+    for (i, ca) in a.chunks.iter().enumerate() {
+        for (j, cb) in b.chunks.iter().enumerate() {
+            pairs.push((chunk_similarity(ca, cb), i, j));
+        }
+    }
+    pairs.sort_by(|x, y| {
+        y.0.total_cmp(&x.0)
+            .then(x.1.abs_diff(x.2).cmp(&y.1.abs_diff(y.2)))
+            .then((x.1, x.2).cmp(&(y.1, y.2)))
+    });
+
+    let mut slot_of_b: Vec<Option<u32>> = vec![None; b.chunks.len()];
+    let mut a_taken = vec![false; a.chunks.len()];
+    for (_, i, j) in pairs {
+        if !a_taken[i] && slot_of_b[j].is_none() {
+            a_taken[i] = true;
+            slot_of_b[j] = Some(i as u32);
+        }
+    }
+    let mut next = a.chunks.len() as u32;
+    slot_of_b
+        .into_iter()
+        .map(|slot| {
+            slot.unwrap_or_else(|| {
+                next += 1;
+                next - 1
+            })
+        })
+        .collect()
 }
 
-fn crossover_chunk(a: &ChunkGene, b: &ChunkGene, rng: &mut dyn RngCore) -> ChunkGene {
+fn crossover_chunk(
+    a: &ChunkGene,
+    b: &ChunkGene,
+    rng: &mut dyn RngCore,
+    strategy: CrossoverStrategy,
+) -> ChunkGene {
     let ni = a.ni.max(b.ni);
     let no = a.no.max(b.no);
     let nn = a.nn.max(b.nn).min(MAX_NN_PER_CHUNK);
 
     let mut inputs_init = bitvec![u8, Lsb0; 0; ni as usize];
+    let picker = Picker::new(strategy, ni as usize, rng);
     for i in 0..ni as usize {
         let bit_a = i < a.inputs_init.len() && a.inputs_init[i];
         let bit_b = i < b.inputs_init.len() && b.inputs_init[i];
-        inputs_init.set(
-            i,
-            if rng.next_u32() & 1 == 0 {
-                bit_a
-            } else {
-                bit_b
-            },
-        );
+        inputs_init.set(i, if picker.take_a(i, rng) { bit_a } else { bit_b });
     }
     let mut outputs_init = bitvec![u8, Lsb0; 0; no as usize];
+    let picker = Picker::new(strategy, no as usize, rng);
     for i in 0..no as usize {
         let bit_a = i < a.outputs_init.len() && a.outputs_init[i];
         let bit_b = i < b.outputs_init.len() && b.outputs_init[i];
-        outputs_init.set(
-            i,
-            if rng.next_u32() & 1 == 0 {
-                bit_a
-            } else {
-                bit_b
-            },
-        );
+        outputs_init.set(i, if picker.take_a(i, rng) { bit_a } else { bit_b });
     }
     let mut internals_init = bitvec![u8, Lsb0; 0; nn as usize];
+    let picker = Picker::new(strategy, nn as usize, rng);
     for i in 0..nn as usize {
         let bit_a = i < a.internals_init.len() && a.internals_init[i];
         let bit_b = i < b.internals_init.len() && b.internals_init[i];
-        internals_init.set(
-            i,
-            if rng.next_u32() & 1 == 0 {
-                bit_a
-            } else {
-                bit_b
-            },
-        );
+        internals_init.set(i, if picker.take_a(i, rng) { bit_a } else { bit_b });
     }
 
-    let mut map: BTreeMap<ConnKey, ConnPair> = BTreeMap::new();
+    let mut map: BTreeMap<GeneId<ConnKey>, ConnPair> = BTreeMap::new();
     for c in &a.conns {
-        map.entry((c.from_section, c.from_index, c.to_section, c.to_index))
-            .or_insert((None, None))
-            .0 = Some(c);
+        map.entry(conn_id(c)).or_insert((None, None)).0 = Some(c);
     }
     for c in &b.conns {
-        map.entry((c.from_section, c.from_index, c.to_section, c.to_index))
-            .or_insert((None, None))
-            .1 = Some(c);
+        map.entry(conn_id(c)).or_insert((None, None)).1 = Some(c);
     }
 
     let mut conns = Vec::new();
-    for ((fs, fi, ts, ti), (ca, cb)) in map {
+    let picker = Picker::new(strategy, map.len(), rng);
+    for (pos, (ca, cb)) in map.into_values().enumerate() {
+        let (ca, cb) = match (ca, cb) {
+            (Some(_), None) if !picker.keep_unshared(pos, true) => continue,
+            (None, Some(_)) if !picker.keep_unshared(pos, false) => continue,
+            (Some(ac), Some(bc)) if strategy != CrossoverStrategy::Uniform => {
+                if picker.take_a(pos, rng) {
+                    (Some(ac), None)
+                } else {
+                    (None, Some(bc))
+                }
+            }
+            pair => pair,
+        };
         let trigger = match (ca, cb) {
             (Some(ac), Some(bc)) => {
                 if rng.next_u32() & 1 == 0 {
@@ -145,16 +304,22 @@ fn crossover_chunk(a: &ChunkGene, b: &ChunkGene, rng: &mut dyn RngCore) -> Chunk
             (None, Some(bc)) => bc.order_tag,
             _ => 0,
         };
+        // genes aligned by innovation may differ in structure; a wins ties
+        let base = ca.or(cb).unwrap();
         conns.push(ConnGene {
-            from_section: fs,
-            to_section: ts,
+            from_section: base.from_section,
+            to_section: base.to_section,
             trigger,
             action,
-            from_index: fi,
-            to_index: ti,
+            from_index: base.from_index,
+            to_index: base.to_index,
             order_tag,
+            innovation: base.innovation,
         });
     }
+    // distinct innovations can still share a structure
+    let mut seen = BTreeSet::new();
+    conns.retain(|c| seen.insert(conn_key(c)));
 
     conns.retain(|c| {
         let from_ok = match c.from_section {
@@ -192,27 +357,37 @@ fn crossover_links(
     b_links: &[LinkGene],
     chunks: &[ChunkGene],
     rng: &mut dyn RngCore,
+    strategy: CrossoverStrategy,
 ) -> Vec<LinkGene> {
-    let mut map: BTreeMap<LinkKey, LinkPair> = BTreeMap::new();
+    let mut map: BTreeMap<GeneId<LinkKey>, LinkPair> = BTreeMap::new();
     for l in a_links {
-        map.entry((l.from_chunk, l.from_out_idx, l.to_chunk, l.to_in_idx))
-            .or_insert((None, None))
-            .0 = Some(l);
+        map.entry(link_id(l)).or_insert((None, None)).0 = Some(l);
     }
     for l in b_links {
-        map.entry((l.from_chunk, l.from_out_idx, l.to_chunk, l.to_in_idx))
-            .or_insert((None, None))
-            .1 = Some(l);
+        map.entry(link_id(l)).or_insert((None, None)).1 = Some(l);
     }
 
     let mut links = Vec::new();
-    for ((fc, fo, tc, ti), (la, lb)) in map {
+    let picker = Picker::new(strategy, map.len(), rng);
+    for (pos, (la, lb)) in map.into_values().enumerate() {
+        let (la, lb) = match (la, lb) {
+            (Some(_), None) if !picker.keep_unshared(pos, true) => continue,
+            (None, Some(_)) if !picker.keep_unshared(pos, false) => continue,
+            (Some(la), Some(lb)) if strategy != CrossoverStrategy::Uniform => {
+                if picker.take_a(pos, rng) {
+                    (Some(la), None)
+                } else {
+                    (None, Some(lb))
+                }
+            }
+            pair => pair,
+        };
+        let base = la.or(lb).unwrap();
+        let (fc, fo, tc, ti, _) = link_key(base);
         if fc as usize >= chunks.len() || tc as usize >= chunks.len() {
             continue;
         }
-        let from_chunk = &chunks[fc as usize];
-        let to_chunk = &chunks[tc as usize];
-        if fo >= from_chunk.no || ti >= to_chunk.ni {
+        if fo >= chunks[fc as usize].no || ti >= chunks[tc as usize].ni {
             continue;
         }
         let trigger = match (la, lb) {
@@ -261,12 +436,325 @@ fn crossover_links(
             to_chunk: tc,
             to_in_idx: ti,
             order_tag,
+            delay: base.delay,
+            innovation: base.innovation,
         });
     }
+    let mut seen = BTreeSet::new();
+    links.retain(|l| seen.insert(link_key(l)));
 
     links
 }
 
+/// Align embeds by `(parent_chunk, child_chunk, gate_bit)`. Shared embeds take
+/// their mode and maps from either parent; under the uniform strategy unshared
+/// ones are kept with p=0.5. Embeds that no longer fit the child's chunks,
+/// reuse a child or form a cycle are dropped.
+fn crossover_embeds(
+    a_embeds: &[EmbedGene],
+    b_embeds: &[EmbedGene],
+    chunks: &[ChunkGene],
+    rng: &mut dyn RngCore,
+    strategy: CrossoverStrategy,
+) -> Vec<EmbedGene> {
+    let mut map: BTreeMap<EmbedKey, EmbedPair> = BTreeMap::new();
+    for e in a_embeds {
+        map.entry((e.parent_chunk, e.child_chunk, e.gate_bit))
+            .or_insert((None, None))
+            .0 = Some(e);
+    }
+    for e in b_embeds {
+        map.entry((e.parent_chunk, e.child_chunk, e.gate_bit))
+            .or_insert((None, None))
+            .1 = Some(e);
+    }
+
+    let mut embeds: Vec<EmbedGene> = Vec::new();
+    let picker = Picker::new(strategy, map.len(), rng);
+    for (pos, (ea, eb)) in map.into_values().enumerate() {
+        let pick = match (ea, eb) {
+            (Some(ea), Some(eb)) => {
+                if picker.take_a(pos, rng) {
+                    ea
+                } else {
+                    eb
+                }
+            }
+            (Some(e), None) | (None, Some(e)) => {
+                let keep = match strategy {
+                    CrossoverStrategy::Uniform => rng.next_u32() & 1 == 0,
+                    _ => picker.keep_unshared(pos, ea.is_some()),
+                };
+                if !keep {
+                    continue;
+                }
+                e
+            }
+            (None, None) => continue,
+        };
+        embeds.push(pick.clone());
+        if Genome::validate_embeds(chunks, &embeds).is_err() {
+            embeds.pop();
+        }
+    }
+    embeds
+}
+
+/// Majority-vote crossover over any number of parents; see
+/// [`crossover_n_weighted`].
+pub fn crossover_n(parents: &[&Genome], rng: &mut dyn RngCore) -> Result<Genome, ValidationError> {
+    recombine_n(parents, &vec![1.0; parents.len()], Vote::Majority, rng)
+}
+
+/// Recombine several parents, each gene being inherited with probability
+/// equal to the weight share of the parents carrying it and its value taken
+/// from one of those parents chosen in proportion to weight.
+///
+/// Chunks of every parent are aligned to the first parent's with
+/// [`align_chunks`], so the child has the first parent's chunk count. Genes
+/// align by innovation number or structural key as in [`crossover_with`].
+/// Panics if `parents` is empty or `weights` differs from it in length.
+pub fn crossover_n_weighted(
+    parents: &[&Genome],
+    weights: &[f32],
+    rng: &mut dyn RngCore,
+) -> Result<Genome, ValidationError> {
+    assert_eq!(parents.len(), weights.len(), "one weight per parent");
+    recombine_n(parents, weights, Vote::Weighted, rng)
+}
+
+#[derive(Clone, Copy)]
+enum Vote {
+    /// Keep genes carried by more than half the weight (ties by coin flip)
+    /// and take the value with the most weight behind it, earliest parent
+    /// first on ties.
+    Majority,
+    /// Keep genes and pick values with probability proportional to weight.
+    Weighted,
+}
+
+impl Vote {
+    fn keep(self, carried: f32, total: f32, rng: &mut dyn RngCore) -> bool {
+        match self {
+            Vote::Majority if carried * 2.0 == total => rng.next_u32() & 1 == 0,
+            Vote::Majority => carried * 2.0 > total,
+            Vote::Weighted => total > 0.0 && rng.gen::<f32>() * total < carried,
+        }
+    }
+
+    /// Pick the version of a gene to inherit from its carriers.
+    fn carrier<'c, T>(self, carriers: &'c [(f32, T)], rng: &mut dyn RngCore) -> &'c T {
+        let idx: Vec<(f32, usize)> = carriers
+            .iter()
+            .enumerate()
+            .map(|(i, (w, _))| (*w, i))
+            .collect();
+        &carriers[self.choose(&idx, rng)].1
+    }
+
+    /// Pick one of the weighted candidates; equal values pool their weight
+    /// under majority vote.
+    fn choose<T: Copy + PartialEq>(self, candidates: &[(f32, T)], rng: &mut dyn RngCore) -> T {
+        match self {
+            Vote::Majority => {
+                let pooled = |v: T| -> f32 {
+                    candidates
+                        .iter()
+                        .filter(|(_, c)| *c == v)
+                        .map(|(w, _)| w)
+                        .sum()
+                };
+                let mut best = candidates[0];
+                for &(_, v) in &candidates[1..] {
+                    if pooled(v) > pooled(best.1) {
+                        best = (0.0, v);
+                    }
+                }
+                best.1
+            }
+            Vote::Weighted => {
+                let total: f32 = candidates.iter().map(|(w, _)| w).sum();
+                let mut roll = rng.gen::<f32>() * total;
+                for &(w, v) in candidates {
+                    if roll < w {
+                        return v;
+                    }
+                    roll -= w;
+                }
+                candidates[candidates.len() - 1].1
+            }
+        }
+    }
+}
+
+fn recombine_n(
+    parents: &[&Genome],
+    weights: &[f32],
+    vote: Vote,
+    rng: &mut dyn RngCore,
+) -> Result<Genome, ValidationError> {
+    let first = parents
+        .first()
+        .expect("crossover needs at least one parent");
+    let slots = first.chunks.len().min(MAX_CHUNKS);
+    let total: f32 = weights.iter().sum();
+
+    // Every parent's chunks, links and embeds renumbered into the first
+    // parent's slots; anything landing outside them is dropped.
+    let mut chunk_slots: Vec<Vec<(f32, &ChunkGene)>> = vec![Vec::new(); slots];
+    let mut links: BTreeMap<GeneId<LinkKey>, Vec<(f32, LinkGene)>> = BTreeMap::new();
+    let mut embeds: BTreeMap<EmbedKey, Vec<(f32, EmbedGene)>> = BTreeMap::new();
+    for (&parent, &w) in parents.iter().zip(weights) {
+        let slot_of = align_chunks(first, parent);
+        let slot = |c: u32| Some(slot_of[c as usize]).filter(|&s| (s as usize) < slots);
+        for (j, chunk) in parent.chunks.iter().enumerate() {
+            if let Some(s) = slot(j as u32) {
+                chunk_slots[s as usize].push((w, chunk));
+            }
+        }
+        for l in &parent.links {
+            if let (Some(from_chunk), Some(to_chunk)) = (slot(l.from_chunk), slot(l.to_chunk)) {
+                let link = LinkGene {
+                    from_chunk,
+                    to_chunk,
+                    ..l.clone()
+                };
+                links.entry(link_id(&link)).or_default().push((w, link));
+            }
+        }
+        for e in &parent.embeds {
+            if let (Some(parent_chunk), Some(child_chunk)) =
+                (slot(e.parent_chunk), slot(e.child_chunk))
+            {
+                let embed = EmbedGene {
+                    parent_chunk,
+                    child_chunk,
+                    ..e.clone()
+                };
+                embeds
+                    .entry((parent_chunk, child_chunk, e.gate_bit))
+                    .or_default()
+                    .push((w, embed));
+            }
+        }
+    }
+
+    let chunks: Vec<ChunkGene> = chunk_slots
+        .iter()
+        .map(|held| recombine_chunk_n(held, total, vote, rng))
+        .collect();
+
+    let mut child_links = Vec::new();
+    for carriers in links.values() {
+        let carried: f32 = carriers.iter().map(|(w, _)| w).sum();
+        if !vote.keep(carried, total, rng) {
+            continue;
+        }
+        let link = vote.carrier(carriers, rng);
+        let (fc, fo, tc, ti, _) = link_key(link);
+        if fo < chunks[fc as usize].no && ti < chunks[tc as usize].ni {
+            child_links.push(link.clone());
+        }
+    }
+    let mut seen = BTreeSet::new();
+    child_links.retain(|l| seen.insert(link_key(l)));
+    fix_link_order_tags(&mut child_links);
+    child_links.truncate(MAX_LINKS);
+
+    let mut child_embeds: Vec<EmbedGene> = Vec::new();
+    for carriers in embeds.values() {
+        let carried: f32 = carriers.iter().map(|(w, _)| w).sum();
+        if !vote.keep(carried, total, rng) {
+            continue;
+        }
+        child_embeds.push(vote.carrier(carriers, rng).clone());
+        if Genome::validate_embeds(&chunks, &child_embeds).is_err() {
+            child_embeds.pop();
+        }
+    }
+
+    Genome::new(
+        chunks,
+        child_links,
+        GenomeMeta::new(first.meta.seed, first.meta.tag.clone()),
+    )
+    .and_then(|g| g.with_embeds(child_embeds))
+}
+
+/// Recombine the chunks aligned into one slot. Section sizes are the largest
+/// among carriers; each init bit and connection gene is voted on.
+fn recombine_chunk_n(
+    held: &[(f32, &ChunkGene)],
+    total: f32,
+    vote: Vote,
+    rng: &mut dyn RngCore,
+) -> ChunkGene {
+    let ni = held.iter().map(|(_, c)| c.ni).max().unwrap_or(0);
+    let no = held.iter().map(|(_, c)| c.no).max().unwrap_or(0);
+    let nn = held
+        .iter()
+        .map(|(_, c)| c.nn)
+        .max()
+        .unwrap_or(0)
+        .min(MAX_NN_PER_CHUNK);
+
+    let mut vote_bits = |len: u32, bits: fn(&ChunkGene) -> &BitVec<u8, Lsb0>| {
+        let mut out = bitvec![u8, Lsb0; 0; len as usize];
+        for i in 0..len as usize {
+            let values: Vec<(f32, bool)> = held
+                .iter()
+                .map(|(w, c)| (*w, bits(c).get(i).is_some_and(|b| *b)))
+                .collect();
+            out.set(i, vote.choose(&values, rng));
+        }
+        out
+    };
+    let inputs_init = vote_bits(ni, |c| &c.inputs_init);
+    let outputs_init = vote_bits(no, |c| &c.outputs_init);
+    let internals_init = vote_bits(nn, |c| &c.internals_init);
+
+    let mut genes: BTreeMap<GeneId<ConnKey>, Vec<(f32, &ConnGene)>> = BTreeMap::new();
+    for (w, chunk) in held {
+        for c in &chunk.conns {
+            genes.entry(conn_id(c)).or_default().push((*w, c));
+        }
+    }
+    let mut conns = Vec::new();
+    for carriers in genes.values() {
+        let carried: f32 = carriers.iter().map(|(w, _)| w).sum();
+        if !vote.keep(carried, total, rng) {
+            continue;
+        }
+        conns.push((*vote.carrier(carriers, rng)).clone());
+    }
+    let mut seen = BTreeSet::new();
+    conns.retain(|c| {
+        let from_ok = match c.from_section {
+            0 => c.from_index < ni,
+            1 => c.from_index < nn,
+            _ => false,
+        };
+        let to_ok = match c.to_section {
+            1 => c.to_index < nn,
+            2 => c.to_index < no,
+            _ => false,
+        };
+        from_ok && to_ok && seen.insert(conn_key(c))
+    });
+    fix_conn_order_tags(&mut conns);
+    conns.truncate(MAX_CONNS_PER_CHUNK);
+
+    ChunkGene {
+        ni,
+        no,
+        nn,
+        inputs_init,
+        outputs_init,
+        internals_init,
+        conns,
+    }
+}
+
 fn fix_conn_order_tags(conns: &mut [ConnGene]) {
     conns.sort_by(|a, b| {
         (a.from_section, a.from_index, a.order_tag).cmp(&(
@@ -351,9 +839,9 @@ mod tests {
     #[test]
     fn crossover_aligns_connections_and_links() {
         let conn_a = ConnGene::new(1, 2, 0, 0, 0, 0, 1).unwrap();
-        let conn_b = ConnGene::new(1, 2, 1, 1, 0, 0, 5).unwrap();
+        let conn_b = ConnGene::new(1, 2, 0, 1, 0, 0, 5).unwrap();
         let link_a = LinkGene::new(0, 0, 0, 0, 1, 0, 1).unwrap();
-        let link_b = LinkGene::new(0, 0, 1, 1, 1, 0, 5).unwrap();
+        let link_b = LinkGene::new(0, 0, 0, 1, 1, 0, 5).unwrap();
         let chunk0_a = ChunkGene::new(
             1,
             1,
@@ -398,7 +886,7 @@ mod tests {
             vals: vec![0; 64],
             idx: 0,
         };
-        let child = crossover(&a, &b, &mut rng);
+        let child = crossover(&a, &b, &mut rng).unwrap();
         assert_eq!(child.chunks.len(), 2);
         assert_eq!(child.chunks[0].conns.len(), 1);
         let c = &child.chunks[0].conns[0];
@@ -412,4 +900,284 @@ mod tests {
         assert_eq!(l.order_tag, link_a.order_tag.max(link_b.order_tag));
         assert!(child.validate().is_ok());
     }
+
+    #[test]
+    fn strategies_pick_expected_parent() {
+        let chunk = |trigger: u8, extra: bool| {
+            let mut conns = vec![ConnGene::new(1, 2, trigger, 0, 0, 0, 0).unwrap()];
+            if extra {
+                conns.push(ConnGene::new(0, 1, 0, 0, 0, 0, 0).unwrap());
+            }
+            ChunkGene::new(
+                1,
+                1,
+                1,
+                bitvec![u8, Lsb0; 0],
+                bitvec![u8, Lsb0; 0],
+                bitvec![u8, Lsb0; 0],
+                conns,
+            )
+        };
+        let a = Genome::new(
+            vec![chunk(1, false)],
+            Vec::new(),
+            GenomeMeta::new(0, "a".into()),
+        )
+        .unwrap();
+        let b = Genome::new(
+            vec![chunk(2, true)],
+            Vec::new(),
+            GenomeMeta::new(1, "b".into()),
+        )
+        .unwrap();
+
+        // every roll favours `a`; b's unshared Input->Internal conn is dropped
+        let mut rng = SeqRng {
+            vals: vec![1; 64],
+            idx: 0,
+        };
+        let kid = crossover_with(&a, &b, &mut rng, CrossoverStrategy::FitterParentBiased).unwrap();
+        assert_eq!(kid.chunks[0].conns.len(), 1);
+        assert_eq!(kid.chunks[0].conns[0].trigger, 1);
+
+        // cut of 0 hands every aligned conn to `b`
+        let mut rng = SeqRng {
+            vals: vec![0; 64],
+            idx: 0,
+        };
+        let kid = crossover_with(&a, &b, &mut rng, CrossoverStrategy::OnePointPerChunk).unwrap();
+        assert_eq!(kid.chunks[0].conns.len(), 2);
+        assert!(kid.chunks[0].conns.iter().any(|c| c.trigger == 2));
+        assert!(kid.validate().is_ok());
+    }
+
+    #[test]
+    fn chunks_align_by_similarity() {
+        let wide = ChunkGene::new(
+            2,
+            1,
+            1,
+            bitvec![u8, Lsb0; 0, 0],
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0],
+            vec![ConnGene::new(0, 1, 0, 0, 1, 0, 0).unwrap()],
+        );
+        let narrow = ChunkGene::new(
+            1,
+            1,
+            1,
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0],
+            vec![ConnGene::new(1, 2, 0, 0, 0, 0, 0).unwrap()],
+        );
+        let link = |from, to| LinkGene::new(from, 0, 0, 0, to, 0, 0).unwrap();
+        let a = Genome::new(
+            vec![wide.clone(), narrow.clone()],
+            vec![link(1, 0)],
+            GenomeMeta::new(0, "a".into()),
+        )
+        .unwrap();
+        let b = Genome::new(
+            vec![narrow, wide],
+            vec![link(0, 1)],
+            GenomeMeta::new(1, "b".into()),
+        )
+        .unwrap();
+        assert_eq!(align_chunks(&a, &b), vec![1, 0]);
+
+        let mut rng = SeqRng {
+            vals: vec![0; 64],
+            idx: 0,
+        };
+        let kid = crossover(&a, &b, &mut rng).unwrap();
+        assert_eq!(kid.chunks[0].ni, 2);
+        assert_eq!(kid.chunks[0].conns.len(), 1);
+        assert_eq!(kid.chunks[1].conns.len(), 1);
+        assert_eq!(kid.links.len(), 1);
+        assert_eq!((kid.links[0].from_chunk, kid.links[0].to_chunk), (1, 0));
+    }
+
+    #[test]
+    fn innovation_numbers_drive_alignment() {
+        let chunk = |to_index: u32| {
+            let mut conn = ConnGene::new(0, 1, 0, 0, 0, to_index, 0).unwrap();
+            conn.innovation = 4;
+            ChunkGene::new(
+                1,
+                1,
+                2,
+                bitvec![u8, Lsb0; 0],
+                bitvec![u8, Lsb0; 0],
+                bitvec![u8, Lsb0; 0, 0],
+                vec![conn],
+            )
+        };
+        let a = Genome::new(vec![chunk(0)], Vec::new(), GenomeMeta::new(0, "a".into())).unwrap();
+        let b = Genome::new(vec![chunk(1)], Vec::new(), GenomeMeta::new(1, "b".into())).unwrap();
+        let mut rng = SeqRng {
+            vals: vec![0; 64],
+            idx: 0,
+        };
+        // one shared gene rather than two disjoint ones
+        let kid = crossover(&a, &b, &mut rng).unwrap();
+        assert_eq!(kid.chunks[0].conns.len(), 1);
+        assert_eq!(kid.chunks[0].conns[0].innovation, 4);
+        assert_eq!(kid.chunks[0].conns[0].to_index, 0);
+    }
+
+    #[test]
+    fn tracked_wires_keep_every_connection() {
+        use crate::builder::fixtures::wire_genome;
+        use crate::innovation::InnovationTracker;
+        let mut wire = wire_genome();
+        let mut tracker = InnovationTracker::new();
+        tracker.assign(&mut wire);
+        // the On and Off connections of each edge are separate genes
+        let numbers: Vec<u32> = wire.chunks[0].conns.iter().map(|c| c.innovation).collect();
+        assert_eq!(numbers, vec![1, 2, 3, 4]);
+
+        let mut rng = SeqRng {
+            vals: vec![0; 64],
+            idx: 0,
+        };
+        let connections = |g: &Genome| {
+            let conns = &g.chunks[0].conns;
+            conns
+                .iter()
+                .map(|c| (c.innovation, c.trigger, c.action))
+                .collect::<Vec<_>>()
+        };
+        let kid = crossover(&wire, &wire, &mut rng).unwrap();
+        assert_eq!(connections(&kid), connections(&wire));
+        let kid = crossover_n(&[&wire, &wire], &mut rng).unwrap();
+        assert_eq!(connections(&kid), connections(&wire));
+    }
+
+    #[test]
+    fn invalid_parent_is_an_error() {
+        let mut conn = ConnGene::new(1, 2, 0, 0, 0, 0, 0).unwrap();
+        conn.trigger = 7;
+        let chunk = ChunkGene::new(
+            1,
+            1,
+            1,
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0],
+            vec![conn],
+        );
+        let bad = Genome {
+            chunks: vec![chunk],
+            links: Vec::new(),
+            embeds: Vec::new(),
+            meta: GenomeMeta::new(0, "bad".into()),
+        };
+        let mut rng = SeqRng {
+            vals: vec![0; 64],
+            idx: 0,
+        };
+        assert!(matches!(
+            crossover(&bad, &bad, &mut rng),
+            Err(ValidationError::InvalidTrigger(7))
+        ));
+    }
+
+    #[test]
+    fn multi_parent_votes_on_genes() {
+        let genome = |conns: &[(u8, u8, u32, u32)], tag: &str| {
+            let conns = conns
+                .iter()
+                .map(|&(fs, ts, fi, ti)| ConnGene::new(fs, ts, 0, 0, fi, ti, ts as u32).unwrap())
+                .collect();
+            let chunk = ChunkGene::new(
+                1,
+                1,
+                2,
+                bitvec![u8, Lsb0; 0],
+                bitvec![u8, Lsb0; 0],
+                bitvec![u8, Lsb0; 0, 0],
+                conns,
+            );
+            Genome::new(vec![chunk], Vec::new(), GenomeMeta::new(0, tag.into())).unwrap()
+        };
+        let shared = (0, 1, 0, 0);
+        let pair = (1, 2, 0, 0);
+        let lone = (1, 1, 0, 1);
+        let a = genome(&[shared, pair], "a");
+        let b = genome(&[shared, pair, lone], "b");
+        let c = genome(&[shared], "c");
+        let key = |c: &ConnGene| (c.from_section, c.to_section, c.from_index, c.to_index);
+
+        let mut rng = SeqRng {
+            vals: vec![0; 64],
+            idx: 0,
+        };
+        let kid = crossover_n(&[&a, &b, &c], &mut rng).unwrap();
+        let keys: Vec<_> = kid.chunks[0].conns.iter().map(key).collect();
+        assert_eq!(keys, vec![shared, pair]);
+
+        // all the weight on `b` reproduces its genes
+        let kid = crossover_n_weighted(&[&a, &b, &c], &[0.0, 1.0, 0.0], &mut rng).unwrap();
+        let mut keys: Vec<_> = kid.chunks[0].conns.iter().map(key).collect();
+        keys.sort();
+        assert_eq!(keys, vec![shared, lone, pair]);
+    }
+
+    #[test]
+    fn crossover_keeps_embeds() {
+        let parent = ChunkGene::new(
+            0,
+            1,
+            2,
+            BitVec::new(),
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0, 0],
+            Vec::new(),
+        );
+        let child = ChunkGene::new(
+            1,
+            1,
+            0,
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0],
+            BitVec::new(),
+            Vec::new(),
+        );
+        let embed = EmbedGene {
+            parent_chunk: 0,
+            child_chunk: 1,
+            gate_bit: 1,
+            gate_section: 1,
+            io_mode: 1,
+            map_in: vec![(0, 0)],
+            map_out: vec![(0, 0)],
+        };
+        let a = Genome::new(
+            vec![parent.clone(), child.clone()],
+            Vec::new(),
+            GenomeMeta::new(0, "a".into()),
+        )
+        .unwrap()
+        .with_embeds(vec![embed.clone()])
+        .unwrap();
+        let b = Genome::new(
+            vec![parent, child],
+            Vec::new(),
+            GenomeMeta::new(1, "b".into()),
+        )
+        .unwrap();
+        let mut rng = SeqRng {
+            vals: vec![0; 64],
+            idx: 0,
+        };
+        let kid = crossover(&a, &b, &mut rng).unwrap();
+        assert_eq!(kid.embeds, vec![embed]);
+
+        let mut rng = SeqRng {
+            vals: vec![1; 64],
+            idx: 0,
+        };
+        assert!(crossover(&a, &b, &mut rng).unwrap().embeds.is_empty());
+    }
 }
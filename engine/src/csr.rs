@@ -287,6 +287,7 @@ mod tests {
             name: None,
             note: None,
             build_hash: None,
+            ports: Vec::new(),
         };
         let csr = build_csr(&chunk);
         let start = csr.offs_on[0] as usize;
@@ -0,0 +1,180 @@
+use std::fmt;
+
+use crate::assembly::{action_name, conn_statement, section_name};
+use crate::chunk::{Connection, MycosChunk, Section};
+
+/// Structural differences between two chunks, from `a` to `b`.
+///
+/// Connections are matched on `(from, to, trigger)`; a matched pair whose
+/// action or order tag differs is `modified`. Repeated keys pair up in table
+/// order. Names, notes, ports and the build hash are not compared.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkDiff {
+    /// Change in `(input, output, internal)` counts.
+    pub size_delta: (i64, i64, i64),
+    pub added: Vec<Connection>,
+    pub removed: Vec<Connection>,
+    /// `(before, after)` pairs.
+    pub modified: Vec<(Connection, Connection)>,
+    /// Initial bits that differ, as `(section, index, now set)`. Bits past a
+    /// section's count read as clear.
+    pub bits: Vec<(Section, u32, bool)>,
+}
+
+impl ChunkDiff {
+    pub fn is_empty(&self) -> bool {
+        self.size_delta == (0, 0, 0)
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.bits.is_empty()
+    }
+}
+
+/// Compare two chunks; see [`ChunkDiff`].
+pub fn diff_chunks(a: &MycosChunk, b: &MycosChunk) -> ChunkDiff {
+    let key = |c: &Connection| {
+        (
+            c.from_section as u8,
+            c.from_index,
+            c.to_section as u8,
+            c.to_index,
+            c.trigger as u8,
+        )
+    };
+    let mut unmatched: Vec<Option<&Connection>> = b.connections.iter().map(Some).collect();
+    let mut diff = ChunkDiff {
+        size_delta: (
+            b.input_count as i64 - a.input_count as i64,
+            b.output_count as i64 - a.output_count as i64,
+            b.internal_count as i64 - a.internal_count as i64,
+        ),
+        ..ChunkDiff::default()
+    };
+    for old in &a.connections {
+        let found = unmatched
+            .iter_mut()
+            .find(|c| c.is_some_and(|c| key(c) == key(old)))
+            .and_then(Option::take);
+        match found {
+            None => diff.removed.push(old.clone()),
+            Some(new) if new.action != old.action || new.order_tag != old.order_tag => {
+                diff.modified.push((old.clone(), new.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    diff.added = unmatched.into_iter().flatten().cloned().collect();
+
+    let bit = |bytes: &[u8], count: u32, i: u32| {
+        i < count
+            && bytes
+                .get((i / 8) as usize)
+                .is_some_and(|b| b >> (i % 8) & 1 == 1)
+    };
+    for (section, (a_bits, a_count), (b_bits, b_count)) in [
+        (
+            Section::Input,
+            (&a.input_bits, a.input_count),
+            (&b.input_bits, b.input_count),
+        ),
+        (
+            Section::Output,
+            (&a.output_bits, a.output_count),
+            (&b.output_bits, b.output_count),
+        ),
+        (
+            Section::Internal,
+            (&a.internal_bits, a.internal_count),
+            (&b.internal_bits, b.internal_count),
+        ),
+    ] {
+        for i in 0..a_count.max(b_count) {
+            let now = bit(b_bits, b_count, i);
+            if bit(a_bits, a_count, i) != now {
+                diff.bits.push((section, i, now));
+            }
+        }
+    }
+    diff
+}
+
+/// One line per change: size changes, then `-` removed, `+` added and `~`
+/// modified connections in `.myca` syntax, then `bit` lines.
+impl fmt::Display for ChunkDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (di, dout, dn) = self.size_delta;
+        for (name, delta) in [("inputs", di), ("outputs", dout), ("internals", dn)] {
+            if delta != 0 {
+                writeln!(f, "{name} {delta:+}")?;
+            }
+        }
+        for conn in &self.removed {
+            writeln!(f, "- {} tag {}", conn_statement(conn), conn.order_tag)?;
+        }
+        for conn in &self.added {
+            writeln!(f, "+ {} tag {}", conn_statement(conn), conn.order_tag)?;
+        }
+        for (old, new) in &self.modified {
+            write!(f, "~ {} tag {}:", conn_statement(old), old.order_tag)?;
+            if old.action != new.action {
+                write!(f, " action {}", action_name(new.action))?;
+            }
+            if old.order_tag != new.order_tag {
+                write!(f, " tag {}", new.order_tag)?;
+            }
+            writeln!(f)?;
+        }
+        for &(section, index, now) in &self.bits {
+            writeln!(f, "bit {} {index} {}", section_name(section), now as u8)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ChunkBuilder;
+    use crate::chunk::{Action, Trigger};
+    use Section::{Input, Internal, Output};
+
+    #[test]
+    fn reports_every_kind_of_change() {
+        let a = ChunkBuilder::new()
+            .inputs(1)
+            .internal(2)
+            .outputs(1)
+            .conn((Input, 0), (Internal, 0), Trigger::On, Action::Enable)
+            .conn((Input, 0), (Internal, 1), Trigger::On, Action::Enable)
+            .conn((Internal, 0), (Output, 0), Trigger::On, Action::Enable)
+            .build()
+            .unwrap();
+        assert!(diff_chunks(&a, &a).is_empty());
+
+        let b = ChunkBuilder::new()
+            .inputs(1)
+            .internal(3)
+            .outputs(1)
+            .set(Internal, 2)
+            .conn((Input, 0), (Internal, 0), Trigger::On, Action::Toggle)
+            .conn((Internal, 0), (Output, 0), Trigger::On, Action::Enable)
+            .conn((Internal, 2), (Output, 0), Trigger::Off, Action::Disable)
+            .build()
+            .unwrap();
+        let diff = diff_chunks(&a, &b);
+        assert_eq!(diff.size_delta, (0, 0, 1));
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.bits, vec![(Internal, 2, true)]);
+        assert_eq!(
+            diff.to_string(),
+            "internals +1\n\
+             - conn input 0 internal 1 on enable tag 1\n\
+             + conn internal 2 output 0 off disable tag 0\n\
+             ~ conn input 0 internal 0 on enable tag 0: action toggle\n\
+             bit internal 2 1\n"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Graphviz DOT rendering of chunks and linked systems.
+//!
+//! Bits are nodes named `i3`, `n3` and `o3` for Input, Internal and Output 3
+//! (prefixed `c2_` in a system), labelled with their port name when they have
+//! one; initially set bits are filled. Inputs share the top rank and outputs
+//! the bottom one. Connection edges are labelled `trigger/action:tag`; links
+//! are dashed and add `+delay` when delayed.
+
+use std::fmt::Write;
+
+use crate::assembly::{action_name, section_name, trigger_name};
+use crate::chunk::{Action, Connection, MycosChunk, Section, Trigger};
+use crate::genome::{pack_bits, ChunkGene, Genome};
+use crate::link::Link;
+
+/// A `digraph` of one chunk.
+pub fn to_dot(chunk: &MycosChunk) -> String {
+    let mut out = String::from("digraph chunk {\n");
+    if let Some(name) = &chunk.name {
+        writeln!(out, "  label=\"{}\";", escape(name)).unwrap();
+    }
+    write_chunk(&mut out, chunk, "", "  ");
+    out.push_str("}\n");
+    out
+}
+
+/// A `digraph` of several chunks, one cluster each, joined by their links.
+pub fn system_to_dot(chunks: &[MycosChunk], links: &[Link]) -> String {
+    let mut out = String::from("digraph system {\n");
+    for (c, chunk) in chunks.iter().enumerate() {
+        writeln!(out, "  subgraph cluster_{c} {{").unwrap();
+        let label = chunk.name.clone().unwrap_or_else(|| format!("chunk {c}"));
+        writeln!(out, "    label=\"{}\";", escape(&label)).unwrap();
+        write_chunk(&mut out, chunk, &format!("c{c}_"), "    ");
+        out.push_str("  }\n");
+    }
+    for link in links {
+        write!(
+            out,
+            "  c{}_o{} -> c{}_i{} [style=dashed, label=\"{}/{}:{}",
+            link.from_chunk,
+            link.from_out_idx,
+            link.to_chunk,
+            link.to_in_idx,
+            trigger_name(link.trigger),
+            action_name(link.action),
+            link.order_tag,
+        )
+        .unwrap();
+        if link.delay > 0 {
+            write!(out, " +{}", link.delay).unwrap();
+        }
+        out.push_str("\"];\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+impl Genome {
+    /// Chunk-level `digraph`: one node per chunk gene with its sizes, one
+    /// edge per link gene labelled `oX->iY trigger/action:tag`, and a dotted
+    /// parent-to-child edge per embed. Works on unvalidated genomes; unknown
+    /// codes render as `?N`.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph genome {\n");
+        for (c, chunk) in self.chunks.iter().enumerate() {
+            writeln!(
+                out,
+                "  c{c} [shape=box, label=\"chunk {c}\\n{} in, {} internal, {} out\\n{} conns\"];",
+                chunk.ni,
+                chunk.nn,
+                chunk.no,
+                chunk.conns.len()
+            )
+            .unwrap();
+        }
+        for link in &self.links {
+            write!(
+                out,
+                "  c{} -> c{} [label=\"o{}->i{} {}/{}:{}",
+                link.from_chunk,
+                link.to_chunk,
+                link.from_out_idx,
+                link.to_in_idx,
+                code_name(
+                    link.trigger,
+                    Trigger::try_from(link.trigger).map(trigger_name)
+                ),
+                code_name(link.action, Action::try_from(link.action).map(action_name)),
+                link.order_tag,
+            )
+            .unwrap();
+            if link.delay > 0 {
+                write!(out, " +{}", link.delay).unwrap();
+            }
+            out.push_str("\"];\n");
+        }
+        for embed in &self.embeds {
+            writeln!(
+                out,
+                "  c{} -> c{} [style=dotted, label=\"embed {} {}\"];",
+                embed.parent_chunk,
+                embed.child_chunk,
+                code_name(
+                    embed.gate_section,
+                    Section::try_from(embed.gate_section).map(section_name)
+                ),
+                embed.gate_bit,
+            )
+            .unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// JSON of the whole genome, in the same form it is saved in.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("genome serializes to JSON")
+    }
+}
+
+impl ChunkGene {
+    /// Connection graph of this gene, rendered like [`to_dot`](fn@to_dot). Connections
+    /// with unknown section, trigger or action codes are left out.
+    pub fn to_dot(&self) -> String {
+        let bits = |init, count: u32| {
+            let mut bytes = pack_bits(init);
+            bytes.resize(count.div_ceil(8) as usize, 0);
+            bytes
+        };
+        let chunk = MycosChunk {
+            input_bits: bits(&self.inputs_init, self.ni),
+            output_bits: bits(&self.outputs_init, self.no),
+            internal_bits: bits(&self.internals_init, self.nn),
+            input_count: self.ni,
+            output_count: self.no,
+            internal_count: self.nn,
+            connections: self
+                .conns
+                .iter()
+                .filter_map(|c| c.to_connection().ok())
+                .collect(),
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        };
+        to_dot(&chunk)
+    }
+}
+
+fn code_name<E>(code: u8, name: Result<&'static str, E>) -> String {
+    name.map_or_else(|_| format!("?{code}"), str::to_string)
+}
+
+/// Node and edge statements of `chunk`, node ids prefixed with `prefix`.
+fn write_chunk(out: &mut String, chunk: &MycosChunk, prefix: &str, indent: &str) {
+    for (section, bits, count) in [
+        (Section::Input, &chunk.input_bits, chunk.input_count),
+        (
+            Section::Internal,
+            &chunk.internal_bits,
+            chunk.internal_count,
+        ),
+        (Section::Output, &chunk.output_bits, chunk.output_count),
+    ] {
+        let ids: Vec<String> = (0..count).map(|i| node(prefix, section, i)).collect();
+        for (i, id) in ids.iter().enumerate() {
+            let i = i as u32;
+            let label = chunk
+                .ports
+                .iter()
+                .find(|p| p.section == section && p.index == i)
+                .map_or_else(
+                    || format!("{} {i}", section_name(section)),
+                    |p| p.name.clone(),
+                );
+            write!(out, "{indent}{id} [label=\"{}\"", escape(&label)).unwrap();
+            if bits[(i / 8) as usize] >> (i % 8) & 1 == 1 {
+                out.push_str(", style=filled");
+            }
+            out.push_str("];\n");
+        }
+        let rank = match section {
+            Section::Input => "source",
+            Section::Internal => continue,
+            Section::Output => "sink",
+        };
+        if !ids.is_empty() {
+            writeln!(out, "{indent}{{ rank={rank}; {}; }}", ids.join("; ")).unwrap();
+        }
+    }
+    for conn in &chunk.connections {
+        writeln!(out, "{indent}{}", edge(prefix, conn)).unwrap();
+    }
+}
+
+fn edge(prefix: &str, conn: &Connection) -> String {
+    format!(
+        "{} -> {} [label=\"{}/{}:{}\"];",
+        node(prefix, conn.from_section, conn.from_index),
+        node(prefix, conn.to_section, conn.to_index),
+        trigger_name(conn.trigger),
+        action_name(conn.action),
+        conn.order_tag,
+    )
+}
+
+fn node(prefix: &str, section: Section, index: u32) -> String {
+    let letter = match section {
+        Section::Input => 'i',
+        Section::Internal => 'n',
+        Section::Output => 'o',
+    };
+    format!("{prefix}{letter}{index}")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ChunkBuilder;
+    use crate::chunk::{Action, Trigger};
+    use Section::{Input, Internal, Output};
+
+    #[test]
+    fn renders_chunks_and_links() {
+        let wire = ChunkBuilder::new()
+            .inputs(1)
+            .internal(1)
+            .outputs(1)
+            .set(Internal, 0)
+            .port(Input, 0, "a\"b")
+            .conn((Input, 0), (Internal, 0), Trigger::On, Action::Enable)
+            .conn((Internal, 0), (Output, 0), Trigger::Toggle, Action::Toggle)
+            .build()
+            .unwrap();
+        assert_eq!(
+            to_dot(&wire),
+            "digraph chunk {\n  \
+               i0 [label=\"a\\\"b\"];\n  \
+               { rank=source; i0; }\n  \
+               n0 [label=\"internal 0\", style=filled];\n  \
+               o0 [label=\"output 0\"];\n  \
+               { rank=sink; o0; }\n  \
+               i0 -> n0 [label=\"on/enable:0\"];\n  \
+               n0 -> o0 [label=\"toggle/toggle:0\"];\n\
+             }\n"
+        );
+
+        let link = Link {
+            from_chunk: 0,
+            from_out_idx: 0,
+            trigger: Trigger::Off,
+            action: Action::Disable,
+            to_chunk: 1,
+            to_in_idx: 0,
+            order_tag: 2,
+            delay: 3,
+        };
+        let dot = system_to_dot(&[wire.clone(), wire], &[link]);
+        assert!(dot.contains("subgraph cluster_1 {\n    label=\"chunk 1\";"));
+        assert!(dot.contains("    c1_n0 -> c1_o0 [label=\"toggle/toggle:0\"];"));
+        assert!(dot.contains("  c0_o0 -> c1_i0 [style=dashed, label=\"off/disable:2 +3\"];"));
+    }
+
+    #[test]
+    fn genome_renders_without_binaries() {
+        let wire = ChunkBuilder::new()
+            .inputs(1)
+            .internal(1)
+            .outputs(1)
+            .conn((Input, 0), (Internal, 0), Trigger::On, Action::Enable)
+            .conn((Internal, 0), (Output, 0), Trigger::On, Action::Enable)
+            .build()
+            .unwrap();
+        let link = Link {
+            from_chunk: 0,
+            from_out_idx: 0,
+            trigger: Trigger::On,
+            action: Action::Enable,
+            to_chunk: 1,
+            to_in_idx: 0,
+            order_tag: 0,
+            delay: 0,
+        };
+        let mut genome = Genome::from_chunks(&[wire.clone(), wire.clone()], &[link]).unwrap();
+        assert_eq!(genome.chunks[0].to_dot(), to_dot(&wire));
+
+        genome.links[0].trigger = 9;
+        let dot = genome.to_dot();
+        assert!(dot
+            .contains("  c1 [shape=box, label=\"chunk 1\\n1 in, 1 internal, 1 out\\n2 conns\"];"));
+        assert!(dot.contains("  c0 -> c1 [label=\"o0->i0 ?9/enable:0\"];"));
+
+        let json = genome.to_json();
+        let back: Genome = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_json(), json);
+    }
+}
@@ -1,4 +1,8 @@
-use crate::chunk::MycosChunk;
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::ops::Range;
+
+use crate::chunk::{Connection, Limit, MycosChunk, ParseLimits, Section};
 use crate::cpu_ref;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -7,11 +11,14 @@ pub enum IoMode {
     Copy = 1,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Embed {
     pub parent_chunk: u32,
     pub child_chunk: u32,
     pub gate_bit: u32,
+    /// Parent section `gate_bit` indexes; Internal unless the gate comes
+    /// straight from a parent input or output.
+    pub gate_section: Section,
     pub io_mode: IoMode,
     pub map_in: Vec<(u32, u32)>,  // (parent_bit, child_in_bit)
     pub map_out: Vec<(u32, u32)>, // (child_out_bit, parent_bit)
@@ -22,8 +29,69 @@ pub struct Embed {
 pub enum EmbedError {
     UnexpectedEof,
     InvalidIoMode(u8),
+    InvalidGateSection(u8),
+    ChunkOutOfRange {
+        embed: u32,
+        chunk: u32,
+    },
+    GateOutOfRange {
+        embed: u32,
+        gate_bit: u32,
+    },
+    MapInOutOfRange {
+        embed: u32,
+        parent_bit: u32,
+        child_bit: u32,
+    },
+    MapOutOutOfRange {
+        embed: u32,
+        child_bit: u32,
+        parent_bit: u32,
+    },
+    LimitExceeded {
+        limit: Limit,
+        value: u64,
+        max: u64,
+    },
 }
 
+impl std::fmt::Display for EmbedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmbedError::UnexpectedEof => write!(f, "unexpected eof"),
+            EmbedError::InvalidIoMode(v) => write!(f, "invalid io mode {v}"),
+            EmbedError::InvalidGateSection(v) => write!(f, "invalid gate section {v}"),
+            EmbedError::ChunkOutOfRange { embed, chunk } => {
+                write!(f, "embed {embed} references chunk {chunk} out of range")
+            }
+            EmbedError::GateOutOfRange { embed, gate_bit } => {
+                write!(f, "embed {embed} gate bit {gate_bit} out of range")
+            }
+            EmbedError::MapInOutOfRange {
+                embed,
+                parent_bit,
+                child_bit,
+            } => write!(
+                f,
+                "embed {embed} maps parent internal {parent_bit} to child input {child_bit} out of range"
+            ),
+            EmbedError::MapOutOutOfRange {
+                embed,
+                child_bit,
+                parent_bit,
+            } => write!(
+                f,
+                "embed {embed} maps child output {child_bit} to parent output {parent_bit} out of range"
+            ),
+            EmbedError::LimitExceeded { limit, value, max } => {
+                write!(f, "{value} {limit} exceeds the limit of {max}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EmbedError {}
+
 fn read_u32(data: &[u8], cursor: &mut usize) -> Result<u32, EmbedError> {
     if *cursor + 4 > data.len() {
         return Err(EmbedError::UnexpectedEof);
@@ -35,6 +103,21 @@ fn read_u32(data: &[u8], cursor: &mut usize) -> Result<u32, EmbedError> {
 
 /// Parse one or more embed records from the given byte slice.
 pub fn parse_embeds(data: &[u8]) -> Result<Vec<Embed>, EmbedError> {
+    parse_embeds_with_limits(data, &ParseLimits::default())
+}
+
+/// [`parse_embeds`] with explicit size limits.
+pub fn parse_embeds_with_limits(
+    data: &[u8],
+    limits: &ParseLimits,
+) -> Result<Vec<Embed>, EmbedError> {
+    let check = |limit, value: u64, max: u64| {
+        if value > max {
+            return Err(EmbedError::LimitExceeded { limit, value, max });
+        }
+        Ok(())
+    };
+    check(Limit::Bytes, data.len() as u64, limits.max_bytes as u64)?;
     let mut cursor = 0usize;
     let mut embeds = Vec::new();
     while cursor < data.len() {
@@ -51,13 +134,26 @@ pub fn parse_embeds(data: &[u8]) -> Result<Vec<Embed>, EmbedError> {
             None => return Err(EmbedError::UnexpectedEof),
         };
         cursor += 1;
-        // skip reserved[3]
-        if cursor + 3 > data.len() {
+        // a Section code; containers before version 3 left the byte reserved,
+        // and parse_container reads those gates as internal
+        let gate_section = match data.get(cursor) {
+            Some(&v) => Section::try_from(v).map_err(|_| EmbedError::InvalidGateSection(v))?,
+            None => return Err(EmbedError::UnexpectedEof),
+        };
+        cursor += 1;
+        // skip reserved[2]
+        if cursor + 2 > data.len() {
             return Err(EmbedError::UnexpectedEof);
         }
-        cursor += 3;
-        let map_in_count = read_u32(data, &mut cursor)? as usize;
-        if cursor + map_in_count * 8 > data.len() {
+        cursor += 2;
+        let map_in_count = read_u32(data, &mut cursor)?;
+        check(
+            Limit::MapEntries,
+            map_in_count as u64,
+            limits.max_map_entries as u64,
+        )?;
+        let map_in_count = map_in_count as usize;
+        if map_in_count * 8 > data.len() - cursor {
             return Err(EmbedError::UnexpectedEof);
         }
         let mut map_in = Vec::with_capacity(map_in_count);
@@ -66,8 +162,14 @@ pub fn parse_embeds(data: &[u8]) -> Result<Vec<Embed>, EmbedError> {
             let child_in_bit = read_u32(data, &mut cursor)?;
             map_in.push((parent_bit, child_in_bit));
         }
-        let map_out_count = read_u32(data, &mut cursor)? as usize;
-        if cursor + map_out_count * 8 > data.len() {
+        let map_out_count = read_u32(data, &mut cursor)?;
+        check(
+            Limit::MapEntries,
+            map_out_count as u64,
+            limits.max_map_entries as u64,
+        )?;
+        let map_out_count = map_out_count as usize;
+        if map_out_count * 8 > data.len() - cursor {
             return Err(EmbedError::UnexpectedEof);
         }
         let mut map_out = Vec::with_capacity(map_out_count);
@@ -80,6 +182,7 @@ pub fn parse_embeds(data: &[u8]) -> Result<Vec<Embed>, EmbedError> {
             parent_chunk,
             child_chunk,
             gate_bit,
+            gate_section,
             io_mode,
             map_in,
             map_out,
@@ -89,6 +192,94 @@ pub fn parse_embeds(data: &[u8]) -> Result<Vec<Embed>, EmbedError> {
     Ok(embeds)
 }
 
+/// Encode embed records into the byte layout read by [`parse_embeds`].
+/// `gate_prev` is runtime state and is not stored.
+pub fn encode_embeds(embeds: &[Embed]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for e in embeds {
+        out.extend_from_slice(&e.parent_chunk.to_le_bytes());
+        out.extend_from_slice(&e.child_chunk.to_le_bytes());
+        out.extend_from_slice(&e.gate_bit.to_le_bytes());
+        out.push(e.io_mode as u8);
+        out.push(e.gate_section as u8);
+        out.extend_from_slice(&[0, 0]); // reserved
+        out.extend_from_slice(&(e.map_in.len() as u32).to_le_bytes());
+        for (parent_bit, child_in_bit) in &e.map_in {
+            out.extend_from_slice(&parent_bit.to_le_bytes());
+            out.extend_from_slice(&child_in_bit.to_le_bytes());
+        }
+        out.extend_from_slice(&(e.map_out.len() as u32).to_le_bytes());
+        for (child_out_bit, parent_bit) in &e.map_out {
+            out.extend_from_slice(&child_out_bit.to_le_bytes());
+            out.extend_from_slice(&parent_bit.to_le_bytes());
+        }
+    }
+    out
+}
+
+/// Check that every embed references existing chunks, that its gate bit lies
+/// in the parent's gate section, and that each `map_in` pair names a
+/// parent internal and child input bit and each `map_out` pair a child output
+/// and parent output bit.
+pub fn validate_embeds(embeds: &[Embed], chunks: &[MycosChunk]) -> Result<(), EmbedError> {
+    for (i, embed) in embeds.iter().enumerate() {
+        let embed_idx = i as u32;
+        let chunk = |index: u32| {
+            chunks
+                .get(index as usize)
+                .ok_or(EmbedError::ChunkOutOfRange {
+                    embed: embed_idx,
+                    chunk: index,
+                })
+        };
+        let parent = chunk(embed.parent_chunk)?;
+        let child = chunk(embed.child_chunk)?;
+        if embed.gate_bit >= section_len(parent, embed.gate_section) {
+            return Err(EmbedError::GateOutOfRange {
+                embed: embed_idx,
+                gate_bit: embed.gate_bit,
+            });
+        }
+        for &(parent_bit, child_bit) in &embed.map_in {
+            if parent_bit >= parent.internal_count || child_bit >= child.input_count {
+                return Err(EmbedError::MapInOutOfRange {
+                    embed: embed_idx,
+                    parent_bit,
+                    child_bit,
+                });
+            }
+        }
+        for &(child_bit, parent_bit) in &embed.map_out {
+            if child_bit >= child.output_count || parent_bit >= parent.output_count {
+                return Err(EmbedError::MapOutOutOfRange {
+                    embed: embed_idx,
+                    child_bit,
+                    parent_bit,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn section_len(chunk: &MycosChunk, section: Section) -> u32 {
+    match section {
+        Section::Input => chunk.input_count,
+        Section::Internal => chunk.internal_count,
+        Section::Output => chunk.output_count,
+    }
+}
+
+/// Current state of the embed's gate bit in `parent`.
+fn gate_state(parent: &MycosChunk, embed: &Embed) -> bool {
+    let bits = match embed.gate_section {
+        Section::Input => &parent.input_bits,
+        Section::Internal => &parent.internal_bits,
+        Section::Output => &parent.output_bits,
+    };
+    get_bit(bits, embed.gate_bit)
+}
+
 fn get_bit(bytes: &[u8], idx: u32) -> bool {
     let byte = bytes[(idx / 8) as usize];
     ((byte >> (idx % 8)) & 1) != 0
@@ -103,39 +294,176 @@ fn set_bit_val(bytes: &mut [u8], idx: u32, val: bool) {
     }
 }
 
-/// Execute the child chunk if the parent's gate bit is set.
-/// Child inputs/outputs are aliased to parent bits per `map_in`/`map_out`.
-/// Parent connections are not evaluated here; caller should run parent logic first if needed.
-pub fn execute_gated_alias(parent: &mut MycosChunk, child: &MycosChunk, embed: &Embed) {
-    if !get_bit(&parent.internal_bits, embed.gate_bit) {
-        return;
+/// Execute a parent chunk together with a child embedded in alias mode.
+///
+/// Mapped bits share storage: child Input `c` of a `map_in` pair *is* parent
+/// Internal `p`, and child Output `c` of a `map_out` pair *is* parent Output
+/// `p` (the first pair wins when a child bit is mapped twice). Both chunks
+/// run as one event loop over that shared view, so a parent change to an
+/// aliased bit fires the child's edges mid-execution and child writes land in
+/// the parent as they happen. Child connections only fire while the parent's
+/// gate bit is set. Parent and child state, including the child's unmapped
+/// bits, are written back afterwards.
+pub fn execute_gated_alias(parent: &mut MycosChunk, child: &mut MycosChunk, embed: &Embed) {
+    let Ok(()) = gated_alias_with(parent, child, embed, |merged, gate, gated| {
+        Ok::<_, Infallible>(cpu_ref::execute_gated(merged, gate, gated))
+    });
+}
+
+/// [`execute_gated_alias`] with the merged chunk run by `execute`, which
+/// gets the gate bit and the range of gated (child) connections and returns
+/// the final Input, Output and Internal bytes like
+/// [`execute`](crate::execute). Neither chunk changes if `execute` fails.
+pub(crate) fn gated_alias_with<E>(
+    parent: &mut MycosChunk,
+    child: &mut MycosChunk,
+    embed: &Embed,
+    execute: impl FnOnce(
+        &MycosChunk,
+        (Section, u32),
+        Range<usize>,
+    ) -> Result<(Vec<u8>, Vec<u8>, Vec<u8>), E>,
+) -> Result<(), E> {
+    let first = |pairs: &[(u32, u32)], bit: u32, key: fn(&(u32, u32)) -> (u32, u32)| {
+        pairs
+            .iter()
+            .map(key)
+            .find(|&(c, _)| c == bit)
+            .map(|(_, p)| p)
+    };
+    let alias_in = |c: u32| first(&embed.map_in, c, |&(p, c)| (c, p));
+    let alias_out = |c: u32| first(&embed.map_out, c, |&(c, p)| (c, p));
+
+    // merged Internal layout: parent internals, child internals, then the
+    // child's unmapped inputs and unmapped outputs
+    let base = parent.internal_count;
+    let mut private = base + child.internal_count;
+    let mut input_at = Vec::with_capacity(child.input_count as usize);
+    for c in 0..child.input_count {
+        input_at.push(alias_in(c).map_or_else(
+            || {
+                private += 1;
+                (Section::Internal, private - 1)
+            },
+            |p| (Section::Internal, p),
+        ));
+    }
+    let mut output_at = Vec::with_capacity(child.output_count as usize);
+    for c in 0..child.output_count {
+        output_at.push(alias_out(c).map_or_else(
+            || {
+                private += 1;
+                (Section::Internal, private - 1)
+            },
+            |p| (Section::Output, p),
+        ));
+    }
+    let child_bit = |section: Section, index: u32| match section {
+        Section::Input => input_at[index as usize],
+        Section::Internal => (Section::Internal, base + index),
+        Section::Output => output_at[index as usize],
+    };
+
+    let mut internals: Vec<bool> = (0..parent.internal_count)
+        .map(|i| get_bit(&parent.internal_bits, i))
+        .chain((0..child.internal_count).map(|i| get_bit(&child.internal_bits, i)))
+        .collect();
+    internals.resize(private as usize, false);
+    for (c, &(section, at)) in input_at.iter().enumerate() {
+        if section == Section::Internal && at >= base + child.internal_count {
+            internals[at as usize] = get_bit(&child.input_bits, c as u32);
+        }
+    }
+    for (c, &(section, at)) in output_at.iter().enumerate() {
+        if section == Section::Internal {
+            internals[at as usize] = get_bit(&child.output_bits, c as u32);
+        }
+    }
+
+    let mut connections = parent.connections.clone();
+    let gated = connections.len()..connections.len() + child.connections.len();
+    connections.extend(child.connections.iter().map(|conn| {
+        let (from_section, from_index) = child_bit(conn.from_section, conn.from_index);
+        let (to_section, to_index) = child_bit(conn.to_section, conn.to_index);
+        Connection {
+            from_section,
+            to_section,
+            from_index,
+            to_index,
+            ..conn.clone()
+        }
+    }));
+    let merged = MycosChunk {
+        internal_bits: pack_bits(&internals),
+        internal_count: private,
+        connections,
+        name: None,
+        note: None,
+        build_hash: None,
+        ports: Vec::new(),
+        ..parent.clone()
+    };
+
+    let gate = (embed.gate_section, embed.gate_bit);
+    let (_, outputs, internals) = execute(&merged, gate, gated)?;
+    let read = |(section, at): (Section, u32)| match section {
+        Section::Output => get_bit(&outputs, at),
+        _ => get_bit(&internals, at),
+    };
+    parent.output_bits = outputs.clone();
+    for i in 0..parent.internal_count {
+        set_bit_val(&mut parent.internal_bits, i, get_bit(&internals, i));
+    }
+    for i in 0..child.internal_count {
+        set_bit_val(&mut child.internal_bits, i, get_bit(&internals, base + i));
     }
-    let mut child_clone = child.clone();
-    // alias inputs from parent (internal/output) bits
-    for (p_bit, c_bit) in &embed.map_in {
-        let val = get_bit(&parent.internal_bits, *p_bit);
-        set_bit_val(&mut child_clone.input_bits, *c_bit, val);
+    for (c, &at) in input_at.iter().enumerate() {
+        set_bit_val(&mut child.input_bits, c as u32, read(at));
     }
-    let (_ci, child_out, _cn) = cpu_ref::execute(&child_clone);
-    for (c_bit, p_bit) in &embed.map_out {
-        let val = get_bit(&child_out, *c_bit);
-        set_bit_val(&mut parent.output_bits, *p_bit, val);
+    for (c, &at) in output_at.iter().enumerate() {
+        set_bit_val(&mut child.output_bits, c as u32, read(at));
     }
+    Ok(())
+}
+
+fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    let mut out = vec![0u8; bits.len().div_ceil(8)];
+    for (i, &bit) in bits.iter().enumerate() {
+        set_bit_val(&mut out, i as u32, bit);
+    }
+    out
 }
 
 /// Execute the child chunk in copy-in/copy-out mode.
 /// Inputs are copied from parent when the gate bit transitions from 0→1.
 /// After running the child to quiescence, outputs are copied back to the parent.
 pub fn execute_gated_copy(parent: &mut MycosChunk, child: &mut MycosChunk, embed: &mut Embed) {
-    let gate_now = get_bit(&parent.internal_bits, embed.gate_bit);
-    if gate_now && !embed.gate_prev {
-        for (p_bit, c_bit) in &embed.map_in {
-            let val = get_bit(&parent.internal_bits, *p_bit);
-            set_bit_val(&mut child.input_bits, *c_bit, val);
-        }
-    }
+    let Ok(()) = gated_copy_with(parent, child, embed, |child| {
+        Ok::<_, Infallible>(cpu_ref::execute(child))
+    });
+}
+
+/// [`execute_gated_copy`] with the child run to quiescence by `execute`.
+/// Nothing changes, `embed.gate_prev` included, if `execute` fails.
+pub(crate) fn gated_copy_with<E>(
+    parent: &mut MycosChunk,
+    child: &mut MycosChunk,
+    embed: &mut Embed,
+    execute: impl FnOnce(&MycosChunk) -> Result<(Vec<u8>, Vec<u8>, Vec<u8>), E>,
+) -> Result<(), E> {
+    let gate_now = gate_state(parent, embed);
     if gate_now {
-        let (ci, co, cn) = cpu_ref::execute(child);
+        // copy in on a rising gate, into a copy of the child so a failed run
+        // leaves it as it was
+        let mut staged = Cow::Borrowed(&*child);
+        if !embed.gate_prev {
+            let staged = staged.to_mut();
+            for (p_bit, c_bit) in &embed.map_in {
+                let val = get_bit(&parent.internal_bits, *p_bit);
+                set_bit_val(&mut staged.input_bits, *c_bit, val);
+            }
+        }
+        let (ci, co, cn) = execute(&staged)?;
         child.input_bits = ci;
         child.output_bits.clone_from(&co);
         child.internal_bits = cn;
@@ -145,12 +473,13 @@ pub fn execute_gated_copy(parent: &mut MycosChunk, child: &mut MycosChunk, embed
         }
     }
     embed.gate_prev = gate_now;
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::chunk::{parse_chunk, MycosChunk};
+    use crate::chunk::{parse_chunk, Action, MycosChunk, Trigger};
 
     #[test]
     fn parse_basic_embed() {
@@ -162,7 +491,8 @@ mod tests {
             1, 0, 0, 0, // child_chunk
             0, 0, 0, 0, // gate_bit
             0, // io_mode alias
-            0, 0, 0, // reserved
+            1, // gate_section internal
+            0, 0, // reserved
             1, 0, 0, 0, // map_in_count
             1, 0, 0, 0, // parent_bit
             0, 0, 0, 0, // child_in_bit
@@ -176,10 +506,24 @@ mod tests {
         assert_eq!(e.parent_chunk, 0);
         assert_eq!(e.child_chunk, 1);
         assert_eq!(e.gate_bit, 0);
+        assert_eq!(e.gate_section, Section::Internal);
         assert_eq!(e.io_mode, IoMode::Alias);
         assert!(!e.gate_prev);
         assert_eq!(e.map_in, vec![(1, 0)]);
         assert_eq!(e.map_out, vec![(0, 0)]);
+
+        assert_eq!(encode_embeds(&embeds), data);
+
+        // a crafted map count fails before the allocation
+        let mut huge = data.clone();
+        huge[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            parse_embeds(&huge),
+            Err(EmbedError::LimitExceeded {
+                limit: Limit::MapEntries,
+                ..
+            })
+        ));
     }
 
     #[test]
@@ -196,6 +540,7 @@ mod tests {
             name: None,
             note: None,
             build_hash: None,
+            ports: Vec::new(),
         };
         // Child chunk from fixture
         let data = std::fs::read(
@@ -205,11 +550,12 @@ mod tests {
                 .join("gated_child.myc"),
         )
         .unwrap();
-        let child = parse_chunk(&data).unwrap();
+        let mut child = parse_chunk(&data).unwrap();
         let embed = Embed {
             parent_chunk: 0,
             child_chunk: 1,
             gate_bit: 0,
+            gate_section: Section::Internal,
             io_mode: IoMode::Alias,
             map_in: vec![(1, 0)],  // parent internal[1] -> child input[0]
             map_out: vec![(0, 0)], // child output[0] -> parent output[0]
@@ -220,17 +566,176 @@ mod tests {
         let mut parent_state = parent.clone();
         // set child input source to 1 but gate stays 0
         parent_state.internal_bits[0] |= 1 << 1;
-        execute_gated_alias(&mut parent_state, &child, &embed);
+        execute_gated_alias(&mut parent_state, &mut child, &embed);
         assert_eq!(parent_state.output_bits[0], 0);
 
         // Case 2: gate=1 -> child active
         let mut parent_state = parent.clone();
         parent_state.internal_bits[0] |= 1 << 0; // gate on
         parent_state.internal_bits[0] |= 1 << 1; // input high
-        execute_gated_alias(&mut parent_state, &child, &embed);
+        execute_gated_alias(&mut parent_state, &mut child, &embed);
         assert_eq!(parent_state.output_bits[0], 1);
     }
 
+    #[test]
+    fn alias_propagates_mid_execution() {
+        // the parent drives internal[1] from its input during the same pass;
+        // a copy taken before execution would miss it
+        let parent = MycosChunk {
+            input_bits: vec![1],
+            output_bits: vec![0],
+            internal_bits: vec![1],
+            input_count: 1,
+            output_count: 1,
+            internal_count: 2,
+            connections: vec![Connection {
+                from_section: Section::Input,
+                to_section: Section::Internal,
+                trigger: Trigger::On,
+                action: Action::Enable,
+                from_index: 0,
+                to_index: 1,
+                order_tag: 0,
+            }],
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        };
+        let data = std::fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("..")
+                .join("fixtures")
+                .join("gated_child.myc"),
+        )
+        .unwrap();
+        let mut child = parse_chunk(&data).unwrap();
+        let embed = Embed {
+            parent_chunk: 0,
+            child_chunk: 1,
+            gate_bit: 0,
+            gate_section: Section::Internal,
+            io_mode: IoMode::Alias,
+            map_in: vec![(1, 0)],
+            map_out: vec![(0, 0)],
+            gate_prev: false,
+        };
+        let mut parent_state = parent.clone();
+        execute_gated_alias(&mut parent_state, &mut child, &embed);
+        assert_eq!(parent_state.internal_bits[0], 0b11);
+        assert_eq!(parent_state.output_bits[0], 1);
+        // the child's view of the shared bits is written back
+        assert_eq!((child.input_bits[0], child.output_bits[0]), (1, 1));
+    }
+
+    #[test]
+    fn gate_from_parent_input() {
+        // Parent chunk: Ni=1 (gate), No=1, Nn=1 (mapped input)
+        let parent = MycosChunk {
+            input_bits: vec![0],
+            output_bits: vec![0],
+            internal_bits: vec![1],
+            input_count: 1,
+            output_count: 1,
+            internal_count: 1,
+            connections: vec![],
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        };
+        let data = std::fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("..")
+                .join("fixtures")
+                .join("gated_child.myc"),
+        )
+        .unwrap();
+        let mut child = parse_chunk(&data).unwrap();
+        let mut embed = Embed {
+            parent_chunk: 0,
+            child_chunk: 1,
+            gate_bit: 0,
+            gate_section: Section::Input,
+            io_mode: IoMode::Copy,
+            map_in: vec![(0, 0)],
+            map_out: vec![(0, 0)],
+            gate_prev: false,
+        };
+        let bytes = encode_embeds(std::slice::from_ref(&embed));
+        assert_eq!(bytes[13], 0);
+        assert_eq!(
+            parse_embeds(&bytes).unwrap()[0].gate_section,
+            Section::Input
+        );
+        validate_embeds(
+            std::slice::from_ref(&embed),
+            &[parent.clone(), child.clone()],
+        )
+        .unwrap();
+
+        let mut parent_state = parent.clone();
+        execute_gated_copy(&mut parent_state, &mut child, &mut embed);
+        assert_eq!(parent_state.output_bits[0], 0);
+        parent_state.input_bits[0] = 1;
+        execute_gated_copy(&mut parent_state, &mut child, &mut embed);
+        assert_eq!(parent_state.output_bits[0], 1);
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_bits() {
+        let chunk = |ni: u32, no: u32, nn: u32| MycosChunk {
+            input_bits: vec![0; ni.div_ceil(8) as usize],
+            output_bits: vec![0; no.div_ceil(8) as usize],
+            internal_bits: vec![0; nn.div_ceil(8) as usize],
+            input_count: ni,
+            output_count: no,
+            internal_count: nn,
+            connections: vec![],
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        };
+        let chunks = [chunk(0, 1, 2), chunk(1, 1, 1)];
+        let embed = Embed {
+            parent_chunk: 0,
+            child_chunk: 1,
+            gate_bit: 0,
+            gate_section: Section::Internal,
+            io_mode: IoMode::Alias,
+            map_in: vec![(1, 0)],
+            map_out: vec![(0, 0)],
+            gate_prev: false,
+        };
+        validate_embeds(std::slice::from_ref(&embed), &chunks).unwrap();
+
+        let check = |edit: fn(&mut Embed)| {
+            let mut bad = embed.clone();
+            edit(&mut bad);
+            validate_embeds(&[embed.clone(), bad], &chunks).unwrap_err()
+        };
+        assert!(matches!(
+            check(|e| e.child_chunk = 2),
+            EmbedError::ChunkOutOfRange { embed: 1, chunk: 2 }
+        ));
+        assert!(matches!(
+            check(|e| e.gate_bit = 2),
+            EmbedError::GateOutOfRange {
+                embed: 1,
+                gate_bit: 2
+            }
+        ));
+        assert!(matches!(
+            check(|e| e.map_in.push((0, 1))),
+            EmbedError::MapInOutOfRange { child_bit: 1, .. }
+        ));
+        assert!(matches!(
+            check(|e| e.map_out[0].1 = 3),
+            EmbedError::MapOutOutOfRange { parent_bit: 3, .. }
+        ));
+    }
+
     #[test]
     fn copy_mode_gate_edges() {
         // Parent chunk: Ni=0, No=1, Nn=2 (gate + mapped input)
@@ -245,6 +750,7 @@ mod tests {
             name: None,
             note: None,
             build_hash: None,
+            ports: Vec::new(),
         };
         let data = std::fs::read(
             std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -258,6 +764,7 @@ mod tests {
             parent_chunk: 0,
             child_chunk: 1,
             gate_bit: 0,
+            gate_section: Section::Internal,
             io_mode: IoMode::Copy,
             map_in: vec![(1, 0)],
             map_out: vec![(0, 0)],
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use crate::FitnessResult;
+
+/// Bounded least-recently-used cache of evaluation results keyed by
+/// [`genome_hash`](crate::genome_hash).
+///
+/// Only valid while evaluation depends on nothing but the genome structure;
+/// `meta` and gene order are not part of the key.
+#[derive(Clone, Debug, Default)]
+pub struct EvalCache {
+    capacity: usize,
+    entries: HashMap<[u8; 32], (FitnessResult, u64)>,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl EvalCache {
+    /// A cache holding at most `capacity` results; 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Self::default()
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Lookups that found a result.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Lookups that did not.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Cached result for `hash`, marking it as recently used.
+    pub fn get(&mut self, hash: [u8; 32]) -> Option<&FitnessResult> {
+        self.clock += 1;
+        match self.entries.get_mut(&hash) {
+            Some((result, used)) => {
+                *used = self.clock;
+                self.hits += 1;
+                Some(result)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a result, evicting the least recently used one when full.
+    pub fn insert(&mut self, hash: [u8; 32], result: FitnessResult) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&hash) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(&h, _)| h);
+            if let Some(h) = oldest {
+                self.entries.remove(&h);
+            }
+        }
+        self.entries.insert(hash, (result, self.clock));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(fitness: f32) -> FitnessResult {
+        FitnessResult {
+            fitness,
+            ..FitnessResult::default()
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = EvalCache::new(2);
+        cache.insert([1; 32], result(0.1));
+        cache.insert([2; 32], result(0.2));
+        assert_eq!(cache.get([1; 32]).unwrap().fitness, 0.1);
+        cache.insert([3; 32], result(0.3));
+        assert!(cache.get([2; 32]).is_none());
+        assert_eq!(cache.get([3; 32]).unwrap().fitness, 0.3);
+        assert_eq!(cache.get([1; 32]).unwrap().fitness, 0.1);
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (3, 1, 2));
+
+        let mut off = EvalCache::new(0);
+        off.insert([1; 32], result(1.0));
+        assert!(off.is_empty());
+    }
+}
@@ -1,13 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::ControlFlow;
+use std::time::Duration;
 
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    checkpoint::{save, Checkpoint},
-    crossover, evaluate_batch,
-    gpu_eval::Episode,
-    mutate, Genome, Task,
+    checkpoint::{export_best, save, Checkpoint, TaskIdentity},
+    crossover_n, crossover_with, genome_hash,
+    gpu_eval::{CpuEvaluator, Episode, Evaluator, OscillationStats},
+    innovation::{conn_id, link_id, ConnKey, GeneId, InnovationTracker, LinkKey},
+    mutate_with,
+    novelty::{behavior, score_novelty},
+    CrossoverStrategy, EvalCache, FitnessResult, Genome, HallOfFame, MutationConfig,
+    MutationRegistry, NoveltyArchive, NoveltyConfig, Parsimony, RandomGenomeParams, ScoreBreakdown,
+    Selection, Task, TaskGenerator, TaskRegistry, ValidationError,
 };
 
 /// Configuration for the evolution loop.
@@ -15,183 +23,1856 @@ use crate::{
 /// The structure intentionally exposes only a subset of the parameters from the
 /// design document so the loop can be exercised in tests without needing the
 /// full runtime. Additional fields can be added as the engine matures.
-#[derive(Clone)]
+///
+/// Serializes with tasks stored by name; `operators` and `on_generation`
+/// are skipped and must be set in code. Fields with a default may be left
+/// out of a config file.
+#[derive(Serialize, Deserialize)]
 pub struct EvoConfig {
     /// Task describing episodes and scoring.
+    #[serde(with = "crate::tasks::by_name")]
     pub task: Task,
+    /// Tasks trained after `task`, in order.
+    #[serde(default)]
+    pub curriculum: Curriculum,
+    /// When set, the first curriculum stage trains on episodes drawn afresh
+    /// every generation instead of the episodes of `task`.
+    #[serde(default)]
+    pub sampling: Option<EpisodeSampling>,
     /// Genome used as a template for initial population.
     pub base_genome: Genome,
+    /// When set, generation zero is a ramped set of random genomes drawn
+    /// with these bounds instead of mutated copies of `base_genome`.
+    #[serde(default)]
+    pub random_init: Option<RandomGenomeParams>,
     /// Number of individuals per generation.
     pub pop_size: usize,
     /// Number of generations to run.
     pub generations: u32,
+    /// Stop after the first generation that ends past this wall-clock
+    /// budget, writing a checkpoint.
+    #[serde(default)]
+    pub max_wall_time: Option<Duration>,
     /// Write a checkpoint every `checkpoint_interval` generations.
     pub checkpoint_interval: u32,
     /// File path for checkpoints. The file is overwritten each time.
     pub checkpoint_path: std::path::PathBuf,
+    /// With every checkpoint, also write the generation's fittest genome as
+    /// chunk and link binaries next to `checkpoint_path`; see
+    /// [`export_best`].
+    #[serde(default)]
+    pub export_best: bool,
     /// Optional speciation threshold; if `None` all individuals share one
     /// species.
     pub speciation_threshold: Option<f32>,
-    /// Tournament size used during selection.
-    pub tournament_size: usize,
+    /// When set, the threshold is nudged after every generation towards
+    /// producing this many species.
+    #[serde(default)]
+    pub target_species: Option<usize>,
+    /// Amount the threshold moves per generation when `target_species` is
+    /// set.
+    pub threshold_step: f32,
+    /// Remove species whose best fitness has not improved for this many
+    /// generations; their offspring go to the surviving species.
+    #[serde(default)]
+    pub stagnation_limit: Option<u32>,
+    /// Size and activity penalties subtracted from every evaluated fitness.
+    #[serde(default)]
+    pub parsimony: Parsimony,
+    /// Weights of the terms in [`compatibility_distance`].
+    #[serde(default)]
+    pub compatibility: CompatibilityCoefficients,
+    /// Parent selection scheme applied within each species.
+    #[serde(default)]
+    pub selection: Selection,
     /// Number of elite individuals preserved per species.
     pub elitism: usize,
     /// Probability of applying crossover when generating offspring.
     pub crossover_rate: f32,
+    /// Recombination scheme used when crossover is applied.
+    #[serde(default)]
+    pub crossover: CrossoverStrategy,
+    /// Parents per crossover. Above two, species with at least that many
+    /// members use majority-vote [`crossover_n`] instead of `crossover`.
+    pub crossover_parents: usize,
     /// Probability of applying mutation to an offspring genome.
     pub mutation_rate: f32,
+    /// Per-operator probabilities used whenever a genome is mutated.
+    #[serde(default)]
+    pub mutation: MutationConfig,
+    /// Custom operators rolled after the built-in ones.
+    #[serde(skip)]
+    pub operators: MutationRegistry,
+    /// Number of best distinct genomes kept in the hall of fame; 0 disables it.
+    #[serde(default)]
+    pub hall_of_fame: usize,
+    /// When set, selection uses fitness blended with behavioural novelty
+    /// instead of raw fitness.
+    #[serde(default)]
+    pub novelty: Option<NoveltyConfig>,
+    /// Capacity of the LRU cache that lets structurally identical genomes,
+    /// such as elites, reuse their evaluation; 0 disables it.
+    #[serde(default)]
+    pub eval_cache: usize,
     /// Seed for the top-level RNG driving evolution.
     pub seed: u64,
+    /// Called once per generation after evaluation and speciation; returning
+    /// `ControlFlow::Break` ends the run early.
+    #[serde(skip)]
+    pub on_generation: Option<GenerationHook>,
+}
+
+impl EvoConfig {
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str::<Self>(json)
+            .map_err(ConfigError::Json)?
+            .validated()
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        toml::from_str::<Self>(toml)
+            .map_err(ConfigError::Toml)?
+            .validated()
+    }
+
+    /// Like [`from_json`](Self::from_json), resolving task names through
+    /// `tasks` instead of [`TaskRegistry::builtin`].
+    pub fn from_json_with(json: &str, tasks: &TaskRegistry) -> Result<Self, ConfigError> {
+        let mut value: serde_json::Value = serde_json::from_str(json).map_err(ConfigError::Json)?;
+        if let Some(fields) = value.as_object_mut() {
+            let stages = fields
+                .get_mut("curriculum")
+                .and_then(|c| c.get_mut("stages"))
+                .and_then(|s| s.as_array_mut());
+            for slot in stages
+                .into_iter()
+                .flatten()
+                .filter_map(|s| s.get_mut("task"))
+            {
+                resolve_json(slot, tasks);
+            }
+            if let Some(slot) = fields.get_mut("task") {
+                resolve_json(slot, tasks);
+            }
+        }
+        serde_json::from_value::<Self>(value)
+            .map_err(ConfigError::Json)?
+            .validated()
+    }
+
+    /// Like [`from_toml`](Self::from_toml), resolving task names through
+    /// `tasks` instead of [`TaskRegistry::builtin`].
+    pub fn from_toml_with(toml: &str, tasks: &TaskRegistry) -> Result<Self, ConfigError> {
+        let mut table: toml::Table = toml::from_str(toml).map_err(ConfigError::Toml)?;
+        let stages = table
+            .get_mut("curriculum")
+            .and_then(|c| c.get_mut("stages"))
+            .and_then(|s| s.as_array_mut());
+        for slot in stages
+            .into_iter()
+            .flatten()
+            .filter_map(|s| s.get_mut("task"))
+        {
+            resolve_toml(slot, tasks);
+        }
+        if let Some(slot) = table.get_mut("task") {
+            resolve_toml(slot, tasks);
+        }
+        table
+            .try_into::<Self>()
+            .map_err(ConfigError::Toml)?
+            .validated()
+    }
+
+    /// Reject values serde accepts but the loop cannot run.
+    fn validated(self) -> Result<Self, ConfigError> {
+        if let Some(sampling) = &self.sampling {
+            if sampling.train == 0 {
+                return Err(ConfigError::Sampling("train must be at least 1"));
+            }
+            if !sampling.generator.in_range() {
+                return Err(ConfigError::Sampling("generator size out of range"));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Stable FNV-1a digest of the serialized config, ignoring how long and
+    /// where the run checkpoints (`generations`, `max_wall_time`,
+    /// `checkpoint_interval`, `checkpoint_path`, `export_best`), so
+    /// extending or relocating a run keeps the digest.
+    pub fn digest(&self) -> u64 {
+        let value = self.to_value_without(&UNDIGESTED);
+        crate::genome::fnv1a(value.to_string().as_bytes())
+    }
+
+    /// Check that `checkpoint` was taken by a run with this config and that
+    /// it was training the same task as this config's task for the
+    /// checkpoint's curriculum stage. Checkpoints that predate the stored
+    /// digest or task pass the corresponding check.
+    pub fn verify_checkpoint(&self, checkpoint: &Checkpoint) -> Result<(), ResumeError> {
+        if checkpoint.config_digest != 0 && checkpoint.config_digest != self.digest() {
+            let ours = self.to_value_without(&UNDIGESTED);
+            let fields = match (checkpoint.config.as_ref(), &ours) {
+                (Some(serde_json::Value::Object(theirs)), serde_json::Value::Object(ours)) => {
+                    let keys: BTreeSet<&String> = theirs.keys().chain(ours.keys()).collect();
+                    keys.into_iter()
+                        .filter(|k| {
+                            !UNDIGESTED.contains(&k.as_str()) && theirs.get(*k) != ours.get(*k)
+                        })
+                        .cloned()
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            return Err(ResumeError::ConfigMismatch { fields });
+        }
+        if let Some(expected) = &checkpoint.task {
+            let found = self.stage_task(checkpoint.stage).map(TaskIdentity::of);
+            if found.as_ref() != Some(expected) {
+                return Err(ResumeError::TaskMismatch {
+                    checkpoint: expected.clone(),
+                    config: found,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Task trained in curriculum stage `stage`: 0 is `task`, `n` is
+    /// `curriculum.stages[n - 1]`.
+    fn stage_task(&self, stage: usize) -> Option<&Task> {
+        match stage {
+            0 => Some(&self.task),
+            n => self.curriculum.stages.get(n - 1).map(|s| &s.task),
+        }
+    }
+
+    /// The serialized config with the top-level `keys` removed.
+    fn to_value_without(&self, keys: &[&str]) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).expect("config serializes to JSON");
+        if let Some(fields) = value.as_object_mut() {
+            for key in keys {
+                fields.remove(*key);
+            }
+        }
+        value
+    }
+
+    /// Read a config file, parsed as TOML when the extension is `.toml` and
+    /// as JSON otherwise.
+    pub fn load(path: &std::path::Path) -> Result<Self, ConfigError> {
+        Self::load_with(path, &TaskRegistry::builtin())
+    }
+
+    /// Like [`load`](Self::load), resolving task names through `tasks`.
+    pub fn load_with(path: &std::path::Path, tasks: &TaskRegistry) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        if path.extension().is_some_and(|e| e == "toml") {
+            Self::from_toml_with(&text, tasks)
+        } else {
+            Self::from_json_with(&text, tasks)
+        }
+    }
+}
+
+/// Replace a task name registered in `tasks` with the task's definition;
+/// anything else is left for [`crate::tasks::by_name`] to reject or read.
+fn resolve_json(slot: &mut serde_json::Value, tasks: &TaskRegistry) {
+    if let Some(task) = slot.as_str().and_then(|name| tasks.get(name)) {
+        *slot = serde_json::to_value(task).expect("task serializes to JSON");
+    }
+}
+
+/// [`resolve_json`] for TOML configs.
+fn resolve_toml(slot: &mut toml::Value, tasks: &TaskRegistry) {
+    if let Some(task) = slot.as_str().and_then(|name| tasks.get(name)) {
+        *slot = toml::Value::try_from(task).expect("task serializes to TOML");
+    }
+}
+
+/// Error loading an [`EvoConfig`].
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    /// `sampling` cannot draw episodes.
+    Sampling(&'static str),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "reading config: {e}"),
+            ConfigError::Json(e) => write!(f, "invalid JSON config: {e}"),
+            ConfigError::Toml(e) => write!(f, "invalid TOML config: {e}"),
+            ConfigError::Sampling(e) => write!(f, "invalid sampling: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Config fields left out of [`EvoConfig::digest`].
+const UNDIGESTED: [&str; 5] = [
+    "generations",
+    "max_wall_time",
+    "checkpoint_interval",
+    "checkpoint_path",
+    "export_best",
+];
+
+/// Error resuming a run with [`resume_evolution`].
+#[derive(Debug)]
+pub enum ResumeError {
+    /// The config digest differs from the checkpoint's. `fields` names the
+    /// differing top-level fields when the checkpoint stores its config.
+    ConfigMismatch {
+        fields: Vec<String>,
+    },
+    /// The checkpoint was training a different task, or a different
+    /// definition of it, than the config has for that curriculum stage.
+    TaskMismatch {
+        checkpoint: TaskIdentity,
+        config: Option<TaskIdentity>,
+    },
+    Validation(ValidationError),
+}
+
+impl std::fmt::Display for ResumeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResumeError::ConfigMismatch { fields } if fields.is_empty() => {
+                write!(f, "config differs from the checkpoint's")
+            }
+            ResumeError::ConfigMismatch { fields } => {
+                write!(
+                    f,
+                    "config differs from the checkpoint's in {}",
+                    fields.join(", ")
+                )
+            }
+            ResumeError::TaskMismatch {
+                checkpoint,
+                config: Some(config),
+            } => write!(
+                f,
+                "checkpoint trains task {checkpoint}, config has {config}"
+            ),
+            ResumeError::TaskMismatch {
+                checkpoint,
+                config: None,
+            } => write!(
+                f,
+                "checkpoint trains task {checkpoint}, config has no such stage"
+            ),
+            ResumeError::Validation(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ResumeError {}
+
+impl From<ValidationError> for ResumeError {
+    fn from(e: ValidationError) -> Self {
+        ResumeError::Validation(e)
+    }
+}
+
+/// Per-generation callback for [`EvoConfig::on_generation`].
+pub type GenerationHook = Box<dyn FnMut(&GenerationSnapshot) -> ControlFlow<()>>;
+
+/// View of the run handed to [`EvoConfig::on_generation`].
+pub struct GenerationSnapshot<'a> {
+    pub stats: &'a GenerationStats,
+    /// Fittest genome of this generation.
+    pub best: &'a Genome,
+    pub hall_of_fame: &'a HallOfFame,
+}
+
+/// Sequence of tasks trained after [`EvoConfig::task`] on the same
+/// population.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Curriculum {
+    pub stages: Vec<CurriculumStage>,
+}
+
+/// One step of a [`Curriculum`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CurriculumStage {
+    #[serde(with = "crate::tasks::by_name")]
+    pub task: Task,
+    /// Best fitness on the previous task at which the loop moves on to this
+    /// one.
+    pub enter_at: f32,
+}
+
+/// Randomised episodes for [`EvoConfig::sampling`].
+///
+/// Training episodes are drawn from the run's RNG each generation, so no
+/// fixed set can be memorised. Validation episodes are drawn once from
+/// `validation_seed` and only ever scored for
+/// [`GenerationStats::validation_fitness`]; they never reach selection.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EpisodeSampling {
+    pub generator: TaskGenerator,
+    /// Training episodes per generation.
+    pub train: usize,
+    /// Held-out validation episodes; 0 disables validation.
+    pub validation: usize,
+    /// Ticks per episode.
+    pub ticks: u32,
+    pub validation_seed: u64,
+}
+
+/// Coefficients of the NEAT-style compatibility distance used for
+/// speciation.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompatibilityCoefficients {
+    /// Weight of genes newer than every gene of the other genome.
+    pub excess: f32,
+    /// Weight of unmatched genes within the other genome's innovation range,
+    /// and of unmatched genes without an innovation number.
+    pub disjoint: f32,
+    /// Weight of the mean trigger/action mismatch over matching genes.
+    pub mismatch: f32,
+    /// Weight of the difference in chunk count.
+    pub chunks: f32,
+}
+
+impl Default for CompatibilityCoefficients {
+    fn default() -> Self {
+        Self {
+            excess: 1.0,
+            disjoint: 1.0,
+            mismatch: 0.4,
+            chunks: 1.0,
+        }
+    }
 }
 
 #[derive(Clone)]
 struct Individual {
     genome: Genome,
     fitness: f32,
+    /// Selection score; equals `fitness` unless novelty search is enabled.
+    score: f32,
+    /// Per-episode scores, the cases of [`Selection::Lexicase`].
+    cases: Vec<f32>,
     species: usize,
 }
 
-/// Run the evolutionary loop returning the final [`Checkpoint`].
+/// Summary of one generation, taken after evaluation and speciation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenerationStats {
+    pub generation: u32,
+    /// Curriculum stage evaluated: 0 for `EvoConfig::task`, `n` for
+    /// `curriculum.stages[n - 1]`.
+    pub stage: usize,
+    pub best_fitness: f32,
+    pub mean_fitness: f32,
+    pub median_fitness: f32,
+    /// Population standard deviation of fitness.
+    pub std_fitness: f32,
+    pub species: usize,
+    pub mean_chunks: f32,
+    pub mean_conns: f32,
+    pub mean_links: f32,
+    pub max_conns: usize,
+    /// Wall time spent in evaluation, cache lookups included; millisecond
+    /// resolution on wasm32.
+    pub eval_time: Duration,
+    /// Individuals whose result came from the evaluation cache.
+    pub cache_hits: usize,
+    /// Task score components of the fittest individual, before parsimony.
+    pub best_breakdown: ScoreBreakdown,
+    /// Score of the fittest individual on the held-out episodes of
+    /// [`EvoConfig::sampling`], before parsimony.
+    #[serde(default)]
+    pub validation_fitness: Option<f32>,
+    /// Oscillation counts over every episode of every individual.
+    #[serde(default)]
+    pub oscillation: OscillationStats,
+}
+
+impl GenerationStats {
+    fn new(
+        generation: u32,
+        stage: usize,
+        population: &[Individual],
+        species: usize,
+        eval_time: Duration,
+        cache_hits: usize,
+        best_breakdown: ScoreBreakdown,
+    ) -> Self {
+        let n = population.len().max(1) as f32;
+        let mut fitness: Vec<f32> = population.iter().map(|i| i.fitness).collect();
+        fitness.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mean = fitness.iter().sum::<f32>() / n;
+        let median = match fitness.len() {
+            0 => 0.0,
+            len if len % 2 == 1 => fitness[len / 2],
+            len => (fitness[len / 2 - 1] + fitness[len / 2]) / 2.0,
+        };
+        let var = fitness.iter().map(|f| (f - mean).powi(2)).sum::<f32>() / n;
+        let conns = |g: &Genome| g.chunks.iter().map(|c| c.conns.len()).sum::<usize>();
+        Self {
+            generation,
+            stage,
+            best_fitness: fitness.last().copied().unwrap_or(0.0),
+            mean_fitness: mean,
+            median_fitness: median,
+            std_fitness: var.sqrt(),
+            species,
+            mean_chunks: population
+                .iter()
+                .map(|i| i.genome.chunks.len())
+                .sum::<usize>() as f32
+                / n,
+            mean_conns: population.iter().map(|i| conns(&i.genome)).sum::<usize>() as f32 / n,
+            mean_links: population
+                .iter()
+                .map(|i| i.genome.links.len())
+                .sum::<usize>() as f32
+                / n,
+            max_conns: population
+                .iter()
+                .map(|i| conns(&i.genome))
+                .max()
+                .unwrap_or(0),
+            eval_time,
+            cache_hits,
+            best_breakdown,
+            validation_fitness: None,
+            oscillation: OscillationStats::default(),
+        }
+    }
+}
+
+/// Outcome of [`run_evolution`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EvolutionReport {
+    /// Final population, RNG state and hall of fame.
+    pub checkpoint: Checkpoint,
+    /// One entry per generation, in order.
+    pub generations: Vec<GenerationStats>,
+}
+
+/// Wall-clock timer; wasm32 has no `Instant`, so it uses `Date.now()`
+/// there.
+struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    start_ms: f64,
+}
+
+impl Stopwatch {
+    fn start() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+            #[cfg(target_arch = "wasm32")]
+            start_ms: js_sys::Date::now(),
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.start.elapsed();
+        #[cfg(target_arch = "wasm32")]
+        Duration::from_secs_f64(((js_sys::Date::now() - self.start_ms) / 1000.0).max(0.0))
+    }
+}
+
+/// A species tracked across generations.
+struct Species {
+    id: usize,
+    /// First member of the previous generation.
+    rep: Genome,
+    /// Best fitness ever reached by a member.
+    best: f32,
+    /// Generations since `best` last improved.
+    stale: u32,
+}
+
+impl Species {
+    fn new(id: usize, rep: Genome) -> Self {
+        Self {
+            id,
+            rep,
+            best: f32::NEG_INFINITY,
+            stale: 0,
+        }
+    }
+
+    /// Record this generation's best member fitness.
+    fn record(&mut self, best: f32) {
+        if best > self.best {
+            self.best = best;
+            self.stale = 0;
+        } else {
+            self.stale += 1;
+        }
+    }
+}
+
+/// Offspring quota per surviving species: its own size plus an even share of
+/// the `freed` slots of extinct species, remainders going to the first
+/// species.
+fn redistribute(sizes: &[usize], freed: usize) -> Vec<usize> {
+    if sizes.is_empty() {
+        return Vec::new();
+    }
+    let n = sizes.len();
+    sizes
+        .iter()
+        .enumerate()
+        .map(|(i, &size)| size + freed / n + usize::from(i < freed % n))
+        .collect()
+}
+
+/// Run the evolutionary loop returning the final [`Checkpoint`], whose
+/// `hall_of_fame` holds the best genomes seen in any generation of the final
+/// curriculum stage, together with per-generation statistics. When the
+/// `on_generation` hook stops the run, the checkpoint holds the evaluated
+/// population of the last generation.
+///
+/// Each generation evaluates the population on the current curriculum stage,
+/// reusing cached results, and subtracts the parsimony penalty. With
+/// `novelty` set it blends in behavioural novelty. It then groups genomes
+/// into species, adapting the threshold towards `target_species`, and drops
+/// stagnant species. The curriculum advances once the best fitness reaches
+/// the next stage's `enter_at`. Each species keeps its elites and breeds its
+/// share of offspring through the configured [`Selection`], crossover and
+/// mutation. Checkpoints are written every `checkpoint_interval` generations
+/// and when `max_wall_time` runs out.
+///
+/// Returns an error if crossover produces an invalid genome, which means an
+/// invalid genome entered the population.
+pub fn run_evolution(config: EvoConfig) -> Result<EvolutionReport, ValidationError> {
+    run_evolution_with(config, &CpuEvaluator)
+}
+
+/// [`run_evolution`] with genomes scored by `evaluator` instead of the CPU
+/// reference runner.
+pub fn run_evolution_with<E: Evaluator + ?Sized>(
+    config: EvoConfig,
+    evaluator: &E,
+) -> Result<EvolutionReport, ValidationError> {
+    Run::new(config).complete(evaluator)
+}
+
+/// Continue the run saved in `checkpoint` until `config.generations` have
+/// been completed in total, scoring with the CPU reference runner.
+///
+/// Unless `force` is set, the config and task must match the ones the
+/// checkpoint was taken with ([`EvoConfig::verify_checkpoint`]).
+/// Population, RNG, hall of fame and curriculum stage are restored; species,
+/// the evaluation cache and the novelty archive start empty, so the result
+/// can differ from an uninterrupted run when those are in use.
+pub fn resume_evolution(
+    config: EvoConfig,
+    checkpoint: Checkpoint,
+    force: bool,
+) -> Result<EvolutionReport, ResumeError> {
+    resume_evolution_with(config, checkpoint, force, &CpuEvaluator)
+}
+
+/// [`resume_evolution`] with genomes scored by `evaluator`.
+pub fn resume_evolution_with<E: Evaluator + ?Sized>(
+    config: EvoConfig,
+    checkpoint: Checkpoint,
+    force: bool,
+    evaluator: &E,
+) -> Result<EvolutionReport, ResumeError> {
+    if !force {
+        config.verify_checkpoint(&checkpoint)?;
+    }
+    Ok(Run::resume(config, checkpoint).complete(evaluator)?)
+}
+
+/// [`run_evolution_with`] as a future that yields to the executor after
+/// every generation, so a browser tab stays responsive while training on the
+/// main thread. Progress is reported through [`EvoConfig::on_generation`].
 ///
-/// The implementation is intentionally minimal but wires together evaluation,
-/// tournament selection, crossover, mutation, and basic checkpointing. It is
-/// sufficient for exercising other components of the engine and can be extended
-/// in future iterations.
-pub fn run_evolution(config: EvoConfig) -> Checkpoint {
-    let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
-
-    // --- Population initialisation ----------------------------------------------------------
-    let mut population: Vec<Individual> = (0..config.pop_size)
-        .map(|_| {
-            let mut g = config.base_genome.clone();
-            let seed = rng.gen();
-            g.meta.seed = seed;
-            // Apply a mutation so the population is not uniform.
-            let mut grng = ChaCha8Rng::seed_from_u64(seed);
-            mutate(&mut g, &mut grng);
-            Individual {
-                genome: g,
-                fitness: 0.0,
+/// On wasm32 each yield waits for a `setTimeout(0)` macrotask, letting the
+/// browser render and handle input; elsewhere the task is simply
+/// rescheduled. Results match [`run_evolution_with`] exactly.
+pub async fn run_evolution_async<E: Evaluator + ?Sized>(
+    config: EvoConfig,
+    evaluator: &E,
+) -> Result<EvolutionReport, ValidationError> {
+    let mut run = Run::new(config);
+    while run.generation < run.config.generations {
+        if run.step(evaluator)?.is_break() {
+            break;
+        }
+        yield_now().await;
+    }
+    Ok(run.finish())
+}
+
+/// Yield once to the event loop via `setTimeout(0)`.
+#[cfg(target_arch = "wasm32")]
+async fn yield_now() {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &"setTimeout".into())
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        match set_timeout {
+            Some(f) => {
+                let _ = f.call2(&JsValue::NULL, &resolve, &JsValue::from(0));
+            }
+            None => {
+                let _ = resolve.call0(&JsValue::NULL);
+            }
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Return `Pending` once, waking immediately so the executor reschedules.
+#[cfg(not(target_arch = "wasm32"))]
+async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            std::task::Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            std::task::Poll::Pending
+        }
+    })
+    .await
+}
+
+/// State of a run between generations.
+struct Run {
+    config: EvoConfig,
+    rng: ChaCha8Rng,
+    innovations: InnovationTracker,
+    population: Vec<Individual>,
+    threshold: Option<f32>,
+    hall_of_fame: HallOfFame,
+    cache: EvalCache,
+    archive: NoveltyArchive,
+    /// Held-out episodes of `config.sampling`.
+    validation: Option<Task>,
+    stats: Vec<GenerationStats>,
+    species: Vec<Species>,
+    next_species: usize,
+    stage: usize,
+    /// Generations completed so far.
+    generation: u32,
+    started: Stopwatch,
+}
+
+impl Run {
+    fn new(config: EvoConfig) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+        let mut base = config.base_genome.clone();
+        let mut innovations = InnovationTracker::from_genomes(std::slice::from_ref(&base));
+        innovations.assign(&mut base);
+
+        // --- Population initialisation ------------------------------------------------------
+        let population: Vec<Individual> = (0..config.pop_size)
+            .map(|i| {
+                let seed = rng.gen();
+                let mut grng = ChaCha8Rng::seed_from_u64(seed);
+                let mut g = match &config.random_init {
+                    Some(params) => Genome::random(&params.ramp(i, config.pop_size), &mut grng),
+                    None => {
+                        let mut g = base.clone();
+                        // Apply a mutation so the population is not uniform.
+                        mutate_with(&mut g, &mut grng, &config.mutation, &config.operators);
+                        g
+                    }
+                };
+                g.meta.seed = seed;
+                innovations.assign(&mut g);
+                Individual {
+                    genome: g,
+                    fitness: 0.0,
+                    score: 0.0,
+                    cases: Vec::new(),
+                    species: 0,
+                }
+            })
+            .collect();
+        Self::from_population(config, rng, innovations, population)
+    }
+
+    /// Pick up where `checkpoint` left off; see [`resume_evolution`].
+    fn resume(config: EvoConfig, checkpoint: Checkpoint) -> Self {
+        let innovations = InnovationTracker::from_genomes(&checkpoint.genomes);
+        let population = checkpoint
+            .genomes
+            .into_iter()
+            .zip(checkpoint.fitness.into_iter().chain(std::iter::repeat(0.0)))
+            .map(|(genome, fitness)| Individual {
+                genome,
+                fitness,
+                score: fitness,
+                cases: Vec::new(),
                 species: 0,
+            })
+            .collect();
+        let mut run = Self::from_population(config, checkpoint.rng, innovations, population);
+        run.generation = checkpoint.generation;
+        run.hall_of_fame = checkpoint.hall_of_fame;
+        run.stage = checkpoint.stage.min(run.config.curriculum.stages.len());
+        run
+    }
+
+    fn from_population(
+        config: EvoConfig,
+        rng: ChaCha8Rng,
+        innovations: InnovationTracker,
+        population: Vec<Individual>,
+    ) -> Self {
+        Self {
+            threshold: config.speciation_threshold,
+            hall_of_fame: HallOfFame::new(config.hall_of_fame),
+            cache: EvalCache::new(config.eval_cache),
+            archive: NoveltyArchive::new(config.novelty.map_or(0, |n| n.archive_capacity)),
+            validation: config.sampling.filter(|s| s.validation > 0).map(|s| {
+                let mut vrng = ChaCha8Rng::seed_from_u64(s.validation_seed);
+                s.generator.sample(s.validation, s.ticks, &mut vrng)
+            }),
+            stats: Vec::with_capacity(config.generations as usize),
+            species: Vec::new(),
+            next_species: 0,
+            stage: 0,
+            generation: 0,
+            started: Stopwatch::start(),
+            config,
+            rng,
+            innovations,
+            population,
+        }
+    }
+
+    /// Step until `config.generations` generations are done or the run stops
+    /// early.
+    fn complete<E: Evaluator + ?Sized>(
+        mut self,
+        evaluator: &E,
+    ) -> Result<EvolutionReport, ValidationError> {
+        while self.generation < self.config.generations {
+            if self.step(evaluator)?.is_break() {
+                break;
             }
-        })
-        .collect();
+        }
+        Ok(self.finish())
+    }
 
-    // Episodes derived from the task. The current `evaluate_batch` stub ignores
-    // these values, but creating them here matches the final API.
-    let episodes: Vec<Episode> = config
-        .task
-        .episodes
-        .iter()
-        .map(|_| Episode::default())
-        .collect();
+    /// Evaluate, speciate and breed one generation. Returns `Break`, before
+    /// breeding, when the `on_generation` hook stops the run, and after
+    /// breeding once `max_wall_time` is used up.
+    fn step<E: Evaluator + ?Sized>(
+        &mut self,
+        evaluator: &E,
+    ) -> Result<ControlFlow<()>, ValidationError> {
+        let Run {
+            config,
+            rng,
+            innovations,
+            population,
+            threshold,
+            hall_of_fame,
+            cache,
+            archive,
+            validation,
+            stats,
+            species,
+            next_species,
+            stage,
+            generation,
+            started,
+        } = self;
+        let gen = *generation;
+        let sampled;
+        let task = match (*stage, config.sampling) {
+            (0, Some(s)) => {
+                // Results for last generation's episodes do not carry over.
+                sampled = s.generator.sample(s.train, s.ticks, rng);
+                *cache = EvalCache::new(config.eval_cache);
+                &sampled
+            }
+            (0, None) => &config.task,
+            (n, _) => &config.curriculum.stages[n - 1].task,
+        };
+        // Episodes derived from the task. No initial input overrides are applied, so
+        // every episode starts from the genome's own `*_init` bits.
+        let episodes = vec![Episode::default(); task.episodes.len()];
 
-    for gen in 0..config.generations {
+        innovations.next_generation();
         // --- Evaluation ---------------------------------------------------------------------
-        let genomes: Vec<Genome> = population.iter().map(|i| i.genome.clone()).collect();
-        let results = evaluate_batch(&genomes, &config.task, &episodes);
-        for (ind, res) in population.iter_mut().zip(results.into_iter()) {
-            ind.fitness = res.fitness;
+        let timer = Stopwatch::start();
+        let hits = cache.hits();
+        let results = evaluate_cached(evaluator, population, task, &episodes, cache);
+        let eval_time = timer.elapsed();
+        let cache_hits = (cache.hits() - hits) as usize;
+        for (ind, res) in population.iter_mut().zip(&results) {
+            ind.fitness = res.fitness - config.parsimony.penalty(&ind.genome, res);
+            ind.score = ind.fitness;
+            ind.cases.clone_from(&res.episode_scores);
+            hall_of_fame.offer(&ind.genome, ind.fitness, gen);
+        }
+        if let Some(novelty) = &config.novelty {
+            let behaviors: Vec<Vec<u32>> = results.iter().map(behavior).collect();
+            let scores = score_novelty(&behaviors, archive, novelty, task.io.observed());
+            for (ind, n) in population.iter_mut().zip(scores) {
+                ind.score = novelty.alpha * ind.fitness + (1.0 - novelty.alpha) * n;
+            }
         }
 
         // --- Speciation ---------------------------------------------------------------------
-        if let Some(thresh) = config.speciation_threshold {
-            let mut reps: Vec<Genome> = Vec::new();
-            for ind in &mut population {
-                let mut assigned = false;
-                for (sid, rep) in reps.iter().enumerate() {
-                    if genome_distance(&ind.genome, rep) <= thresh {
-                        ind.species = sid;
-                        assigned = true;
-                        break;
+        // Species persist across generations; each is matched against its
+        // representative from the previous generation.
+        if let Some(thresh) = *threshold {
+            for ind in population.iter_mut() {
+                let found = species.iter().find(|s| {
+                    compatibility_distance(&ind.genome, &s.rep, &config.compatibility) <= thresh
+                });
+                ind.species = match found {
+                    Some(s) => s.id,
+                    None => {
+                        species.push(Species::new(*next_species, ind.genome.clone()));
+                        *next_species += 1;
+                        *next_species - 1
                     }
-                }
-                if !assigned {
-                    ind.species = reps.len();
-                    reps.push(ind.genome.clone());
-                }
+                };
             }
         } else {
-            for ind in &mut population {
+            for ind in population.iter_mut() {
                 ind.species = 0;
             }
+            if let (true, Some(first)) = (species.is_empty(), population.first()) {
+                species.push(Species::new(0, first.genome.clone()));
+            }
+        }
+        species.retain_mut(|s| match population.iter().find(|i| i.species == s.id) {
+            Some(first) => {
+                s.rep = first.genome.clone();
+                true
+            }
+            None => false,
+        });
+        if let (Some(thresh), Some(target)) = (*threshold, config.target_species) {
+            *threshold = Some(adjust_threshold(
+                thresh,
+                species.len(),
+                target,
+                config.threshold_step,
+            ));
+        }
+
+        let fittest = population
+            .iter()
+            .zip(&results)
+            .max_by(|(a, _), (b, _)| a.fitness.partial_cmp(&b.fitness).unwrap());
+        let best_breakdown = fittest
+            .map(|(_, r)| r.breakdown.clone())
+            .unwrap_or_default();
+        let mut summary = GenerationStats::new(
+            gen,
+            *stage,
+            population,
+            species.len(),
+            eval_time,
+            cache_hits,
+            best_breakdown,
+        );
+        if let (0, Some(held_out), Some((best, _))) = (*stage, validation.as_ref(), fittest) {
+            let episodes = vec![Episode::default(); held_out.episodes.len()];
+            summary.validation_fitness = evaluator
+                .evaluate(std::slice::from_ref(&best.genome), held_out, &episodes)
+                .first()
+                .map(|r| r.fitness);
+        }
+        for res in &results {
+            summary.oscillation.merge(&res.oscillation);
+        }
+        stats.push(summary);
+        if let (Some(hook), Some(best)) = (
+            config.on_generation.as_mut(),
+            population
+                .iter()
+                .max_by(|a, b| a.fitness.partial_cmp(&b.fitness).unwrap()),
+        ) {
+            let snapshot = GenerationSnapshot {
+                stats: &stats[stats.len() - 1],
+                best: &best.genome,
+                hall_of_fame,
+            };
+            if hook(&snapshot).is_break() {
+                *generation = gen + 1;
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+
+        // --- Stagnation ---------------------------------------------------------------------
+        // The species holding the overall best individual is never removed.
+        let leader = population
+            .iter()
+            .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap())
+            .map(|i| i.species);
+        let mut extinct = Vec::new();
+        for s in species.iter_mut() {
+            let best = population
+                .iter()
+                .filter(|i| i.species == s.id)
+                .map(|i| i.score)
+                .fold(f32::NEG_INFINITY, f32::max);
+            s.record(best);
+            if config.stagnation_limit.is_some_and(|n| s.stale >= n) && Some(s.id) != leader {
+                extinct.push(s.id);
+            }
         }
+        species.retain(|s| !extinct.contains(&s.id));
+
+        // --- Curriculum ---------------------------------------------------------------------
+        // Fitness on the next task is not comparable, so everything scored on
+        // this one starts over.
+        let best = stats[stats.len() - 1].best_fitness;
+        if config
+            .curriculum
+            .stages
+            .get(*stage)
+            .is_some_and(|next| best >= next.enter_at)
+        {
+            *stage += 1;
+            *cache = EvalCache::new(config.eval_cache);
+            *archive = NoveltyArchive::new(config.novelty.map_or(0, |n| n.archive_capacity));
+            *hall_of_fame = HallOfFame::new(config.hall_of_fame);
+            for s in species.iter_mut() {
+                s.best = f32::NEG_INFINITY;
+                s.stale = 0;
+            }
+        }
+
+        let best_genome = config
+            .export_best
+            .then(|| {
+                population
+                    .iter()
+                    .max_by(|a, b| a.fitness.partial_cmp(&b.fitness).unwrap())
+                    .map(|i| i.genome.clone())
+            })
+            .flatten();
 
         // --- Selection & Reproduction -------------------------------------------------------
-        let mut species_map: HashMap<usize, Vec<Individual>> = HashMap::new();
-        for ind in population.into_iter() {
-            species_map.entry(ind.species).or_default().push(ind);
+        let mut species_map: BTreeMap<usize, Vec<Individual>> = BTreeMap::new();
+        let mut freed = 0;
+        for ind in std::mem::take(population) {
+            if extinct.contains(&ind.species) {
+                freed += 1;
+            } else {
+                species_map.entry(ind.species).or_default().push(ind);
+            }
         }
+        let sizes: Vec<usize> = species_map.values().map(Vec::len).collect();
+        let quotas = redistribute(&sizes, freed);
 
         let mut next_population: Vec<Individual> = Vec::with_capacity(config.pop_size);
-        for (species_id, mut members) in species_map.into_iter() {
-            // Sort descending by fitness so elites are first.
-            members.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap());
-            let elite_count = config.elitism.min(members.len());
+        for ((species_id, mut members), quota) in species_map.into_iter().zip(quotas) {
+            // Sort descending by score so elites are first.
+            members.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+            let scores: Vec<f32> = members.iter().map(|m| m.score).collect();
+            let cases: Vec<&[f32]> = members.iter().map(|m| &m.cases[..]).collect();
+            let elite_count = config.elitism.min(members.len()).min(quota);
             for e in members.iter().take(elite_count) {
                 next_population.push(e.clone());
             }
 
-            let offspring = members.len().saturating_sub(elite_count);
+            let offspring = quota - elite_count;
             for _ in 0..offspring {
-                let p1 = tournament_index(&members, config.tournament_size, &mut rng);
+                let p1 = config.selection.select(&scores, &cases, rng);
                 let mut child = members[p1].genome.clone();
-                if rng.gen::<f32>() < config.crossover_rate && members.len() > 1 {
-                    let p2 = tournament_index(&members, config.tournament_size, &mut rng);
-                    child = crossover(&members[p1].genome, &members[p2].genome, &mut rng);
+                let k = config.crossover_parents;
+                if rng.gen::<f32>() < config.crossover_rate {
+                    if k > 2 && members.len() >= k {
+                        let mut picks = vec![p1];
+                        picks.extend((1..k).map(|_| config.selection.select(&scores, &cases, rng)));
+                        // fittest first
+                        picks.sort_by(|&x, &y| {
+                            members[y].score.partial_cmp(&members[x].score).unwrap()
+                        });
+                        let parents: Vec<&Genome> =
+                            picks.iter().map(|&i| &members[i].genome).collect();
+                        child = crossover_n(&parents, rng)?;
+                    } else if members.len() > 1 {
+                        let p2 = config.selection.select(&scores, &cases, rng);
+                        // the fitter parent goes first
+                        let (fa, fb) = if members[p2].score > members[p1].score {
+                            (p2, p1)
+                        } else {
+                            (p1, p2)
+                        };
+                        child = crossover_with(
+                            &members[fa].genome,
+                            &members[fb].genome,
+                            rng,
+                            config.crossover,
+                        )?;
+                    }
                 }
                 if rng.gen::<f32>() < config.mutation_rate {
                     let seed = rng.gen();
                     child.meta.seed = seed;
                     let mut grng = ChaCha8Rng::seed_from_u64(seed);
-                    mutate(&mut child, &mut grng);
+                    mutate_with(&mut child, &mut grng, &config.mutation, &config.operators);
+                    innovations.assign(&mut child);
                 }
                 next_population.push(Individual {
                     genome: child,
                     fitness: 0.0,
+                    score: 0.0,
+                    cases: Vec::new(),
                     species: species_id,
                 });
             }
         }
-        population = next_population;
+        *population = next_population;
+        *generation = gen + 1;
 
         // --- Checkpointing ------------------------------------------------------------------
-        if config.checkpoint_interval > 0 && (gen + 1) % config.checkpoint_interval == 0 {
+        let out_of_time = config.max_wall_time.is_some_and(|t| started.elapsed() >= t);
+        if out_of_time
+            || config.checkpoint_interval > 0 && (gen + 1) % config.checkpoint_interval == 0
+        {
             let cp = Checkpoint {
                 generation: gen + 1,
                 genomes: population.iter().map(|i| i.genome.clone()).collect(),
                 fitness: population.iter().map(|i| i.fitness).collect(),
                 rng: rng.clone(),
+                hall_of_fame: hall_of_fame.clone(),
+                config_digest: config.digest(),
+                config: Some(config.to_value_without(&["checkpoint_path"])),
+                task: config.stage_task(*stage).map(TaskIdentity::of),
+                stage: *stage,
             };
             let _ = save(&config.checkpoint_path, &cp);
+            if let Some(best) = best_genome {
+                let dir = config
+                    .checkpoint_path
+                    .parent()
+                    .unwrap_or(std::path::Path::new(""));
+                let _ = export_best(dir, gen + 1, &best);
+            }
         }
+        if out_of_time {
+            return Ok(ControlFlow::Break(()));
+        }
+        Ok(ControlFlow::Continue(()))
     }
 
-    Checkpoint {
-        generation: config.generations,
-        genomes: population.iter().map(|i| i.genome.clone()).collect(),
-        fitness: population.iter().map(|i| i.fitness).collect(),
-        rng,
+    fn finish(self) -> EvolutionReport {
+        EvolutionReport {
+            checkpoint: Checkpoint {
+                generation: self.generation,
+                genomes: self.population.iter().map(|i| i.genome.clone()).collect(),
+                fitness: self.population.iter().map(|i| i.fitness).collect(),
+                config_digest: self.config.digest(),
+                config: Some(self.config.to_value_without(&["checkpoint_path"])),
+                task: self.config.stage_task(self.stage).map(TaskIdentity::of),
+                stage: self.stage,
+                rng: self.rng,
+                hall_of_fame: self.hall_of_fame,
+            },
+            generations: self.stats,
+        }
     }
 }
 
-fn tournament_index(members: &[Individual], k: usize, rng: &mut ChaCha8Rng) -> usize {
-    let mut best_idx = rng.gen_range(0..members.len());
-    let mut best_fit = members[best_idx].fitness;
-    for _ in 1..k {
-        let idx = rng.gen_range(0..members.len());
-        if members[idx].fitness > best_fit {
-            best_fit = members[idx].fitness;
-            best_idx = idx;
+/// Evaluate the population, reusing cached results and evaluating each
+/// distinct uncached genome once.
+fn evaluate_cached<E: Evaluator + ?Sized>(
+    evaluator: &E,
+    population: &[Individual],
+    task: &Task,
+    episodes: &[Episode],
+    cache: &mut EvalCache,
+) -> Vec<FitnessResult> {
+    let genomes = population.iter().map(|i| i.genome.clone());
+    if cache.capacity() == 0 {
+        return evaluator.evaluate(&genomes.collect::<Vec<_>>(), task, episodes);
+    }
+    // Genomes that do not validate have no structural hash; they are
+    // evaluated every time.
+    let hashes: Vec<Option<[u8; 32]>> = population
+        .iter()
+        .map(|i| genome_hash(&i.genome).ok())
+        .collect();
+    let cached: Vec<Option<FitnessResult>> = hashes
+        .iter()
+        .map(|h| h.and_then(|h| cache.get(h).cloned()))
+        .collect();
+    let mut pending: HashMap<[u8; 32], usize> = HashMap::new();
+    let mut batch = Vec::new();
+    let mut slots = Vec::with_capacity(population.len());
+    for ((hash, hit), genome) in hashes.iter().zip(&cached).zip(genomes) {
+        slots.push(match hash {
+            _ if hit.is_some() => None,
+            Some(hash) => Some(*pending.entry(*hash).or_insert_with(|| {
+                batch.push(genome);
+                batch.len() - 1
+            })),
+            None => {
+                batch.push(genome);
+                Some(batch.len() - 1)
+            }
+        });
+    }
+    let fresh = evaluator.evaluate(&batch, task, episodes);
+    for (&hash, &i) in &pending {
+        cache.insert(hash, fresh[i].clone());
+    }
+    cached
+        .into_iter()
+        .zip(slots)
+        .map(|(hit, slot)| {
+            hit.unwrap_or_else(|| fresh[slot.expect("uncached genome evaluated")].clone())
+        })
+        .collect()
+}
+
+/// Move the threshold one step towards `target` species: down when there
+/// are too few, so genomes split apart, and up when there are too many. It
+/// never drops below `step`.
+fn adjust_threshold(threshold: f32, species: usize, target: usize, step: f32) -> f32 {
+    let next = match species.cmp(&target) {
+        std::cmp::Ordering::Less => threshold - step,
+        std::cmp::Ordering::Greater => threshold + step,
+        std::cmp::Ordering::Equal => threshold,
+    };
+    next.max(step)
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum GeneKey {
+    Conn(usize, GeneId<ConnKey>),
+    Link(GeneId<LinkKey>),
+}
+
+impl GeneKey {
+    fn innovation(&self) -> u32 {
+        match self {
+            GeneKey::Conn(_, GeneId::Innovation(n)) | GeneKey::Link(GeneId::Innovation(n)) => *n,
+            _ => 0,
+        }
+    }
+}
+
+/// `(trigger, action)` of every connection and link gene keyed by identity.
+fn gene_map(g: &Genome) -> BTreeMap<GeneKey, (u8, u8)> {
+    let conns = g.chunks.iter().enumerate().flat_map(|(i, chunk)| {
+        chunk
+            .conns
+            .iter()
+            .map(move |c| (GeneKey::Conn(i, conn_id(c)), (c.trigger, c.action)))
+    });
+    let links = g
+        .links
+        .iter()
+        .map(|l| (GeneKey::Link(link_id(l)), (l.trigger, l.action)));
+    conns.chain(links).collect()
+}
+
+/// NEAT compatibility distance
+/// `c_e·E/N + c_d·D/N + c_m·M̄ + c_c·|Δchunks|`.
+///
+/// Connection genes match within the same chunk index, link genes across the
+/// genome, by innovation number or, when unassigned, structural key. `E`
+/// counts unmatched genes newer than the other genome's newest gene, `D` the
+/// remaining unmatched ones, and `M̄` the mean number of differing
+/// trigger/action fields over matched genes. `N` is the larger gene count,
+/// or 1 below 20 genes.
+pub fn compatibility_distance(a: &Genome, b: &Genome, c: &CompatibilityCoefficients) -> f32 {
+    let genes_a = gene_map(a);
+    let genes_b = gene_map(b);
+    let newest = |genes: &BTreeMap<GeneKey, (u8, u8)>| {
+        genes.keys().map(GeneKey::innovation).max().unwrap_or(0)
+    };
+    let (newest_a, newest_b) = (newest(&genes_a), newest(&genes_b));
+
+    let (mut excess, mut disjoint, mut matched, mut mismatches) = (0u32, 0u32, 0u32, 0u32);
+    for (key, va) in &genes_a {
+        match genes_b.get(key) {
+            Some(vb) => {
+                matched += 1;
+                mismatches += (va.0 != vb.0) as u32 + (va.1 != vb.1) as u32;
+            }
+            None if key.innovation() > newest_b => excess += 1,
+            None => disjoint += 1,
+        }
+    }
+    for key in genes_b.keys().filter(|k| !genes_a.contains_key(k)) {
+        if key.innovation() > newest_a {
+            excess += 1;
+        } else {
+            disjoint += 1;
         }
     }
-    best_idx
+
+    let n = genes_a.len().max(genes_b.len());
+    let n = if n < 20 { 1.0 } else { n as f32 };
+    let mean_mismatch = if matched == 0 {
+        0.0
+    } else {
+        mismatches as f32 / matched as f32
+    };
+    let dc = a.chunks.len().abs_diff(b.chunks.len()) as f32;
+    c.excess * excess as f32 / n
+        + c.disjoint * disjoint as f32 / n
+        + c.mismatch * mean_mismatch
+        + c.chunks * dc
 }
 
-fn genome_distance(a: &Genome, b: &Genome) -> f32 {
-    let dc = (a.chunks.len() as i32 - b.chunks.len() as i32).abs() as f32;
-    let conns_a: usize = a.chunks.iter().map(|c| c.conns.len()).sum();
-    let conns_b: usize = b.chunks.iter().map(|c| c.conns.len()).sum();
-    let dconns = (conns_a as i32 - conns_b as i32).abs() as f32;
-    dc + dconns
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::{ChunkGene, ConnGene, GenomeMeta};
+    use bitvec::prelude::*;
+
+    fn genome(conns: &[(u32, u8)]) -> Genome {
+        let conns = conns
+            .iter()
+            .map(|&(innovation, trigger)| ConnGene {
+                innovation,
+                ..ConnGene::new(0, 1, trigger, 0, 0, innovation, innovation).unwrap()
+            })
+            .collect();
+        let chunk = ChunkGene::new(
+            1,
+            0,
+            8,
+            bitvec![u8, Lsb0; 0],
+            BitVec::new(),
+            bitvec![u8, Lsb0; 0; 8],
+            conns,
+        );
+        Genome::new(vec![chunk], Vec::new(), GenomeMeta::new(0, "t".into())).unwrap()
+    }
+
+    fn wire_config() -> EvoConfig {
+        use crate::builder::fixtures::wire_genome;
+        use crate::tasks::t00_wire_echo;
+
+        EvoConfig {
+            task: t00_wire_echo(),
+            curriculum: Curriculum::default(),
+            sampling: None,
+            base_genome: wire_genome(),
+            random_init: None,
+            pop_size: 8,
+            generations: 3,
+            max_wall_time: None,
+            checkpoint_interval: 0,
+            checkpoint_path: std::env::temp_dir().join("mycos_report_test.json"),
+            export_best: false,
+            speciation_threshold: Some(2.0),
+            parsimony: Parsimony::default(),
+            compatibility: CompatibilityCoefficients::default(),
+            target_species: Some(2),
+            threshold_step: 0.5,
+            stagnation_limit: Some(2),
+            selection: Selection::Tournament { size: 2 },
+            elitism: 1,
+            crossover_rate: 0.5,
+            crossover: CrossoverStrategy::Uniform,
+            crossover_parents: 3,
+            mutation_rate: 1.0,
+            mutation: MutationConfig::default(),
+            operators: MutationRegistry::new(),
+            hall_of_fame: 3,
+            novelty: None,
+            eval_cache: 0,
+            seed: 1,
+            on_generation: None,
+        }
+    }
+
+    #[test]
+    fn report_tracks_each_generation() {
+        let report = run_evolution(wire_config()).unwrap();
+        assert_eq!(report.generations.len(), 3);
+        for (gen, stats) in report.generations.iter().enumerate() {
+            assert_eq!(stats.generation, gen as u32);
+            assert!(stats.best_fitness >= stats.mean_fitness);
+            assert!(stats.species >= 1);
+            // no parsimony, so the breakdown accounts for the best fitness
+            assert_eq!(stats.best_breakdown.score, stats.best_fitness);
+            // 8 individuals on both episodes of T-00
+            assert_eq!(stats.oscillation.episodes, 16);
+        }
+        assert_eq!(report.checkpoint.genomes.len(), 8);
+        let best = report.checkpoint.hall_of_fame.best().unwrap();
+        assert_eq!(best.fitness, 1.0);
+    }
+
+    #[test]
+    fn sampled_episodes_are_redrawn_and_validated() {
+        let config = || EvoConfig {
+            sampling: Some(EpisodeSampling {
+                generator: TaskGenerator::Xor { inputs: 1 },
+                train: 4,
+                validation: 3,
+                ticks: 2,
+                validation_seed: 9,
+            }),
+            eval_cache: 16,
+            ..wire_config()
+        };
+        let report = run_evolution(config()).unwrap();
+        for stats in &report.generations {
+            let validation = stats.validation_fitness.unwrap();
+            assert!((0.0..=1.0).contains(&validation));
+            assert_eq!(stats.best_breakdown.episodes.len(), 4);
+            // the cache is emptied with every new draw
+            assert_eq!(stats.cache_hits, 0);
+        }
+        let again = run_evolution(config()).unwrap();
+        let fitness = |r: &EvolutionReport| {
+            r.generations
+                .iter()
+                .map(|s| (s.mean_fitness, s.validation_fitness))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fitness(&report), fitness(&again));
+        assert!(run_evolution(wire_config()).unwrap().generations[0]
+            .validation_fitness
+            .is_none());
+    }
+
+    #[test]
+    fn hook_can_stop_the_run() {
+        let mut calls = 0;
+        let stop: GenerationHook = Box::new(move |snap| {
+            calls += 1;
+            assert_eq!(snap.stats.generation, calls - 1);
+            if snap.stats.best_fitness < 1.0 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+        let report = run_evolution(EvoConfig {
+            on_generation: Some(stop),
+            ..wire_config()
+        })
+        .unwrap();
+        // the base genome is already perfect, so the first generation stops it
+        assert_eq!(report.generations.len(), 1);
+        assert_eq!(report.checkpoint.generation, 1);
+    }
+
+    #[test]
+    fn novelty_search_keeps_task_fitness() {
+        let report = run_evolution(EvoConfig {
+            novelty: Some(NoveltyConfig {
+                k: 3,
+                alpha: 0.0,
+                ..NoveltyConfig::default()
+            }),
+            ..wire_config()
+        })
+        .unwrap();
+        assert_eq!(report.generations.len(), 3);
+        // stats and the hall of fame report task fitness, not the blend
+        assert!(report.generations.iter().all(|s| s.best_fitness <= 1.0));
+        assert_eq!(report.checkpoint.hall_of_fame.best().unwrap().fitness, 1.0);
+    }
+
+    #[test]
+    fn cache_reuses_elite_results() {
+        let plain = run_evolution(wire_config()).unwrap();
+        let cached = run_evolution(EvoConfig {
+            eval_cache: 64,
+            ..wire_config()
+        })
+        .unwrap();
+        assert_eq!(plain.generations[0].cache_hits, 0);
+        // elites are carried over unchanged
+        assert!(cached.generations[1..].iter().all(|s| s.cache_hits > 0));
+        let fitness = |r: &EvolutionReport| r.checkpoint.fitness.clone();
+        assert_eq!(fitness(&plain), fitness(&cached));
+    }
+
+    #[test]
+    fn parsimony_lowers_fitness() {
+        let report = run_evolution(EvoConfig {
+            parsimony: Parsimony {
+                conns: 0.01,
+                ..Parsimony::default()
+            },
+            ..wire_config()
+        })
+        .unwrap();
+        // every genome has connections, so nothing reaches a perfect score
+        assert!(report.generations.iter().all(|s| s.best_fitness < 1.0));
+    }
+
+    #[test]
+    fn random_init_varies_generation_zero() {
+        let report = run_evolution(EvoConfig {
+            random_init: Some(RandomGenomeParams {
+                internals: 1..=4,
+                conns: 0..=12,
+                ..RandomGenomeParams::default()
+            }),
+            generations: 1,
+            elitism: 8,
+            ..wire_config()
+        })
+        .unwrap();
+        let sizes: Vec<usize> = report
+            .checkpoint
+            .genomes
+            .iter()
+            .map(|g| g.chunks[0].conns.len())
+            .collect();
+        assert!(sizes.iter().any(|&n| n != sizes[0]));
+    }
+
+    #[test]
+    fn curriculum_advances_on_threshold() {
+        use crate::tasks::t01_xor_2;
+
+        let stage = |enter_at| CurriculumStage {
+            task: t01_xor_2(),
+            enter_at,
+        };
+        let report = run_evolution(EvoConfig {
+            curriculum: Curriculum {
+                stages: vec![stage(0.9)],
+            },
+            ..wire_config()
+        })
+        .unwrap();
+        // the wire genomes solve T-00 at once and move on to XOR
+        let stages: Vec<usize> = report.generations.iter().map(|s| s.stage).collect();
+        assert_eq!(stages, vec![0, 1, 1]);
+
+        let report = run_evolution(EvoConfig {
+            curriculum: Curriculum {
+                stages: vec![stage(2.0)],
+            },
+            ..wire_config()
+        })
+        .unwrap();
+        assert!(report.generations.iter().all(|s| s.stage == 0));
+    }
+
+    #[test]
+    fn custom_evaluator_drives_the_loop() {
+        use std::cell::Cell;
+
+        /// Scores genomes by size and counts the genomes it is handed.
+        struct Counting(Cell<usize>);
+        impl Evaluator for Counting {
+            fn evaluate(&self, genomes: &[Genome], _: &Task, _: &[Episode]) -> Vec<FitnessResult> {
+                self.0.set(self.0.get() + genomes.len());
+                genomes
+                    .iter()
+                    .map(|g| FitnessResult {
+                        fitness: g.chunks[0].conns.len() as f32,
+                        episode_scores: vec![0.0],
+                        ..FitnessResult::default()
+                    })
+                    .collect()
+            }
+        }
+        let evaluator = Counting(Cell::new(0));
+        let report = run_evolution_with(wire_config(), &evaluator).unwrap();
+        assert_eq!(evaluator.0.get(), 3 * 8);
+        let best = report.generations[2].best_fitness;
+        assert!(best >= report.generations[0].best_fitness && best >= 4.0);
+    }
+
+    #[test]
+    fn async_run_yields_between_generations() {
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        let sync = run_evolution(wire_config()).unwrap();
+        let mut fut = std::pin::pin!(run_evolution_async(wire_config(), &CpuEvaluator));
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut yields = 0;
+        let report = loop {
+            match fut.as_mut().poll(&mut cx) {
+                Poll::Ready(report) => break report.unwrap(),
+                Poll::Pending => yields += 1,
+            }
+        };
+        assert_eq!(yields, 3);
+        let best = |r: &EvolutionReport| {
+            r.generations
+                .iter()
+                .map(|s| s.best_fitness)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(best(&report), best(&sync));
+        assert_eq!(report.checkpoint.fitness, sync.checkpoint.fitness);
+    }
+
+    #[test]
+    fn config_round_trips_through_files() {
+        let config = EvoConfig {
+            curriculum: Curriculum {
+                stages: vec![CurriculumStage {
+                    task: crate::tasks::t01_xor_2(),
+                    enter_at: 0.9,
+                }],
+            },
+            novelty: Some(NoveltyConfig::default()),
+            ..wire_config()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""task":"T-00 Wire-Echo""#));
+        let toml = toml::to_string(&config).unwrap();
+        for loaded in [
+            EvoConfig::from_json(&json).unwrap(),
+            EvoConfig::from_toml(&toml).unwrap(),
+        ] {
+            assert_eq!(loaded.task.name, "T-00 Wire-Echo");
+            assert_eq!(loaded.curriculum.stages[0].task.name, "T-01 XOR-2");
+            assert_eq!(loaded.selection, config.selection);
+            assert_eq!(loaded.novelty, config.novelty);
+            assert_eq!(
+                genome_hash(&loaded.base_genome).unwrap(),
+                genome_hash(&config.base_genome).unwrap()
+            );
+            assert_eq!(loaded.digest(), config.digest());
+        }
+        let longer = EvoConfig {
+            generations: 50,
+            checkpoint_path: "elsewhere.json".into(),
+            ..wire_config()
+        };
+        assert_eq!(longer.digest(), wire_config().digest());
+        let reseeded = EvoConfig {
+            seed: 12345,
+            ..wire_config()
+        };
+        assert_ne!(reseeded.digest(), wire_config().digest());
+
+        // optional fields fall back to their defaults
+        let mut value = serde_json::to_value(&config).unwrap();
+        let fields = value.as_object_mut().unwrap();
+        fields.retain(|k, _| !matches!(k.as_str(), "selection" | "novelty" | "curriculum"));
+        fields.insert("task".into(), "T-02".into());
+        let loaded = EvoConfig::from_json(&value.to_string()).unwrap();
+        assert_eq!(loaded.task.name, "T-02 SR-Latch");
+        assert_eq!(loaded.selection, Selection::default());
+        assert!(loaded.novelty.is_none());
+
+        value["task"] = "T-99".into();
+        let err = EvoConfig::from_json(&value.to_string()).err().unwrap();
+        assert!(err.to_string().contains("unknown task"));
+
+        // custom tasks resolve through a registry
+        let mut custom = crate::tasks::xor_n(3);
+        custom.name = "my-xor".into();
+        let mut tasks = TaskRegistry::new();
+        tasks.insert(custom.clone());
+        value["task"] = "my-xor".into();
+        let json = value.to_string();
+        assert!(EvoConfig::from_json(&json).is_err());
+        let from_json = EvoConfig::from_json_with(&json, &tasks).unwrap();
+        let toml = toml::to_string(&from_json).unwrap();
+        let from_toml = EvoConfig::from_toml_with(&toml, &tasks).unwrap();
+        for loaded in [from_json, from_toml] {
+            assert_eq!(loaded.task.digest(), custom.digest());
+        }
+
+        // sampling that cannot draw episodes is rejected
+        let sampling = EpisodeSampling {
+            generator: TaskGenerator::Xor { inputs: 2 },
+            train: 4,
+            validation: 0,
+            ticks: 2,
+            validation_seed: 0,
+        };
+        for (bad, reason) in [
+            (
+                EpisodeSampling {
+                    train: 0,
+                    ..sampling
+                },
+                "train",
+            ),
+            (
+                EpisodeSampling {
+                    generator: TaskGenerator::Xor { inputs: 40 },
+                    ..sampling
+                },
+                "out of range",
+            ),
+            (
+                EpisodeSampling {
+                    generator: TaskGenerator::Counter { modulus: 0 },
+                    ..sampling
+                },
+                "out of range",
+            ),
+        ] {
+            let config = EvoConfig {
+                sampling: Some(bad),
+                ..wire_config()
+            };
+            let json = serde_json::to_string(&config).unwrap();
+            let toml = toml::to_string(&config).unwrap();
+            for err in [
+                EvoConfig::from_json(&json).err().unwrap(),
+                EvoConfig::from_toml(&toml).err().unwrap(),
+            ] {
+                assert!(matches!(err, ConfigError::Sampling(_)));
+                assert!(err.to_string().contains(reason), "{err}");
+            }
+        }
+        let config = EvoConfig {
+            sampling: Some(sampling),
+            ..wire_config()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(EvoConfig::from_json(&json).is_ok());
+    }
+
+    #[test]
+    fn resume_continues_and_checks_the_config() {
+        let first = run_evolution(EvoConfig {
+            generations: 2,
+            ..wire_config()
+        })
+        .unwrap();
+        let checkpoint = first.checkpoint;
+        assert_eq!(checkpoint.task.as_ref().unwrap().name, "T-00 Wire-Echo");
+        assert!(checkpoint
+            .config
+            .as_ref()
+            .unwrap()
+            .get("checkpoint_path")
+            .is_none());
+
+        let report = resume_evolution(wire_config(), checkpoint.clone(), false).unwrap();
+        let gens: Vec<u32> = report.generations.iter().map(|s| s.generation).collect();
+        assert_eq!(gens, vec![2]);
+        assert_eq!(report.checkpoint.generation, 3);
+
+        let reseeded = || EvoConfig {
+            seed: 12345,
+            ..wire_config()
+        };
+        match resume_evolution(reseeded(), checkpoint.clone(), false) {
+            Err(ResumeError::ConfigMismatch { fields }) => assert_eq!(fields, ["seed"]),
+            _ => panic!("expected a config mismatch"),
+        }
+        assert!(resume_evolution(reseeded(), checkpoint.clone(), true).is_ok());
+
+        let mut other_task = checkpoint;
+        other_task.task.as_mut().unwrap().digest ^= 1;
+        let err = resume_evolution(wire_config(), other_task, false)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ResumeError::TaskMismatch { .. }));
+    }
+
+    #[test]
+    fn wall_time_budget_stops_and_checkpoints() {
+        let path = std::env::temp_dir().join("mycos_wall_time_test.json");
+        let _ = std::fs::remove_file(&path);
+        let report = run_evolution(EvoConfig {
+            max_wall_time: Some(Duration::ZERO),
+            checkpoint_path: path.clone(),
+            ..wire_config()
+        })
+        .unwrap();
+        assert_eq!(report.generations.len(), 1);
+        assert_eq!(report.checkpoint.generation, 1);
+        let saved = crate::checkpoint::load(&path).unwrap();
+        assert_eq!(saved.generation, 1);
+        assert_eq!(saved.genomes.len(), 8);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn checkpoints_export_the_best_genome() {
+        let dir = std::env::temp_dir().join("mycos_export_best_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        run_evolution(EvoConfig {
+            checkpoint_interval: 2,
+            checkpoint_path: dir.join("checkpoint.json"),
+            export_best: true,
+            generations: 4,
+            ..wire_config()
+        })
+        .unwrap();
+        let mut exported = Vec::new();
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            let bytes = std::fs::read(&path).unwrap();
+            if name.ends_with(".myc") {
+                crate::parse_chunk(&bytes).unwrap();
+            } else if name.ends_with(".links.bin") {
+                crate::parse_links(&bytes).unwrap();
+            } else {
+                continue;
+            }
+            exported.push(name[..13].to_string());
+        }
+        exported.sort();
+        exported.dedup();
+        assert_eq!(exported, ["best_gen_0002", "best_gen_0004"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stagnant_species_lose_their_quota() {
+        let mut s = Species::new(0, genome(&[]));
+        s.record(1.0);
+        s.record(1.0);
+        s.record(0.5);
+        assert_eq!(s.stale, 2);
+        s.record(2.0);
+        assert_eq!(s.stale, 0);
+
+        assert_eq!(redistribute(&[3, 2, 4], 5), vec![5, 4, 5]);
+        assert!(redistribute(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn threshold_moves_towards_target() {
+        assert_eq!(adjust_threshold(3.0, 2, 5, 0.5), 2.5);
+        assert_eq!(adjust_threshold(3.0, 8, 5, 0.5), 3.5);
+        assert_eq!(adjust_threshold(3.0, 5, 5, 0.5), 3.0);
+        assert_eq!(adjust_threshold(0.6, 1, 5, 0.5), 0.5);
+    }
+
+    #[test]
+    fn distance_counts_excess_disjoint_and_mismatch() {
+        let c = CompatibilityCoefficients {
+            excess: 1.0,
+            disjoint: 2.0,
+            mismatch: 4.0,
+            chunks: 0.0,
+        };
+        // 1 matches with a trigger mismatch, 2 is disjoint, 5 and 6 are excess
+        let a = genome(&[(1, 0), (2, 0), (3, 0)]);
+        let b = genome(&[(1, 1), (3, 0), (5, 0), (6, 0)]);
+        assert_eq!(compatibility_distance(&a, &a, &c), 0.0);
+        assert_eq!(compatibility_distance(&a, &b, &c), 2.0 + 2.0 + 4.0 * 0.5);
+        assert_eq!(
+            compatibility_distance(&a, &b, &c),
+            compatibility_distance(&b, &a, &c)
+        );
+    }
 }
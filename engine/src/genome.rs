@@ -1,11 +1,18 @@
 use bitvec::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::chunk::{duplicate_edges, Action, Connection, MycosChunk, Section, Trigger};
+use crate::embed::{Embed, IoMode};
+use crate::link::Link;
+
 /// Top-level genome structure containing chunk genes and links between them.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Genome {
     pub chunks: Vec<ChunkGene>,
     pub links: Vec<LinkGene>,
+    /// Gated child chunks; absent in genomes saved before embeds existed.
+    #[serde(default)]
+    pub embeds: Vec<EmbedGene>,
     pub meta: GenomeMeta,
 }
 
@@ -19,6 +26,7 @@ impl Genome {
         let genome = Self {
             chunks: chunks.clone(),
             links: links.clone(),
+            embeds: Vec::new(),
             meta,
         };
         // Validate before sorting to surface errors early.
@@ -28,10 +36,19 @@ impl Genome {
         Ok(Self {
             chunks,
             links,
+            embeds: Vec::new(),
             meta: genome.meta,
         })
     }
 
+    /// Attach embed genes, validating them against the chunk list.
+    pub fn with_embeds(mut self, embeds: Vec<EmbedGene>) -> Result<Self, ValidationError> {
+        Genome::validate_embeds(&self.chunks, &embeds)?;
+        self.embeds = embeds;
+        self.sort();
+        Ok(self)
+    }
+
     fn validate_chunks_and_links(
         &self,
         chunks: &[ChunkGene],
@@ -41,27 +58,55 @@ impl Genome {
             chunk.validate().map_err(|e| e.in_chunk(i as u32))?;
         }
         for link in links {
-            link.validate()?;
-            if (link.from_chunk as usize) >= chunks.len() {
-                return Err(ValidationError::InvalidLinkFromChunk(link.from_chunk));
-            }
-            if (link.to_chunk as usize) >= chunks.len() {
-                return Err(ValidationError::InvalidLinkToChunk(link.to_chunk));
-            }
-            let from_chunk = &chunks[link.from_chunk as usize];
-            if link.from_out_idx >= from_chunk.no {
-                return Err(ValidationError::InvalidLinkFromIndex {
-                    chunk: link.from_chunk,
-                    index: link.from_out_idx,
-                });
-            }
-            let to_chunk = &chunks[link.to_chunk as usize];
-            if link.to_in_idx >= to_chunk.ni {
-                return Err(ValidationError::InvalidLinkToIndex {
-                    chunk: link.to_chunk,
-                    index: link.to_in_idx,
-                });
-            }
+            Genome::validate_link(chunks, link)?;
+        }
+        match Genome::duplicate_links(links).into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Errors for [`duplicate_edges`] among `links`, keyed by chunk output.
+    fn duplicate_links(links: &[LinkGene]) -> Vec<ValidationError> {
+        duplicate_edges(
+            links,
+            |l| {
+                let from = (l.from_chunk, l.from_out_idx);
+                (from, (l.to_chunk, l.to_in_idx, l.trigger), l.order_tag)
+            },
+            |first, second| ValidationError::DuplicateLink { first, second },
+            |l| ValidationError::LinkOrderTagCollision {
+                chunk: l.from_chunk,
+                index: l.from_out_idx,
+                order_tag: l.order_tag,
+            },
+        )
+    }
+
+    pub(crate) fn validate_link(
+        chunks: &[ChunkGene],
+        link: &LinkGene,
+    ) -> Result<(), ValidationError> {
+        link.validate()?;
+        if (link.from_chunk as usize) >= chunks.len() {
+            return Err(ValidationError::InvalidLinkFromChunk(link.from_chunk));
+        }
+        if (link.to_chunk as usize) >= chunks.len() {
+            return Err(ValidationError::InvalidLinkToChunk(link.to_chunk));
+        }
+        let from_chunk = &chunks[link.from_chunk as usize];
+        if link.from_out_idx >= from_chunk.no {
+            return Err(ValidationError::InvalidLinkFromIndex {
+                chunk: link.from_chunk,
+                index: link.from_out_idx,
+            });
+        }
+        let to_chunk = &chunks[link.to_chunk as usize];
+        if link.to_in_idx >= to_chunk.ni {
+            return Err(ValidationError::InvalidLinkToIndex {
+                chunk: link.to_chunk,
+                index: link.to_in_idx,
+            });
         }
         Ok(())
     }
@@ -79,14 +124,128 @@ impl Genome {
         });
     }
 
+    pub(crate) fn validate_embeds(
+        chunks: &[ChunkGene],
+        embeds: &[EmbedGene],
+    ) -> Result<(), ValidationError> {
+        let mut parent_of = vec![None; chunks.len()];
+        for embed in embeds {
+            embed.validate(chunks)?;
+            let child = embed.child_chunk as usize;
+            if parent_of[child].is_some() {
+                return Err(ValidationError::InvalidEmbedChild(embed.child_chunk));
+            }
+            parent_of[child] = Some(embed.parent_chunk as usize);
+        }
+        // Walking up from any chunk must terminate at a root.
+        for start in 0..chunks.len() {
+            let mut cur = start;
+            for _ in 0..chunks.len() {
+                match parent_of[cur] {
+                    Some(p) if p == start => return Err(ValidationError::EmbedCycle(start as u32)),
+                    Some(p) => cur = p,
+                    None => break,
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Validate the genome after construction.
     pub fn validate(&self) -> Result<(), ValidationError> {
-        self.validate_chunks_and_links(&self.chunks, &self.links)
+        self.validate_chunks_and_links(&self.chunks, &self.links)?;
+        Genome::validate_embeds(&self.chunks, &self.embeds)
     }
 
-    /// Sort connections and links according to canonical rules.
+    /// Every violation in the genome instead of only the first.
+    ///
+    /// Chunk errors come from [`ChunkGene::validate_all`], wrapped in
+    /// [`ValidationError::InChunk`]. Embed child reuse and cycles are only
+    /// checked once every embed is valid on its own.
+    pub fn validate_all(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = self
+            .chunks
+            .iter()
+            .enumerate()
+            .flat_map(|(i, chunk)| {
+                chunk
+                    .validate_all()
+                    .into_iter()
+                    .map(move |error| ValidationError::InChunk {
+                        chunk: i as u32,
+                        error: Box::new(error),
+                    })
+            })
+            .collect();
+        for link in &self.links {
+            if let Err(e) = Genome::validate_link(&self.chunks, link) {
+                errors.push(e);
+            }
+        }
+        errors.extend(Genome::duplicate_links(&self.links));
+        let embed_errors = self
+            .embeds
+            .iter()
+            .filter_map(|e| e.validate(&self.chunks).err());
+        let before = errors.len();
+        errors.extend(embed_errors);
+        if errors.len() == before {
+            errors.extend(Genome::validate_embeds(&self.chunks, &self.embeds).err());
+        }
+        errors
+    }
+
+    /// Sort connections, links and embeds according to canonical rules.
     pub fn sort(&mut self) {
         Genome::sort_internal(&mut self.chunks, &mut self.links);
+        self.embeds
+            .sort_by_key(|e| (e.parent_chunk, e.child_chunk, e.gate_section, e.gate_bit));
+    }
+
+    /// Emit every embed gene as an [`Embed`] record.
+    pub fn to_embeds(&self) -> Result<Vec<Embed>, ValidationError> {
+        self.validate()?;
+        Ok(self.embeds.iter().map(EmbedGene::to_embed).collect())
+    }
+
+    /// Drop embeds whose parent bits, child bits or gate no longer exist.
+    fn prune_embeds(&mut self) {
+        let chunks = &self.chunks;
+        for embed in &mut self.embeds {
+            let (parent, child) = (
+                &chunks[embed.parent_chunk as usize],
+                &chunks[embed.child_chunk as usize],
+            );
+            embed.map_in.retain(|&(p, c)| p < parent.nn && c < child.ni);
+            embed
+                .map_out
+                .retain(|&(c, p)| c < child.no && p < parent.no);
+        }
+        self.embeds
+            .retain(|e| e.gate_bit < chunks[e.parent_chunk as usize].section_len(e.gate_section));
+    }
+
+    /// Emit every chunk gene as a [`MycosChunk`] ready for `encode_chunk` or
+    /// the CPU reference executor.
+    pub fn to_chunks(&self) -> Result<Vec<MycosChunk>, ValidationError> {
+        self.validate()?;
+        self.chunks.iter().map(ChunkGene::to_chunk).collect()
+    }
+
+    /// Emit every link gene as a [`Link`] record.
+    pub fn to_links(&self) -> Result<Vec<Link>, ValidationError> {
+        self.validate()?;
+        self.links.iter().map(LinkGene::to_link).collect()
+    }
+
+    /// Import parsed chunks and links as a genome for further evolution.
+    ///
+    /// Chunk metadata such as the TLV name is dropped; the genome is validated
+    /// and sorted like any other constructed via [`Genome::new`].
+    pub fn from_chunks(chunks: &[MycosChunk], links: &[Link]) -> Result<Self, ValidationError> {
+        let chunks = chunks.iter().map(ChunkGene::from_chunk).collect();
+        let links = links.iter().map(LinkGene::from_link).collect();
+        Genome::new(chunks, links, GenomeMeta::new(0, String::new()))
     }
 
     /// Resize the number of input bits for a chunk.
@@ -99,6 +258,7 @@ impl Genome {
             chunk.resize_inputs(new_ni);
             self.links
                 .retain(|l| !(l.to_chunk == chunk_idx as u32 && l.to_in_idx >= new_ni));
+            self.prune_embeds();
             self.sort();
         }
     }
@@ -111,6 +271,7 @@ impl Genome {
             chunk.resize_outputs(new_no);
             self.links
                 .retain(|l| !(l.from_chunk == chunk_idx as u32 && l.from_out_idx >= new_no));
+            self.prune_embeds();
             self.sort();
         }
     }
@@ -121,6 +282,7 @@ impl Genome {
     pub fn resize_chunk_internals(&mut self, chunk_idx: usize, new_nn: u32) {
         if let Some(chunk) = self.chunks.get_mut(chunk_idx) {
             chunk.resize_internals(new_nn);
+            self.prune_embeds();
             self.sort();
         }
     }
@@ -173,6 +335,90 @@ impl ChunkGene {
         }
     }
 
+    /// Bit count of a section by code: 0 = input, 1 = internal, 2 = output;
+    /// 0 for any other code.
+    pub fn section_len(&self, section: u8) -> u32 {
+        match section {
+            0 => self.ni,
+            1 => self.nn,
+            2 => self.no,
+            _ => 0,
+        }
+    }
+
+    /// Convert this gene into a [`MycosChunk`], validating it first.
+    pub fn to_chunk(&self) -> Result<MycosChunk, ValidationError> {
+        self.validate()?;
+        let connections = self
+            .conns
+            .iter()
+            .map(ConnGene::to_connection)
+            .collect::<Result<_, _>>()?;
+        Ok(MycosChunk {
+            input_bits: pack_bits(&self.inputs_init),
+            output_bits: pack_bits(&self.outputs_init),
+            internal_bits: pack_bits(&self.internals_init),
+            input_count: self.ni,
+            output_count: self.no,
+            internal_count: self.nn,
+            connections,
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        })
+    }
+
+    /// Build a gene from a parsed chunk. The result is not validated.
+    pub fn from_chunk(chunk: &MycosChunk) -> Self {
+        let conns = chunk
+            .connections
+            .iter()
+            .map(ConnGene::from_connection)
+            .collect();
+        Self::new(
+            chunk.input_count,
+            chunk.output_count,
+            chunk.internal_count,
+            unpack_bits(&chunk.input_bits, chunk.input_count),
+            unpack_bits(&chunk.output_bits, chunk.output_count),
+            unpack_bits(&chunk.internal_bits, chunk.internal_count),
+            conns,
+        )
+    }
+
+    /// Every violation in the chunk instead of only the first: init length
+    /// mismatches, then one error per invalid connection, repeated connection
+    /// or reused order tag.
+    pub fn validate_all(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if self.inputs_init.len() != self.ni as usize {
+            errors.push(ValidationError::InputsLenMismatch {
+                expected: self.ni,
+                actual: self.inputs_init.len(),
+            });
+        }
+        if self.outputs_init.len() != self.no as usize {
+            errors.push(ValidationError::OutputsLenMismatch {
+                expected: self.no,
+                actual: self.outputs_init.len(),
+            });
+        }
+        if self.internals_init.len() != self.nn as usize {
+            errors.push(ValidationError::InternalsLenMismatch {
+                expected: self.nn,
+                actual: self.internals_init.len(),
+            });
+        }
+        for conn in &self.conns {
+            if let Err(e) = self.validate_conn(conn) {
+                errors.push(e);
+            }
+        }
+        errors.extend(self.duplicate_conns());
+        errors
+    }
+
     pub fn validate(&self) -> Result<(), ValidationError> {
         if self.inputs_init.len() != self.ni as usize {
             return Err(ValidationError::InputsLenMismatch {
@@ -193,55 +439,81 @@ impl ChunkGene {
             });
         }
         for conn in &self.conns {
-            conn.validate()?;
-            match conn.from_section {
-                0 => {
-                    if conn.from_index >= self.ni {
-                        return Err(ValidationError::FromIndexOutOfRange {
-                            section: conn.from_section,
-                            index: conn.from_index,
-                        });
-                    }
-                }
-                1 => {
-                    if conn.from_index >= self.nn {
-                        return Err(ValidationError::FromIndexOutOfRange {
-                            section: conn.from_section,
-                            index: conn.from_index,
-                        });
-                    }
-                }
-                _ => {
-                    return Err(ValidationError::InvalidConnEdge {
-                        from_section: conn.from_section,
-                        to_section: conn.to_section,
-                    })
+            self.validate_conn(conn)?;
+        }
+        match self.duplicate_conns().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Errors for [`duplicate_edges`] among this chunk's connections.
+    fn duplicate_conns(&self) -> Vec<ValidationError> {
+        duplicate_edges(
+            &self.conns,
+            |c| {
+                let from = (c.from_section, c.from_index);
+                (from, (c.to_section, c.to_index, c.trigger), c.order_tag)
+            },
+            |first, second| ValidationError::DuplicateConn { first, second },
+            |c| ValidationError::ConnOrderTagCollision {
+                section: c.from_section,
+                index: c.from_index,
+                order_tag: c.order_tag,
+            },
+        )
+    }
+
+    /// Check one connection gene against this chunk's section sizes.
+    pub(crate) fn validate_conn(&self, conn: &ConnGene) -> Result<(), ValidationError> {
+        conn.validate()?;
+        match conn.from_section {
+            0 => {
+                if conn.from_index >= self.ni {
+                    return Err(ValidationError::FromIndexOutOfRange {
+                        section: conn.from_section,
+                        index: conn.from_index,
+                    });
                 }
             }
-            match conn.to_section {
-                1 => {
-                    if conn.to_index >= self.nn {
-                        return Err(ValidationError::ToIndexOutOfRange {
-                            section: conn.to_section,
-                            index: conn.to_index,
-                        });
-                    }
+            1 => {
+                if conn.from_index >= self.nn {
+                    return Err(ValidationError::FromIndexOutOfRange {
+                        section: conn.from_section,
+                        index: conn.from_index,
+                    });
                 }
-                2 => {
-                    if conn.to_index >= self.no {
-                        return Err(ValidationError::ToIndexOutOfRange {
-                            section: conn.to_section,
-                            index: conn.to_index,
-                        });
-                    }
+            }
+            _ => {
+                return Err(ValidationError::InvalidConnEdge {
+                    from_section: conn.from_section,
+                    to_section: conn.to_section,
+                })
+            }
+        }
+        match conn.to_section {
+            1 => {
+                if conn.to_index >= self.nn {
+                    return Err(ValidationError::ToIndexOutOfRange {
+                        section: conn.to_section,
+                        index: conn.to_index,
+                    });
                 }
-                _ => {
-                    return Err(ValidationError::InvalidConnEdge {
-                        from_section: conn.from_section,
-                        to_section: conn.to_section,
-                    })
+            }
+            2 => {
+                if conn.to_index >= self.no {
+                    return Err(ValidationError::ToIndexOutOfRange {
+                        section: conn.to_section,
+                        index: conn.to_index,
+                    });
                 }
             }
+            _ => {
+                return Err(ValidationError::InvalidConnEdge {
+                    from_section: conn.from_section,
+                    to_section: conn.to_section,
+                })
+            }
         }
         Ok(())
     }
@@ -292,6 +564,26 @@ impl ChunkGene {
     }
 }
 
+/// Pack a bitset into LSB-first bytes as used by the chunk binary format.
+pub(crate) fn pack_bits(bits: &BitSlice<u8, Lsb0>) -> Vec<u8> {
+    let mut out = vec![0u8; bits.len().div_ceil(8)];
+    for i in bits.iter_ones() {
+        out[i / 8] |= 1 << (i % 8);
+    }
+    out
+}
+
+/// Unpack `count` LSB-first bits from `bytes`; missing bytes read as zero.
+fn unpack_bits(bytes: &[u8], count: u32) -> BitVec<u8, Lsb0> {
+    let mut bits = BitVec::<u8, Lsb0>::from_slice(bytes);
+    bits.resize(count as usize, false);
+    bits
+}
+
+fn is_unassigned(innovation: &u32) -> bool {
+    *innovation == 0
+}
+
 /// Gene describing a connection within a chunk.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ConnGene {
@@ -302,6 +594,9 @@ pub struct ConnGene {
     pub from_index: u32,
     pub to_index: u32,
     pub order_tag: u32,
+    /// Historical marking assigned by an `InnovationTracker`; 0 = unassigned.
+    #[serde(default, skip_serializing_if = "is_unassigned")]
+    pub innovation: u32,
 }
 
 impl ConnGene {
@@ -322,11 +617,46 @@ impl ConnGene {
             from_index,
             to_index,
             order_tag,
+            innovation: 0,
         };
         conn.validate()?;
         Ok(conn)
     }
 
+    /// Convert into the binary-level [`Connection`] record.
+    pub fn to_connection(&self) -> Result<Connection, ValidationError> {
+        self.validate()?;
+        let edge_err = || ValidationError::InvalidConnEdge {
+            from_section: self.from_section,
+            to_section: self.to_section,
+        };
+        Ok(Connection {
+            from_section: Section::try_from(self.from_section).map_err(|_| edge_err())?,
+            to_section: Section::try_from(self.to_section).map_err(|_| edge_err())?,
+            trigger: Trigger::try_from(self.trigger)
+                .map_err(|_| ValidationError::InvalidTrigger(self.trigger))?,
+            action: Action::try_from(self.action)
+                .map_err(|_| ValidationError::InvalidAction(self.action))?,
+            from_index: self.from_index,
+            to_index: self.to_index,
+            order_tag: self.order_tag,
+        })
+    }
+
+    /// Build a gene from a binary-level [`Connection`] record.
+    pub fn from_connection(conn: &Connection) -> Self {
+        Self {
+            from_section: conn.from_section as u8,
+            to_section: conn.to_section as u8,
+            trigger: conn.trigger as u8,
+            action: conn.action as u8,
+            from_index: conn.from_index,
+            to_index: conn.to_index,
+            order_tag: conn.order_tag,
+            innovation: 0,
+        }
+    }
+
     pub fn validate(&self) -> Result<(), ValidationError> {
         if self.trigger > 2 {
             return Err(ValidationError::InvalidTrigger(self.trigger));
@@ -354,6 +684,16 @@ pub struct LinkGene {
     pub to_chunk: u32,
     pub to_in_idx: u32,
     pub order_tag: u32,
+    /// Ticks of latency, see [`Link::delay`].
+    #[serde(default, skip_serializing_if = "is_undelayed")]
+    pub delay: u16,
+    /// Historical marking assigned by an `InnovationTracker`; 0 = unassigned.
+    #[serde(default, skip_serializing_if = "is_unassigned")]
+    pub innovation: u32,
+}
+
+fn is_undelayed(delay: &u16) -> bool {
+    *delay == 0
 }
 
 impl LinkGene {
@@ -375,11 +715,44 @@ impl LinkGene {
             to_chunk,
             to_in_idx,
             order_tag,
+            delay: 0,
+            innovation: 0,
         };
         link.validate()?;
         Ok(link)
     }
 
+    /// Convert into the binary-level [`Link`] record.
+    pub fn to_link(&self) -> Result<Link, ValidationError> {
+        Ok(Link {
+            from_chunk: self.from_chunk,
+            from_out_idx: self.from_out_idx,
+            trigger: Trigger::try_from(self.trigger)
+                .map_err(|_| ValidationError::InvalidTrigger(self.trigger))?,
+            action: Action::try_from(self.action)
+                .map_err(|_| ValidationError::InvalidAction(self.action))?,
+            to_chunk: self.to_chunk,
+            to_in_idx: self.to_in_idx,
+            order_tag: self.order_tag,
+            delay: self.delay,
+        })
+    }
+
+    /// Build a gene from a binary-level [`Link`] record.
+    pub fn from_link(link: &Link) -> Self {
+        Self {
+            from_chunk: link.from_chunk,
+            from_out_idx: link.from_out_idx,
+            trigger: link.trigger as u8,
+            action: link.action as u8,
+            to_chunk: link.to_chunk,
+            to_in_idx: link.to_in_idx,
+            order_tag: link.order_tag,
+            delay: link.delay,
+            innovation: 0,
+        }
+    }
+
     pub fn validate(&self) -> Result<(), ValidationError> {
         if self.trigger > 2 {
             return Err(ValidationError::InvalidTrigger(self.trigger));
@@ -391,21 +764,201 @@ impl LinkGene {
     }
 }
 
+/// Gene gating a child chunk behind a parent bit (spec §8).
+///
+/// `map_in` pairs are `(parent internal bit, child input bit)`; `map_out`
+/// pairs are `(child output bit, parent output bit)`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EmbedGene {
+    pub parent_chunk: u32,
+    pub child_chunk: u32,
+    pub gate_bit: u32,
+    /// Parent section of `gate_bit` as a [`Section`] code, as in embed
+    /// records: 0 = input, 1 = internal, 2 = output.
+    #[serde(default = "internal_gate", skip_serializing_if = "is_internal_gate")]
+    pub gate_section: u8,
+    /// 0 = alias, 1 = copy in/out.
+    pub io_mode: u8,
+    pub map_in: Vec<(u32, u32)>,
+    pub map_out: Vec<(u32, u32)>,
+}
+
+fn internal_gate() -> u8 {
+    1
+}
+
+fn is_internal_gate(section: &u8) -> bool {
+    *section == 1
+}
+
+impl EmbedGene {
+    /// Check chunk references, the gate bit and every mapped bit.
+    pub fn validate(&self, chunks: &[ChunkGene]) -> Result<(), ValidationError> {
+        if self.io_mode > 1 {
+            return Err(ValidationError::InvalidIoMode(self.io_mode));
+        }
+        if self.gate_section > 2 {
+            return Err(ValidationError::InvalidEmbedGateSection(self.gate_section));
+        }
+        let parent = chunks
+            .get(self.parent_chunk as usize)
+            .ok_or(ValidationError::InvalidEmbedParent(self.parent_chunk))?;
+        let child = chunks
+            .get(self.child_chunk as usize)
+            .filter(|_| self.child_chunk != self.parent_chunk)
+            .ok_or(ValidationError::InvalidEmbedChild(self.child_chunk))?;
+        if self.gate_bit >= parent.section_len(self.gate_section) {
+            return Err(ValidationError::InvalidEmbedGate {
+                chunk: self.parent_chunk,
+                gate_bit: self.gate_bit,
+            });
+        }
+        for &(parent_bit, child_bit) in &self.map_in {
+            if parent_bit >= parent.nn || child_bit >= child.ni {
+                return Err(ValidationError::InvalidEmbedMapIn {
+                    parent_bit,
+                    child_bit,
+                });
+            }
+        }
+        for &(child_bit, parent_bit) in &self.map_out {
+            if child_bit >= child.no || parent_bit >= parent.no {
+                return Err(ValidationError::InvalidEmbedMapOut {
+                    child_bit,
+                    parent_bit,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert into the binary-level [`Embed`] record.
+    pub fn to_embed(&self) -> Embed {
+        Embed {
+            parent_chunk: self.parent_chunk,
+            child_chunk: self.child_chunk,
+            gate_bit: self.gate_bit,
+            gate_section: match self.gate_section {
+                0 => Section::Input,
+                2 => Section::Output,
+                _ => Section::Internal,
+            },
+            io_mode: if self.io_mode == 0 {
+                IoMode::Alias
+            } else {
+                IoMode::Copy
+            },
+            map_in: self.map_in.clone(),
+            map_out: self.map_out.clone(),
+            gate_prev: false,
+        }
+    }
+
+    /// Build a gene from a binary-level [`Embed`] record.
+    pub fn from_embed(embed: &Embed) -> Self {
+        Self {
+            parent_chunk: embed.parent_chunk,
+            child_chunk: embed.child_chunk,
+            gate_bit: embed.gate_bit,
+            gate_section: embed.gate_section as u8,
+            io_mode: embed.io_mode as u8,
+            map_in: embed.map_in.clone(),
+            map_out: embed.map_out.clone(),
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| {
+        (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
 /// Errors that can occur during validation of genome structures.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ValidationError {
-    InvalidConnEdge { from_section: u8, to_section: u8 },
-    FromIndexOutOfRange { section: u8, index: u32 },
-    ToIndexOutOfRange { section: u8, index: u32 },
-    InputsLenMismatch { expected: u32, actual: usize },
-    OutputsLenMismatch { expected: u32, actual: usize },
-    InternalsLenMismatch { expected: u32, actual: usize },
+    InvalidConnEdge {
+        from_section: u8,
+        to_section: u8,
+    },
+    FromIndexOutOfRange {
+        section: u8,
+        index: u32,
+    },
+    ToIndexOutOfRange {
+        section: u8,
+        index: u32,
+    },
+    InputsLenMismatch {
+        expected: u32,
+        actual: usize,
+    },
+    OutputsLenMismatch {
+        expected: u32,
+        actual: usize,
+    },
+    InternalsLenMismatch {
+        expected: u32,
+        actual: usize,
+    },
     InvalidLinkFromChunk(u32),
     InvalidLinkToChunk(u32),
-    InvalidLinkFromIndex { chunk: u32, index: u32 },
-    InvalidLinkToIndex { chunk: u32, index: u32 },
+    InvalidLinkFromIndex {
+        chunk: u32,
+        index: u32,
+    },
+    InvalidLinkToIndex {
+        chunk: u32,
+        index: u32,
+    },
     InvalidTrigger(u8),
     InvalidAction(u8),
+    InvalidIoMode(u8),
+    InvalidEmbedGateSection(u8),
+    InvalidEmbedParent(u32),
+    InvalidEmbedChild(u32),
+    InvalidEmbedGate {
+        chunk: u32,
+        gate_bit: u32,
+    },
+    InvalidEmbedMapIn {
+        parent_bit: u32,
+        child_bit: u32,
+    },
+    InvalidEmbedMapOut {
+        child_bit: u32,
+        parent_bit: u32,
+    },
+    EmbedCycle(u32),
+    /// Connection gene `second` repeats the source, target and trigger of
+    /// `first`.
+    DuplicateConn {
+        first: u32,
+        second: u32,
+    },
+    /// Two connection genes from the same source bit share an order tag.
+    ConnOrderTagCollision {
+        section: u8,
+        index: u32,
+        order_tag: u32,
+    },
+    /// Link gene `second` repeats the source, target and trigger of `first`.
+    DuplicateLink {
+        first: u32,
+        second: u32,
+    },
+    /// Two link genes from the same chunk output share an order tag.
+    LinkOrderTagCollision {
+        chunk: u32,
+        index: u32,
+        order_tag: u32,
+    },
+    /// A chunk-level error reported by [`Genome::validate_all`].
+    InChunk {
+        chunk: u32,
+        error: Box<ValidationError>,
+    },
 }
 
 impl ValidationError {
@@ -465,6 +1018,55 @@ impl std::fmt::Display for ValidationError {
             }
             InvalidTrigger(t) => write!(f, "invalid trigger {}", t),
             InvalidAction(a) => write!(f, "invalid action {}", a),
+            InvalidIoMode(m) => write!(f, "invalid io mode {}", m),
+            InvalidEmbedGateSection(s) => write!(f, "invalid embed gate section {}", s),
+            InvalidEmbedParent(c) => write!(f, "embed parent_chunk {} out of range", c),
+            InvalidEmbedChild(c) => write!(f, "embed child_chunk {} invalid or reused", c),
+            InvalidEmbedGate { chunk, gate_bit } => {
+                write!(
+                    f,
+                    "embed gate_bit {} out of range for chunk {}",
+                    gate_bit, chunk
+                )
+            }
+            InvalidEmbedMapIn {
+                parent_bit,
+                child_bit,
+            } => write!(f, "embed map_in {}->{} out of range", parent_bit, child_bit),
+            InvalidEmbedMapOut {
+                child_bit,
+                parent_bit,
+            } => write!(
+                f,
+                "embed map_out {}->{} out of range",
+                child_bit, parent_bit
+            ),
+            EmbedCycle(c) => write!(f, "embed cycle through chunk {}", c),
+            DuplicateConn { first, second } => {
+                write!(f, "connection {} duplicates connection {}", second, first)
+            }
+            ConnOrderTagCollision {
+                section,
+                index,
+                order_tag,
+            } => write!(
+                f,
+                "order tag {} used twice by connections from section {} index {}",
+                order_tag, section, index
+            ),
+            DuplicateLink { first, second } => {
+                write!(f, "link {} duplicates link {}", second, first)
+            }
+            LinkOrderTagCollision {
+                chunk,
+                index,
+                order_tag,
+            } => write!(
+                f,
+                "order tag {} used twice by links from chunk {} output {}",
+                order_tag, chunk, index
+            ),
+            InChunk { chunk, error } => write!(f, "chunk {}: {}", chunk, error),
         }
     }
 }
@@ -499,6 +1101,25 @@ mod tests {
             vec![conn],
         );
         assert!(chunk.validate().is_ok());
+        let mut twice = chunk.clone();
+        twice.conns.push(ConnGene {
+            order_tag: 1,
+            ..twice.conns[0].clone()
+        });
+        assert_eq!(
+            twice.validate(),
+            Err(ValidationError::DuplicateConn {
+                first: 0,
+                second: 1
+            })
+        );
+        twice.conns[1].trigger = 1;
+        assert!(twice.validate().is_ok());
+        twice.conns[1].order_tag = 0;
+        assert!(matches!(
+            twice.validate(),
+            Err(ValidationError::ConnOrderTagCollision { .. })
+        ));
 
         let bad_conn = ConnGene::new(0, 1, 0, 0, 1, 0, 0).unwrap();
         let bad_chunk = ChunkGene::new(
@@ -514,11 +1135,49 @@ mod tests {
             bad_chunk.validate(),
             Err(ValidationError::FromIndexOutOfRange { .. })
         ));
+
+        let mut messy = bad_chunk.clone();
+        messy.internals_init.push(true);
+        let good = ConnGene::new(0, 1, 0, 0, 0, 0, 0).unwrap();
+        messy.conns.push(good.clone());
+        messy.conns.push(good);
+        let errors = messy.validate_all();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::InternalsLenMismatch {
+                    expected: 1,
+                    actual: 2
+                },
+                ValidationError::FromIndexOutOfRange {
+                    section: 0,
+                    index: 1
+                },
+                ValidationError::DuplicateConn {
+                    first: 1,
+                    second: 2
+                },
+                ValidationError::ConnOrderTagCollision {
+                    section: 0,
+                    index: 0,
+                    order_tag: 0
+                },
+            ]
+        );
+        let genome = Genome {
+            chunks: vec![messy],
+            ..Genome::new(Vec::new(), Vec::new(), GenomeMeta::new(0, String::new())).unwrap()
+        };
+        assert_eq!(genome.validate_all().len(), 4);
+        assert!(matches!(
+            &genome.validate_all()[0],
+            ValidationError::InChunk { chunk: 0, .. }
+        ));
     }
 
     #[test]
     fn genome_validate_and_sort() {
-        let conn_a1 = ConnGene::new(1, 2, 0, 0, 0, 0, 1).unwrap();
+        let conn_a1 = ConnGene::new(1, 2, 1, 0, 0, 0, 1).unwrap();
         let conn_a0 = ConnGene::new(1, 2, 0, 0, 0, 0, 0).unwrap();
         let chunk_a = ChunkGene::new(
             0,
@@ -558,6 +1217,107 @@ mod tests {
         assert!(genome.validate().is_ok());
     }
 
+    #[test]
+    fn chunk_round_trip() {
+        let chunk_a = ChunkGene::new(
+            1,
+            1,
+            9,
+            bitvec![u8, Lsb0; 1],
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0, 1, 0, 0, 0, 0, 0, 0, 1],
+            vec![
+                ConnGene::new(0, 1, 0, 0, 0, 8, 0).unwrap(),
+                ConnGene::new(1, 2, 2, 1, 8, 0, 0).unwrap(),
+            ],
+        );
+        let chunk_b = ChunkGene::new(
+            1,
+            0,
+            0,
+            bitvec![u8, Lsb0; 0],
+            BitVec::new(),
+            BitVec::new(),
+            Vec::new(),
+        );
+        let link = LinkGene::new(0, 0, 1, 2, 1, 0, 4).unwrap();
+        let genome = Genome::new(
+            vec![chunk_a, chunk_b],
+            vec![link],
+            GenomeMeta::new(0, String::new()),
+        )
+        .unwrap();
+
+        let chunks = genome.to_chunks().unwrap();
+        let links = genome.to_links().unwrap();
+        assert_eq!(chunks[0].internal_bits, vec![0b0000_0010, 0b0000_0001]);
+        for chunk in &chunks {
+            crate::chunk::validate_chunk(chunk).unwrap();
+        }
+        crate::link::validate_links(&links, &chunks).unwrap();
+
+        let encoded: Vec<MycosChunk> = chunks
+            .iter()
+            .map(|c| crate::chunk::parse_chunk(&crate::chunk::encode_chunk(c)).unwrap())
+            .collect();
+        let back = Genome::from_chunks(&encoded, &links).unwrap();
+        assert_eq!(
+            serde_json::to_string(&back).unwrap(),
+            serde_json::to_string(&genome).unwrap()
+        );
+    }
+
+    #[test]
+    fn embed_validation() {
+        let chunk = || {
+            ChunkGene::new(
+                1,
+                1,
+                1,
+                bitvec![u8, Lsb0; 0],
+                bitvec![u8, Lsb0; 0],
+                bitvec![u8, Lsb0; 0],
+                Vec::new(),
+            )
+        };
+        let embed = |parent_chunk, child_chunk, gate_bit| EmbedGene {
+            parent_chunk,
+            child_chunk,
+            gate_bit,
+            gate_section: 1,
+            io_mode: 0,
+            map_in: vec![(0, 0)],
+            map_out: vec![(0, 0)],
+        };
+        let genome = Genome::new(
+            vec![chunk(), chunk()],
+            Vec::new(),
+            GenomeMeta::new(0, String::new()),
+        )
+        .unwrap();
+        let ok = genome.clone().with_embeds(vec![embed(0, 1, 0)]).unwrap();
+        assert_eq!(ok.to_embeds().unwrap()[0].io_mode, IoMode::Alias);
+        assert_eq!(
+            genome.clone().with_embeds(vec![embed(0, 1, 1)]).err(),
+            Some(ValidationError::InvalidEmbedGate {
+                chunk: 0,
+                gate_bit: 1
+            })
+        );
+        assert_eq!(
+            genome
+                .clone()
+                .with_embeds(vec![embed(0, 1, 0), embed(1, 0, 0)])
+                .err(),
+            Some(ValidationError::EmbedCycle(0))
+        );
+
+        // shrinking the parent's internals drops the embed with its gate
+        let mut ok = ok;
+        ok.resize_chunk_internals(0, 0);
+        assert!(ok.embeds.is_empty());
+    }
+
     #[test]
     fn resize_inputs_drops_invalid_refs() {
         let chunk0 = ChunkGene::new(
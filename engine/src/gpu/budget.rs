@@ -0,0 +1,304 @@
+//! Device memory planning for batches.
+//!
+//! A [`SystemBuffers`](super::buffers::SystemBuffers) batch concatenates its
+//! instances into one buffer per binding, so a large enough batch outgrows
+//! the device's buffer limits and fails at allocation. [`Footprint`] predicts
+//! what one instance adds to each buffer and [`split_batch`] cuts a batch into
+//! consecutive sub-batches that each fit a [`MemoryBudget`].
+
+#![cfg(any(feature = "webgpu", feature = "gpu-native"))]
+
+use std::ops::Range;
+
+use wgpu::Limits;
+
+use super::buffers::{
+    BINDING_COUNT, COUNTS, CSR_EFFECTS_OFF, CSR_EFFECTS_ON, CSR_EFFECTS_TOGGLE, CSR_OFFS_OFF,
+    CSR_OFFS_ON, CSR_OFFS_TOGGLE, CURR_INPUTS, CURR_INTERNALS, CURR_OUTPUTS, DELAYED_LINKS,
+    DELAY_LINES, DISPATCH, FRONTIER, HASH_RING, HASH_STATE, INSTANCES, METRICS, PREV_INPUTS,
+    PREV_INTERNALS, PREV_OUTPUTS, PROPOSALS, QUEUES, WINNERS,
+};
+use crate::chunk::{MycosChunk, Section};
+use crate::link::{GlobalBitMap, Link};
+
+const BINDINGS: usize = BINDING_COUNT as usize;
+
+/// Words a binding's buffer holds however small the batch: the counts
+/// uniform, the list lengths and indirect arguments shared by the whole
+/// batch, and one word or entry for tables wgpu would otherwise see empty.
+const MIN_WORDS: [u64; BINDINGS] = {
+    let mut min = [1; BINDINGS];
+    min[COUNTS as usize] = 8;
+    min[FRONTIER as usize] = 2;
+    min[QUEUES as usize] = 2;
+    min[DISPATCH as usize] = 6;
+    min[CSR_EFFECTS_ON as usize] = 4;
+    min[CSR_EFFECTS_OFF as usize] = 4;
+    min[CSR_EFFECTS_TOGGLE as usize] = 4;
+    min[PROPOSALS as usize] = 4;
+    min[WINNERS as usize] = 2;
+    min[DELAYED_LINKS as usize] = 8;
+    min
+};
+
+/// Words one instance adds to each buffer of a
+/// [`SystemBuffers`](super::buffers::SystemBuffers) batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Footprint {
+    words: [u64; BINDINGS],
+}
+
+impl Footprint {
+    /// Footprint of `chunks` joined by `links` as one instance keeping the
+    /// last `hash_window` Internal hashes, as
+    /// [`SystemBuffers::new`](super::buffers::SystemBuffers::new) would lay it
+    /// out.
+    pub fn new(chunks: &[MycosChunk], links: &[Link], hash_window: u32) -> Self {
+        let map = GlobalBitMap::new(chunks);
+        let state = |section| (map.total(section) as u64).div_ceil(32).max(1);
+        let bits = [Section::Input, Section::Internal, Section::Output]
+            .map(|section| map.total(section) as u64)
+            .iter()
+            .sum::<u64>();
+        let mut effects = [0u64; 3];
+        for conn in chunks.iter().flat_map(|c| &c.connections) {
+            effects[conn.trigger as usize] += 1;
+        }
+        let (delayed, links): (Vec<&Link>, Vec<&Link>) = links.iter().partition(|l| l.delay > 0);
+        for link in links {
+            effects[link.trigger as usize] += 1;
+        }
+
+        let mut words = [0; BINDINGS];
+        let mut set = |bindings: &[u32], n: u64| {
+            for &binding in bindings {
+                words[binding as usize] = n;
+            }
+        };
+        set(&[PREV_INPUTS, CURR_INPUTS], state(Section::Input));
+        set(&[PREV_INTERNALS, CURR_INTERNALS], state(Section::Internal));
+        set(&[PREV_OUTPUTS, CURR_OUTPUTS], state(Section::Output));
+        set(&[FRONTIER, WINNERS], bits * 2);
+        set(&[CSR_OFFS_ON, CSR_OFFS_OFF, CSR_OFFS_TOGGLE], bits + 1);
+        set(&[CSR_EFFECTS_ON], effects[0] * 4);
+        set(&[CSR_EFFECTS_OFF], effects[1] * 4);
+        set(&[CSR_EFFECTS_TOGGLE], effects[2] * 4);
+        set(&[PROPOSALS], effects.iter().sum::<u64>() * 4);
+        set(&[METRICS, HASH_STATE], 4);
+        set(&[HASH_RING], hash_window as u64 * 4);
+        set(&[INSTANCES], 16);
+        set(&[DELAYED_LINKS], delayed.len() as u64 * 8);
+        set(
+            &[DELAY_LINES],
+            delayed.iter().map(|l| (l.delay as u64).div_ceil(32)).sum(),
+        );
+        Self { words }
+    }
+
+    /// Bytes the instance adds to the buffer at `binding`.
+    pub fn bytes(&self, binding: u32) -> u64 {
+        self.words[binding as usize] * 4
+    }
+
+    /// Bytes the instance adds over every buffer.
+    pub fn total(&self) -> u64 {
+        self.words.iter().sum::<u64>() * 4
+    }
+
+    /// `n` instances of this footprint together, e.g. every episode of one
+    /// genome.
+    pub fn times(&self, n: u64) -> Self {
+        Self {
+            words: self.words.map(|w| w * n),
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        for (w, o) in self.words.iter_mut().zip(&other.words) {
+            *w += o;
+        }
+    }
+
+    /// Bytes of each buffer of a batch with this footprint.
+    fn buffer_bytes(&self) -> [u64; BINDINGS] {
+        std::array::from_fn(|b| self.words[b].max(MIN_WORDS[b]) * 4)
+    }
+}
+
+/// Device memory a batch may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// Bytes of any one buffer.
+    pub max_buffer: u64,
+    /// Bytes of every buffer of a batch together.
+    pub max_total: u64,
+}
+
+impl MemoryBudget {
+    /// Buffers up to the smaller of `max_buffer_size` and
+    /// `max_storage_buffer_binding_size`, with no cap on the total: wgpu
+    /// does not report how much memory the device has.
+    pub fn from_limits(limits: &Limits) -> Self {
+        Self {
+            max_buffer: limits
+                .max_buffer_size
+                .min(limits.max_storage_buffer_binding_size as u64),
+            max_total: u64::MAX,
+        }
+    }
+
+    /// Whether a batch of `footprint`, the sum of its instances, fits.
+    pub fn fits(&self, footprint: &Footprint) -> bool {
+        self.overflow(footprint).is_none()
+    }
+
+    /// The first binding whose buffer is too large, or `None` for the
+    /// total, with the bytes needed and the limit.
+    fn overflow(&self, footprint: &Footprint) -> Option<(Option<u32>, u64, u64)> {
+        let bytes = footprint.buffer_bytes();
+        if let Some(b) = bytes.iter().position(|&n| n > self.max_buffer) {
+            return Some((Some(b as u32), bytes[b], self.max_buffer));
+        }
+        let total: u64 = bytes.iter().sum();
+        (total > self.max_total).then_some((None, total, self.max_total))
+    }
+}
+
+/// Why [`split_batch`] could not place an instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BudgetError {
+    /// Instance `instance` does not fit even in a batch of its own: it needs
+    /// `bytes` in the buffer at `binding`, or over all of them when `None`,
+    /// against a limit of `limit`.
+    InstanceTooLarge {
+        instance: usize,
+        binding: Option<u32>,
+        bytes: u64,
+        limit: u64,
+    },
+}
+
+impl std::fmt::Display for BudgetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetError::InstanceTooLarge {
+                instance,
+                binding,
+                bytes,
+                limit,
+            } => {
+                write!(f, "instance {instance} needs {bytes} bytes ")?;
+                match binding {
+                    Some(b) => write!(f, "in binding {b}")?,
+                    None => write!(f, "in total")?,
+                }
+                write!(f, ", over the limit of {limit}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BudgetError {}
+
+/// Cut a batch of instances with `footprints` into consecutive ranges that
+/// each fit `budget`, filling every range before starting the next.
+pub fn split_batch(
+    footprints: &[Footprint],
+    budget: &MemoryBudget,
+) -> Result<Vec<Range<usize>>, BudgetError> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut batch = Footprint {
+        words: [0; BINDINGS],
+    };
+    for (i, footprint) in footprints.iter().enumerate() {
+        let mut grown = batch;
+        grown.add(footprint);
+        if budget.fits(&grown) {
+            batch = grown;
+            continue;
+        }
+        if let Some((binding, bytes, limit)) = budget.overflow(footprint) {
+            return Err(BudgetError::InstanceTooLarge {
+                instance: i,
+                binding,
+                bytes,
+                limit,
+            });
+        }
+        batches.push(start..i);
+        start = i;
+        batch = *footprint;
+    }
+    if start < footprints.len() {
+        batches.push(start..footprints.len());
+    }
+    Ok(batches)
+}
+
+#[cfg(all(test, feature = "gpu-native"))]
+mod tests {
+    use super::*;
+    use crate::builder::fixtures::{relay, wire};
+    use crate::chunk::{Action, Trigger};
+    use crate::gpu::buffers::SystemBuffers;
+    use crate::gpu::device::test_device;
+    use crate::gpu::pipeline::Pipelines;
+
+    #[test]
+    fn splits_batches_to_fit() {
+        let chunks = [wire(), wire()];
+        let links = [
+            relay(Trigger::On, Action::Enable, 0),
+            Link {
+                delay: 40,
+                ..relay(Trigger::Off, Action::Disable, 1)
+            },
+        ];
+        let footprint = Footprint::new(&chunks, &links, 8);
+        assert_eq!(footprint.bytes(DELAY_LINES), 8);
+        assert_eq!(footprint.times(3).total(), 3 * footprint.total());
+
+        // room for three instances at a time, in buffers and in total
+        let three = footprint.times(3).buffer_bytes();
+        let budget = MemoryBudget {
+            max_buffer: *three.iter().max().unwrap(),
+            max_total: u64::MAX,
+        };
+        let ranges = split_batch(&[footprint; 7], &budget).unwrap();
+        assert_eq!(ranges, vec![0..3, 3..6, 6..7]);
+        let budget = MemoryBudget {
+            max_buffer: u64::MAX,
+            max_total: three.iter().sum(),
+        };
+        assert_eq!(split_batch(&[footprint; 7], &budget).unwrap().len(), 3);
+        assert_eq!(split_batch(&[], &budget).unwrap(), Vec::new());
+
+        let budget = MemoryBudget {
+            max_buffer: footprint.bytes(PROPOSALS) - 1,
+            max_total: u64::MAX,
+        };
+        let err = split_batch(&[footprint], &budget).unwrap_err();
+        assert_eq!(
+            err,
+            BudgetError::InstanceTooLarge {
+                instance: 0,
+                binding: Some(PROPOSALS),
+                bytes: 144,
+                limit: 143,
+            }
+        );
+
+        // the prediction matches what gets allocated
+        let Some((device, _queue)) = test_device() else {
+            return;
+        };
+        let pipelines = Pipelines::new(&device);
+        let system = (&chunks[..], &links[..]);
+        let buffers = SystemBuffers::new(&device, &pipelines, &[system; 3], 8).unwrap();
+        let batch = footprint.times(3).buffer_bytes();
+        for (binding, bytes) in batch.iter().enumerate() {
+            assert_eq!(buffers.buffer(binding as u32).size(), *bytes, "{binding}");
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! Device buffers for a batch of loaded systems.
+//!
+//! [`SystemBuffers`] owns one buffer per binding of `kernels.wgsl` shared by
+//! every system of the batch: each system, or instance, gets a slice of each
+//! buffer sized by [`plan_device_layout_with`], described by its
+//! [`InstanceInfo`] in the `instances` table. The initial state, the global
+//! CSR of connections and undelayed links and the table of delayed links
+//! are uploaded up front, and one bind group per kernel serves
+//! [`tick`](super::pipeline::tick). Prev starts equal to Curr, so like
+//! [`EpisodeRunner`](crate::runner::EpisodeRunner) the initial bits fire no
+//! edges.
+
+#![cfg(any(feature = "webgpu", feature = "gpu-native"))]
+
+use std::ops::Range;
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, Buffer, BufferDescriptor, BufferUsages, Device,
+    Queue,
+};
+
+use super::pipeline::{scoped, GpuError, Pipelines, KERNELS};
+
+use crate::chunk::{MycosChunk, Section};
+use crate::layout::{bit_to_word, plan_device_layout_with, WORD_ALIGN};
+use crate::link::{build_global_csr_gated, ConnectionGate, GlobalBitMap, GlobalBits, Link};
+
+/// Number of bindings in `@group(0)` of `kernels.wgsl`.
+pub const BINDING_COUNT: u32 = 24;
+
+pub const COUNTS: u32 = 0;
+pub const PREV_INPUTS: u32 = 1;
+pub const CURR_INPUTS: u32 = 2;
+pub const PREV_INTERNALS: u32 = 3;
+pub const CURR_INTERNALS: u32 = 4;
+pub const PREV_OUTPUTS: u32 = 5;
+pub const CURR_OUTPUTS: u32 = 6;
+pub const FRONTIER: u32 = 7;
+pub const QUEUES: u32 = 8;
+pub const DISPATCH: u32 = 9;
+pub const CSR_OFFS_ON: u32 = 10;
+pub const CSR_OFFS_OFF: u32 = 11;
+pub const CSR_OFFS_TOGGLE: u32 = 12;
+pub const CSR_EFFECTS_ON: u32 = 13;
+pub const CSR_EFFECTS_OFF: u32 = 14;
+pub const CSR_EFFECTS_TOGGLE: u32 = 15;
+pub const PROPOSALS: u32 = 16;
+pub const WINNERS: u32 = 17;
+pub const METRICS: u32 = 18;
+pub const HASH_RING: u32 = 19;
+pub const HASH_STATE: u32 = 20;
+pub const INSTANCES: u32 = 21;
+pub const DELAYED_LINKS: u32 = 22;
+pub const DELAY_LINES: u32 = 23;
+
+const LABELS: [&str; BINDING_COUNT as usize] = [
+    "counts",
+    "prev-inputs",
+    "curr-inputs",
+    "prev-internals",
+    "curr-internals",
+    "prev-outputs",
+    "curr-outputs",
+    "frontier",
+    "queues",
+    "dispatch",
+    "csr-offs-on",
+    "csr-offs-off",
+    "csr-offs-toggle",
+    "csr-effects-on",
+    "csr-effects-off",
+    "csr-effects-toggle",
+    "proposals",
+    "winners",
+    "metrics",
+    "hash-ring",
+    "hash-state",
+    "instances",
+    "delayed-links",
+    "delay-lines",
+];
+
+/// Where one instance of a [`SystemBuffers`] batch lives, mirrored on the
+/// device as `instances[i]`. Bit bases count one slot per global bit, word
+/// bases count `u32` state words and effect bases count CSR effects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InstanceInfo {
+    pub input_bits: u32,
+    pub internal_bits: u32,
+    pub output_bits: u32,
+    /// First winner slot.
+    pub bit_base: u32,
+    pub input_base: u32,
+    pub internal_base: u32,
+    pub output_base: u32,
+    /// First word of the CSR offsets, `total_bits + 1` per trigger.
+    pub offs_base: u32,
+    pub on_base: u32,
+    pub off_base: u32,
+    pub toggle_base: u32,
+    /// First slot of the proposals buffer.
+    pub proposal_base: u32,
+    /// Proposals a round can emit at most: every effect of the instance.
+    pub proposal_cap: u32,
+    /// First entry of the delayed link table.
+    pub delayed_base: u32,
+    /// Links with a delay, which travel through delay lines instead of the
+    /// CSR.
+    pub delayed_count: u32,
+}
+
+impl InstanceInfo {
+    pub fn total_bits(&self) -> u32 {
+        self.input_bits + self.internal_bits + self.output_bits
+    }
+
+    /// Words of `section` in the batch state buffers.
+    pub fn words(&self, section: Section) -> Range<usize> {
+        let (base, bits) = match section {
+            Section::Input => (self.input_base, self.input_bits),
+            Section::Internal => (self.internal_base, self.internal_bits),
+            Section::Output => (self.output_base, self.output_bits),
+        };
+        base as usize..(base + bits.div_ceil(32)) as usize
+    }
+
+    fn to_words(self) -> [u32; 16] {
+        [
+            self.input_bits,
+            self.internal_bits,
+            self.output_bits,
+            self.bit_base,
+            self.input_base,
+            self.internal_base,
+            self.output_base,
+            self.offs_base,
+            self.on_base,
+            self.off_base,
+            self.toggle_base,
+            self.proposal_base,
+            self.proposal_cap,
+            self.delayed_base,
+            self.delayed_count,
+            0,
+        ]
+    }
+}
+
+/// Every device buffer of a batch of loaded systems and the bind groups
+/// over them.
+pub struct SystemBuffers {
+    instances: Vec<InstanceInfo>,
+    /// Initial Inputs, Internals and Outputs of the whole batch, re-uploaded
+    /// by [`reset`](Self::reset).
+    initial: [Vec<u32>; 3],
+    buffers: Vec<Buffer>,
+    /// One per kernel, indexed like [`KERNELS`].
+    bind_groups: Vec<BindGroup>,
+}
+
+impl SystemBuffers {
+    /// Create and fill the buffers for `systems`, each a set of chunks
+    /// joined by links and run as its own instance; links with a delay get a
+    /// delay line of `delay` bits each, keeping the last
+    /// `hash_window` Internal hashes of each for cycle detection, and bind
+    /// them for each of `pipelines`.
+    ///
+    /// Fails if the device rejects a buffer or bind group, e.g. one past its
+    /// limits, or runs out of memory; see [`Footprint`](super::budget::Footprint)
+    /// to size batches ahead. Panics if `systems` is empty or `hash_window` is
+    /// 0.
+    pub fn new(
+        device: &Device,
+        pipelines: &Pipelines,
+        systems: &[(&[MycosChunk], &[Link])],
+        hash_window: u32,
+    ) -> Result<Self, GpuError> {
+        let systems: Vec<_> = systems.iter().map(|&(c, l)| (c, l, &[][..])).collect();
+        Self::new_gated(device, pipelines, &systems, hash_window)
+    }
+
+    /// [`new`](Self::new) for systems whose gated connections only fire
+    /// while their gate bit is set, which K2 checks as it expands them.
+    pub fn new_gated(
+        device: &Device,
+        pipelines: &Pipelines,
+        systems: &[(&[MycosChunk], &[Link], &[ConnectionGate])],
+        hash_window: u32,
+    ) -> Result<Self, GpuError> {
+        assert!(!systems.is_empty(), "a batch needs at least one system");
+        assert!(hash_window > 0, "hash window must hold at least one tick");
+
+        let mut instances = Vec::with_capacity(systems.len());
+        let mut initial: [Vec<u32>; 3] = Default::default();
+        let mut offs: [Vec<u32>; 3] = Default::default();
+        let mut effects: [Vec<u32>; 3] = Default::default();
+        let mut delayed_links: Vec<u32> = Vec::new();
+        let mut delay_lines = 0;
+        let mut bits = 0;
+        let mut proposals = 0;
+        for &(chunks, links, gates) in systems {
+            let (delayed, links): (Vec<Link>, Vec<Link>) =
+                links.iter().cloned().partition(|l| l.delay > 0);
+            let layout = plan_device_layout_with(chunks, &links, WORD_ALIGN);
+            let (csr, effect_gates) = build_global_csr_gated(chunks, &links, gates);
+            let total = layout.total_bits() as usize;
+            let info = InstanceInfo {
+                input_bits: layout.input_bits,
+                internal_bits: layout.internal_bits,
+                output_bits: layout.output_bits,
+                bit_base: bits,
+                input_base: initial[0].len() as u32,
+                internal_base: initial[1].len() as u32,
+                output_base: initial[2].len() as u32,
+                offs_base: offs[0].len() as u32,
+                on_base: (effects[0].len() / 4) as u32,
+                off_base: (effects[1].len() / 4) as u32,
+                toggle_base: (effects[2].len() / 4) as u32,
+                proposal_base: proposals,
+                proposal_cap: csr.effects.len() as u32,
+                delayed_base: (delayed_links.len() / 8) as u32,
+                delayed_count: delayed.len() as u32,
+            };
+
+            let global = GlobalBits::new(chunks);
+            for link in &delayed {
+                delayed_links.extend([
+                    global.id(link.from_chunk, Section::Output, link.from_out_idx),
+                    link.trigger as u32,
+                    global.id(link.to_chunk, Section::Input, link.to_in_idx),
+                    link.action as u32,
+                    link.order_tag,
+                    link.delay as u32,
+                    delay_lines,
+                    0,
+                ]);
+                delay_lines += (link.delay as u32).div_ceil(32);
+            }
+
+            let map = GlobalBitMap::new(chunks);
+            let sections = [Section::Input, Section::Internal, Section::Output];
+            for (words, section) in initial.iter_mut().zip(sections) {
+                words.extend(pack_section(chunks, &map, section));
+            }
+            let triggers = [&csr.offs_on, &csr.offs_off, &csr.offs_tog];
+            for ((o, offs), effects) in triggers.into_iter().zip(&mut offs).zip(&mut effects) {
+                let base = o[0];
+                offs.extend(o.iter().map(|v| v - base));
+                let range = base as usize..o[total] as usize;
+                effects.extend(
+                    csr.effects[range.clone()]
+                        .iter()
+                        .zip(&effect_gates[range])
+                        .flat_map(|(e, &gate)| [e.to_bit, e.order_tag, e.action as u32, gate]),
+                );
+            }
+            bits += info.total_bits();
+            proposals += info.proposal_cap;
+            instances.push(info);
+        }
+
+        let n = instances.len();
+        let counts = [
+            n as u32,
+            hash_window,
+            0,
+            pipelines.max_workgroups(),
+            initial[0].len() as u32,
+            initial[1].len() as u32,
+            initial[2].len() as u32,
+            0,
+        ];
+        let slots = bits.max(1) as usize;
+        let contents: Vec<Vec<u32>> = vec![
+            counts.to_vec(),
+            initial[0].clone(),
+            initial[0].clone(),
+            initial[1].clone(),
+            initial[1].clone(),
+            initial[2].clone(),
+            initial[2].clone(),
+            vec![0; slots * 2],
+            vec![0; 2],
+            vec![0; 6],
+            offs[0].clone(),
+            offs[1].clone(),
+            offs[2].clone(),
+            non_empty(effects[0].clone(), 4),
+            non_empty(effects[1].clone(), 4),
+            non_empty(effects[2].clone(), 4),
+            vec![0; proposals.max(1) as usize * 4],
+            vec![0; slots * 2],
+            vec![0; n * 4],
+            vec![0; n * hash_window as usize * 4],
+            vec![0; n * 4],
+            instances.iter().flat_map(|i| i.to_words()).collect(),
+            non_empty(delayed_links, 8),
+            vec![0; delay_lines.max(1) as usize],
+        ];
+        let buffers = contents
+            .iter()
+            .enumerate()
+            .map(|(binding, words)| {
+                let usage = match binding as u32 {
+                    COUNTS => BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                    DISPATCH => BufferUsages::STORAGE | BufferUsages::INDIRECT,
+                    _ => BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+                };
+                let bytes = words_to_bytes(words);
+                // mapping a buffer the device failed to create panics, so
+                // check it first
+                let buffer = scoped(device, || {
+                    device.create_buffer(&BufferDescriptor {
+                        label: Some(LABELS[binding]),
+                        size: bytes.len() as u64,
+                        usage,
+                        mapped_at_creation: true,
+                    })
+                })?;
+                buffer
+                    .slice(..)
+                    .get_mapped_range_mut()
+                    .copy_from_slice(&bytes);
+                buffer.unmap();
+                Ok(buffer)
+            })
+            .collect::<Result<Vec<Buffer>, GpuError>>()?;
+
+        let bind_groups = scoped(device, || {
+            KERNELS
+                .iter()
+                .enumerate()
+                .map(|(kernel, (entry, _, bindings))| {
+                    let entries: Vec<BindGroupEntry> = bindings
+                        .iter()
+                        .map(|&binding| BindGroupEntry {
+                            binding,
+                            resource: buffers[binding as usize].as_entire_binding(),
+                        })
+                        .collect();
+                    device.create_bind_group(&BindGroupDescriptor {
+                        label: Some(entry),
+                        layout: pipelines.bind_group_layout(kernel),
+                        entries: &entries,
+                    })
+                })
+                .collect()
+        })?;
+
+        Ok(Self {
+            instances,
+            initial,
+            buffers,
+            bind_groups,
+        })
+    }
+
+    /// Number of instances in the batch.
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Always false: [`new`](Self::new) rejects an empty batch.
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// Where each instance lives, in the order of the systems passed to
+    /// [`new`](Self::new).
+    pub fn instances(&self) -> &[InstanceInfo] {
+        &self.instances
+    }
+
+    /// Bind group of `kernel`, one of the kernel constants of
+    /// [`pipeline`](super::pipeline).
+    pub fn bind_group(&self, kernel: usize) -> &BindGroup {
+        &self.bind_groups[kernel]
+    }
+
+    /// Initial words of `section` for the whole batch, as uploaded by
+    /// [`new`](Self::new); [`InstanceInfo::words`] slices out one instance.
+    pub fn initial(&self, section: Section) -> &[u32] {
+        &self.initial[section as usize]
+    }
+
+    /// Buffer bound at `binding`, one of the binding constants.
+    pub fn buffer(&self, binding: u32) -> &Buffer {
+        &self.buffers[binding as usize]
+    }
+
+    /// State words of the batch over all three sections, the items of the
+    /// per-word kernels.
+    pub fn words(&self) -> u32 {
+        self.initial.iter().map(|w| w.len() as u32).sum()
+    }
+
+    /// The `hash_state` buffer [`tick`](super::pipeline::tick) reads.
+    pub fn hash_state(&self) -> &Buffer {
+        self.buffer(HASH_STATE)
+    }
+
+    /// Restore the state uploaded by [`new`](Self::new): Prev and Curr get
+    /// the initial bits, the frontier, metrics, delayed effects in flight and
+    /// the cycle hash history are cleared. The CSR is left alone.
+    pub fn reset(&self, queue: &Queue) {
+        let state = [
+            (PREV_INPUTS, CURR_INPUTS),
+            (PREV_INTERNALS, CURR_INTERNALS),
+            (PREV_OUTPUTS, CURR_OUTPUTS),
+        ];
+        for ((prev, curr), words) in state.into_iter().zip(&self.initial) {
+            self.write(queue, prev, words);
+            self.write(queue, curr, words);
+        }
+        for binding in [QUEUES, WINNERS, METRICS, HASH_RING, HASH_STATE, DELAY_LINES] {
+            self.clear(queue, binding);
+        }
+    }
+
+    /// Overwrite the start of the buffer at `binding` with `words`, e.g.
+    /// Curr Inputs of the whole batch before a tick, or Prev and Curr Inputs
+    /// together to preset them without firing edges.
+    pub fn write(&self, queue: &Queue, binding: u32, words: &[u32]) {
+        queue.write_buffer(self.buffer(binding), 0, &words_to_bytes(words));
+    }
+
+    /// Zero the buffer at `binding`.
+    pub fn clear(&self, queue: &Queue, binding: u32) {
+        let buffer = self.buffer(binding);
+        queue.write_buffer(buffer, 0, &vec![0u8; buffer.size() as usize]);
+    }
+}
+
+/// `section` of every chunk packed into words at its global bits, at least
+/// one word long.
+fn pack_section(chunks: &[MycosChunk], map: &GlobalBitMap, section: Section) -> Vec<u32> {
+    let mut words = vec![0u32; map.total(section).div_ceil(32).max(1) as usize];
+    for (c, chunk) in chunks.iter().enumerate() {
+        let (bytes, count) = match section {
+            Section::Input => (&chunk.input_bits, chunk.input_count),
+            Section::Internal => (&chunk.internal_bits, chunk.internal_count),
+            Section::Output => (&chunk.output_bits, chunk.output_count),
+        };
+        for i in (0..count).filter(|i| bytes[(i / 8) as usize] >> (i % 8) & 1 == 1) {
+            let global = map
+                .global(c as u32, section, i)
+                .expect("bit within its chunk");
+            let (word, mask) = bit_to_word(global);
+            words[word as usize] |= mask;
+        }
+    }
+    words
+}
+
+fn non_empty(mut words: Vec<u32>, min: usize) -> Vec<u32> {
+    if words.is_empty() {
+        words.resize(min, 0);
+    }
+    words
+}
+
+fn words_to_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
@@ -1,3 +1,4 @@
+#[cfg(all(target_arch = "wasm32", feature = "webgpu"))]
 use wasm_bindgen::JsValue;
 
 /// Initialize WebGPU and return the device and queue.
@@ -35,3 +36,70 @@ pub async fn init_device() -> Result<(wgpu::Device, wgpu::Queue), JsValue> {
         .await
         .map_err(|e| JsValue::from_str(&e.to_string()))
 }
+
+/// Why [`init_device_native`] could not produce a device.
+#[cfg(all(not(target_arch = "wasm32"), feature = "gpu-native"))]
+#[derive(Debug)]
+pub enum DeviceError {
+    NoAdapter,
+    RequestDevice(wgpu::RequestDeviceError),
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "gpu-native"))]
+impl std::fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeviceError::NoAdapter => write!(f, "no suitable GPU adapters found"),
+            DeviceError::RequestDevice(e) => write!(f, "device request failed: {e}"),
+        }
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "gpu-native"))]
+impl std::error::Error for DeviceError {}
+
+/// Initialize a native (Vulkan, Metal, DX12 or GL) device and queue,
+/// blocking until they are ready.
+///
+/// This function is only available off `wasm32` with the `gpu-native`
+/// feature enabled. It selects the first high-performance adapter like
+/// [`init_device`] does in the browser, but requests the adapter's own limits,
+/// since the kernels bind more storage buffers than the portable defaults
+/// allow.
+#[cfg(all(not(target_arch = "wasm32"), feature = "gpu-native"))]
+pub fn init_device_native() -> Result<(wgpu::Device, wgpu::Queue), DeviceError> {
+    let instance = wgpu::Instance::default();
+
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        force_fallback_adapter: false,
+        compatible_surface: None,
+    }))
+    .ok_or(DeviceError::NoAdapter)?;
+
+    let descriptor = wgpu::DeviceDescriptor {
+        label: Some("mycos-device"),
+        required_features: wgpu::Features::empty(),
+        required_limits: adapter.limits(),
+    };
+
+    pollster::block_on(adapter.request_device(&descriptor, None))
+        .map_err(DeviceError::RequestDevice)
+}
+
+/// Device for GPU tests, or `None` when this machine has no adapter, which
+/// the calling test should treat as a skip. Set `MYCOS_REQUIRE_GPU` to turn
+/// a missing adapter into a failure instead, as CI does.
+#[cfg(all(test, not(target_arch = "wasm32"), feature = "gpu-native"))]
+pub(crate) fn test_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    match init_device_native() {
+        Ok(pair) => Some(pair),
+        Err(e) if std::env::var_os("MYCOS_REQUIRE_GPU").is_some() => {
+            panic!("MYCOS_REQUIRE_GPU is set but {e}")
+        }
+        Err(e) => {
+            eprintln!("skipping GPU test: {e}");
+            None
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Embedded chunks on the kernels.
+//!
+//! [`execute_gated_alias`] and [`execute_gated_copy`] follow their CPU
+//! counterparts in [`crate::embed`]: the same merged alias view and the same
+//! copy-in/copy-out I/O mapping, with the chunk run to quiescence by one
+//! [`tick`] on the device. The child connections of an alias embed are
+//! gated in the CSR, so K2 drops their proposals while the parent's gate bit
+//! is clear; the gate is checked per round, as on the CPU.
+//!
+//! A device error leaves both chunks, and the embed, as they were.
+
+#![cfg(any(feature = "webgpu", feature = "gpu-native"))]
+
+use std::ops::Range;
+
+use wgpu::{CommandEncoderDescriptor, Device, Queue};
+
+use super::buffers::{
+    SystemBuffers, CURR_INPUTS, CURR_INTERNALS, CURR_OUTPUTS, PREV_INPUTS, PREV_INTERNALS,
+};
+use super::pipeline::{read_buffers, tick, GpuError, Pipelines};
+use crate::chunk::{MycosChunk, Section};
+use crate::embed::{gated_alias_with, gated_copy_with, Embed};
+use crate::link::ConnectionGate;
+use crate::runner::MAX_ROUNDS;
+
+/// Input, Output and Internal bytes of a chunk.
+type Bytes = (Vec<u8>, Vec<u8>, Vec<u8>);
+
+/// [`execute_gated_alias`](crate::execute_gated_alias) on the device.
+pub fn execute_gated_alias(
+    device: &Device,
+    queue: &Queue,
+    pipelines: &Pipelines,
+    parent: &mut MycosChunk,
+    child: &mut MycosChunk,
+    embed: &Embed,
+) -> Result<(), GpuError> {
+    gated_alias_with(parent, child, embed, |merged, gate, gated| {
+        execute_gated(device, queue, pipelines, merged, gate, gated)
+    })
+}
+
+/// [`execute_gated_copy`](crate::execute_gated_copy) on the device.
+pub fn execute_gated_copy(
+    device: &Device,
+    queue: &Queue,
+    pipelines: &Pipelines,
+    parent: &mut MycosChunk,
+    child: &mut MycosChunk,
+    embed: &mut Embed,
+) -> Result<(), GpuError> {
+    gated_copy_with(parent, child, embed, |child| {
+        execute_gated(
+            device,
+            queue,
+            pipelines,
+            child,
+            (Section::Internal, 0),
+            0..0,
+        )
+    })
+}
+
+/// Run `chunk` to quiescence like [`execute`](crate::execute), with the
+/// connections in `gated` firing only while `gate` is set, and return its
+/// Input, Output and Internal bytes.
+///
+/// Every set Input and Internal bit fires on the first round: Prev starts
+/// clear instead of equal to Curr.
+fn execute_gated(
+    device: &Device,
+    queue: &Queue,
+    pipelines: &Pipelines,
+    chunk: &MycosChunk,
+    gate: (Section, u32),
+    gated: Range<usize>,
+) -> Result<Bytes, GpuError> {
+    let gates = [ConnectionGate {
+        chunk: 0,
+        gate,
+        connections: gated,
+    }];
+    let buffers = SystemBuffers::new_gated(
+        device,
+        pipelines,
+        &[(std::slice::from_ref(chunk), &[], &gates)],
+        1,
+    )?;
+    buffers.clear(queue, PREV_INPUTS);
+    buffers.clear(queue, PREV_INTERNALS);
+    tick(device, queue, pipelines, &buffers, MAX_ROUNDS)?;
+
+    let encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("mycos-embed-readback"),
+    });
+    let state = read_buffers(
+        device,
+        queue,
+        encoder,
+        &[
+            buffers.buffer(CURR_INPUTS),
+            buffers.buffer(CURR_OUTPUTS),
+            buffers.buffer(CURR_INTERNALS),
+        ],
+    )?;
+    let bytes = |words: &[u32], count: u32| -> Vec<u8> {
+        let mut bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        bytes.truncate(count.div_ceil(8) as usize);
+        bytes
+    };
+    Ok((
+        bytes(&state[0], chunk.input_count),
+        bytes(&state[1], chunk.output_count),
+        bytes(&state[2], chunk.internal_count),
+    ))
+}
+
+#[cfg(all(test, feature = "gpu-native"))]
+mod tests {
+    use super::*;
+    use crate::builder::ChunkBuilder;
+    use crate::chunk::{Action, Trigger};
+    use crate::embed::IoMode;
+    use crate::gpu::device::test_device;
+
+    #[test]
+    fn matches_the_cpu_embed() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let pipelines = Pipelines::new(&device);
+        use Section::{Input, Internal, Output};
+        // internal 0 gates the child; the parent drives internal 1 from its
+        // input, aliased to the child's input
+        let parent = ChunkBuilder::new()
+            .inputs(1)
+            .internal(2)
+            .outputs(1)
+            .conn((Input, 0), (Internal, 1), Trigger::On, Action::Enable)
+            .build()
+            .unwrap();
+        let child = ChunkBuilder::new()
+            .inputs(1)
+            .internal(1)
+            .outputs(1)
+            .conn((Input, 0), (Internal, 0), Trigger::On, Action::Enable)
+            .conn((Internal, 0), (Output, 0), Trigger::On, Action::Enable)
+            .build()
+            .unwrap();
+
+        for mode in [IoMode::Alias, IoMode::Copy] {
+            for (input, gate) in [(1, 0), (1, 1), (0, 1)] {
+                let mut parent = parent.clone();
+                parent.input_bits[0] = input;
+                // copy mode does not run the parent, so set its mapped bit
+                let mapped = if mode == IoMode::Copy { input << 1 } else { 0 };
+                parent.internal_bits[0] = gate | mapped;
+                let mut embed = Embed {
+                    parent_chunk: 0,
+                    child_chunk: 1,
+                    gate_bit: 0,
+                    gate_section: Internal,
+                    io_mode: mode,
+                    map_in: vec![(1, 0)],
+                    map_out: vec![(0, 0)],
+                    gate_prev: false,
+                };
+                let (mut cpu_parent, mut cpu_child) = (parent.clone(), child.clone());
+                let (mut gpu_parent, mut gpu_child) = (parent.clone(), child.clone());
+                if mode == IoMode::Alias {
+                    crate::embed::execute_gated_alias(&mut cpu_parent, &mut cpu_child, &embed);
+                    execute_gated_alias(
+                        &device,
+                        &queue,
+                        &pipelines,
+                        &mut gpu_parent,
+                        &mut gpu_child,
+                        &embed,
+                    )
+                    .unwrap();
+                } else {
+                    let mut cpu_embed = embed.clone();
+                    crate::embed::execute_gated_copy(
+                        &mut cpu_parent,
+                        &mut cpu_child,
+                        &mut cpu_embed,
+                    );
+                    execute_gated_copy(
+                        &device,
+                        &queue,
+                        &pipelines,
+                        &mut gpu_parent,
+                        &mut gpu_child,
+                        &mut embed,
+                    )
+                    .unwrap();
+                    assert_eq!(embed.gate_prev, cpu_embed.gate_prev);
+                }
+                let state = |c: &MycosChunk| {
+                    (
+                        c.input_bits.clone(),
+                        c.internal_bits.clone(),
+                        c.output_bits.clone(),
+                    )
+                };
+                assert_eq!(state(&gpu_parent), state(&cpu_parent), "{mode:?} {gate}");
+                assert_eq!(state(&gpu_child), state(&cpu_child), "{mode:?} {gate}");
+                // the child answers only through an open gate
+                assert_eq!(gpu_parent.output_bits[0], gate & input);
+            }
+        }
+    }
+}
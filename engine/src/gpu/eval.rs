@@ -0,0 +1,641 @@
+//! Batch evaluation on the wavefront kernels.
+//!
+//! [`GpuEvaluator`] loads every (genome, episode) pair of a batch as one
+//! instance of a shared [`SystemBuffers`] and runs them all in lockstep, one
+//! [`tick`] per tick, so each kernel launch steps the whole batch. Stimulus
+//! goes up and Inputs, Internals and Outputs come back once per tick;
+//! scoring happens on the host exactly as for
+//! [`evaluate_batch`](crate::evaluate_batch).
+
+#![cfg(any(feature = "webgpu", feature = "gpu-native"))]
+
+use wgpu::{CommandEncoderDescriptor, Device, Queue};
+
+use super::budget::{split_batch, Footprint, MemoryBudget};
+use super::buffers::{
+    InstanceInfo, SystemBuffers, CURR_INPUTS, CURR_INTERNALS, CURR_OUTPUTS, PREV_INPUTS,
+};
+use super::pipeline::{read_buffers, scoped, tick, GpuError, Pipelines, TickInfo};
+#[cfg(feature = "verify")]
+use super::verify::{Divergence, Shadow};
+use crate::chunk::{MycosChunk, Section};
+use crate::genome::Genome;
+use crate::gpu_eval::{
+    evaluate_genome, failed_result, fitness_from_traces, Episode, Evaluator, FitnessResult,
+};
+use crate::link::{GlobalBitMap, Link};
+use crate::policy::DetectorConfig;
+use crate::runner::{word_bit, EpisodeTrace, TickStats, MAX_ROUNDS};
+use crate::tasks::{Io, Task};
+#[cfg(feature = "verify")]
+use std::sync::Mutex;
+
+/// Evaluator backed by a GPU device.
+///
+/// Results match [`CpuEvaluator`](crate::CpuEvaluator): the same episodes,
+/// presets and scoring, with per-tick rounds, effects, oscillation and cycle
+/// period counted on the device by the same wavefront rounds as the CPU
+/// reference. Genomes that fail validation, or that do not fit the device's
+/// [`MemoryBudget`] even alone, are evaluated on the CPU.
+///
+/// Batches larger than the budget run as several consecutive sub-batches,
+/// split between genomes by [`split_batch`]. A device error in a sub-batch
+/// fails its genomes; see [`evaluate_batch`](Self::evaluate_batch).
+///
+/// With the `verify` feature every instance is shadowed by the CPU
+/// reference; see [`take_divergences`](Self::take_divergences).
+pub struct GpuEvaluator {
+    device: Device,
+    queue: Queue,
+    pipelines: Pipelines,
+    budget: MemoryBudget,
+    #[cfg(feature = "verify")]
+    divergences: Mutex<Vec<Divergence>>,
+}
+
+impl GpuEvaluator {
+    pub fn new(device: Device, queue: Queue) -> Self {
+        let pipelines = Pipelines::new(&device);
+        Self {
+            budget: MemoryBudget::from_limits(&device.limits()),
+            device,
+            queue,
+            pipelines,
+            #[cfg(feature = "verify")]
+            divergences: Mutex::default(),
+        }
+    }
+
+    /// Evaluator on a native device from
+    /// [`init_device_native`](super::device::init_device_native).
+    #[cfg(all(not(target_arch = "wasm32"), feature = "gpu-native"))]
+    pub fn native() -> Result<Self, super::device::DeviceError> {
+        let (device, queue) = super::device::init_device_native()?;
+        Ok(Self::new(device, queue))
+    }
+
+    /// Keep batches within `budget` instead of the device's buffer limits.
+    pub fn with_budget(mut self, budget: MemoryBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Divergences from the CPU reference found since the last call, in
+    /// the order they were found; at most one per (genome, episode) of each
+    /// evaluated batch.
+    #[cfg(feature = "verify")]
+    pub fn take_divergences(&self) -> Vec<Divergence> {
+        std::mem::take(&mut self.divergences.lock().unwrap())
+    }
+}
+
+impl Evaluator for GpuEvaluator {
+    /// [`evaluate_batch`](Self::evaluate_batch), with a genome hit by a
+    /// device error scored as failed: fitness 0 and no outputs.
+    fn evaluate(
+        &self,
+        genomes: &[Genome],
+        task: &Task,
+        episodes: &[Episode],
+    ) -> Vec<FitnessResult> {
+        self.evaluate_batch(genomes, task, episodes)
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|_| failed_result(task)))
+            .collect()
+    }
+}
+
+impl GpuEvaluator {
+    /// Evaluate `genomes` like [`Evaluator::evaluate`], reporting a device
+    /// error, such as a rejected buffer or running out of memory, on every
+    /// genome of the sub-batch it hit. Other sub-batches are unaffected.
+    pub fn evaluate_batch(
+        &self,
+        genomes: &[Genome],
+        task: &Task,
+        episodes: &[Episode],
+    ) -> Vec<Result<FitnessResult, GpuError>> {
+        let mut results: Vec<Option<Result<FitnessResult, GpuError>>> = vec![None; genomes.len()];
+        let mut loaded = Vec::new();
+        let mut footprints = Vec::new();
+        let window = DetectorConfig::default().window as u32;
+        for (g, genome) in genomes.iter().enumerate() {
+            match (genome.to_chunks(), genome.to_links()) {
+                (Ok(chunks), Ok(links)) if !task.episodes.is_empty() => {
+                    let footprint =
+                        Footprint::new(&chunks, &links, window).times(task.episodes.len() as u64);
+                    if self.budget.fits(&footprint) {
+                        footprints.push(footprint);
+                        loaded.push(Loaded {
+                            genome: g,
+                            map: GlobalBitMap::new(&chunks),
+                            chunks,
+                            links,
+                        });
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            results[g] = Some(Ok(evaluate_genome(genome, task, episodes)));
+        }
+
+        let batches = split_batch(&footprints, &self.budget).expect("every genome fits alone");
+        for batch in batches {
+            let loaded = &loaded[batch];
+            match self.run(loaded, task, episodes) {
+                Ok(traces) => {
+                    for (l, traces) in loaded.iter().zip(traces) {
+                        results[l.genome] = Some(Ok(fitness_from_traces(task, traces)));
+                    }
+                }
+                Err(err) => {
+                    for l in loaded {
+                        results[l.genome] = Some(Err(err.clone()));
+                    }
+                }
+            }
+        }
+        results
+            .into_iter()
+            .map(|r| r.expect("every genome evaluated"))
+            .collect()
+    }
+
+    /// Run every episode of `task` on every loaded genome as one batch, with
+    /// instance `l * episodes + e` playing episode `e` of `loaded[l]`, and
+    /// return each genome's traces in episode order.
+    fn run(
+        &self,
+        loaded: &[Loaded],
+        task: &Task,
+        episodes: &[Episode],
+    ) -> Result<Vec<Vec<EpisodeTrace>>, GpuError> {
+        // the stimulus and preset writes go unchecked otherwise
+        scoped(&self.device, || self.run_scoped(loaded, task, episodes)).and_then(|r| r)
+    }
+
+    fn run_scoped(
+        &self,
+        loaded: &[Loaded],
+        task: &Task,
+        episodes: &[Episode],
+    ) -> Result<Vec<Vec<EpisodeTrace>>, GpuError> {
+        let specs = &task.episodes;
+        let instances: Vec<(&Loaded, usize)> = loaded
+            .iter()
+            .flat_map(|l| (0..specs.len()).map(move |e| (l, e)))
+            .collect();
+        let mut batch = self.load(&instances, task, episodes)?;
+
+        #[cfg(feature = "verify")]
+        let mut shadows: Vec<Shadow> = instances
+            .iter()
+            .map(|&(l, e)| {
+                let preset = episodes.get(e).map_or(&[][..], |e| &e.inputs[..]);
+                Shadow::new(l.genome, e, &l.chunks, &l.links, &task.io.inputs, preset)
+            })
+            .collect();
+        let mut traces = vec![EpisodeTrace::default(); instances.len()];
+        let ticks = specs.iter().map(|s| s.stimulus.len()).max().unwrap_or(0);
+        for t in 0..ticks {
+            let (infos, state) = self.step(&mut batch, &instances, task, t, MAX_ROUNDS)?;
+            let all = batch.buffers.instances();
+            for (i, (info, tick)) in all.iter().zip(&infos).enumerate() {
+                let (l, e) = instances[i];
+                // episodes shorter than the batch have already ended
+                if t >= specs[e].stimulus.len() {
+                    continue;
+                }
+                traces[i].ticks.push(TickStats {
+                    rounds: tick.rounds,
+                    effects: tick.effects,
+                    oscillator: !tick.settled,
+                    period: tick.period,
+                });
+                let observed = l.observe(&task.io.outputs, &task.io.probes, info, &state);
+                traces[i].outputs.push(observed);
+                #[cfg(feature = "verify")]
+                if let Some(mut divergence) = shadows[i].tick(
+                    t,
+                    &specs[e].stimulus[t],
+                    info,
+                    tick.rounds,
+                    &state,
+                    MAX_ROUNDS,
+                ) {
+                    divergence.round =
+                        self.first_differing_round(l, e, task, episodes, &divergence)?;
+                    self.divergences.lock().unwrap().push(divergence);
+                }
+            }
+        }
+        Ok(traces
+            .chunks(specs.len())
+            .map(|episodes| episodes.to_vec())
+            .collect())
+    }
+
+    /// Load `instances`, each a genome and the episode it plays, as a batch
+    /// and preset their Inputs from `episodes`.
+    fn load(
+        &self,
+        instances: &[(&Loaded, usize)],
+        task: &Task,
+        episodes: &[Episode],
+    ) -> Result<Batch, GpuError> {
+        let systems: Vec<(&[MycosChunk], &[Link])> = instances
+            .iter()
+            .map(|(l, _)| (&l.chunks[..], &l.links[..]))
+            .collect();
+        let buffers = SystemBuffers::new(
+            &self.device,
+            &self.pipelines,
+            &systems,
+            DetectorConfig::default().window as u32,
+        )?;
+
+        let mut inputs = buffers.initial(Section::Input).to_vec();
+        let mut preset = false;
+        for (info, &(l, e)) in buffers.instances().iter().zip(instances) {
+            if let Some(episode) = episodes.get(e).filter(|e| !e.inputs.is_empty()) {
+                let words = &mut inputs[info.words(Section::Input)];
+                l.drive(words, &task.io.inputs, &episode.inputs);
+                preset = true;
+            }
+        }
+        if preset {
+            buffers.write(&self.queue, PREV_INPUTS, &inputs);
+        }
+        Ok(Batch { buffers, inputs })
+    }
+
+    /// Drive tick `t` of each instance's episode, run it with at most
+    /// `max_rounds` rounds and read back the batch's Inputs, Internals and
+    /// Outputs.
+    fn step(
+        &self,
+        batch: &mut Batch,
+        instances: &[(&Loaded, usize)],
+        task: &Task,
+        t: usize,
+        max_rounds: u32,
+    ) -> Result<(Vec<TickInfo>, Vec<Vec<u32>>), GpuError> {
+        let Batch { buffers, inputs } = batch;
+        for (info, &(l, e)) in buffers.instances().iter().zip(instances) {
+            if let Some(stimulus) = task.episodes[e].stimulus.get(t) {
+                let words = &mut inputs[info.words(Section::Input)];
+                l.drive(words, &task.io.inputs, stimulus);
+            }
+        }
+        buffers.write(&self.queue, CURR_INPUTS, inputs);
+        let infos = tick(
+            &self.device,
+            &self.queue,
+            &self.pipelines,
+            buffers,
+            max_rounds,
+        )?;
+        let encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("mycos-state-readback"),
+            });
+        let state = read_buffers(
+            &self.device,
+            &self.queue,
+            encoder,
+            &[
+                buffers.buffer(CURR_INPUTS),
+                buffers.buffer(CURR_INTERNALS),
+                buffers.buffer(CURR_OUTPUTS),
+            ],
+        )?;
+        inputs.clone_from(&state[0]);
+        Ok((infos, state))
+    }
+
+    /// First round of the tick of `divergence` after which the device's
+    /// state words differ from the CPU's, found by replaying episode `e` of
+    /// `l` alone with that tick capped at one round, then two, and so on.
+    /// `None` if the state agrees after every round the tick took.
+    #[cfg(feature = "verify")]
+    fn first_differing_round(
+        &self,
+        l: &Loaded,
+        e: usize,
+        task: &Task,
+        episodes: &[Episode],
+        divergence: &Divergence,
+    ) -> Result<Option<u32>, GpuError> {
+        let instances = [(l, e)];
+        let preset = episodes.get(e).map_or(&[][..], |e| &e.inputs[..]);
+        let stimulus = &task.episodes[e].stimulus[..=divergence.tick];
+        for round in 1..=divergence.gpu_rounds.max(divergence.cpu_rounds) {
+            let mut batch = self.load(&instances, task, episodes)?;
+            let mut shadow = Shadow::new(l.genome, e, &l.chunks, &l.links, &task.io.inputs, preset);
+            for (t, stimulus) in stimulus.iter().enumerate() {
+                let max_rounds = if t == divergence.tick {
+                    round
+                } else {
+                    MAX_ROUNDS
+                };
+                let (infos, state) = self.step(&mut batch, &instances, task, t, max_rounds)?;
+                let info = &batch.buffers.instances()[0];
+                let found = shadow.tick(t, stimulus, info, infos[0].rounds, &state, max_rounds);
+                if found.is_some_and(|d| d.word.is_some()) {
+                    return Ok(Some(round));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Device state of a batch between ticks.
+struct Batch {
+    buffers: SystemBuffers,
+    /// Inputs of the batch as the host last drove or read them.
+    inputs: Vec<u32>,
+}
+
+/// One genome loaded into the batch.
+struct Loaded {
+    genome: usize,
+    chunks: Vec<MycosChunk>,
+    links: Vec<Link>,
+    map: GlobalBitMap,
+}
+
+impl Loaded {
+    /// Set the mapped Input bits of `words`, one instance's Input words, from
+    /// `stimulus`, where bit `k` drives `ports[k]`; ports outside the system
+    /// are ignored.
+    fn drive(&self, words: &mut [u32], ports: &[Io], stimulus: &[u32]) {
+        for (k, port) in ports.iter().enumerate() {
+            let Some(bit) = self.map.global(port.chunk_id, Section::Input, port.bit_idx) else {
+                continue;
+            };
+            let mask = 1 << (bit % 32);
+            if word_bit(stimulus, k) {
+                words[(bit / 32) as usize] |= mask;
+            } else {
+                words[(bit / 32) as usize] &= !mask;
+            }
+        }
+    }
+
+    /// Outputs then probes of instance `info` read from the batch `state`
+    /// (Inputs, Internals, Outputs), packed like [`EpisodeTrace::outputs`];
+    /// unmapped bits read 0.
+    fn observe(
+        &self,
+        outputs: &[Io],
+        probes: &[Io],
+        info: &InstanceInfo,
+        state: &[Vec<u32>],
+    ) -> Vec<u32> {
+        let read = |section: Section, words: &[u32], port: &Io| {
+            let words = &words[info.words(section)];
+            self.map
+                .global(port.chunk_id, section, port.bit_idx)
+                .is_some_and(|bit| words[(bit / 32) as usize] >> (bit % 32) & 1 == 1)
+        };
+        let observed = outputs
+            .iter()
+            .map(|p| read(Section::Output, &state[2], p))
+            .chain(probes.iter().map(|p| read(Section::Internal, &state[1], p)));
+        let mut words = vec![0u32; (outputs.len() + probes.len()).div_ceil(32)];
+        for (k, high) in observed.enumerate() {
+            if high {
+                words[k / 32] |= 1 << (k % 32);
+            }
+        }
+        words
+    }
+}
+
+#[cfg(all(test, feature = "gpu-native"))]
+mod tests {
+    use super::*;
+    use crate::builder::fixtures::{relay, wire};
+    use crate::builder::ChunkBuilder;
+    use crate::chunk::{Action, Trigger};
+    use crate::gpu::device::test_device;
+    use crate::gpu::pipeline::WORKGROUP_SIZE;
+    use crate::gpu_eval::CpuEvaluator;
+    use crate::init::RandomGenomeParams;
+    use crate::mutations::{mutate, MutationConfig};
+    use crate::tasks::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use Section::{Input, Internal};
+
+    #[test]
+    fn matches_the_cpu_evaluator() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let gpu = GpuEvaluator::new(device, queue);
+        let wire = wire();
+        let link = relay(Trigger::On, Action::Enable, 0);
+        let echo = Genome::from_chunks(std::slice::from_ref(&wire), &[]).unwrap();
+        let relay = Genome::from_chunks(&[wire.clone(), wire.clone()], &[link]).unwrap();
+        let mut delayed = relay.clone();
+        delayed.links[0].delay = 1;
+        let mut slower = relay.clone();
+        slower.links[0].delay = 2;
+
+        for task in [t00_wire_echo(), t04_cross_chunk_relay()] {
+            let genomes = [echo.clone(), relay.clone(), delayed.clone(), slower.clone()];
+            let episodes = vec![Episode::default(); task.episodes.len()];
+            let cpu = CpuEvaluator.evaluate(&genomes, &task, &episodes);
+            let results = gpu.evaluate(&genomes, &task, &episodes);
+            for (gpu, cpu) in results.iter().zip(&cpu) {
+                assert_eq!(gpu.outputs, cpu.outputs);
+                assert_eq!(gpu.fitness, cpu.fitness);
+                let work = |r: &FitnessResult| {
+                    r.metrics
+                        .iter()
+                        .map(|m| (m.rounds, m.effects))
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(work(gpu), work(cpu));
+            }
+            if task.episodes[0].stimulus.len() > 1 {
+                // the delay lines hold the relay back
+                assert_ne!(results[1].outputs, results[2].outputs);
+                assert_ne!(results[2].outputs, results[3].outputs);
+            }
+        }
+
+        // enough instances to span several workgroups
+        let task = t04_cross_chunk_relay();
+        let genomes = vec![relay.clone(); 2 * WORKGROUP_SIZE as usize];
+        let episodes = vec![Episode::default(); task.episodes.len()];
+        let cpu = CpuEvaluator.evaluate(&genomes[..1], &task, &episodes);
+        for result in gpu.evaluate(&genomes, &task, &episodes) {
+            assert_eq!(
+                (result.outputs, result.fitness),
+                (cpu[0].outputs.clone(), cpu[0].fitness)
+            );
+        }
+
+        // a bit that toggles itself runs to the cap over several submissions
+        let spin = ChunkBuilder::new()
+            .inputs(1)
+            .internal(1)
+            .outputs(1)
+            .conn((Input, 0), (Internal, 0), Trigger::On, Action::Toggle)
+            .conn(
+                (Internal, 0),
+                (Internal, 0),
+                Trigger::Toggle,
+                Action::Toggle,
+            )
+            .build()
+            .unwrap();
+        let spin = Genome::from_chunks(&[spin], &[]).unwrap();
+        let task = t00_wire_echo();
+        let episodes = vec![Episode::default(); task.episodes.len()];
+        let result = gpu.evaluate(std::slice::from_ref(&spin), &task, &episodes);
+        let cpu = CpuEvaluator.evaluate(std::slice::from_ref(&spin), &task, &episodes);
+        assert_eq!(result[0].outputs, cpu[0].outputs);
+        assert!(result[0].metrics.iter().any(|m| m.oscillator));
+        assert!(result[0].metrics.iter().all(|m| m.rounds <= MAX_ROUNDS));
+
+        // a preset input fires no edge
+        let task = t00_wire_echo();
+        let episodes = vec![Episode { inputs: vec![1] }; task.episodes.len()];
+        let cpu = CpuEvaluator.evaluate(std::slice::from_ref(&echo), &task, &episodes);
+        let results = gpu.evaluate(std::slice::from_ref(&echo), &task, &episodes);
+        assert_eq!(results[0].outputs, cpu[0].outputs);
+
+        // no device errors on the way
+        let results = gpu.evaluate_batch(std::slice::from_ref(&echo), &task, &episodes);
+        assert_eq!(results[0].as_ref().unwrap().outputs, cpu[0].outputs);
+
+        // every tick of the runs above shadowed the CPU exactly
+        #[cfg(feature = "verify")]
+        assert_eq!(gpu.take_divergences(), Vec::new());
+
+        // capped one round at a time, the device agrees with the CPU after
+        // every round of a tick
+        #[cfg(feature = "verify")]
+        {
+            let task = t04_cross_chunk_relay();
+            let episodes = vec![Episode::default(); task.episodes.len()];
+            let chunks = relay.to_chunks().unwrap();
+            let loaded = Loaded {
+                genome: 0,
+                map: GlobalBitMap::new(&chunks),
+                chunks,
+                links: relay.to_links().unwrap(),
+            };
+            let divergence = Divergence {
+                genome: 0,
+                episode: 0,
+                tick: 1,
+                word: None,
+                gpu_rounds: 8,
+                cpu_rounds: 8,
+                round: None,
+            };
+            let round = gpu.first_differing_round(&loaded, 0, &task, &episodes, &divergence);
+            assert_eq!(round.unwrap(), None);
+        }
+
+        // a budget of two relays per batch splits the batch, and a genome
+        // too wide for it runs on the CPU
+        let task = t04_cross_chunk_relay();
+        let episodes = vec![Episode::default(); task.episodes.len()];
+        let footprint = |g: &Genome| {
+            let window = DetectorConfig::default().window as u32;
+            Footprint::new(&g.to_chunks().unwrap(), &g.to_links().unwrap(), window)
+                .times(task.episodes.len() as u64)
+        };
+        let wide = ChunkBuilder::new().inputs(1).internal(4096).outputs(1);
+        let wide = Genome::from_chunks(&[wide.build().unwrap()], &[]).unwrap();
+        let budget = MemoryBudget {
+            max_buffer: u64::MAX,
+            max_total: footprint(&relay).times(2).total() + 64,
+        };
+        assert!(!budget.fits(&footprint(&relay).times(3)));
+        assert!(!budget.fits(&footprint(&wide)));
+        let gpu = gpu.with_budget(budget);
+        let genomes = [relay.clone(), echo.clone(), wide, relay.clone(), delayed];
+        let cpu = CpuEvaluator.evaluate(&genomes, &task, &episodes);
+        for (gpu, cpu) in gpu.evaluate(&genomes, &task, &episodes).iter().zip(&cpu) {
+            assert_eq!((&gpu.outputs, gpu.fitness), (&cpu.outputs, cpu.fitness));
+        }
+    }
+
+    #[test]
+    fn matches_the_cpu_evaluator_on_random_genomes() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let gpu = GpuEvaluator::new(device, queue);
+        let params = RandomGenomeParams {
+            chunks: 1..=3,
+            inputs: 1..=4,
+            outputs: 1..=4,
+            internals: 1..=8,
+            conns: 0..=24,
+            links: 0..=4,
+        };
+        // every operator fires often, so links, delays, order tags and
+        // gates all show up
+        let config = MutationConfig {
+            p_add_conn: 0.5,
+            p_rewire: 0.5,
+            p_flip_trigger: 0.5,
+            p_flip_action: 0.5,
+            p_bump_order: 0.5,
+            p_add_link: 0.5,
+            p_init_tweak: 0.5,
+            p_gate_insert: 0.5,
+            ..MutationConfig::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(117);
+        let tasks = [
+            t00_wire_echo(),
+            t01_xor_2(),
+            t02_sr_latch(),
+            t03_pulse_counter(),
+            t04_cross_chunk_relay(),
+            t05_shift_register(),
+            t06_sequence_detector(),
+            t07_memory_cell(),
+            t08_gated_d_latch(),
+        ];
+        for task in &tasks {
+            let genomes: Vec<Genome> = (0..24)
+                .map(|_| {
+                    let mut genome = Genome::random(&params, &mut rng);
+                    for _ in 0..rng.gen_range(0..4) {
+                        mutate(&mut genome, &mut rng, &config);
+                    }
+                    genome
+                })
+                .collect();
+            let episodes = vec![Episode::default(); task.episodes.len()];
+            let cpu = CpuEvaluator.evaluate(&genomes, task, &episodes);
+            let results = gpu.evaluate(&genomes, task, &episodes);
+            for (g, (gpu, cpu)) in results.iter().zip(&cpu).enumerate() {
+                let at = format!("{} genome {g}", task.name);
+                assert_eq!(gpu.outputs, cpu.outputs, "{at}");
+                assert_eq!(gpu.fitness, cpu.fitness, "{at}");
+                let metrics = |r: &FitnessResult| {
+                    r.metrics
+                        .iter()
+                        .map(|m| (m.rounds, m.effects, m.oscillator, m.period))
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(metrics(gpu), metrics(cpu), "{at}");
+            }
+        }
+        #[cfg(feature = "verify")]
+        assert_eq!(gpu.take_divergences(), Vec::new());
+    }
+}
@@ -1,93 +1,200 @@
 // WGSL compute kernels for Mycos execution pipeline.
 // All kernels operate on u32 word arrays with LSB-first bit order.
 // Each entry point is deterministic.
+//
+// Buffers hold a batch of independent systems ("instances") back to back,
+// each finding its slice of every buffer through `instances[i]`; per-instance
+// scalars (metrics, hash state) are arrays indexed by instance. Kernels are
+// sized by the work they do rather than by instance:
+//
+// * per word: K1/K5 diff every state word of the batch, appending each flip
+//   to one shared frontier list;
+// * per frontier entry: K2 expands one flipped bit's CSR slices into the
+//   shared proposal list, dispatched indirectly from the frontier length;
+// * per proposal: K3 keeps the best proposal per target bit with atomic
+//   maxima and K4 commits the winners, dispatched indirectly from the
+//   proposal length;
+// * per instance: round bookkeeping, cycle hashing and finalize.
+//
+// Appends go through atomic counters, so list order varies from run to run,
+// but every proposal carries a unique (order_tag, rank) key and K3 keeps the
+// largest per target, so results do not depend on it.
+//
+// Rounds are dispatched speculatively, several per submission: each
+// instance keeps its own round count and stops on its own, so extra rounds
+// and finalize passes are no-ops for instances that are already done.
 
 const WORD_BITS : u32 = 32u;
+const WORKGROUP_SIZE : u32 = 64u;
+
+const SECTION_INPUT : u32 = 0u;
+const SECTION_INTERNAL : u32 = 1u;
+const SECTION_OUTPUT : u32 = 2u;
+
+// Effects a tick may apply before its instance stops, like the CPU
+// reference's guard.
+const MAX_EFFECTS : u32 = 5000000u;
 
 struct Counts {
-    input_bits: u32;
-    internal_bits: u32;
-    output_bits: u32;
-    frontier_cap: u32;
-    proposal_cap: u32;
-    hash_window: u32;
-    _pad0: u32;
-    _pad1: u32;
-};
+    instances: u32,
+    hash_window: u32,
+    max_rounds: u32,
+    max_workgroups: u32, // per dispatch dimension
+    input_words: u32, // state words of the whole batch per section
+    internal_words: u32,
+    output_words: u32,
+    _pad: u32,
+}
 @group(0) @binding(0) var<uniform> counts: Counts;
 
 struct Words {
-    data: array<u32>;
-};
+    data: array<u32>,
+}
 
+struct AtomicWords {
+    data: array<atomic<u32>>,
+}
+
+// Curr is written bit by bit by concurrent K4 invocations, hence atomic.
 @group(0) @binding(1) var<storage, read_write> prev_inputs: Words;
-@group(0) @binding(2) var<storage, read_write> curr_inputs: Words;
+@group(0) @binding(2) var<storage, read_write> curr_inputs: AtomicWords;
 @group(0) @binding(3) var<storage, read_write> prev_internals: Words;
-@group(0) @binding(4) var<storage, read_write> curr_internals: Words;
+@group(0) @binding(4) var<storage, read_write> curr_internals: AtomicWords;
 @group(0) @binding(5) var<storage, read_write> prev_outputs: Words;
-@group(0) @binding(6) var<storage, read_write> curr_outputs: Words;
-
-// Frontier lists and counts
-@group(0) @binding(7) var<storage, read_write> frontier_on: Words;
-@group(0) @binding(8) var<storage, read_write> frontier_off: Words;
-@group(0) @binding(9) var<storage, read_write> frontier_toggle: Words;
-
-struct FrontierCounts {
-    on: u32;
-    off: u32;
-    toggle: u32;
-    _pad: u32;
-};
-@group(0) @binding(10) var<storage, read_write> frontier_counts: FrontierCounts;
-
-// CSR adjacency
+@group(0) @binding(6) var<storage, read_write> curr_outputs: AtomicWords;
+
+// Bits that flipped since Prev, over the whole batch: at most one entry per
+// bit per round.
+struct FrontierEntry {
+    instance: u32,
+    edge: u32, // instance-local bit id << 1 | 1 if it rose
+}
+@group(0) @binding(7) var<storage, read_write> frontier: array<FrontierEntry>;
+
+// Lengths of the shared frontier and proposal lists
+struct Queues {
+    frontier_len: atomic<u32>,
+    proposal_len: atomic<u32>,
+}
+@group(0) @binding(8) var<storage, read_write> queues: Queues;
+
+// Indirect dispatch arguments: K2 over the frontier, K3/K4 over proposals
+struct DispatchArgs {
+    x: u32,
+    y: u32,
+    z: u32,
+}
+struct Dispatch {
+    frontier: DispatchArgs,
+    proposals: DispatchArgs,
+}
+@group(0) @binding(9) var<storage, read_write> dispatch: Dispatch;
+
+// CSR adjacency; offsets are relative to the instance's effect base
 struct Effect {
-    to_bit: u32;
-    order_tag: u32;
-    action: u32; // 0=Enable,1=Disable,2=Toggle
-    _pad: u32;
-};
-
-@group(0) @binding(11) var<storage, read> csr_offs_on: Words;
-@group(0) @binding(12) var<storage, read> csr_offs_off: Words;
-@group(0) @binding(13) var<storage, read> csr_offs_toggle: Words;
-@group(0) @binding(14) var<storage, read> csr_effects_on: array<Effect>;
-@group(0) @binding(15) var<storage, read> csr_effects_off: array<Effect>;
-@group(0) @binding(16) var<storage, read> csr_effects_toggle: array<Effect>;
-
-// Proposals buffer
-@group(0) @binding(17) var<storage, read_write> proposals: array<Effect>;
-struct U32Buf { value: u32; };
-@group(0) @binding(18) var<storage, read_write> proposal_count: U32Buf;
-
-// Winners buffer
-struct Winner {
-    to_bit: u32;
-    action: u32;
-    _pad0: u32;
-    _pad1: u32;
-};
-@group(0) @binding(19) var<storage, read_write> winners: array<Winner>;
-@group(0) @binding(20) var<storage, read_write> winners_count: U32Buf;
-
-// Metrics
+    to_bit: u32,
+    order_tag: u32,
+    action: u32, // 0=Enable,1=Disable,2=Toggle
+    gate: u32, // 0 = always fires, else 1 + instance-local id of the gate bit
+}
+
+@group(0) @binding(10) var<storage, read> csr_offs_on: Words;
+@group(0) @binding(11) var<storage, read> csr_offs_off: Words;
+@group(0) @binding(12) var<storage, read> csr_offs_toggle: Words;
+@group(0) @binding(13) var<storage, read> csr_effects_on: array<Effect>;
+@group(0) @binding(14) var<storage, read> csr_effects_off: array<Effect>;
+@group(0) @binding(15) var<storage, read> csr_effects_toggle: array<Effect>;
+
+// One effect of the current round. `rank` is the effect's index in its
+// instance's global CSR, effects of the three triggers numbered one after
+// another, so it breaks order_tag ties towards the effect stored later.
+struct Proposal {
+    instance: u32,
+    to_bit: u32,
+    order_tag: u32,
+    rank_action: u32, // rank << 2 | action
+}
+@group(0) @binding(16) var<storage, read_write> proposals: array<Proposal>;
+
+// Best proposal per bit, two words per bit slot: the highest order_tag
+// proposed, then the highest rank + 1 among proposals with that tag. Both
+// are 0 while no proposal targets the bit; K4 clears them again.
+@group(0) @binding(17) var<storage, read_write> winners: AtomicWords;
+
+// Metrics, reset by K1 every tick
 struct Metrics {
-    effects_applied: u32;
-    _pad0: u32;
-    _pad1: u32;
-    _pad2: u32;
-};
-@group(0) @binding(21) var<storage, read_write> metrics: Metrics;
-
-// Cycle hash ring buffer
-@group(0) @binding(22) var<storage, read_write> hash_ring: Words; // length = hash_window * 4
+    effects_applied: atomic<u32>,
+    rounds: u32,
+    running: u32, // 1 while the current round expands a frontier
+    pending: atomic<u32>, // 1 while the frontier is non-empty
+}
+@group(0) @binding(18) var<storage, read_write> metrics: array<Metrics>;
+
+// Cycle hash ring buffer, cleared by K1 every tick and fed after each round
+// that changes the Internal state
+@group(0) @binding(19) var<storage, read_write> hash_ring: Words; // length = instances * hash_window * 4
 struct HashState {
-    pos: u32;
-    detected: u32;
-    period: u32;
-    _pad: u32;
-};
-@group(0) @binding(23) var<storage, read_write> hash_state: HashState;
+    pos: u32,
+    changed: atomic<u32>, // 1 once an Internal bit flipped this round
+    period: u32, // last period detected this tick, 0 if none
+    done: u32, // 1 once Kfinal has finalized the current tick
+}
+@group(0) @binding(20) var<storage, read_write> hash_state: array<HashState>;
+
+// Where each instance lives in the batch buffers
+struct Instance {
+    input_bits: u32,
+    internal_bits: u32,
+    output_bits: u32,
+    bit_base: u32, // winner slots, one per bit
+    input_base: u32, // state words per section
+    internal_base: u32,
+    output_base: u32,
+    offs_base: u32, // total bits + 1 words per trigger
+    on_base: u32, // effects per trigger
+    off_base: u32,
+    toggle_base: u32,
+    proposal_base: u32,
+    proposal_cap: u32,
+    delayed_base: u32, // first of the instance's delayed links
+    delayed_count: u32,
+    _pad: u32,
+}
+@group(0) @binding(21) var<storage, read> instances: array<Instance>;
+
+// Links with a delay, kept out of the CSR. Each owns a `delay`-bit shift
+// register in `delay_lines`: an Output edge sets bit `delay - 1`, and every
+// tick Kfinal delivers bit 0 to the target Input and shifts right by one,
+// so the effect lands `delay` ticks after its edge.
+struct DelayedLink {
+    from_bit: u32,
+    trigger: u32, // 0=On,1=Off,2=Toggle
+    to_bit: u32,
+    action: u32,
+    order_tag: u32,
+    delay: u32,
+    line_base: u32, // first word of the shift register
+    _pad: u32,
+}
+@group(0) @binding(22) var<storage, read> delayed_links: array<DelayedLink>;
+@group(0) @binding(23) var<storage, read_write> delay_lines: AtomicWords;
+
+// Item handled by an invocation. Dispatches with more workgroups than fit
+// in one dimension run as a 2D grid, rows of `groups.x` workgroups.
+fn invocation_index(id: vec3<u32>, groups: vec3<u32>) -> u32 {
+    return id.y * groups.x * WORKGROUP_SIZE + id.x;
+}
+
+// Grid giving each of `n` items an invocation, like `workgroup_grid` on the
+// host.
+fn grid(n: u32) -> DispatchArgs {
+    let groups = (n + WORKGROUP_SIZE - 1u) / WORKGROUP_SIZE;
+    if (groups <= counts.max_workgroups) {
+        return DispatchArgs(groups, 1u, 1u);
+    }
+    let rows = (groups + counts.max_workgroups - 1u) / counts.max_workgroups;
+    return DispatchArgs(counts.max_workgroups, rows, 1u);
+}
 
 fn word_index(bit: u32) -> u32 {
     return bit / WORD_BITS;
@@ -110,7 +217,8 @@ fn murmur_mix(h: u32, k: u32) -> u32 {
     return hh * 5u + 0xe6546b64u;
 }
 
-fn murmur_fmix(mut h: u32) -> u32 {
+fn murmur_fmix(x: u32) -> u32 {
+    var h = x;
     h = h ^ (h >> 16u);
     h = h * 0x85ebca6bu;
     h = h ^ (h >> 13u);
@@ -119,388 +227,602 @@ fn murmur_fmix(mut h: u32) -> u32 {
     return h;
 }
 
-// ---------------------------------------------------------------
-// K1_detect_edges: Compute bit transitions and build initial frontiers.
-// ---------------------------------------------------------------
-@compute @workgroup_size(64)
-fn k1_detect_edges(@builtin(global_invocation_id) id: vec3<u32>) {
-    if (id.x != 0u || id.y != 0u || id.z != 0u) {
-        return;
+fn section_bits(inst: Instance, section: u32) -> u32 {
+    switch section {
+        case 0u: { return inst.input_bits; }
+        case 1u: { return inst.internal_bits; }
+        default: { return inst.output_bits; }
+    }
+}
+
+fn section_base(inst: Instance, section: u32) -> u32 {
+    switch section {
+        case 0u: { return inst.input_base; }
+        case 1u: { return inst.internal_base; }
+        default: { return inst.output_base; }
     }
+}
 
-    frontier_counts.on = 0u;
-    frontier_counts.off = 0u;
-    frontier_counts.toggle = 0u;
-
-    var global_bit: u32 = 0u;
-
-    let input_words = (counts.input_bits + WORD_BITS - 1u) / WORD_BITS;
-    for (var w = 0u; w < input_words; w = w + 1u) {
-        let cur = curr_inputs.data[w];
-        let prev = prev_inputs.data[w];
-        let flips = cur ^ prev;
-        var mask = 1u;
-        for (var b = 0u; b < WORD_BITS && global_bit < counts.input_bits; b = b + 1u) {
-            if ((flips & mask) != 0u) {
-                if ((cur & mask) != 0u) {
-                    frontier_on.data[frontier_counts.on] = global_bit;
-                    frontier_counts.on = frontier_counts.on + 1u;
-                }
-                if ((prev & mask) != 0u) {
-                    frontier_off.data[frontier_counts.off] = global_bit;
-                    frontier_counts.off = frontier_counts.off + 1u;
-                }
-                frontier_toggle.data[frontier_counts.toggle] = global_bit;
-                frontier_counts.toggle = frontier_counts.toggle + 1u;
+// First instance-local bit id of a section: Inputs, then Internals, then
+// Outputs.
+fn section_first_bit(inst: Instance, section: u32) -> u32 {
+    switch section {
+        case 0u: { return 0u; }
+        case 1u: { return inst.input_bits; }
+        default: { return inst.input_bits + inst.internal_bits; }
+    }
+}
+
+// Section of an instance-local bit id.
+fn section_of(inst: Instance, bit: u32) -> u32 {
+    if (bit >= inst.input_bits + inst.internal_bits) {
+        return SECTION_OUTPUT;
+    } else if (bit >= inst.input_bits) {
+        return SECTION_INTERNAL;
+    }
+    return SECTION_INPUT;
+}
+
+fn read_curr(section: u32, idx: u32) -> u32 {
+    switch section {
+        case 0u: { return atomicLoad(&curr_inputs.data[idx]); }
+        case 1u: { return atomicLoad(&curr_internals.data[idx]); }
+        default: { return atomicLoad(&curr_outputs.data[idx]); }
+    }
+}
+
+fn write_curr(section: u32, idx: u32, value: u32) {
+    switch section {
+        case 0u: { atomicStore(&curr_inputs.data[idx], value); }
+        case 1u: { atomicStore(&curr_internals.data[idx], value); }
+        default: { atomicStore(&curr_outputs.data[idx], value); }
+    }
+}
+
+// Apply `action` to the bits of `mask` in Curr word `idx` of `section`,
+// atomically, since other invocations may write other bits of the word.
+fn apply_curr(section: u32, idx: u32, mask: u32, action: u32) {
+    switch section {
+        case 0u: {
+            if (action == 0u) {
+                atomicOr(&curr_inputs.data[idx], mask);
+            } else if (action == 1u) {
+                atomicAnd(&curr_inputs.data[idx], ~mask);
+            } else {
+                atomicXor(&curr_inputs.data[idx], mask);
+            }
+        }
+        case 1u: {
+            if (action == 0u) {
+                atomicOr(&curr_internals.data[idx], mask);
+            } else if (action == 1u) {
+                atomicAnd(&curr_internals.data[idx], ~mask);
+            } else {
+                atomicXor(&curr_internals.data[idx], mask);
+            }
+        }
+        default: {
+            if (action == 0u) {
+                atomicOr(&curr_outputs.data[idx], mask);
+            } else if (action == 1u) {
+                atomicAnd(&curr_outputs.data[idx], ~mask);
+            } else {
+                atomicXor(&curr_outputs.data[idx], mask);
             }
-            global_bit = global_bit + 1u;
-            mask = mask << 1u;
         }
     }
+}
 
-    let internal_offset = counts.input_bits;
-    let internal_words = (counts.internal_bits + WORD_BITS - 1u) / WORD_BITS;
-    for (var w = 0u; w < internal_words; w = w + 1u) {
-        let cur = curr_internals.data[w];
-        let prev = prev_internals.data[w];
-        let flips = cur ^ prev;
-        var mask = 1u;
-        for (var b = 0u; b < WORD_BITS && (b + w * WORD_BITS) < counts.internal_bits; b = b + 1u) {
-            if ((flips & mask) != 0u) {
-                let idx = internal_offset + w * WORD_BITS + b;
-                if ((cur & mask) != 0u) {
-                    frontier_on.data[frontier_counts.on] = idx;
-                    frontier_counts.on = frontier_counts.on + 1u;
-                }
-                if ((prev & mask) != 0u) {
-                    frontier_off.data[frontier_counts.off] = idx;
-                    frontier_counts.off = frontier_counts.off + 1u;
-                }
-                frontier_toggle.data[frontier_counts.toggle] = idx;
-                frontier_counts.toggle = frontier_counts.toggle + 1u;
+fn read_prev(section: u32, idx: u32) -> u32 {
+    switch section {
+        case 0u: { return prev_inputs.data[idx]; }
+        case 1u: { return prev_internals.data[idx]; }
+        default: { return prev_outputs.data[idx]; }
+    }
+}
+
+fn write_prev(section: u32, idx: u32, value: u32) {
+    switch section {
+        case 0u: { prev_inputs.data[idx] = value; }
+        case 1u: { prev_internals.data[idx] = value; }
+        default: { prev_outputs.data[idx] = value; }
+    }
+}
+
+// Whether `bit`, an instance-local global id, is set in Curr.
+fn curr_bit(inst: Instance, bit: u32) -> bool {
+    let section = section_of(inst, bit);
+    let local = bit - section_first_bit(inst, section);
+    let word = read_curr(section, section_base(inst, section) + word_index(local));
+    return (word & bit_mask(local)) != 0u;
+}
+
+// Whether instance `i` has edges left to expand and its guards let it run
+// another round.
+fn can_run(i: u32) -> bool {
+    return atomicLoad(&metrics[i].pending) != 0u && metrics[i].rounds < counts.max_rounds && atomicLoad(&metrics[i].effects_applied) < MAX_EFFECTS;
+}
+
+// Instance owning state word `word` of `section`: the last one whose
+// section starts at or before it. Every instance has at least one word per
+// section, so the bases are strictly increasing.
+fn word_owner(section: u32, word: u32) -> u32 {
+    var lo = 0u;
+    var hi = counts.instances;
+    while (hi - lo > 1u) {
+        let mid = (lo + hi) / 2u;
+        if (section_base(instances[mid], section) <= word) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    return lo;
+}
+
+// Diff state word `w` of the batch (Inputs, then Internals, then Outputs)
+// against Prev, append each flipped bit to the frontier and set Prev to
+// Curr. With `running_only`, words of instances outside a round are left
+// alone.
+fn detect_word(w: u32, running_only: bool) {
+    var section = SECTION_INPUT;
+    var idx = w;
+    if (idx >= counts.input_words) {
+        section = SECTION_INTERNAL;
+        idx = idx - counts.input_words;
+        if (idx >= counts.internal_words) {
+            section = SECTION_OUTPUT;
+            idx = idx - counts.internal_words;
+            if (idx >= counts.output_words) {
+                return;
             }
-            mask = mask << 1u;
         }
     }
+    let i = word_owner(section, idx);
+    if (running_only && metrics[i].running == 0u) {
+        return;
+    }
+    let inst = instances[i];
+    let cur = read_curr(section, idx);
+    let prev = read_prev(section, idx);
+    write_prev(section, idx, cur);
+
+    let first = (idx - section_base(inst, section)) * WORD_BITS;
+    let bits = section_bits(inst, section);
+    if (first >= bits) {
+        return;
+    }
+    var valid = 0xffffffffu;
+    if (bits - first < WORD_BITS) {
+        valid = (1u << (bits - first)) - 1u;
+    }
+    var flips = (cur ^ prev) & valid;
+    if (flips == 0u) {
+        return;
+    }
+    atomicStore(&metrics[i].pending, 1u);
+    if (section == SECTION_INTERNAL) {
+        atomicStore(&hash_state[i].changed, 1u);
+    }
+    var slot = atomicAdd(&queues.frontier_len, countOneBits(flips));
+    let bit_base = section_first_bit(inst, section) + first;
+    while (flips != 0u) {
+        let b = firstTrailingBit(flips);
+        let rose = (cur >> b) & 1u;
+        frontier[slot] = FrontierEntry(i, ((bit_base + b) << 1u) | rose);
+        slot = slot + 1u;
+        flips = flips & (flips - 1u);
+    }
+}
 
-    let output_offset = counts.input_bits + counts.internal_bits;
-    let output_words = (counts.output_bits + WORD_BITS - 1u) / WORD_BITS;
-    for (var w = 0u; w < output_words; w = w + 1u) {
-        let cur = curr_outputs.data[w];
-        let prev = prev_outputs.data[w];
-        let flips = cur ^ prev;
-        var mask = 1u;
-        for (var b = 0u; b < WORD_BITS && (b + w * WORD_BITS) < counts.output_bits; b = b + 1u) {
-            if ((flips & mask) != 0u) {
-                let idx = output_offset + w * WORD_BITS + b;
-                if ((cur & mask) != 0u) {
-                    frontier_on.data[frontier_counts.on] = idx;
-                    frontier_counts.on = frontier_counts.on + 1u;
-                }
-                if ((prev & mask) != 0u) {
-                    frontier_off.data[frontier_counts.off] = idx;
-                    frontier_counts.off = frontier_counts.off + 1u;
-                }
-                frontier_toggle.data[frontier_counts.toggle] = idx;
-                frontier_counts.toggle = frontier_counts.toggle + 1u;
+// Record instance `i`'s Internal state in its hash ring after a round that
+// changed it, and the cycle period if the state repeats one of the last
+// `hash_window` states of the tick: the distance back to the oldest match,
+// `hash_window` for a full lap.
+fn observe_internals(i: u32) {
+    let inst = instances[i];
+    var h0: u32 = 0u;
+    var h1: u32 = 0u;
+    var h2: u32 = 0u;
+    var h3: u32 = 0u;
+    let words = (inst.internal_bits + WORD_BITS - 1u) / WORD_BITS;
+    for (var k = 0u; k < words; k = k + 1u) {
+        let w = atomicLoad(&curr_internals.data[inst.internal_base + k]);
+        h0 = murmur_mix(h0, w);
+        h1 = murmur_mix(h1, rotl32(w, 8u));
+        h2 = murmur_mix(h2, rotl32(w, 16u));
+        h3 = murmur_mix(h3, rotl32(w, 24u));
+    }
+    let len = words * 4u;
+    h0 = murmur_fmix(h0 ^ len);
+    h1 = murmur_fmix(h1 ^ len);
+    h2 = murmur_fmix(h2 ^ len);
+    h3 = murmur_fmix(h3 ^ len);
+
+    let window = counts.hash_window;
+    let ring = i * window * 4u;
+    let pos = hash_state[i].pos;
+    for (var k = 0u; k < window; k = k + 1u) {
+        let base = ring + k * 4u;
+        if (hash_ring.data[base] == h0 && hash_ring.data[base + 1u] == h1 && hash_ring.data[base + 2u] == h2 && hash_ring.data[base + 3u] == h3) {
+            let period = (window + pos - k) % window;
+            hash_state[i].period = select(period, window, period == 0u);
+            break;
+        }
+    }
+    let base = ring + pos * 4u;
+    hash_ring.data[base] = h0;
+    hash_ring.data[base + 1u] = h1;
+    hash_ring.data[base + 2u] = h2;
+    hash_ring.data[base + 3u] = h3;
+    hash_state[i].pos = (pos + 1u) % window;
+}
+
+// Put the effects of instance `inst`'s delayed links fired by an edge of
+// Output bit `bit` in flight. A link fired twice in a tick still lands once.
+fn fire_delayed(inst: Instance, bit: u32, on: bool, off: bool) {
+    for (var k = inst.delayed_base; k < inst.delayed_base + inst.delayed_count; k = k + 1u) {
+        let link = delayed_links[k];
+        let fires = link.trigger == 2u || (link.trigger == 0u && on) || (link.trigger == 1u && off);
+        if (link.from_bit == bit && fires) {
+            let last = link.delay - 1u;
+            atomicOr(&delay_lines.data[link.line_base + word_index(last)], bit_mask(last));
+        }
+    }
+}
+
+// Deliver the delayed link effects due next tick to the Inputs of instance
+// `inst`, highest `order_tag` winning per bit and later links breaking ties,
+// then move every other effect in flight one tick on.
+fn deliver_delayed(inst: Instance) {
+    let end = inst.delayed_base + inst.delayed_count;
+    for (var k = inst.delayed_base; k < end; k = k + 1u) {
+        let link = delayed_links[k];
+        if ((atomicLoad(&delay_lines.data[link.line_base]) & 1u) == 0u) {
+            continue;
+        }
+        var wins = true;
+        for (var j = inst.delayed_base; j < end; j = j + 1u) {
+            let other = delayed_links[j];
+            let due = (atomicLoad(&delay_lines.data[other.line_base]) & 1u) != 0u;
+            let beats = other.order_tag > link.order_tag || (other.order_tag == link.order_tag && j > k);
+            if (j != k && due && other.to_bit == link.to_bit && beats) {
+                wins = false;
             }
-            mask = mask << 1u;
+        }
+        if (wins) {
+            apply_curr(SECTION_INPUT, inst.input_base + word_index(link.to_bit), bit_mask(link.to_bit), link.action);
+        }
+    }
+    for (var k = inst.delayed_base; k < end; k = k + 1u) {
+        let link = delayed_links[k];
+        let words = (link.delay + WORD_BITS - 1u) / WORD_BITS;
+        for (var w = 0u; w < words; w = w + 1u) {
+            let idx = link.line_base + w;
+            var next = 0u;
+            if (w + 1u < words) {
+                next = atomicLoad(&delay_lines.data[idx + 1u]) << 31u;
+            }
+            atomicStore(&delay_lines.data[idx], (atomicLoad(&delay_lines.data[idx]) >> 1u) | next);
         }
     }
 }
 
 // ---------------------------------------------------------------
-// K2_expand_count: First pass of CSR expansion, counting proposals.
+// K1_begin_tick (per instance): Reset the tick's metrics and cycle history,
+// and empty the frontier.
 // ---------------------------------------------------------------
 @compute @workgroup_size(64)
-fn k2_expand_count(@builtin(global_invocation_id) id: vec3<u32>) {
-    if (id.x != 0u || id.y != 0u || id.z != 0u) {
+fn k1_begin_tick(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let i = invocation_index(id, groups);
+    if (i == 0u) {
+        atomicStore(&queues.frontier_len, 0u);
+    }
+    if (i >= counts.instances) {
         return;
     }
+    atomicStore(&metrics[i].effects_applied, 0u);
+    metrics[i].rounds = 0u;
+    metrics[i].running = 0u;
+    atomicStore(&metrics[i].pending, 0u);
+    hash_state[i].pos = 0u;
+    atomicStore(&hash_state[i].changed, 0u);
+    hash_state[i].period = 0u;
+    hash_state[i].done = 0u;
+    let ring = i * counts.hash_window * 4u;
+    for (var k = 0u; k < counts.hash_window * 4u; k = k + 1u) {
+        hash_ring.data[ring + k] = 0u;
+    }
+}
 
-    var total: u32 = 0u;
+// ---------------------------------------------------------------
+// K1_detect_edges (per state word): Compute bit transitions of every
+// instance, build the initial frontier and catch Prev up with Curr.
+// ---------------------------------------------------------------
+@compute @workgroup_size(64)
+fn k1_detect_edges(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    detect_word(invocation_index(id, groups), false);
+}
 
-    var i: u32 = 0u;
-    while (i < frontier_counts.on) {
-        let bit = frontier_on.data[i];
-        let start = csr_offs_on.data[bit];
-        let end = csr_offs_on.data[bit + 1u];
-        total = total + (end - start);
-        i = i + 1u;
+// ---------------------------------------------------------------
+// K2_begin_round (per instance): Start a round if the frontier is non-empty
+// and the round and effect caps allow. Instances that stopped stay stopped:
+// their frontier is empty or a cap is reached. Invocation 0 sizes the K2
+// dispatches by the frontier and empties the proposal list.
+// ---------------------------------------------------------------
+@compute @workgroup_size(64)
+fn k2_begin_round(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let i = invocation_index(id, groups);
+    if (i == 0u) {
+        dispatch.frontier = grid(atomicLoad(&queues.frontier_len));
+        atomicStore(&queues.proposal_len, 0u);
     }
-
-    i = 0u;
-    while (i < frontier_counts.off) {
-        let bit = frontier_off.data[i];
-        let start = csr_offs_off.data[bit];
-        let end = csr_offs_off.data[bit + 1u];
-        total = total + (end - start);
-        i = i + 1u;
+    if (i >= counts.instances) {
+        return;
+    }
+    if (!can_run(i)) {
+        metrics[i].running = 0u;
+        return;
     }
+    metrics[i].running = 1u;
+    metrics[i].rounds = metrics[i].rounds + 1u;
+    atomicStore(&metrics[i].pending, 0u);
+    atomicStore(&hash_state[i].changed, 0u);
+}
 
-    i = 0u;
-    while (i < frontier_counts.toggle) {
-        let bit = frontier_toggle.data[i];
-        let start = csr_offs_toggle.data[bit];
-        let end = csr_offs_toggle.data[bit + 1u];
-        total = total + (end - start);
-        i = i + 1u;
+// ---------------------------------------------------------------
+// K2_fire_delayed (per frontier entry): Output edges put the effects of
+// their delayed links in flight.
+// ---------------------------------------------------------------
+@compute @workgroup_size(64)
+fn k2_fire_delayed(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let e = invocation_index(id, groups);
+    if (e >= atomicLoad(&queues.frontier_len)) {
+        return;
+    }
+    let entry = frontier[e];
+    if (metrics[entry.instance].running == 0u) {
+        return;
     }
+    let inst = instances[entry.instance];
+    let bit = entry.edge >> 1u;
+    if (bit >= inst.input_bits + inst.internal_bits) {
+        let rose = (entry.edge & 1u) != 0u;
+        fire_delayed(inst, bit, rose, !rose);
+    }
+}
 
-    proposal_count.value = total;
+// Whether `effect` of instance `inst` fires: gated (embedded child)
+// connections only fire while their gate bit is set.
+fn gate_open(inst: Instance, effect: Effect) -> bool {
+    return effect.gate == 0u || curr_bit(inst, effect.gate - 1u);
 }
 
 // ---------------------------------------------------------------
-// K2_expand_emit: Second pass CSR expansion emitting proposals.
+// K2_expand (per frontier entry): Expand the edge's On or Off CSR slice and
+// its Toggle slice into proposals, reserving their slots in the proposal
+// list with one atomic add.
 // ---------------------------------------------------------------
 @compute @workgroup_size(64)
-fn k2_expand_emit(@builtin(global_invocation_id) id: vec3<u32>) {
-    if (id.x != 0u || id.y != 0u || id.z != 0u) {
+fn k2_expand(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let e = invocation_index(id, groups);
+    if (e >= atomicLoad(&queues.frontier_len)) {
+        return;
+    }
+    let entry = frontier[e];
+    let i = entry.instance;
+    if (metrics[i].running == 0u) {
+        return;
+    }
+    let inst = instances[i];
+    let bit = inst.offs_base + (entry.edge >> 1u);
+    let rose = (entry.edge & 1u) != 0u;
+    let total = inst.offs_base + inst.input_bits + inst.internal_bits + inst.output_bits;
+    // ranks continue from one trigger's effects to the next
+    let off_rank = csr_offs_on.data[total];
+    let toggle_rank = off_rank + csr_offs_off.data[total];
+
+    var lo = csr_offs_off.data[bit];
+    var hi = csr_offs_off.data[bit + 1u];
+    if (rose) {
+        lo = csr_offs_on.data[bit];
+        hi = csr_offs_on.data[bit + 1u];
+    }
+    let tlo = csr_offs_toggle.data[bit];
+    let thi = csr_offs_toggle.data[bit + 1u];
+
+    var open = 0u;
+    for (var j = lo; j < hi; j = j + 1u) {
+        var effect = csr_effects_off[inst.off_base + j];
+        if (rose) {
+            effect = csr_effects_on[inst.on_base + j];
+        }
+        open = open + select(0u, 1u, gate_open(inst, effect));
+    }
+    for (var j = tlo; j < thi; j = j + 1u) {
+        open = open + select(0u, 1u, gate_open(inst, csr_effects_toggle[inst.toggle_base + j]));
+    }
+    if (open == 0u) {
         return;
     }
 
-    var idx: u32 = 0u;
-
-    var i: u32 = 0u;
-    while (i < frontier_counts.on) {
-        let bit = frontier_on.data[i];
-        let start = csr_offs_on.data[bit];
-        let end = csr_offs_on.data[bit + 1u];
-        var j = start;
-        while (j < end) {
-            proposals[idx] = csr_effects_on[j];
-            idx = idx + 1u;
-            j = j + 1u;
+    var slot = atomicAdd(&queues.proposal_len, open);
+    for (var j = lo; j < hi; j = j + 1u) {
+        var effect = csr_effects_off[inst.off_base + j];
+        var rank = off_rank + j;
+        if (rose) {
+            effect = csr_effects_on[inst.on_base + j];
+            rank = j;
         }
-        i = i + 1u;
-    }
-
-    i = 0u;
-    while (i < frontier_counts.off) {
-        let bit = frontier_off.data[i];
-        let start = csr_offs_off.data[bit];
-        let end = csr_offs_off.data[bit + 1u];
-        var j = start;
-        while (j < end) {
-            proposals[idx] = csr_effects_off[j];
-            idx = idx + 1u;
-            j = j + 1u;
+        if (gate_open(inst, effect)) {
+            proposals[slot] = Proposal(i, effect.to_bit, effect.order_tag, (rank << 2u) | effect.action);
+            slot = slot + 1u;
         }
-        i = i + 1u;
-    }
-
-    i = 0u;
-    while (i < frontier_counts.toggle) {
-        let bit = frontier_toggle.data[i];
-        let start = csr_offs_toggle.data[bit];
-        let end = csr_offs_toggle.data[bit + 1u];
-        var j = start;
-        while (j < end) {
-            proposals[idx] = csr_effects_toggle[j];
-            idx = idx + 1u;
-            j = j + 1u;
+    }
+    for (var j = tlo; j < thi; j = j + 1u) {
+        let effect = csr_effects_toggle[inst.toggle_base + j];
+        if (gate_open(inst, effect)) {
+            proposals[slot] = Proposal(i, effect.to_bit, effect.order_tag, ((toggle_rank + j) << 2u) | effect.action);
+            slot = slot + 1u;
         }
-        i = i + 1u;
     }
+}
 
-    proposal_count.value = idx;
+// ---------------------------------------------------------------
+// K3_args (single invocation): Size the K3/K4 dispatches by the proposals
+// and empty the frontier for K5.
+// ---------------------------------------------------------------
+@compute @workgroup_size(1)
+fn k3_args() {
+    dispatch.proposals = grid(atomicLoad(&queues.proposal_len));
+    atomicStore(&queues.frontier_len, 0u);
+}
+
+// Winner slot of proposal `p`: its target's first word in `winners`.
+fn winner_slot(p: Proposal) -> u32 {
+    return (instances[p.instance].bit_base + p.to_bit) * 2u;
 }
 
 // ---------------------------------------------------------------
-// K3_resolve: Sort proposals and select winners per to_bit.
+// K3_max_tag / K3_max_rank (per proposal): Segmented max-reduce keyed by
+// target bit, without sorting: the highest order_tag proposed for each bit,
+// then the highest rank among the proposals carrying it. The CPU reference
+// resolves the same way.
 // ---------------------------------------------------------------
 @compute @workgroup_size(64)
-fn k3_resolve(@builtin(global_invocation_id) id: vec3<u32>) {
-    if (id.x != 0u || id.y != 0u || id.z != 0u) {
+fn k3_max_tag(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let k = invocation_index(id, groups);
+    if (k >= atomicLoad(&queues.proposal_len)) {
         return;
     }
+    let p = proposals[k];
+    atomicMax(&winners.data[winner_slot(p)], p.order_tag);
+}
 
-    let n = proposal_count.value;
-
-    // Insertion sort by (to_bit, order_tag)
-    var i: u32 = 1u;
-    while (i < n) {
-        var key = proposals[i];
-        var j: i32 = i32(i);
-        loop {
-            if (j <= 0) { break; }
-            let prev = proposals[u32(j - 1)];
-            if (prev.to_bit > key.to_bit || (prev.to_bit == key.to_bit && prev.order_tag > key.order_tag)) {
-                proposals[u32(j)] = prev;
-                j = j - 1;
-            } else {
-                break;
-            }
-        }
-        proposals[u32(j)] = key;
-        i = i + 1u;
-    }
-
-    // Take last proposal for each to_bit
-    var wcount: u32 = 0u;
-    var k: u32 = 0u;
-    while (k < n) {
-        var current = proposals[k];
-        while (k + 1u < n && proposals[k + 1u].to_bit == current.to_bit) {
-            k = k + 1u;
-            current = proposals[k];
-        }
-        winners[wcount].to_bit = current.to_bit;
-        winners[wcount].action = current.action;
-        wcount = wcount + 1u;
-        k = k + 1u;
+@compute @workgroup_size(64)
+fn k3_max_rank(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let k = invocation_index(id, groups);
+    if (k >= atomicLoad(&queues.proposal_len)) {
+        return;
+    }
+    let p = proposals[k];
+    let slot = winner_slot(p);
+    if (atomicLoad(&winners.data[slot]) == p.order_tag) {
+        atomicMax(&winners.data[slot + 1u], (p.rank_action >> 2u) + 1u);
     }
-    winners_count.value = wcount;
 }
 
 // ---------------------------------------------------------------
-// K4_commit: Apply winning proposals to current state.
+// K4_commit (per proposal): Apply the winning proposal of each bit to Curr
+// and clear its slot. Ranks are unique, so exactly one proposal per slot
+// matches, and the others cannot match a cleared slot. Link effects land on
+// Inputs.
 // ---------------------------------------------------------------
 @compute @workgroup_size(64)
-fn k4_commit(@builtin(global_invocation_id) id: vec3<u32>) {
-    if (id.x != 0u || id.y != 0u || id.z != 0u) {
+fn k4_commit(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let k = invocation_index(id, groups);
+    if (k >= atomicLoad(&queues.proposal_len)) {
         return;
     }
-
-    let internal_offset = counts.input_bits;
-    let output_offset = counts.input_bits + counts.internal_bits;
-    let n = winners_count.value;
-
-    for (var i: u32 = 0u; i < n; i = i + 1u) {
-        let w = winners[i];
-        let bit = w.to_bit;
-        let action = w.action;
-        if (bit >= internal_offset && bit < output_offset) {
-            let local = bit - internal_offset;
-            let word = word_index(local);
-            let mask = bit_mask(local);
-            var val = curr_internals.data[word];
-            if (action == 0u) {
-                val = val | mask;
-            } else if (action == 1u) {
-                val = val & (~mask);
-            } else {
-                val = val ^ mask;
-            }
-            curr_internals.data[word] = val;
-        } else if (bit >= output_offset) {
-            let local = bit - output_offset;
-            let word = word_index(local);
-            let mask = bit_mask(local);
-            var val = curr_outputs.data[word];
-            if (action == 0u) {
-                val = val | mask;
-            } else if (action == 1u) {
-                val = val & (~mask);
-            } else {
-                val = val ^ mask;
-            }
-            curr_outputs.data[word] = val;
-        }
-        metrics.effects_applied = metrics.effects_applied + 1u;
+    let p = proposals[k];
+    let slot = winner_slot(p);
+    if (atomicLoad(&winners.data[slot + 1u]) != (p.rank_action >> 2u) + 1u) {
+        return;
     }
+    atomicStore(&winners.data[slot], 0u);
+    atomicStore(&winners.data[slot + 1u], 0u);
+    atomicAdd(&metrics[p.instance].effects_applied, 1u);
+
+    let inst = instances[p.instance];
+    let section = section_of(inst, p.to_bit);
+    let local = p.to_bit - section_first_bit(inst, section);
+    let word = section_base(inst, section) + word_index(local);
+    apply_curr(section, word, bit_mask(local), p.rank_action & 3u);
 }
 
 // ---------------------------------------------------------------
-// K5_next_frontier: Diff internals to build next frontier.
+// K5_next_frontier (per state word): Diff every section of the instances in
+// a round against the previous round to build the next frontier; Output
+// edges feed links, Input edges come from them.
 // ---------------------------------------------------------------
 @compute @workgroup_size(64)
-fn k5_next_frontier(@builtin(global_invocation_id) id: vec3<u32>) {
-    if (id.x != 0u || id.y != 0u || id.z != 0u) {
+fn k5_next_frontier(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    detect_word(invocation_index(id, groups), true);
+}
+
+// ---------------------------------------------------------------
+// K5_hash_internals (per instance): A changed Internal state goes into the
+// cycle hash ring.
+// ---------------------------------------------------------------
+@compute @workgroup_size(64)
+fn k5_hash_internals(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let i = invocation_index(id, groups);
+    if (i >= counts.instances || metrics[i].running == 0u) {
         return;
     }
-
-    frontier_counts.on = 0u;
-    frontier_counts.off = 0u;
-    frontier_counts.toggle = 0u;
-
-    let internal_words = (counts.internal_bits + WORD_BITS - 1u) / WORD_BITS;
-    let offset = counts.input_bits;
-    for (var w = 0u; w < internal_words; w = w + 1u) {
-        let cur = curr_internals.data[w];
-        let prev = prev_internals.data[w];
-        let flips = cur ^ prev;
-        var mask = 1u;
-        for (var b = 0u; b < WORD_BITS && (w * WORD_BITS + b) < counts.internal_bits; b = b + 1u) {
-            if ((flips & mask) != 0u) {
-                let idx = offset + w * WORD_BITS + b;
-                if ((cur & mask) != 0u) {
-                    frontier_on.data[frontier_counts.on] = idx;
-                    frontier_counts.on = frontier_counts.on + 1u;
-                }
-                if ((prev & mask) != 0u) {
-                    frontier_off.data[frontier_counts.off] = idx;
-                    frontier_counts.off = frontier_counts.off + 1u;
-                }
-                frontier_toggle.data[frontier_counts.toggle] = idx;
-                frontier_counts.toggle = frontier_counts.toggle + 1u;
-            }
-            mask = mask << 1u;
-        }
-        prev_internals.data[w] = cur;
+    if (atomicLoad(&hash_state[i].changed) != 0u) {
+        observe_internals(i);
     }
 }
 
 // ---------------------------------------------------------------
-// Kfinal_finalize: Commit prev = curr and write per-tick metrics.
+// Kfinal_finalize (per instance): Once an instance has stopped, commit
+// prev = curr and drop the period of a tick that settled, exactly once per
+// tick. Delayed link effects due next tick then land on Curr Inputs, so K1
+// of that tick sees them as edges before the host writes its stimulus over
+// the mapped bits.
 // ---------------------------------------------------------------
 @compute @workgroup_size(64)
-fn kfinal_finalize(@builtin(global_invocation_id) id: vec3<u32>) {
-    if (id.x != 0u || id.y != 0u || id.z != 0u) {
+fn kfinal_finalize(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let i = invocation_index(id, groups);
+    if (i >= counts.instances || hash_state[i].done != 0u || can_run(i)) {
         return;
     }
-
-    var h0: u32 = 0u;
-    var h1: u32 = 0u;
-    var h2: u32 = 0u;
-    var h3: u32 = 0u;
-    let words = (counts.internal_bits + WORD_BITS - 1u) / WORD_BITS;
-    for (var i = 0u; i < words; i = i + 1u) {
-        let w = curr_internals.data[i];
-        h0 = murmur_mix(h0, w);
-        h1 = murmur_mix(h1, rotl32(w, 8u));
-        h2 = murmur_mix(h2, rotl32(w, 16u));
-        h3 = murmur_mix(h3, rotl32(w, 24u));
+    let inst = instances[i];
+    if (atomicLoad(&metrics[i].pending) == 0u) {
+        hash_state[i].period = 0u;
     }
-    let len = words * 4u;
-    h0 = murmur_fmix(h0 ^ len);
-    h1 = murmur_fmix(h1 ^ len);
-    h2 = murmur_fmix(h2 ^ len);
-    h3 = murmur_fmix(h3 ^ len);
+    hash_state[i].done = 1u;
 
-    var repeat: u32 = 0u;
-    var period: u32 = 0u;
-    let window = counts.hash_window;
-    for (var i = 0u; i < window; i = i + 1u) {
-        let base = i * 4u;
-        if (hash_ring.data[base] == h0 && hash_ring.data[base + 1u] == h1 && hash_ring.data[base + 2u] == h2 && hash_ring.data[base + 3u] == h3) {
-            repeat = 1u;
-            period = (window + hash_state.pos - i) % window;
+    for (var section = 0u; section < 3u; section = section + 1u) {
+        let base = section_base(inst, section);
+        let words = (section_bits(inst, section) + WORD_BITS - 1u) / WORD_BITS;
+        for (var k = 0u; k < words; k = k + 1u) {
+            write_prev(section, base + k, read_curr(section, base + k));
         }
     }
-    let pos = hash_state.pos;
-    let base = pos * 4u;
-    hash_ring.data[base] = h0;
-    hash_ring.data[base + 1u] = h1;
-    hash_ring.data[base + 2u] = h2;
-    hash_ring.data[base + 3u] = h3;
-    hash_state.pos = (pos + 1u) % window;
-    hash_state.detected = repeat;
-    hash_state.period = period;
-
-    let input_words = (counts.input_bits + WORD_BITS - 1u) / WORD_BITS;
-    for (var i = 0u; i < input_words; i = i + 1u) {
-        prev_inputs.data[i] = curr_inputs.data[i];
-    }
-
-    let internal_words = (counts.internal_bits + WORD_BITS - 1u) / WORD_BITS;
-    for (var i = 0u; i < internal_words; i = i + 1u) {
-        prev_internals.data[i] = curr_internals.data[i];
-    }
-
-    let output_words = (counts.output_bits + WORD_BITS - 1u) / WORD_BITS;
-    for (var i = 0u; i < output_words; i = i + 1u) {
-        prev_outputs.data[i] = curr_outputs.data[i];
-    }
+    deliver_delayed(inst);
 }
-
@@ -1,3 +1,13 @@
+#[cfg(any(feature = "webgpu", feature = "gpu-native"))]
+pub mod budget;
+#[cfg(any(feature = "webgpu", feature = "gpu-native"))]
+pub mod buffers;
 pub mod device;
-#[cfg(feature = "webgpu")]
+#[cfg(any(feature = "webgpu", feature = "gpu-native"))]
+pub mod embed;
+#[cfg(any(feature = "webgpu", feature = "gpu-native"))]
+pub mod eval;
+#[cfg(any(feature = "webgpu", feature = "gpu-native"))]
 pub mod pipeline;
+#[cfg(all(feature = "verify", any(feature = "webgpu", feature = "gpu-native")))]
+pub mod verify;
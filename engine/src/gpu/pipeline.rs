@@ -6,224 +6,678 @@
 //! issue dispatch commands in the proper order and insert barriers between
 //! rounds.
 //!
-//! The implementation is intentionally minimal – it records commands but leaves
-//! buffer management to the caller. The function will be compiled only when the
-//! `webgpu` feature is enabled.
+//! Every kernel gets its own bind group over just the bindings it uses, so
+//! no pipeline needs more storage buffers per stage than the adapter allows.
+//! The buffers come from [`SystemBuffers`], which may hold many systems, and
+//! each kernel runs one invocation per item of its [`Grid`]: per instance,
+//! per state word of the batch, or per frontier entry and per proposal,
+//! dispatched indirectly with sizes the kernels write themselves.
+//! [`WORKGROUP_SIZE`] items go to a workgroup, on a grid from
+//! [`workgroup_grid`] that stays within the device's per-dimension workgroup
+//! limit. The module will be compiled only when the `webgpu` or `gpu-native`
+//! feature is enabled.
 
-#![cfg(feature = "webgpu")]
+#![cfg(any(feature = "webgpu", feature = "gpu-native"))]
 
+use std::future::Future;
+use std::task::{Context, Poll, Waker};
 use std::{convert::TryInto, sync::mpsc};
 use wgpu::{
-    BindGroup, Buffer, BufferDescriptor, BufferUsages, CommandEncoderDescriptor,
-    ComputePassDescriptor, ComputePipeline, Device, Maintain, MapMode, Queue,
+    BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Buffer,
+    BufferBindingType, BufferDescriptor, BufferUsages, CommandEncoder, CommandEncoderDescriptor,
+    ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Device, ErrorFilter,
+    Maintain, MapMode, PipelineLayoutDescriptor, Queue, ShaderModule, ShaderModuleDescriptor,
+    ShaderSource, ShaderStages,
 };
 
-/// Convenience struct bundling all compute pipelines used during a tick.
-///
-/// The fields correspond to the WGSL entry points defined in `kernels.wgsl`.
-#[allow(missing_docs)]
+use super::buffers::{
+    SystemBuffers, COUNTS, CSR_EFFECTS_OFF, CSR_EFFECTS_ON, CSR_EFFECTS_TOGGLE, CSR_OFFS_OFF,
+    CSR_OFFS_ON, CSR_OFFS_TOGGLE, CURR_INPUTS, CURR_INTERNALS, CURR_OUTPUTS, DELAYED_LINKS,
+    DELAY_LINES, DISPATCH, FRONTIER, HASH_RING, HASH_STATE, INSTANCES, METRICS, PREV_INPUTS,
+    PREV_INTERNALS, PREV_OUTPUTS, PROPOSALS, QUEUES, WINNERS,
+};
+
+pub const K1_BEGIN_TICK: usize = 0;
+pub const K1_DETECT_EDGES: usize = 1;
+pub const K2_BEGIN_ROUND: usize = 2;
+pub const K2_FIRE_DELAYED: usize = 3;
+pub const K2_EXPAND: usize = 4;
+pub const K3_ARGS: usize = 5;
+pub const K3_MAX_TAG: usize = 6;
+pub const K3_MAX_RANK: usize = 7;
+pub const K4_COMMIT: usize = 8;
+pub const K5_NEXT_FRONTIER: usize = 9;
+pub const K5_HASH_INTERNALS: usize = 10;
+pub const KFINAL_FINALIZE: usize = 11;
+
+/// `@workgroup_size` of every kernel but the single-invocation ones.
+pub const WORKGROUP_SIZE: u32 = 64;
+
+/// Rounds [`tick`] records before its first readback; each further
+/// submission doubles the count.
+pub const SPECULATIVE_ROUNDS: u32 = 8;
+
+const ROUND: [usize; 9] = [
+    K2_BEGIN_ROUND,
+    K2_FIRE_DELAYED,
+    K2_EXPAND,
+    K3_ARGS,
+    K3_MAX_TAG,
+    K3_MAX_RANK,
+    K4_COMMIT,
+    K5_NEXT_FRONTIER,
+    K5_HASH_INTERNALS,
+];
+
+/// What a kernel runs one invocation per.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grid {
+    /// Instance of the batch.
+    Instances,
+    /// State word of the batch, Inputs, then Internals, then Outputs.
+    Words,
+    /// A single invocation.
+    Once,
+    /// Entry of the frontier, sized on the device by K2 begin.
+    Frontier,
+    /// Proposal of the round, sized on the device by K3 args.
+    Proposals,
+}
+
+/// Entry point of each kernel, its grid and the bindings it uses, indexed
+/// by the kernel constants above.
+pub const KERNELS: [(&str, Grid, &[u32]); 12] = [
+    (
+        "k1_begin_tick",
+        Grid::Instances,
+        &[COUNTS, QUEUES, METRICS, HASH_RING, HASH_STATE],
+    ),
+    (
+        "k1_detect_edges",
+        Grid::Words,
+        &[
+            COUNTS,
+            PREV_INPUTS,
+            CURR_INPUTS,
+            PREV_INTERNALS,
+            CURR_INTERNALS,
+            PREV_OUTPUTS,
+            CURR_OUTPUTS,
+            FRONTIER,
+            QUEUES,
+            METRICS,
+            HASH_STATE,
+            INSTANCES,
+        ],
+    ),
+    (
+        "k2_begin_round",
+        Grid::Instances,
+        &[COUNTS, QUEUES, DISPATCH, METRICS, HASH_STATE],
+    ),
+    (
+        "k2_fire_delayed",
+        Grid::Frontier,
+        &[
+            COUNTS,
+            FRONTIER,
+            QUEUES,
+            METRICS,
+            INSTANCES,
+            DELAYED_LINKS,
+            DELAY_LINES,
+        ],
+    ),
+    (
+        "k2_expand",
+        Grid::Frontier,
+        &[
+            COUNTS,
+            CURR_INPUTS,
+            CURR_INTERNALS,
+            CURR_OUTPUTS,
+            FRONTIER,
+            QUEUES,
+            CSR_OFFS_ON,
+            CSR_OFFS_OFF,
+            CSR_OFFS_TOGGLE,
+            CSR_EFFECTS_ON,
+            CSR_EFFECTS_OFF,
+            CSR_EFFECTS_TOGGLE,
+            PROPOSALS,
+            METRICS,
+            INSTANCES,
+        ],
+    ),
+    ("k3_args", Grid::Once, &[COUNTS, QUEUES, DISPATCH]),
+    (
+        "k3_max_tag",
+        Grid::Proposals,
+        &[COUNTS, QUEUES, PROPOSALS, WINNERS, INSTANCES],
+    ),
+    (
+        "k3_max_rank",
+        Grid::Proposals,
+        &[COUNTS, QUEUES, PROPOSALS, WINNERS, INSTANCES],
+    ),
+    (
+        "k4_commit",
+        Grid::Proposals,
+        &[
+            COUNTS,
+            CURR_INPUTS,
+            CURR_INTERNALS,
+            CURR_OUTPUTS,
+            QUEUES,
+            PROPOSALS,
+            WINNERS,
+            METRICS,
+            INSTANCES,
+        ],
+    ),
+    (
+        "k5_next_frontier",
+        Grid::Words,
+        &[
+            COUNTS,
+            PREV_INPUTS,
+            CURR_INPUTS,
+            PREV_INTERNALS,
+            CURR_INTERNALS,
+            PREV_OUTPUTS,
+            CURR_OUTPUTS,
+            FRONTIER,
+            QUEUES,
+            METRICS,
+            HASH_STATE,
+            INSTANCES,
+        ],
+    ),
+    (
+        "k5_hash_internals",
+        Grid::Instances,
+        &[
+            COUNTS,
+            CURR_INTERNALS,
+            METRICS,
+            HASH_RING,
+            HASH_STATE,
+            INSTANCES,
+        ],
+    ),
+    (
+        "kfinal_finalize",
+        Grid::Instances,
+        &[
+            COUNTS,
+            PREV_INPUTS,
+            CURR_INPUTS,
+            PREV_INTERNALS,
+            CURR_INTERNALS,
+            PREV_OUTPUTS,
+            CURR_OUTPUTS,
+            METRICS,
+            HASH_STATE,
+            INSTANCES,
+            DELAYED_LINKS,
+            DELAY_LINES,
+        ],
+    ),
+];
+
+/// Convenience struct bundling all compute pipelines used during a tick,
+/// one per entry point of `kernels.wgsl`.
 pub struct Pipelines {
-    pub k1_detect_edges: ComputePipeline,
-    pub k2_expand_count: ComputePipeline,
-    pub k2_expand_emit: ComputePipeline,
-    pub k3_resolve: ComputePipeline,
-    pub k4_commit: ComputePipeline,
-    pub k5_next_frontier: ComputePipeline,
-    pub kfinal_finalize: ComputePipeline,
-}
-
-/// Cycle detection result returned from [`tick`].
-pub struct CycleInfo {
-    pub detected: bool,
+    /// Indexed like [`KERNELS`].
+    pipelines: Vec<ComputePipeline>,
+    layouts: Vec<BindGroupLayout>,
+    shader: ShaderModule,
+    /// `max_compute_workgroups_per_dimension` of the device.
+    max_workgroups: u32,
+}
+
+impl Pipelines {
+    /// Compile `kernels.wgsl` once and create a bind group layout and a
+    /// pipeline per kernel from it.
+    pub fn new(device: &Device) -> Self {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("mycos-kernels"),
+            source: ShaderSource::Wgsl(include_str!("kernels.wgsl").into()),
+        });
+        let layouts: Vec<BindGroupLayout> = KERNELS
+            .iter()
+            .map(|(entry, _, bindings)| kernel_layout(device, entry, bindings))
+            .collect();
+        let pipelines = KERNELS
+            .iter()
+            .zip(&layouts)
+            .map(|((entry, _, _), layout)| {
+                let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                    label: Some(entry),
+                    bind_group_layouts: &[layout],
+                    push_constant_ranges: &[],
+                });
+                device.create_compute_pipeline(&ComputePipelineDescriptor {
+                    label: Some(entry),
+                    layout: Some(&layout),
+                    module: &shader,
+                    entry_point: entry,
+                })
+            })
+            .collect();
+        Self {
+            pipelines,
+            layouts,
+            shader,
+            max_workgroups: device.limits().max_compute_workgroups_per_dimension,
+        }
+    }
+
+    /// Bind group layout of `kernel`, covering its [`KERNELS`] bindings.
+    pub fn bind_group_layout(&self, kernel: usize) -> &BindGroupLayout {
+        &self.layouts[kernel]
+    }
+
+    /// Workgroup grid that gives each of `items` an invocation on this
+    /// device; see [`workgroup_grid`].
+    pub fn grid(&self, items: u32) -> Result<(u32, u32), GpuError> {
+        workgroup_grid(items, self.max_workgroups)
+    }
+
+    /// `max_compute_workgroups_per_dimension` of the device, which the
+    /// kernels also need to size their indirect dispatches.
+    pub fn max_workgroups(&self) -> u32 {
+        self.max_workgroups
+    }
+
+    /// The compiled `kernels.wgsl`, kept so further pipelines on the same
+    /// device can use its entry points without compiling it again.
+    pub fn shader(&self) -> &ShaderModule {
+        &self.shader
+    }
+
+    /// Pipeline of `kernel`, one of the kernel constants.
+    pub fn pipeline(&self, kernel: usize) -> &ComputePipeline {
+        &self.pipelines[kernel]
+    }
+}
+
+/// Workgroups `(x, y)` to dispatch so each of `items` gets one invocation,
+/// [`WORKGROUP_SIZE`] to a workgroup. Dispatches needing more than
+/// `max_per_dimension` workgroups spill into rows of `max_per_dimension`,
+/// which the kernels flatten back into an item index; the surplus
+/// invocations of the last row find no item and return.
+///
+/// Fails with [`GpuError::DispatchTooLarge`] if even the 2D grid exceeds
+/// `max_per_dimension` rows.
+pub fn workgroup_grid(items: u32, max_per_dimension: u32) -> Result<(u32, u32), GpuError> {
+    let groups = items.div_ceil(WORKGROUP_SIZE).max(1);
+    if groups <= max_per_dimension {
+        return Ok((groups, 1));
+    }
+    let rows = groups.div_ceil(max_per_dimension);
+    if rows > max_per_dimension {
+        return Err(GpuError::DispatchTooLarge(items));
+    }
+    Ok((max_per_dimension, rows))
+}
+
+fn kernel_layout(device: &Device, label: &str, bindings: &[u32]) -> BindGroupLayout {
+    let entries: Vec<BindGroupLayoutEntry> = bindings
+        .iter()
+        .map(|&binding| {
+            let ty = match binding {
+                COUNTS => BufferBindingType::Uniform,
+                CSR_OFFS_ON..=CSR_EFFECTS_TOGGLE | INSTANCES | DELAYED_LINKS => {
+                    BufferBindingType::Storage { read_only: true }
+                }
+                _ => BufferBindingType::Storage { read_only: false },
+            };
+            BindGroupLayoutEntry {
+                binding,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }
+        })
+        .collect();
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &entries,
+    })
+}
+
+/// Work done by one [`tick`] of one instance.
+pub struct TickInfo {
+    /// K2–K5 rounds that expanded a frontier.
+    pub rounds: u32,
+    /// Winning effects committed by K4.
+    pub effects: u32,
+    /// Whether the frontier emptied before a guard stopped the tick.
+    pub settled: bool,
+    /// Period of the last cycle K5 detected in the Internal state over the
+    /// tick's rounds, like the CPU reference's cycle detector; 0 if none was
+    /// detected or the tick settled.
     pub period: u32,
 }
 
-/// Execute one tick of the GPU pipeline.
+/// Why work on the device failed. The state of the buffers involved is
+/// unknown afterwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuError {
+    /// wgpu rejected a call, e.g. a buffer or binding over the device limits.
+    Validation(String),
+    /// The device had no memory left for a buffer or submission.
+    OutOfMemory,
+    /// A readback buffer could not be mapped, e.g. after the device was lost.
+    Readback(String),
+    /// Some instance was still running after `max_rounds` rounds had been
+    /// recorded, which the kernels rule out.
+    Unfinished,
+    /// A dispatch over this many items would need more workgroups than the
+    /// device's 2D grid holds.
+    DispatchTooLarge(u32),
+    /// An error scope had not settled when [`scoped`] popped it, as on the
+    /// web, where the browser checks work asynchronously, so whether the
+    /// work was valid is unknown.
+    Unsettled,
+}
+
+impl std::fmt::Display for GpuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuError::Validation(e) => write!(f, "device validation error: {e}"),
+            GpuError::OutOfMemory => write!(f, "device out of memory"),
+            GpuError::Readback(e) => write!(f, "readback failed: {e}"),
+            GpuError::Unfinished => write!(f, "an instance ran past max_rounds"),
+            GpuError::DispatchTooLarge(items) => {
+                write!(f, "{items} items exceed the device's dispatch limits")
+            }
+            GpuError::Unsettled => write!(f, "device error scope did not settle"),
+        }
+    }
+}
+
+impl std::error::Error for GpuError {}
+
+impl From<wgpu::Error> for GpuError {
+    fn from(e: wgpu::Error) -> Self {
+        match e {
+            wgpu::Error::OutOfMemory { .. } => GpuError::OutOfMemory,
+            wgpu::Error::Validation { description, .. } => GpuError::Validation(description),
+        }
+    }
+}
+
+/// Run `f` inside out-of-memory and validation error scopes on `device`,
+/// returning its result or the error a scope caught.
 ///
-/// `max_rounds` caps the number of wavefront rounds that may be executed. The
-/// caller must provide the `frontier_counts` storage buffer bound at
-/// `@group(0) @binding(10)`. The function will repeatedly dispatch K2–K5 rounds
-/// until the frontier is empty or `max_rounds` is reached, then run
-/// `Kfinal_finalize`.
+/// Errors wgpu reports outside a scope go to the device's uncaptured error
+/// handler, which panics by default, and a failed `Queue::submit` always
+/// panics, so everything that records or uploads work runs scoped and is
+/// checked before it is submitted.
+pub fn scoped<T>(device: &Device, f: impl FnOnce() -> T) -> Result<T, GpuError> {
+    device.push_error_scope(ErrorFilter::OutOfMemory);
+    device.push_error_scope(ErrorFilter::Validation);
+    let value = f();
+    let validation = pop_error_scope(device);
+    let out_of_memory = pop_error_scope(device);
+    out_of_memory.and(validation).map(|()| value)
+}
+
+/// Pop the innermost error scope. Native devices settle it at once; on the
+/// web it settles only after the browser has checked the work, which a
+/// synchronous caller cannot wait for, so it fails with
+/// [`GpuError::Unsettled`] there.
+fn pop_error_scope(device: &Device) -> Result<(), GpuError> {
+    let mut popped = std::pin::pin!(device.pop_error_scope());
+    scope_result(
+        popped
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop())),
+    )
+}
+
+/// What a polled error scope says about the work it covered.
+fn scope_result(popped: Poll<Option<wgpu::Error>>) -> Result<(), GpuError> {
+    match popped {
+        Poll::Ready(None) => Ok(()),
+        Poll::Ready(Some(e)) => Err(e.into()),
+        Poll::Pending => Err(GpuError::Unsettled),
+    }
+}
+
+/// Execute one tick of the GPU pipeline on every instance of `buffers`,
+/// returning one [`TickInfo`] per instance.
 ///
-/// Each round submits a command buffer and waits for completion so that the
-/// frontier counts can be read back on the CPU. This makes the function
-/// synchronous but keeps the loop logic simple and deterministic.
+/// `max_rounds` caps the number of wavefront rounds each instance may
+/// execute. Rounds are not driven from the host: K1 and a first batch of
+/// [`SPECULATIVE_ROUNDS`] K2–K5 rounds are recorded into one command buffer
+/// followed by `Kfinal_finalize`. The per-frontier and per-proposal kernels
+/// of each round are dispatched indirectly, sized on the device from the
+/// lists the previous kernels filled. Each instance counts its own rounds and
+/// stops when its frontier empties or it reaches the round or effect cap,
+/// leaving the rest of the batch as no-ops, and Kfinal finalizes it once it
+/// has stopped. Only the final metrics and hash state are read back; if some
+/// instance is still running, another batch twice the size is submitted,
+/// until `max_rounds` rounds have been recorded in total.
+///
+/// Each submission waits for completion, so the function is synchronous,
+/// but a tick that settles within the first batch costs a single round trip.
+/// Every batch is recorded under [`scoped`] and only submitted once clean.
+/// Fails with [`GpuError::DispatchTooLarge`] before recording anything if
+/// the batch has more words, bits or effects than the device can dispatch
+/// over.
 pub fn tick(
     device: &Device,
     queue: &Queue,
-    bind_group: &BindGroup,
     pipelines: &Pipelines,
-    frontier_counts: &Buffer,
-    hash_state: &Buffer,
+    buffers: &SystemBuffers,
     max_rounds: u32,
-) -> CycleInfo {
-    const FRONTIER_SIZE: u64 = std::mem::size_of::<[u32; 4]>() as u64;
-    const HASH_STATE_SIZE: u64 = std::mem::size_of::<[u32; 4]>() as u64;
-
-    let readback = device.create_buffer(&BufferDescriptor {
-        label: Some("frontier-counts-readback"),
-        size: FRONTIER_SIZE,
-        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
-
-    let hash_readback = device.create_buffer(&BufferDescriptor {
-        label: Some("hash-state-readback"),
-        size: HASH_STATE_SIZE,
-        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
-
-    // Helper to copy frontier counts to `readback` and return whether the
-    // frontier is empty.
-    let mut fetch_empty = |mut encoder: wgpu::CommandEncoder| -> bool {
-        encoder.copy_buffer_to_buffer(frontier_counts, 0, &readback, 0, FRONTIER_SIZE);
-        queue.submit(Some(encoder.finish()));
-
-        let slice = readback.slice(..);
-        let (sender, receiver) = mpsc::channel();
-        slice.map_async(MapMode::Read, move |v| sender.send(v).unwrap());
-        device.poll(Maintain::Wait);
-        receiver.recv().unwrap().unwrap();
-        let data = slice.get_mapped_range();
-        let on = u32::from_le_bytes(data[0..4].try_into().unwrap());
-        let off = u32::from_le_bytes(data[4..8].try_into().unwrap());
-        let toggle = u32::from_le_bytes(data[8..12].try_into().unwrap());
-        drop(data);
-        readback.unmap();
-        on == 0 && off == 0 && toggle == 0
+) -> Result<Vec<TickInfo>, GpuError> {
+    let instances = buffers.instances();
+    // the frontier holds at most every bit, proposals at most every effect
+    let bits = instances.iter().map(|i| i.total_bits()).sum();
+    let effects = instances.iter().map(|i| i.proposal_cap).sum();
+    pipelines.grid(bits)?;
+    pipelines.grid(effects)?;
+    let grids = TickGrids {
+        instances: pipelines.grid(instances.len() as u32)?,
+        words: pipelines.grid(buffers.words())?,
     };
 
     // K1: detect edges and seed the frontier.
-    {
-        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
-            label: Some("K1_detect_edges"),
-        });
-        {
-            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-                label: Some("K1_detect_edges"),
-                ..Default::default()
-            });
-            pass.set_pipeline(&pipelines.k1_detect_edges);
-            pass.set_bind_group(0, bind_group, &[]);
-            pass.dispatch_workgroups(1, 1, 1);
-        }
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("mycos-tick"),
+    });
+    scoped(device, || {
+        queue.write_buffer(buffers.buffer(COUNTS), 8, &max_rounds.to_le_bytes());
+        dispatch(&mut encoder, pipelines, buffers, &grids, K1_BEGIN_TICK);
+        dispatch(&mut encoder, pipelines, buffers, &grids, K1_DETECT_EDGES);
+    })?;
 
-        if fetch_empty(encoder) {
-            // Frontier empty after seeding; no rounds to execute.
-            let mut final_encoder = device.create_command_encoder(&CommandEncoderDescriptor {
-                label: Some("Kfinal_finalize"),
-            });
-            {
-                let mut pass = final_encoder.begin_compute_pass(&ComputePassDescriptor {
-                    label: Some("Kfinal_finalize"),
-                    ..Default::default()
-                });
-                pass.set_pipeline(&pipelines.kfinal_finalize);
-                pass.set_bind_group(0, bind_group, &[]);
-                pass.dispatch_workgroups(1, 1, 1);
+    // Wavefront micro-step loop, in speculative batches.
+    let mut recorded = 0;
+    let mut batch = SPECULATIVE_ROUNDS;
+    loop {
+        let rounds = batch.min(max_rounds - recorded);
+        scoped(device, || {
+            for _ in 0..rounds {
+                for kernel in ROUND {
+                    dispatch(&mut encoder, pipelines, buffers, &grids, kernel);
+                }
             }
-            final_encoder.copy_buffer_to_buffer(hash_state, 0, &hash_readback, 0, HASH_STATE_SIZE);
-            queue.submit(Some(final_encoder.finish()));
-
-            let slice = hash_readback.slice(..);
-            let (sender, receiver) = mpsc::channel();
-            slice.map_async(MapMode::Read, move |v| sender.send(v).unwrap());
-            device.poll(Maintain::Wait);
-            receiver.recv().unwrap().unwrap();
-            let data = slice.get_mapped_range();
-            let detected = u32::from_le_bytes(data[4..8].try_into().unwrap()) != 0;
-            let period = u32::from_le_bytes(data[8..12].try_into().unwrap());
-            drop(data);
-            hash_readback.unmap();
-            return CycleInfo { detected, period };
-        }
-    }
+            // Finalize stopped instances by copying Curr→Prev and landing
+            // delayed link effects.
+            dispatch(&mut encoder, pipelines, buffers, &grids, KFINAL_FINALIZE);
+        })?;
+        recorded += rounds;
 
-    // Wavefront micro-step loop.
-    let mut round = 0;
-    while round < max_rounds {
-        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
-            label: Some("mycos-round"),
-        });
-        {
-            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-                label: Some("K2_expand_count"),
-                ..Default::default()
-            });
-            pass.set_pipeline(&pipelines.k2_expand_count);
-            pass.set_bind_group(0, bind_group, &[]);
-            pass.dispatch_workgroups(1, 1, 1);
-        }
-        {
-            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-                label: Some("K2_expand_emit"),
-                ..Default::default()
-            });
-            pass.set_pipeline(&pipelines.k2_expand_emit);
-            pass.set_bind_group(0, bind_group, &[]);
-            pass.dispatch_workgroups(1, 1, 1);
-        }
-        {
-            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-                label: Some("K3_resolve"),
-                ..Default::default()
-            });
-            pass.set_pipeline(&pipelines.k3_resolve);
-            pass.set_bind_group(0, bind_group, &[]);
-            pass.dispatch_workgroups(1, 1, 1);
+        let state = read_buffers(
+            device,
+            queue,
+            encoder,
+            &[buffers.hash_state(), buffers.buffer(METRICS)],
+        )?;
+        if state[0].chunks_exact(4).all(|hash| hash[3] != 0) {
+            return Ok(state[0]
+                .chunks_exact(4)
+                .zip(state[1].chunks_exact(4))
+                .map(|(hash, metrics)| TickInfo {
+                    rounds: metrics[1],
+                    effects: metrics[0],
+                    settled: metrics[3] == 0,
+                    period: hash[2],
+                })
+                .collect());
         }
-        {
-            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-                label: Some("K4_commit"),
-                ..Default::default()
-            });
-            pass.set_pipeline(&pipelines.k4_commit);
-            pass.set_bind_group(0, bind_group, &[]);
-            pass.dispatch_workgroups(1, 1, 1);
-        }
-        {
-            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-                label: Some("K5_next_frontier"),
-                ..Default::default()
-            });
-            pass.set_pipeline(&pipelines.k5_next_frontier);
-            pass.set_bind_group(0, bind_group, &[]);
-            pass.dispatch_workgroups(1, 1, 1);
-        }
-
-        if fetch_empty(encoder) {
-            break;
+        if recorded >= max_rounds {
+            return Err(GpuError::Unfinished);
         }
-        round += 1;
+        encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("mycos-rounds"),
+        });
+        batch = batch.saturating_mul(2);
     }
+}
 
-    // Finalize tick by copying Curr→Prev, hashing internals, and writing metrics.
-    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
-        label: Some("Kfinal_finalize"),
+/// Workgroup grids of the direct dispatches of a tick.
+struct TickGrids {
+    instances: (u32, u32),
+    words: (u32, u32),
+}
+
+fn dispatch(
+    encoder: &mut CommandEncoder,
+    pipelines: &Pipelines,
+    buffers: &SystemBuffers,
+    grids: &TickGrids,
+    kernel: usize,
+) {
+    let (entry, grid, _) = KERNELS[kernel];
+    let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+        label: Some(entry),
+        ..Default::default()
     });
-    {
-        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-            label: Some("Kfinal_finalize"),
-            ..Default::default()
-        });
-        pass.set_pipeline(&pipelines.kfinal_finalize);
-        pass.set_bind_group(0, bind_group, &[]);
-        pass.dispatch_workgroups(1, 1, 1);
+    pass.set_pipeline(pipelines.pipeline(kernel));
+    pass.set_bind_group(0, buffers.bind_group(kernel), &[]);
+    // the indirect arguments are the frontier's, then the proposals'
+    match grid {
+        Grid::Instances => pass.dispatch_workgroups(grids.instances.0, grids.instances.1, 1),
+        Grid::Words => pass.dispatch_workgroups(grids.words.0, grids.words.1, 1),
+        Grid::Once => pass.dispatch_workgroups(1, 1, 1),
+        Grid::Frontier => pass.dispatch_workgroups_indirect(buffers.buffer(DISPATCH), 0),
+        Grid::Proposals => pass.dispatch_workgroups_indirect(buffers.buffer(DISPATCH), 12),
     }
-    encoder.copy_buffer_to_buffer(hash_state, 0, &hash_readback, 0, HASH_STATE_SIZE);
-    queue.submit(Some(encoder.finish()));
+}
+
+/// Submit `encoder` followed by copies of every buffer in `buffers` into one
+/// staging buffer, wait, and return each buffer's contents as words.
+///
+/// Nothing is submitted if recording the copies, or anything left in
+/// `encoder`, raised an error.
+pub fn read_buffers(
+    device: &Device,
+    queue: &Queue,
+    mut encoder: CommandEncoder,
+    buffers: &[&Buffer],
+) -> Result<Vec<Vec<u32>>, GpuError> {
+    let total: u64 = buffers.iter().map(|b| b.size()).sum();
+    let (staging, commands) = scoped(device, || {
+        let staging = device.create_buffer(&BufferDescriptor {
+            label: Some("mycos-readback"),
+            size: total.max(4),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut offset = 0;
+        for buffer in buffers {
+            encoder.copy_buffer_to_buffer(buffer, 0, &staging, offset, buffer.size());
+            offset += buffer.size();
+        }
+        (staging, encoder.finish())
+    })?;
+    queue.submit(Some(commands));
 
-    let slice = hash_readback.slice(..);
+    let slice = staging.slice(..);
     let (sender, receiver) = mpsc::channel();
-    slice.map_async(MapMode::Read, move |v| sender.send(v).unwrap());
+    slice.map_async(MapMode::Read, move |v| {
+        let _ = sender.send(v);
+    });
     device.poll(Maintain::Wait);
-    receiver.recv().unwrap().unwrap();
+    match receiver.recv() {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(GpuError::Readback(e.to_string())),
+        Err(e) => return Err(GpuError::Readback(e.to_string())),
+    }
     let data = slice.get_mapped_range();
-    let detected = u32::from_le_bytes(data[4..8].try_into().unwrap()) != 0;
-    let period = u32::from_le_bytes(data[8..12].try_into().unwrap());
+    let mut offset = 0;
+    let words = buffers
+        .iter()
+        .map(|buffer| {
+            let bytes = &data[offset..offset + buffer.size() as usize];
+            offset += bytes.len();
+            bytes
+                .chunks_exact(4)
+                .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+                .collect()
+        })
+        .collect();
     drop(data);
-    hash_readback.unmap();
-    CycleInfo { detected, period }
+    staging.unmap();
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_covers_instances_within_limits() {
+        assert_eq!(workgroup_grid(0, 65535), Ok((1, 1)));
+        assert_eq!(workgroup_grid(64, 65535), Ok((1, 1)));
+        assert_eq!(workgroup_grid(65, 65535), Ok((2, 1)));
+        // 1000 workgroups at 300 per dimension: four rows of 300
+        assert_eq!(workgroup_grid(64 * 1000, 300), Ok((300, 4)));
+        let (x, y) = workgroup_grid(64 * 1000 + 1, 300).unwrap();
+        assert!(x * y * WORKGROUP_SIZE > 64 * 1000 && x <= 300 && y <= 300);
+        assert_eq!(workgroup_grid(64 * 9, 3), Ok((3, 3)));
+        assert_eq!(
+            workgroup_grid(64 * 10, 3),
+            Err(GpuError::DispatchTooLarge(640))
+        );
+    }
+
+    #[test]
+    fn unsettled_scopes_are_errors() {
+        assert_eq!(scope_result(Poll::Ready(None)), Ok(()));
+        assert_eq!(scope_result(Poll::Pending), Err(GpuError::Unsettled));
+    }
+
+    #[cfg(feature = "gpu-native")]
+    #[test]
+    fn scoped_reports_errors_instead_of_panicking() {
+        let Some((device, _queue)) = crate::gpu::device::test_device() else {
+            return;
+        };
+        let buffer = |size: u64| {
+            scoped(&device, || {
+                device.create_buffer(&BufferDescriptor {
+                    label: None,
+                    size,
+                    usage: BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                })
+            })
+        };
+        assert!(buffer(4).is_ok());
+        let err = buffer(device.limits().max_buffer_size + 4).unwrap_err();
+        assert!(matches!(err, GpuError::Validation(_)), "{err}");
+        // the scopes are gone, and the device still works
+        assert!(buffer(4).is_ok());
+    }
 }
@@ -0,0 +1,250 @@
+//! GPU/CPU golden comparison.
+//!
+//! With the `verify` feature, [`GpuEvaluator`](super::eval::GpuEvaluator)
+//! shadows every instance of its batch with the CPU reference executor, fed
+//! the same preset and stimulus, and after each tick compares the round count
+//! and the Input, Internal and Output words read back from the device. The
+//! first mismatch of an instance is kept as a [`Divergence`]; later ticks of
+//! that instance are not compared, since their state no longer lines up.
+//! The evaluator then replays the instance up to that tick with its round
+//! cap raised one round at a time to find the first round whose state
+//! differs.
+//!
+//! Delayed link effects due on the next tick are already on the device's
+//! Inputs when a tick is read back, so the CPU moves them on before
+//! comparing.
+
+#![cfg(all(feature = "verify", any(feature = "webgpu", feature = "gpu-native")))]
+
+use std::fmt;
+
+use super::buffers::InstanceInfo;
+use crate::chunk::{MycosChunk, Section};
+use crate::cpu_ref::System;
+use crate::link::Link;
+use crate::runner::word_bit;
+use crate::tasks::Io;
+
+/// First point where a device tick disagreed with the CPU reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Index of the genome in the evaluated batch.
+    pub genome: usize,
+    pub episode: usize,
+    pub tick: usize,
+    /// First differing state word, or `None` when only the round counts
+    /// differ.
+    pub word: Option<WordDivergence>,
+    /// Rounds the tick took on the device.
+    pub gpu_rounds: u32,
+    /// Rounds the tick took on the CPU.
+    pub cpu_rounds: u32,
+    /// First round of the tick, counted from 1, after which the state words
+    /// differ, or `None` when they agree after every round the tick took.
+    pub round: Option<u32>,
+}
+
+/// A state word that differs between the device and the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WordDivergence {
+    pub section: Section,
+    /// Word of the section, counted from the instance's first word in
+    /// [`GlobalBitMap`](crate::link::GlobalBitMap) order.
+    pub index: usize,
+    pub gpu: u32,
+    pub cpu: u32,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "genome {} episode {} tick {}: ",
+            self.genome, self.episode, self.tick
+        )?;
+        if let Some(round) = self.round {
+            write!(f, "state differs from round {round}; ")?;
+        }
+        if let Some(w) = &self.word {
+            write!(
+                f,
+                "{:?} word {} is {:#010x} on the device, {:#010x} on the CPU; ",
+                w.section, w.index, w.gpu, w.cpu
+            )?;
+        }
+        write!(
+            f,
+            "{} rounds on the device, {} on the CPU",
+            self.gpu_rounds, self.cpu_rounds
+        )
+    }
+}
+
+/// CPU reference run of one batch instance.
+pub(crate) struct Shadow<'a> {
+    genome: usize,
+    episode: usize,
+    ports: &'a [Io],
+    system: System<'a>,
+    diverged: bool,
+}
+
+impl<'a> Shadow<'a> {
+    /// Load `chunks` and `links` and preset the mapped Inputs from `preset`,
+    /// like [`EpisodeRunner::run_episode_from`](crate::EpisodeRunner::run_episode_from).
+    pub(crate) fn new(
+        genome: usize,
+        episode: usize,
+        chunks: &'a [MycosChunk],
+        links: &[Link],
+        ports: &'a [Io],
+        preset: &[u32],
+    ) -> Self {
+        let mut system = System::new(chunks, links);
+        if !preset.is_empty() {
+            for (k, port) in ports.iter().enumerate() {
+                system.preset_input(port.chunk_id, port.bit_idx, word_bit(preset, k));
+            }
+        }
+        Self {
+            genome,
+            episode,
+            ports,
+            system,
+            diverged: false,
+        }
+    }
+
+    /// Run tick `tick` on `stimulus` with at most `max_rounds` rounds and
+    /// compare it with the device's: `gpu_rounds` and the instance's words
+    /// of the batch `state` (Inputs, Internals, Outputs). Returns the
+    /// instance's first divergence once, with [`Divergence::round`] unset.
+    pub(crate) fn tick(
+        &mut self,
+        tick: usize,
+        stimulus: &[u32],
+        info: &InstanceInfo,
+        gpu_rounds: u32,
+        state: &[Vec<u32>],
+        max_rounds: u32,
+    ) -> Option<Divergence> {
+        if self.diverged {
+            return None;
+        }
+        for (k, port) in self.ports.iter().enumerate() {
+            self.system
+                .write_input(port.chunk_id, port.bit_idx, word_bit(stimulus, k));
+        }
+        let cpu_rounds = self.system.settle(max_rounds).rounds;
+        // the device lands delayed effects due next tick on the Inputs as it
+        // finalizes this one, before the next stimulus goes up
+        self.system.advance_tick();
+
+        let sections = [Section::Input, Section::Internal, Section::Output];
+        let word = sections
+            .into_iter()
+            .zip(state)
+            .find_map(|(section, words)| {
+                let gpu = &words[info.words(section)];
+                let cpu = self.system.global_words(section);
+                let index = gpu.iter().zip(&cpu).position(|(g, c)| g != c)?;
+                Some(WordDivergence {
+                    section,
+                    index,
+                    gpu: gpu[index],
+                    cpu: cpu[index],
+                })
+            });
+        if word.is_none() && gpu_rounds == cpu_rounds {
+            return None;
+        }
+        self.diverged = true;
+        Some(Divergence {
+            genome: self.genome,
+            episode: self.episode,
+            tick,
+            word,
+            gpu_rounds,
+            cpu_rounds,
+            round: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::fixtures::wire;
+    use crate::runner::MAX_ROUNDS;
+
+    #[test]
+    fn reports_the_first_differing_word_once() {
+        let chunks = vec![wire(), wire()];
+        let ports = [Io {
+            chunk_id: 1,
+            bit_idx: 0,
+        }];
+        // the instance sits behind one word of another in each section
+        let info = InstanceInfo {
+            input_bits: 2,
+            internal_bits: 2,
+            output_bits: 2,
+            input_base: 1,
+            internal_base: 1,
+            output_base: 1,
+            ..Default::default()
+        };
+        let state = |output: u32| vec![vec![7, 0b10], vec![7, 0b10], vec![7, output]];
+
+        let mut shadow = Shadow::new(3, 1, &chunks, &[], &ports, &[]);
+        assert_eq!(
+            shadow.tick(0, &[1], &info, 3, &state(0b10), MAX_ROUNDS),
+            None
+        );
+
+        let mut shadow = Shadow::new(3, 1, &chunks, &[], &ports, &[]);
+        let divergence = shadow
+            .tick(0, &[1], &info, 3, &state(0b11), MAX_ROUNDS)
+            .unwrap();
+        assert_eq!(
+            divergence.word,
+            Some(WordDivergence {
+                section: Section::Output,
+                index: 0,
+                gpu: 0b11,
+                cpu: 0b10,
+            })
+        );
+        assert_eq!((divergence.genome, divergence.episode), (3, 1));
+        assert_eq!(
+            divergence.to_string(),
+            "genome 3 episode 1 tick 0: Output word 0 is 0x00000003 on the device, \
+             0x00000002 on the CPU; 3 rounds on the device, 3 on the CPU"
+        );
+        assert_eq!(
+            shadow.tick(1, &[1], &info, 0, &state(0b11), MAX_ROUNDS),
+            None
+        );
+
+        let mut shadow = Shadow::new(3, 1, &chunks, &[], &ports, &[]);
+        let divergence = shadow
+            .tick(0, &[1], &info, 2, &state(0b10), MAX_ROUNDS)
+            .unwrap();
+        assert_eq!((divergence.word, divergence.cpu_rounds), (None, 3));
+
+        // capped at one round, the edge has only reached the Internal
+        let mut shadow = Shadow::new(3, 1, &chunks, &[], &ports, &[]);
+        let after_one = vec![vec![7, 0b10], vec![7, 0b10], vec![7, 0]];
+        assert_eq!(shadow.tick(0, &[1], &info, 1, &after_one, 1), None);
+
+        let divergence = Divergence {
+            round: Some(2),
+            ..divergence
+        };
+        assert_eq!(
+            divergence.to_string(),
+            "genome 3 episode 1 tick 0: state differs from round 2; \
+             2 rounds on the device, 3 on the CPU"
+        );
+    }
+}
@@ -1,3 +1,9 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::runner::{EpisodeRunner, EpisodeTrace};
+use crate::scoring::{score_lenient, ScoreBreakdown};
 use crate::{genome::Genome, tasks::Task};
 
 /// Inputs for a single episode within a batch evaluation.
@@ -20,37 +26,248 @@ pub struct EpisodeMetrics {
     pub period: u32,
 }
 
+impl EpisodeMetrics {
+    /// Sum rounds and effects over all ticks; an oscillating tick marks the
+    /// whole episode and its period is kept.
+    fn from_trace(trace: &EpisodeTrace) -> Self {
+        let mut metrics = Self::default();
+        for tick in &trace.ticks {
+            metrics.rounds = metrics.rounds.saturating_add(tick.rounds);
+            metrics.effects = metrics.effects.saturating_add(tick.effects);
+            if tick.oscillator {
+                metrics.oscillator = true;
+                metrics.period = tick.period;
+            }
+        }
+        metrics
+    }
+}
+
+/// Oscillation counts over a set of episodes. Filled per genome by the
+/// evaluator and merged per generation by the evolution loop.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OscillationStats {
+    pub episodes: u32,
+    /// Episodes with at least one oscillating tick.
+    pub oscillating: u32,
+    /// Oscillating episodes by reported period; 0 counts those that hit a
+    /// guard without a detected cycle.
+    pub periods: BTreeMap<u32, u32>,
+}
+
+impl OscillationStats {
+    pub fn from_metrics(metrics: &[EpisodeMetrics]) -> Self {
+        let mut stats = Self::default();
+        for m in metrics {
+            stats.record(m);
+        }
+        stats
+    }
+
+    pub fn record(&mut self, metrics: &EpisodeMetrics) {
+        self.episodes += 1;
+        if metrics.oscillator {
+            self.oscillating += 1;
+            *self.periods.entry(metrics.period).or_default() += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.episodes += other.episodes;
+        self.oscillating += other.oscillating;
+        for (&period, &n) in &other.periods {
+            *self.periods.entry(period).or_default() += n;
+        }
+    }
+
+    /// Fraction of episodes that oscillated, 0 when there were none.
+    pub fn fraction(&self) -> f32 {
+        if self.episodes == 0 {
+            0.0
+        } else {
+            self.oscillating as f32 / self.episodes as f32
+        }
+    }
+}
+
 /// Result of evaluating a genome over a sequence of episodes.
 #[derive(Clone, Debug, Default)]
 pub struct FitnessResult {
-    /// Fitness score for the genome. Currently always `0.0`.
+    /// Fitness score for the genome as computed by `scoring::score`.
     pub fitness: f32,
+    /// Score of each episode; `fitness` is their mean.
+    pub episode_scores: Vec<f32>,
+    /// Components of the score; empty for genomes that failed validation.
+    pub breakdown: ScoreBreakdown,
     /// Metrics collected for each episode.
     pub metrics: Vec<EpisodeMetrics>,
-    /// Captured output words per episode.
+    /// Oscillation summary of `metrics`.
+    pub oscillation: OscillationStats,
+    /// Captured output words per episode, ticks concatenated in order.
     pub outputs: Vec<Vec<u32>>,
 }
 
 /// Evaluate a batch of genomes against a task and episodes.
 ///
-/// This function provides a temporary CPU-side implementation so that the
-/// evaluation API compiles even when the `webgpu` feature is disabled. A future
-/// version will upload the genomes to the GPU and execute the wavefront kernels
-/// in parallel.
-pub fn evaluate_batch(
-    genomes: &[Genome],
-    _task: &Task,
-    episodes: &[Episode],
-) -> Vec<FitnessResult> {
-    let mut results = Vec::with_capacity(genomes.len());
-    for _genome in genomes {
-        let metrics = vec![EpisodeMetrics::default(); episodes.len()];
-        let outputs = vec![Vec::<u32>::new(); episodes.len()];
-        results.push(FitnessResult {
-            fitness: 0.0,
-            metrics,
-            outputs,
-        });
-    }
-    results
+/// Every episode of `task` is run on the CPU reference runner and scored with
+/// `scoring::score_lenient`. `episodes[i].inputs`, when non-empty, presets the mapped
+/// inputs of task episode `i` before its first tick. Genomes that fail
+/// validation score `0.0` with empty metrics. `gpu::eval::GpuEvaluator` runs
+/// the same episodes on the wavefront kernels instead.
+///
+/// With the `parallel` feature genomes are evaluated across rayon threads.
+/// Evaluation of one genome depends only on that genome (any randomness must be
+/// seeded from `genome.meta.seed`), so results match the serial path exactly.
+pub fn evaluate_batch(genomes: &[Genome], task: &Task, episodes: &[Episode]) -> Vec<FitnessResult> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        genomes
+            .par_iter()
+            .map(|genome| evaluate_genome(genome, task, episodes))
+            .collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        genomes
+            .iter()
+            .map(|genome| evaluate_genome(genome, task, episodes))
+            .collect()
+    }
+}
+
+/// Backend that scores genomes for the evolution loop.
+///
+/// Implementations must return one result per genome, in order. The loop
+/// assumes results depend only on the genome, task and episodes.
+pub trait Evaluator {
+    fn evaluate(&self, genomes: &[Genome], task: &Task, episodes: &[Episode])
+        -> Vec<FitnessResult>;
+}
+
+/// The CPU reference runner, via [`evaluate_batch`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuEvaluator;
+
+impl Evaluator for CpuEvaluator {
+    fn evaluate(
+        &self,
+        genomes: &[Genome],
+        task: &Task,
+        episodes: &[Episode],
+    ) -> Vec<FitnessResult> {
+        evaluate_batch(genomes, task, episodes)
+    }
+}
+
+pub(crate) fn evaluate_genome(genome: &Genome, task: &Task, episodes: &[Episode]) -> FitnessResult {
+    let Ok(runner) = EpisodeRunner::from_genome(task, genome) else {
+        return failed_result(task);
+    };
+    let traces: Vec<EpisodeTrace> = task
+        .episodes
+        .iter()
+        .enumerate()
+        .map(|(i, spec)| {
+            let preset = episodes.get(i).map_or(&[][..], |e| &e.inputs[..]);
+            runner.run_episode_from(spec, preset)
+        })
+        .collect();
+    fitness_from_traces(task, traces)
+}
+
+/// Result of a genome that could not be run: fitness 0, default metrics
+/// and no outputs for every episode.
+pub(crate) fn failed_result(task: &Task) -> FitnessResult {
+    let metrics = vec![EpisodeMetrics::default(); task.episodes.len()];
+    FitnessResult {
+        fitness: 0.0,
+        episode_scores: vec![0.0; task.episodes.len()],
+        breakdown: ScoreBreakdown::default(),
+        oscillation: OscillationStats::from_metrics(&metrics),
+        metrics,
+        outputs: vec![Vec::new(); task.episodes.len()],
+    }
+}
+
+/// Score one genome's traces, one per task episode, into its result.
+pub(crate) fn fitness_from_traces(task: &Task, traces: Vec<EpisodeTrace>) -> FitnessResult {
+    let metrics: Vec<EpisodeMetrics> = traces.iter().map(EpisodeMetrics::from_trace).collect();
+    let outputs = traces.iter().map(|t| t.outputs.concat()).collect();
+    let per_tick: Vec<Vec<Vec<u32>>> = traces.into_iter().map(|t| t.outputs).collect();
+    let breakdown = score_lenient(task, &per_tick, &metrics);
+    FitnessResult {
+        fitness: breakdown.score,
+        episode_scores: breakdown.episodes.iter().map(|e| e.score).collect(),
+        breakdown,
+        oscillation: OscillationStats::from_metrics(&metrics),
+        metrics,
+        outputs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::fixtures::wire_genome;
+    use crate::tasks::t00_wire_echo;
+
+    fn echo_genome(connected: bool) -> Genome {
+        let mut genome = wire_genome();
+        if !connected {
+            genome.chunks[0].conns.clear();
+        }
+        genome
+    }
+
+    #[test]
+    fn fitness_reflects_behaviour() {
+        let task = t00_wire_echo();
+        let episodes = vec![Episode::default(); task.episodes.len()];
+        let results = evaluate_batch(&[echo_genome(true), echo_genome(false)], &task, &episodes);
+        assert_eq!(results[0].fitness, 1.0);
+        assert_eq!(results[0].outputs, vec![vec![1], vec![0]]);
+        assert!(results[0].metrics[0].effects > 0);
+        assert!(!results[0].metrics[0].oscillator);
+        assert_eq!(results[1].fitness, 0.5);
+        assert_eq!(results[1].episode_scores, vec![0.0, 1.0]);
+        assert_eq!(results[1].metrics[0].effects, 0);
+    }
+
+    #[test]
+    fn oscillation_stats_count_and_merge() {
+        let looping = |period| EpisodeMetrics {
+            oscillator: true,
+            period,
+            ..EpisodeMetrics::default()
+        };
+        let mut stats = OscillationStats::from_metrics(&[
+            EpisodeMetrics::default(),
+            looping(2),
+            looping(0),
+            EpisodeMetrics::default(),
+        ]);
+        assert_eq!(stats.fraction(), 0.5);
+        stats.merge(&OscillationStats::from_metrics(&[looping(2)]));
+        assert_eq!((stats.episodes, stats.oscillating), (5, 3));
+        assert_eq!(stats.periods, BTreeMap::from([(0, 1), (2, 2)]));
+        assert_eq!(OscillationStats::default().fraction(), 0.0);
+
+        let task = t00_wire_echo();
+        let results = evaluate_batch(&[echo_genome(true)], &task, &[]);
+        assert_eq!(results[0].oscillation.episodes, 2);
+        assert_eq!(results[0].oscillation.oscillating, 0);
+    }
+
+    #[test]
+    fn batch_matches_serial() {
+        let task = t00_wire_echo();
+        let genomes: Vec<Genome> = (0..64).map(|i| echo_genome(i % 3 != 0)).collect();
+        let batch = evaluate_batch(&genomes, &task, &[]);
+        for (genome, res) in genomes.iter().zip(&batch) {
+            let serial = evaluate_genome(genome, &task, &[]);
+            assert_eq!(res.fitness, serial.fitness);
+            assert_eq!(res.outputs, serial.outputs);
+        }
+    }
 }
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{genome_hash, Genome};
+
+/// A genome kept by the [`HallOfFame`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HallEntry {
+    pub genome: Genome,
+    pub fitness: f32,
+    /// Generation in which the genome was first admitted.
+    pub generation: u32,
+    /// [`genome_hash`] used for deduplication.
+    pub hash: [u8; 32],
+}
+
+/// The best `capacity` distinct genomes seen during a run, best first.
+///
+/// Genomes are deduplicated by [`genome_hash`], so elites carried across
+/// generations, and copies differing only in meta or gene order, occupy a
+/// single entry.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct HallOfFame {
+    capacity: usize,
+    entries: Vec<HallEntry>,
+}
+
+impl HallOfFame {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Entries ordered by descending fitness.
+    pub fn entries(&self) -> &[HallEntry] {
+        &self.entries
+    }
+
+    pub fn best(&self) -> Option<&HallEntry> {
+        self.entries.first()
+    }
+
+    /// Offer a genome, returning whether it was admitted. Genomes that would
+    /// not make the cut are rejected without hashing, and genomes that do
+    /// not validate are never admitted.
+    pub fn offer(&mut self, genome: &Genome, fitness: f32, generation: u32) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+        if self.entries.len() == self.capacity
+            && self
+                .entries
+                .last()
+                .is_some_and(|worst| fitness <= worst.fitness)
+        {
+            return false;
+        }
+        let Ok(hash) = genome_hash(genome) else {
+            return false;
+        };
+        if self.entries.iter().any(|e| e.hash == hash) {
+            return false;
+        }
+        let pos = self.entries.partition_point(|e| e.fitness >= fitness);
+        self.entries.insert(
+            pos,
+            HallEntry {
+                genome: genome.clone(),
+                fitness,
+                generation,
+                hash,
+            },
+        );
+        self.entries.truncate(self.capacity);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::{ChunkGene, GenomeMeta};
+    use bitvec::prelude::*;
+
+    fn genome(nn: u32) -> Genome {
+        let chunk = ChunkGene::new(
+            0,
+            0,
+            nn,
+            BitVec::new(),
+            BitVec::new(),
+            bitvec![u8, Lsb0; 0; nn as usize],
+            Vec::new(),
+        );
+        Genome::new(
+            vec![chunk],
+            Vec::new(),
+            GenomeMeta::new(nn as u64, "".into()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn keeps_best_distinct_genomes() {
+        let mut hof = HallOfFame::new(2);
+        assert!(hof.offer(&genome(1), 0.5, 0));
+        assert!(!hof.offer(&genome(1), 0.5, 1));
+        assert!(hof.offer(&genome(2), 0.9, 1));
+        assert!(!hof.offer(&genome(3), 0.1, 2));
+        assert!(hof.offer(&genome(3), 0.7, 2));
+        let fitness: Vec<f32> = hof.entries().iter().map(|e| e.fitness).collect();
+        assert_eq!(fitness, vec![0.9, 0.7]);
+        assert_eq!(hof.best().unwrap().generation, 1);
+
+        let json = serde_json::to_string(&hof).unwrap();
+        let back: HallOfFame = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.entries().len(), 2);
+        assert_eq!(back.capacity(), 2);
+    }
+}
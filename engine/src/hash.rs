@@ -0,0 +1,157 @@
+//! Structural SHA-256 hashes of chunks and genomes.
+//!
+//! Hashes cover the canonical form only: section sizes, initial bits (padding
+//! bits cleared) and connections sorted by `(from_section, from_index,
+//! order_tag)`. Names, notes, ports, the build hash itself, innovation numbers
+//! and genome meta are excluded, so chunks differing only in metadata or
+//! connection order hash equal.
+
+use sha2::{Digest, Sha256};
+
+use crate::chunk::MycosChunk;
+use crate::embed::encode_embeds;
+use crate::genome::{Genome, ValidationError};
+use crate::link::encode_links;
+
+/// SHA-256 of the canonical form of `chunk`.
+pub fn chunk_hash(chunk: &MycosChunk) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"MYCOSCH0");
+    for count in [
+        chunk.input_count,
+        chunk.output_count,
+        chunk.internal_count,
+        chunk.connections.len() as u32,
+    ] {
+        hasher.update(count.to_le_bytes());
+    }
+    for (bits, count) in [
+        (&chunk.input_bits, chunk.input_count),
+        (&chunk.output_bits, chunk.output_count),
+        (&chunk.internal_bits, chunk.internal_count),
+    ] {
+        hasher.update(masked(bits, count));
+    }
+    let mut conns: Vec<_> = chunk.connections.iter().collect();
+    conns.sort_by_key(|c| {
+        (
+            c.from_section as u8,
+            c.from_index,
+            c.order_tag,
+            c.to_section as u8,
+            c.to_index,
+            c.trigger as u8,
+            c.action as u8,
+        )
+    });
+    for c in conns {
+        hasher.update([
+            c.from_section as u8,
+            c.to_section as u8,
+            c.trigger as u8,
+            c.action as u8,
+        ]);
+        hasher.update(c.from_index.to_le_bytes());
+        hasher.update(c.to_index.to_le_bytes());
+        hasher.update(c.order_tag.to_le_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// SHA-256 over the [`chunk_hash`] of every chunk in order, then the links and
+/// embeds in canonical order. Fails if the genome does not validate.
+pub fn genome_hash(genome: &Genome) -> Result<[u8; 32], ValidationError> {
+    let mut canonical = genome.clone();
+    canonical.sort();
+    let mut links = canonical.to_links()?;
+    links.sort_by_key(|l| {
+        (
+            l.from_chunk,
+            l.from_out_idx,
+            l.order_tag,
+            l.to_chunk,
+            l.to_in_idx,
+        )
+    });
+    let mut hasher = Sha256::new();
+    hasher.update(b"MYCOSGN0");
+    let chunks = canonical.to_chunks()?;
+    hasher.update((chunks.len() as u32).to_le_bytes());
+    for chunk in &chunks {
+        hasher.update(chunk_hash(chunk));
+    }
+    let links = encode_links(&links);
+    hasher.update((links.len() as u32).to_le_bytes());
+    hasher.update(links);
+    hasher.update(encode_embeds(&canonical.to_embeds()?));
+    Ok(hasher.finalize().into())
+}
+
+/// Store the chunk's [`chunk_hash`] in its `build_hash` TLV.
+pub fn stamp_build_hash(chunk: &mut MycosChunk) {
+    chunk.build_hash = Some(chunk_hash(chunk).to_vec());
+}
+
+/// Whether the `build_hash` TLV matches the chunk's structure; `None` when
+/// the chunk carries no build hash.
+pub fn verify_build_hash(chunk: &MycosChunk) -> Option<bool> {
+    let stored = chunk.build_hash.as_deref()?;
+    Some(stored == chunk_hash(chunk))
+}
+
+/// `bits` with every byte past `count` bits dropped and the padding bits of
+/// the last byte cleared.
+fn masked(bits: &[u8], count: u32) -> Vec<u8> {
+    let mut out: Vec<u8> = bits
+        .iter()
+        .copied()
+        .take(count.div_ceil(8) as usize)
+        .collect();
+    out.resize(count.div_ceil(8) as usize, 0);
+    if !count.is_multiple_of(8) {
+        if let Some(last) = out.last_mut() {
+            *last &= (1u8 << (count % 8)) - 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::parse_chunk;
+
+    #[test]
+    fn hash_ignores_metadata_and_order() {
+        let data = std::fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("..")
+                .join("fixtures")
+                .join("oscillator_2cycle.myc"),
+        )
+        .unwrap();
+        let chunk = parse_chunk(&data).unwrap();
+        let base = chunk_hash(&chunk);
+
+        let mut renamed = chunk.clone();
+        renamed.name = Some("renamed".into());
+        renamed.connections.reverse();
+        *renamed.internal_bits.last_mut().unwrap() |= 0x80; // padding bit
+        assert_eq!(chunk_hash(&renamed), base);
+
+        let mut changed = chunk.clone();
+        changed.connections[0].order_tag += 1;
+        assert_ne!(chunk_hash(&changed), base);
+
+        assert_eq!(verify_build_hash(&chunk), None);
+        stamp_build_hash(&mut renamed);
+        assert_eq!(verify_build_hash(&renamed), Some(true));
+        renamed.connections.pop();
+        assert_eq!(verify_build_hash(&renamed), Some(false));
+
+        let genome = Genome::from_chunks(std::slice::from_ref(&chunk), &[]).unwrap();
+        let mut twin = genome.clone();
+        twin.meta.seed += 1;
+        assert_eq!(genome_hash(&genome).unwrap(), genome_hash(&twin).unwrap());
+    }
+}
@@ -0,0 +1,233 @@
+use std::ops::RangeInclusive;
+
+use bitvec::prelude::*;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::genome::{ChunkGene, ConnGene, Genome, GenomeMeta, LinkGene};
+
+/// Size bounds for [`Genome::random`]. Every count is drawn uniformly from
+/// its range.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RandomGenomeParams {
+    pub chunks: RangeInclusive<u32>,
+    /// Input bits per chunk.
+    pub inputs: RangeInclusive<u32>,
+    /// Output bits per chunk.
+    pub outputs: RangeInclusive<u32>,
+    /// Internal bits per chunk.
+    pub internals: RangeInclusive<u32>,
+    /// Connection genes per chunk; fewer when a chunk runs out of distinct
+    /// edges.
+    pub conns: RangeInclusive<u32>,
+    /// Link genes per genome, only drawn when there are at least two chunks;
+    /// fewer when a draw repeats an earlier link.
+    pub links: RangeInclusive<u32>,
+}
+
+impl Default for RandomGenomeParams {
+    fn default() -> Self {
+        Self {
+            chunks: 1..=1,
+            inputs: 1..=4,
+            outputs: 1..=4,
+            internals: 1..=16,
+            conns: 1..=16,
+            links: 0..=0,
+        }
+    }
+}
+
+impl RandomGenomeParams {
+    /// Bounds for genome `i` of `n` in a ramped population: the upper end
+    /// of every range grows linearly from its minimum (`i = 0`) to its
+    /// maximum (`i = n - 1`).
+    pub fn ramp(&self, i: usize, n: usize) -> Self {
+        let scale = |r: &RangeInclusive<u32>| {
+            let (lo, hi) = (*r.start(), (*r.end()).max(*r.start()));
+            let step = if n > 1 {
+                (hi - lo) as u64 * i.min(n - 1) as u64 / (n - 1) as u64
+            } else {
+                (hi - lo) as u64
+            };
+            lo..=lo + step as u32
+        };
+        Self {
+            chunks: scale(&self.chunks),
+            inputs: scale(&self.inputs),
+            outputs: scale(&self.outputs),
+            internals: scale(&self.internals),
+            conns: scale(&self.conns),
+            links: scale(&self.links),
+        }
+    }
+}
+
+impl Genome {
+    /// Generate a random genome within `params`.
+    ///
+    /// Init bits are cleared and connections are sparse: each chunk gets up
+    /// to its drawn count of distinct `(from, to)` edges with random
+    /// trigger and action. The result is valid by construction.
+    pub fn random(params: &RandomGenomeParams, rng: &mut dyn RngCore) -> Genome {
+        let draw = |r: &RangeInclusive<u32>, rng: &mut dyn RngCore| {
+            rng.gen_range(*r.start()..=(*r.end()).max(*r.start()))
+        };
+        let n_chunks = draw(&params.chunks, rng);
+        let chunks: Vec<ChunkGene> = (0..n_chunks)
+            .map(|_| {
+                let (ni, no, nn) = (
+                    draw(&params.inputs, rng),
+                    draw(&params.outputs, rng),
+                    draw(&params.internals, rng),
+                );
+                let target = draw(&params.conns, rng);
+                ChunkGene::new(
+                    ni,
+                    no,
+                    nn,
+                    bitvec![u8, Lsb0; 0; ni as usize],
+                    bitvec![u8, Lsb0; 0; no as usize],
+                    bitvec![u8, Lsb0; 0; nn as usize],
+                    random_conns(ni, no, nn, target, rng),
+                )
+            })
+            .collect();
+
+        let mut links: Vec<LinkGene> = Vec::new();
+        let sources: Vec<u32> = (0..n_chunks)
+            .filter(|&c| chunks[c as usize].no > 0)
+            .collect();
+        let sinks: Vec<u32> = (0..n_chunks)
+            .filter(|&c| chunks[c as usize].ni > 0)
+            .collect();
+        if n_chunks > 1 && !sources.is_empty() && !sinks.is_empty() {
+            for _ in 0..draw(&params.links, rng) {
+                let from_chunk = sources[rng.gen_range(0..sources.len())];
+                let to_chunk = sinks[rng.gen_range(0..sinks.len())];
+                let from_out_idx = rng.gen_range(0..chunks[from_chunk as usize].no);
+                let to_in_idx = rng.gen_range(0..chunks[to_chunk as usize].ni);
+                let trigger = rng.gen_range(0..3);
+                let action = rng.gen_range(0..3);
+                if links.iter().any(|l| {
+                    (
+                        l.from_chunk,
+                        l.from_out_idx,
+                        l.to_chunk,
+                        l.to_in_idx,
+                        l.trigger,
+                    ) == (from_chunk, from_out_idx, to_chunk, to_in_idx, trigger)
+                }) {
+                    continue;
+                }
+                let order_tag = links
+                    .iter()
+                    .filter(|l| l.from_chunk == from_chunk && l.from_out_idx == from_out_idx)
+                    .count() as u32;
+                links.push(LinkGene {
+                    from_chunk,
+                    from_out_idx,
+                    trigger,
+                    action,
+                    to_chunk,
+                    to_in_idx,
+                    order_tag,
+                    delay: 0,
+                    innovation: 0,
+                });
+            }
+        }
+        let meta = GenomeMeta::new(rng.gen(), "random".into());
+        Genome::new(chunks, links, meta).expect("random genome is valid")
+    }
+}
+
+/// Up to `target` distinct connections over the edges a chunk of this shape
+/// allows.
+fn random_conns(ni: u32, no: u32, nn: u32, target: u32, rng: &mut dyn RngCore) -> Vec<ConnGene> {
+    let mut edges: Vec<(u8, u8)> = Vec::new();
+    if nn > 0 {
+        edges.push((1, 1));
+        if ni > 0 {
+            edges.push((0, 1));
+        }
+        if no > 0 {
+            edges.push((1, 2));
+        }
+    }
+    let size = |section: u8| match section {
+        0 => ni,
+        1 => nn,
+        _ => no,
+    };
+    let capacity: u64 = edges
+        .iter()
+        .map(|&(f, t)| size(f) as u64 * size(t) as u64)
+        .sum();
+    let target = (target as u64).min(capacity) as usize;
+    let mut conns: Vec<ConnGene> = Vec::with_capacity(target);
+    while conns.len() < target {
+        let (from_section, to_section) = edges[rng.gen_range(0..edges.len())];
+        let from_index = rng.gen_range(0..size(from_section));
+        let to_index = rng.gen_range(0..size(to_section));
+        let same_source =
+            |c: &&ConnGene| c.from_section == from_section && c.from_index == from_index;
+        if conns
+            .iter()
+            .filter(same_source)
+            .any(|c| c.to_section == to_section && c.to_index == to_index)
+        {
+            continue;
+        }
+        let order_tag = conns.iter().filter(same_source).count() as u32;
+        conns.push(ConnGene {
+            from_section,
+            to_section,
+            trigger: rng.gen_range(0..3),
+            action: rng.gen_range(0..3),
+            from_index,
+            to_index,
+            order_tag,
+            innovation: 0,
+        });
+    }
+    conns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome_hash;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn random_genomes_are_valid_and_ramped() {
+        let params = RandomGenomeParams {
+            chunks: 1..=4,
+            inputs: 0..=3,
+            outputs: 0..=3,
+            internals: 0..=8,
+            conns: 0..=40,
+            links: 0..=6,
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        for i in 0..50 {
+            let g = Genome::random(&params.ramp(i, 50), &mut rng);
+            g.validate().unwrap();
+            assert!((1..=4).contains(&g.chunks.len()));
+            if i == 0 {
+                // the smallest ramp step is fixed at every minimum
+                assert_eq!(g.chunks.len(), 1);
+                assert!(g.chunks[0].conns.is_empty());
+            }
+        }
+        let last = params.ramp(49, 50);
+        assert_eq!(last, params);
+        assert_eq!(params.ramp(0, 1), params);
+
+        let a = Genome::random(&params, &mut ChaCha8Rng::seed_from_u64(5));
+        let b = Genome::random(&params, &mut ChaCha8Rng::seed_from_u64(5));
+        assert_eq!(genome_hash(&a).unwrap(), genome_hash(&b).unwrap());
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use crate::genome::{ConnGene, Genome, LinkGene};
+
+/// Structural key of a connection:
+/// `(from_section, from_index, to_section, to_index, trigger)`.
+pub type ConnKey = (u8, u32, u8, u32, u8);
+/// Structural key of a link:
+/// `(from_chunk, from_out_idx, to_chunk, to_in_idx, trigger)`.
+pub type LinkKey = (u32, u32, u32, u32, u8);
+
+/// Alignment identity of a gene: its innovation number once assigned,
+/// otherwise its structural key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GeneId<K> {
+    Structural(K),
+    Innovation(u32),
+}
+
+pub fn conn_key(c: &ConnGene) -> ConnKey {
+    (
+        c.from_section,
+        c.from_index,
+        c.to_section,
+        c.to_index,
+        c.trigger,
+    )
+}
+
+pub fn link_key(l: &LinkGene) -> LinkKey {
+    (
+        l.from_chunk,
+        l.from_out_idx,
+        l.to_chunk,
+        l.to_in_idx,
+        l.trigger,
+    )
+}
+
+pub fn conn_id(c: &ConnGene) -> GeneId<ConnKey> {
+    match c.innovation {
+        0 => GeneId::Structural(conn_key(c)),
+        n => GeneId::Innovation(n),
+    }
+}
+
+pub fn link_id(l: &LinkGene) -> GeneId<LinkKey> {
+    match l.innovation {
+        0 => GeneId::Structural(link_key(l)),
+        n => GeneId::Innovation(n),
+    }
+}
+
+/// NEAT-style source of historical markings.
+///
+/// Mutation leaves new connection and link genes unassigned (innovation 0);
+/// [`InnovationTracker::assign`] numbers them afterwards. Genes that appear
+/// with the same structure (and, for connections, in the same chunk) during
+/// one generation receive the same number, so independent discoveries align
+/// in crossover.
+#[derive(Clone, Debug, Default)]
+pub struct InnovationTracker {
+    last: u32,
+    conns: HashMap<(u32, ConnKey), u32>,
+    links: HashMap<LinkKey, u32>,
+}
+
+impl InnovationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continue numbering after the highest innovation found in `genomes`.
+    pub fn from_genomes(genomes: &[Genome]) -> Self {
+        let last = genomes
+            .iter()
+            .flat_map(|g| {
+                g.chunks
+                    .iter()
+                    .flat_map(|c| c.conns.iter().map(|c| c.innovation))
+                    .chain(g.links.iter().map(|l| l.innovation))
+            })
+            .max()
+            .unwrap_or(0);
+        Self {
+            last,
+            ..Self::default()
+        }
+    }
+
+    /// Highest innovation number handed out so far.
+    pub fn last(&self) -> u32 {
+        self.last
+    }
+
+    /// Number every unassigned connection and link gene of `genome`.
+    pub fn assign(&mut self, genome: &mut Genome) {
+        for (idx, chunk) in genome.chunks.iter_mut().enumerate() {
+            for conn in chunk.conns.iter_mut().filter(|c| c.innovation == 0) {
+                let last = &mut self.last;
+                conn.innovation = *self
+                    .conns
+                    .entry((idx as u32, conn_key(conn)))
+                    .or_insert_with(|| {
+                        *last += 1;
+                        *last
+                    });
+            }
+        }
+        for link in genome.links.iter_mut().filter(|l| l.innovation == 0) {
+            let last = &mut self.last;
+            link.innovation = *self.links.entry(link_key(link)).or_insert_with(|| {
+                *last += 1;
+                *last
+            });
+        }
+    }
+
+    /// Forget the structures seen this generation; numbering continues.
+    pub fn next_generation(&mut self) {
+        self.conns.clear();
+        self.links.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::{ChunkGene, GenomeMeta};
+    use bitvec::prelude::*;
+
+    fn genome(conns: Vec<ConnGene>) -> Genome {
+        let chunk = ChunkGene::new(
+            1,
+            1,
+            2,
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0, 0],
+            conns,
+        );
+        Genome::new(vec![chunk], Vec::new(), GenomeMeta::new(0, "t".into())).unwrap()
+    }
+
+    #[test]
+    fn same_structure_shares_innovation() {
+        let a_conn = ConnGene::new(0, 1, 0, 0, 0, 0, 0).unwrap();
+        let b_conn = ConnGene::new(1, 2, 0, 0, 1, 0, 0).unwrap();
+        let mut a = genome(vec![a_conn.clone()]);
+        let mut b = genome(vec![a_conn, b_conn.clone()]);
+        let mut tracker = InnovationTracker::new();
+        tracker.assign(&mut a);
+        tracker.assign(&mut b);
+        assert_eq!(a.chunks[0].conns[0].innovation, 1);
+        assert_eq!(b.chunks[0].conns[0].innovation, 1);
+        assert_eq!(b.chunks[0].conns[1].innovation, 2);
+
+        tracker.next_generation();
+        let mut c = genome(vec![b_conn]);
+        tracker.assign(&mut c);
+        assert_eq!(c.chunks[0].conns[0].innovation, 3);
+        assert_eq!(InnovationTracker::from_genomes(&[a, b, c]).last(), 3);
+    }
+}
@@ -1,3 +1,6 @@
+use crate::chunk::{MycosChunk, Section};
+use crate::link::{GlobalBitMap, Link};
+
 pub fn bit_to_word(bit_idx: u32) -> (u32, u32) {
     let word_idx = bit_idx >> 5; // divide by 32
     let mask = 1u32 << (bit_idx & 31); // LSB-first within word
@@ -48,9 +51,153 @@ pub fn connection_table_offset(ni: u32, no: u32, nn: u32) -> usize {
     HEADER_BYTES + bits_total + pad
 }
 
+/// Offset alignment of [`plan_device_layout`]: one `u32` word.
+pub const WORD_ALIGN: u64 = 4;
+
+/// Offset alignment wgpu requires by default for storage buffer bindings
+/// (`min_storage_buffer_offset_alignment`). Plan with this to sub-allocate
+/// every buffer from a single device allocation.
+pub const STORAGE_OFFSET_ALIGN: u64 = 256;
+
+/// Size in bytes of one CSR effect on the device (`to_bit`, `order_tag`,
+/// `action`, padding).
+pub const DEVICE_EFFECT_BYTES: u64 = 16;
+
+/// A byte range within a [`DeviceLayout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BufferRange {
+    pub offset: u64,
+    pub bytes: u64,
+}
+
+impl BufferRange {
+    pub fn end(&self) -> u64 {
+        self.offset + self.bytes
+    }
+}
+
+/// Sizes and offsets of the GPU buffers for a system, in kernel binding
+/// order: Prev/Curr state per section, the frontier list, then per-trigger
+/// CSR offsets and effects.
+///
+/// State buffers pack every chunk's section back to back as in
+/// [`GlobalBitMap`]. The frontier holds up to one two-word entry per bit
+/// and CSR offsets have one word per global bit plus one. No range is empty,
+/// since wgpu rejects zero-sized bindings: an unused buffer still gets one
+/// word, or one effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceLayout {
+    /// Alignment every offset was rounded up to.
+    pub align: u64,
+    pub input_bits: u32,
+    pub internal_bits: u32,
+    pub output_bits: u32,
+    pub prev_inputs: BufferRange,
+    pub curr_inputs: BufferRange,
+    pub prev_internals: BufferRange,
+    pub curr_internals: BufferRange,
+    pub prev_outputs: BufferRange,
+    pub curr_outputs: BufferRange,
+    /// Instance and edge (bit id and direction) of every flipped bit.
+    pub frontier: BufferRange,
+    pub csr_offs_on: BufferRange,
+    pub csr_offs_off: BufferRange,
+    pub csr_offs_toggle: BufferRange,
+    pub csr_effects_on: BufferRange,
+    pub csr_effects_off: BufferRange,
+    pub csr_effects_toggle: BufferRange,
+    /// End of the last range, rounded up to `align`.
+    pub total_bytes: u64,
+}
+
+impl DeviceLayout {
+    /// Total bits over all sections, the frontier capacity.
+    pub fn total_bits(&self) -> u32 {
+        self.input_bits + self.internal_bits + self.output_bits
+    }
+}
+
+/// [`plan_device_layout_with`] for chunk connections only, word aligned.
+pub fn plan_device_layout(chunks: &[MycosChunk]) -> DeviceLayout {
+    plan_device_layout_with(chunks, &[], WORD_ALIGN)
+}
+
+/// Plan the device buffers for `chunks` joined by `links`, whose effects are
+/// counted into the CSR arrays alongside the connections. Offsets and the
+/// total are rounded up to `align`, e.g. [`STORAGE_OFFSET_ALIGN`].
+///
+/// Panics unless `align` is a power of two of at least [`WORD_ALIGN`].
+pub fn plan_device_layout_with(chunks: &[MycosChunk], links: &[Link], align: u64) -> DeviceLayout {
+    assert!(
+        align.is_power_of_two() && align >= WORD_ALIGN,
+        "device alignment {align} is not a power of two of at least {WORD_ALIGN}"
+    );
+    let map = GlobalBitMap::new(chunks);
+    let input_bits = map.total(Section::Input);
+    let internal_bits = map.total(Section::Internal);
+    let output_bits = map.total(Section::Output);
+    let total_bits = (input_bits + internal_bits + output_bits) as u64;
+
+    let mut effects = [0u64; 3];
+    for conn in chunks.iter().flat_map(|c| &c.connections) {
+        effects[conn.trigger as usize] += 1;
+    }
+    for link in links {
+        effects[link.trigger as usize] += 1;
+    }
+    let state = |bits: u32| (bits as u64).div_ceil(32).max(1) * 4;
+
+    let mut end = 0u64;
+    let mut next = |bytes: u64| {
+        let range = BufferRange {
+            offset: end.next_multiple_of(align),
+            bytes,
+        };
+        end = range.end();
+        range
+    };
+    let prev_inputs = next(state(input_bits));
+    let curr_inputs = next(state(input_bits));
+    let prev_internals = next(state(internal_bits));
+    let curr_internals = next(state(internal_bits));
+    let prev_outputs = next(state(output_bits));
+    let curr_outputs = next(state(output_bits));
+    let frontier = next(total_bits.max(1) * 8);
+    let csr_offs_on = next((total_bits + 1) * 4);
+    let csr_offs_off = next((total_bits + 1) * 4);
+    let csr_offs_toggle = next((total_bits + 1) * 4);
+    let [on, off, toggle] = effects.map(|n| n.max(1) * DEVICE_EFFECT_BYTES);
+    let csr_effects_on = next(on);
+    let csr_effects_off = next(off);
+    let csr_effects_toggle = next(toggle);
+
+    DeviceLayout {
+        align,
+        input_bits,
+        internal_bits,
+        output_bits,
+        prev_inputs,
+        curr_inputs,
+        prev_internals,
+        curr_internals,
+        prev_outputs,
+        curr_outputs,
+        frontier,
+        csr_offs_on,
+        csr_offs_off,
+        csr_offs_toggle,
+        csr_effects_on,
+        csr_effects_off,
+        csr_effects_toggle,
+        total_bytes: end.next_multiple_of(align),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::builder::ChunkBuilder;
+    use crate::chunk::{Action, Trigger};
 
     #[test]
     fn bit_to_word_edges() {
@@ -101,4 +248,70 @@ mod tests {
         // Total bits bytes = 3 -> pad = 1 -> 32 + 3 + 1 = 36
         assert_eq!(conn_off, 36);
     }
+
+    #[test]
+    fn device_layout_sizes_and_alignment() {
+        let chunk = ChunkBuilder::new()
+            .inputs(40)
+            .internal(3)
+            .outputs(1)
+            .conn(
+                (Section::Input, 0),
+                (Section::Internal, 0),
+                Trigger::On,
+                Action::Enable,
+            )
+            .conn(
+                (Section::Input, 1),
+                (Section::Internal, 1),
+                Trigger::On,
+                Action::Enable,
+            )
+            .conn(
+                (Section::Internal, 0),
+                (Section::Output, 0),
+                Trigger::Toggle,
+                Action::Toggle,
+            )
+            .build()
+            .unwrap();
+        let layout = plan_device_layout(std::slice::from_ref(&chunk));
+        assert_eq!(layout.total_bits(), 44);
+        assert_eq!(
+            layout.prev_inputs,
+            BufferRange {
+                offset: 0,
+                bytes: 8
+            }
+        );
+        assert_eq!(layout.curr_inputs.offset, 8);
+        assert_eq!(layout.prev_internals.bytes, 4);
+        assert_eq!(layout.frontier.bytes, 44 * 8);
+        assert_eq!(layout.csr_offs_toggle.bytes, 45 * 4);
+        assert_eq!(layout.csr_effects_on.bytes, 2 * DEVICE_EFFECT_BYTES);
+        // no Off connections, but the buffer still exists
+        assert_eq!(layout.csr_effects_off.bytes, DEVICE_EFFECT_BYTES);
+        assert_eq!(layout.total_bytes, layout.csr_effects_toggle.end());
+
+        let link = Link {
+            from_chunk: 0,
+            from_out_idx: 0,
+            trigger: Trigger::Off,
+            action: Action::Disable,
+            to_chunk: 1,
+            to_in_idx: 0,
+            order_tag: 0,
+            delay: 0,
+        };
+        let chunks = [chunk.clone(), chunk];
+        let aligned = plan_device_layout_with(&chunks, &[link], STORAGE_OFFSET_ALIGN);
+        assert_eq!(aligned.input_bits, 80);
+        assert_eq!(aligned.prev_inputs.bytes, 12);
+        assert_eq!(aligned.curr_inputs.offset, 256);
+        assert_eq!(aligned.csr_effects_off.bytes, DEVICE_EFFECT_BYTES);
+        assert_eq!(aligned.csr_effects_on.bytes, 4 * DEVICE_EFFECT_BYTES);
+        // 13 buffers, the 88-bit frontier spanning three slots and the
+        // offset arrays two
+        assert_eq!(aligned.total_bytes, 18 * 256);
+    }
 }
@@ -1,51 +1,130 @@
+pub mod archive;
+pub mod assembly;
+pub mod builder;
 pub mod checkpoint;
 pub mod chunk;
+pub mod container;
 pub mod cpu_ref;
 pub mod crossover;
 pub mod csr;
+pub mod diff;
+pub mod dot;
 pub mod embed;
+pub mod eval_cache;
 pub mod evolution;
 pub mod genome;
 pub mod gpu_eval;
+pub mod hall_of_fame;
+pub mod hash;
+pub mod init;
+pub mod innovation;
 pub mod layout;
 pub mod link;
+pub mod map_elites;
 pub mod mutations;
+pub mod novelty;
 pub mod policy;
+pub mod repair;
+pub mod runner;
 pub mod scc;
 pub mod scoring;
+pub mod selection;
 pub mod tasks;
+pub mod truth_table;
 
 #[cfg(all(target_arch = "wasm32", feature = "webgpu"))]
 pub mod api;
-#[cfg(all(target_arch = "wasm32", feature = "webgpu"))]
+#[cfg(any(
+    all(target_arch = "wasm32", feature = "webgpu"),
+    feature = "gpu-native"
+))]
 pub mod gpu;
-pub use checkpoint::{load, save, Checkpoint};
+pub use archive::{ArchiveEntry, GenomeArchive};
+pub use assembly::{emit_assembly, parse_assembly, AssemblyError};
+pub use builder::ChunkBuilder;
+pub use checkpoint::{export_best, load, peek, save, Checkpoint, CheckpointInfo, TaskIdentity};
 pub use chunk::{
-    parse_chunk, validate_chunk, Action, Connection, Error, MycosChunk, Section, Trigger,
+    extract_subchunk, parse_chunk, parse_chunk_with_limits, validate_chunk, validate_chunk_all,
+    Action, ChunkView, Connection, Error, Limit, MycosChunk, ParseLimits, Port, Section, Trigger,
+};
+pub use container::{
+    encode_container, parse_container, validate_container, Container, ContainerError,
+};
+pub use crossover::{
+    align_chunks, chunk_similarity, crossover, crossover_n, crossover_n_weighted, crossover_with,
+    CrossoverStrategy,
 };
-pub use crossover::crossover;
 pub use csr::{build_csr, Effect, CSR};
-pub use embed::{execute_gated_alias, execute_gated_copy, parse_embeds, Embed, EmbedError, IoMode};
-pub use evolution::{run_evolution, EvoConfig};
-pub use genome::{ChunkGene, ConnGene, Genome, GenomeMeta, LinkGene, ValidationError};
-pub use gpu_eval::{evaluate_batch, Episode, EpisodeMetrics, FitnessResult};
+pub use diff::{diff_chunks, ChunkDiff};
+pub use dot::{system_to_dot, to_dot};
+pub use embed::{
+    encode_embeds, execute_gated_alias, execute_gated_copy, parse_embeds, parse_embeds_with_limits,
+    validate_embeds, Embed, EmbedError, IoMode,
+};
+pub use eval_cache::EvalCache;
+pub use evolution::{
+    compatibility_distance, resume_evolution, resume_evolution_with, run_evolution,
+    run_evolution_async, run_evolution_with, CompatibilityCoefficients, ConfigError, Curriculum,
+    CurriculumStage, EpisodeSampling, EvoConfig, EvolutionReport, GenerationHook,
+    GenerationSnapshot, GenerationStats, ResumeError,
+};
+pub use genome::{ChunkGene, ConnGene, EmbedGene, Genome, GenomeMeta, LinkGene, ValidationError};
+pub use gpu_eval::{
+    evaluate_batch, CpuEvaluator, Episode, EpisodeMetrics, Evaluator, FitnessResult,
+    OscillationStats,
+};
+pub use hall_of_fame::{HallEntry, HallOfFame};
+pub use hash::{chunk_hash, genome_hash, stamp_build_hash, verify_build_hash};
+pub use init::RandomGenomeParams;
+pub use innovation::{GeneId, InnovationTracker};
 pub use layout::{
-    bit_to_word, clr_bit, connection_table_offset, section_offsets, set_bit, xor_bit, HEADER_BYTES,
+    bit_to_word, clr_bit, connection_table_offset, plan_device_layout, plan_device_layout_with,
+    section_offsets, set_bit, xor_bit, BufferRange, DeviceLayout, DEVICE_EFFECT_BYTES,
+    HEADER_BYTES, STORAGE_OFFSET_ALIGN, WORD_ALIGN,
 };
 pub use link::{
-    build_link_csr, compute_base_offsets, parse_links, validate_links, ChunkOffsets, Link,
-    LinkError,
+    build_global_csr, build_link_csr, compute_base_offsets, encode_links, link_by_name,
+    merge_chunks, parse_links, parse_links_with_limits, resolve_port, validate_links, ChunkIo,
+    ChunkOffsets, ConnectionGate, GlobalBitMap, GlobalBits, Link, LinkError,
 };
-pub use mutations::mutate;
+pub use map_elites::{run_map_elites, Elite, EliteArchive, Feature, FeatureAxis, MapElitesConfig};
+pub use mutations::{
+    mutate, mutate_with, CutFeedbackArc, MutationConfig, MutationOperator, MutationRecord,
+    MutationRegistry,
+};
+pub use novelty::{behavior, behavior_distance, score_novelty, NoveltyArchive, NoveltyConfig};
 pub use policy::{
-    clamp_commutative, freeze_last_stable, parity_quench, CycleDetector, ExecutionResult, Policy,
+    clamp_commutative, freeze_last_stable, parity_quench, stochastic_quench, CycleConfirmation,
+    CycleDetector, DetectorConfig, ExecutionResult, Policy, PolicyConfig,
+};
+pub use repair::Repair;
+pub use runner::{EpisodeRunner, EpisodeTrace, TickStats};
+pub use scc::{
+    build_internal_graph, build_system_graph, cross_chunk_loops, feedback_arcs,
+    scc_ids_and_topo_levels, system_scc_ids_and_topo_levels, FeedbackArc, SystemBit, SystemEdge,
+};
+pub use scoring::{
+    score, score_episodes, score_lenient, ActivityPenalty, EpisodeScore, Parsimony, ScoreBreakdown,
+    ScoreError, ScoringSpec,
 };
-pub use scc::{build_internal_graph, scc_ids_and_topo_levels};
-pub use scoring::{score, ScoringSpec};
+pub use selection::Selection;
 pub use tasks::{
-    t00_wire_echo, t01_xor_2, t02_sr_latch, t03_pulse_counter, t04_cross_chunk_relay, EpisodeSpec,
-    Io, IoMap, Task,
+    adder, builtin_tasks, counter, parity, t00_wire_echo, t01_xor_2, t02_sr_latch,
+    t03_pulse_counter, t04_cross_chunk_relay, t05_shift_register, t06_sequence_detector,
+    t07_memory_cell, t08_gated_d_latch, task_by_name, write_builtin_tasks, xor_n, EpisodeSpec, Io,
+    IoMap, Task, TaskError, TaskGenerator, TaskRegistry,
+};
+
+pub use truth_table::{
+    truth_table, truth_table_with_limit, TruthTable, TruthTableError, DEFAULT_MAX_INPUTS,
 };
 
 #[cfg(all(target_arch = "wasm32", feature = "webgpu"))]
 pub use gpu::device::init_device;
+#[cfg(all(not(target_arch = "wasm32"), feature = "gpu-native"))]
+pub use gpu::device::{init_device_native, DeviceError};
+#[cfg(any(
+    all(target_arch = "wasm32", feature = "webgpu"),
+    feature = "gpu-native"
+))]
+pub use gpu::eval::GpuEvaluator;
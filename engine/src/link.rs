@@ -1,8 +1,13 @@
-use crate::chunk::{Action, MycosChunk, Trigger};
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::{Action, Connection, Limit, MycosChunk, ParseLimits, Section, Trigger};
 use crate::csr::{Effect, CSR};
+use crate::genome::ChunkGene;
 use crate::layout::bit_to_word;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Link {
     pub from_chunk: u32,
     pub from_out_idx: u32,
@@ -11,6 +16,23 @@ pub struct Link {
     pub to_chunk: u32,
     pub to_in_idx: u32,
     pub order_tag: u32,
+    /// Ticks of latency: with `delay > 0` the effect lands on the target
+    /// input at the start of the tick `delay` ticks after the source edge
+    /// instead of within the same settle.
+    #[serde(default)]
+    pub delay: u16,
+}
+
+impl Link {
+    /// Parse a link from JSON. Chunk and bit indices are checked later by
+    /// [`validate_links`].
+    pub fn from_json(json: &str) -> Result<Self, LinkError> {
+        serde_json::from_str(json).map_err(LinkError::Json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("link serializes to JSON")
+    }
 }
 
 #[derive(Debug)]
@@ -22,6 +44,11 @@ pub enum LinkError {
     ToChunkOutOfRange(u32),
     FromOutIndexOutOfRange { chunk: u32, index: u32 },
     ToInIndexOutOfRange { chunk: u32, index: u32 },
+    UnknownChunk(String),
+    AmbiguousChunk(String),
+    UnknownPort { chunk: String, port: String },
+    LimitExceeded { limit: Limit, value: u64, max: u64 },
+    Json(serde_json::Error),
 }
 
 impl std::fmt::Display for LinkError {
@@ -38,17 +65,45 @@ impl std::fmt::Display for LinkError {
             LinkError::ToInIndexOutOfRange { chunk, index } => {
                 write!(f, "to chunk {chunk} input index {index} out of range")
             }
+            LinkError::UnknownChunk(name) => write!(f, "no chunk named {name:?}"),
+            LinkError::AmbiguousChunk(name) => write!(f, "several chunks named {name:?}"),
+            LinkError::UnknownPort { chunk, port } => {
+                write!(f, "chunk {chunk:?} has no such port {port:?}")
+            }
+            LinkError::LimitExceeded { limit, value, max } => {
+                write!(f, "{value} {limit} exceeds the limit of {max}")
+            }
+            LinkError::Json(e) => write!(f, "invalid JSON link: {e}"),
         }
     }
 }
 
 impl std::error::Error for LinkError {}
 
+/// Size in bytes of a single packed link record.
+pub const LINK_BYTES: usize = 24;
+
 pub fn parse_links(data: &[u8]) -> Result<Vec<Link>, LinkError> {
-    const LINK_BYTES: usize = 24;
-    if data.len() % LINK_BYTES != 0 {
+    parse_links_with_limits(data, &ParseLimits::default())
+}
+
+/// [`parse_links`] with explicit size limits.
+pub fn parse_links_with_limits(data: &[u8], limits: &ParseLimits) -> Result<Vec<Link>, LinkError> {
+    let check = |limit, value: usize, max: usize| {
+        if value > max {
+            return Err(LinkError::LimitExceeded {
+                limit,
+                value: value as u64,
+                max: max as u64,
+            });
+        }
+        Ok(())
+    };
+    check(Limit::Bytes, data.len(), limits.max_bytes)?;
+    if !data.len().is_multiple_of(LINK_BYTES) {
         return Err(LinkError::UnexpectedEof);
     }
+    check(Limit::Links, data.len() / LINK_BYTES, limits.max_links)?;
     let mut links = Vec::with_capacity(data.len() / LINK_BYTES);
     for chunk in data.chunks_exact(LINK_BYTES) {
         let from_chunk = u32::from_le_bytes(chunk[0..4].try_into().unwrap());
@@ -56,7 +111,7 @@ pub fn parse_links(data: &[u8]) -> Result<Vec<Link>, LinkError> {
         let trigger =
             Trigger::try_from(chunk[8]).map_err(|_| LinkError::InvalidTrigger(chunk[8]))?;
         let action = Action::try_from(chunk[9]).map_err(|_| LinkError::InvalidAction(chunk[9]))?;
-        // chunk[10..12] reserved
+        let delay = u16::from_le_bytes(chunk[10..12].try_into().unwrap());
         let to_chunk = u32::from_le_bytes(chunk[12..16].try_into().unwrap());
         let to_in_idx = u32::from_le_bytes(chunk[16..20].try_into().unwrap());
         let order_tag = u32::from_le_bytes(chunk[20..24].try_into().unwrap());
@@ -68,12 +123,108 @@ pub fn parse_links(data: &[u8]) -> Result<Vec<Link>, LinkError> {
             to_chunk,
             to_in_idx,
             order_tag,
+            delay,
         });
     }
     Ok(links)
 }
 
-pub fn validate_links(links: &[Link], chunks: &[MycosChunk]) -> Result<(), LinkError> {
+/// Encode links into the packed little-endian table read by [`parse_links`].
+pub fn encode_links(links: &[Link]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(links.len() * LINK_BYTES);
+    for link in links {
+        out.extend_from_slice(&link.from_chunk.to_le_bytes());
+        out.extend_from_slice(&link.from_out_idx.to_le_bytes());
+        out.push(link.trigger as u8);
+        out.push(link.action as u8);
+        out.extend_from_slice(&link.delay.to_le_bytes());
+        out.extend_from_slice(&link.to_chunk.to_le_bytes());
+        out.extend_from_slice(&link.to_in_idx.to_le_bytes());
+        out.extend_from_slice(&link.order_tag.to_le_bytes());
+    }
+    out
+}
+
+/// Build a link from `from = (chunk_name, output_port)` to
+/// `to = (chunk_name, input_port)`, looking chunks up by their TLV name and
+/// bits by their [`Port`](crate::chunk::Port) names. The link has no delay.
+pub fn link_by_name(
+    chunks: &[MycosChunk],
+    from: (&str, &str),
+    to: (&str, &str),
+    trigger: Trigger,
+    action: Action,
+    order_tag: u32,
+) -> Result<Link, LinkError> {
+    let (from_chunk, from_out_idx) = resolve_port(chunks, from, Section::Output)?;
+    let (to_chunk, to_in_idx) = resolve_port(chunks, to, Section::Input)?;
+    Ok(Link {
+        from_chunk,
+        from_out_idx,
+        trigger,
+        action,
+        to_chunk,
+        to_in_idx,
+        order_tag,
+        delay: 0,
+    })
+}
+
+/// Chunk index and bit index of a named port in `section`.
+pub fn resolve_port(
+    chunks: &[MycosChunk],
+    (chunk, port): (&str, &str),
+    section: Section,
+) -> Result<(u32, u32), LinkError> {
+    let mut named = chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.name.as_deref() == Some(chunk));
+    let (index, found) = named
+        .next()
+        .ok_or_else(|| LinkError::UnknownChunk(chunk.to_string()))?;
+    if named.next().is_some() {
+        return Err(LinkError::AmbiguousChunk(chunk.to_string()));
+    }
+    let bit = found
+        .port(section, port)
+        .ok_or_else(|| LinkError::UnknownPort {
+            chunk: chunk.to_string(),
+            port: port.to_string(),
+        })?;
+    Ok((index as u32, bit))
+}
+
+/// Input and Output counts of a chunk, whatever its representation, so
+/// links can be validated against parsed chunks and genome genes alike.
+pub trait ChunkIo {
+    fn input_count(&self) -> u32;
+    fn output_count(&self) -> u32;
+}
+
+impl ChunkIo for MycosChunk {
+    fn input_count(&self) -> u32 {
+        self.input_count
+    }
+
+    fn output_count(&self) -> u32 {
+        self.output_count
+    }
+}
+
+impl ChunkIo for ChunkGene {
+    fn input_count(&self) -> u32 {
+        self.ni
+    }
+
+    fn output_count(&self) -> u32 {
+        self.no
+    }
+}
+
+/// Check every link against the chunks' I/O counts. `chunks` may be parsed
+/// [`MycosChunk`]s or a genome's [`ChunkGene`]s.
+pub fn validate_links<C: ChunkIo>(links: &[Link], chunks: &[C]) -> Result<(), LinkError> {
     for link in links {
         let from_chunk = chunks
             .get(link.from_chunk as usize)
@@ -81,13 +232,13 @@ pub fn validate_links(links: &[Link], chunks: &[MycosChunk]) -> Result<(), LinkE
         let to_chunk = chunks
             .get(link.to_chunk as usize)
             .ok_or(LinkError::ToChunkOutOfRange(link.to_chunk))?;
-        if link.from_out_idx >= from_chunk.output_count {
+        if link.from_out_idx >= from_chunk.output_count() {
             return Err(LinkError::FromOutIndexOutOfRange {
                 chunk: link.from_chunk,
                 index: link.from_out_idx,
             });
         }
-        if link.to_in_idx >= to_chunk.input_count {
+        if link.to_in_idx >= to_chunk.input_count() {
             return Err(LinkError::ToInIndexOutOfRange {
                 chunk: link.to_chunk,
                 index: link.to_in_idx,
@@ -127,6 +278,83 @@ pub fn compute_base_offsets(chunks: &[MycosChunk]) -> Vec<ChunkOffsets> {
     offs
 }
 
+impl ChunkOffsets {
+    /// Offset of `section`.
+    pub fn of(&self, section: Section) -> u32 {
+        match section {
+            Section::Input => self.input,
+            Section::Output => self.output,
+            Section::Internal => self.internal,
+        }
+    }
+}
+
+/// Per-section global bit indices of a system, laid out by
+/// [`compute_base_offsets`]: each section's buffer holds that section of
+/// every chunk in chunk order, so `(chunk, section, local)` maps to the
+/// chunk's base offset plus `local`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalBitMap {
+    offsets: Vec<ChunkOffsets>,
+    /// Bits per section over all chunks.
+    totals: ChunkOffsets,
+}
+
+impl GlobalBitMap {
+    pub fn new(chunks: &[MycosChunk]) -> Self {
+        Self {
+            offsets: compute_base_offsets(chunks),
+            totals: ChunkOffsets {
+                input: chunks.iter().map(|c| c.input_count).sum(),
+                output: chunks.iter().map(|c| c.output_count).sum(),
+                internal: chunks.iter().map(|c| c.internal_count).sum(),
+            },
+        }
+    }
+
+    /// Number of chunks mapped.
+    pub fn chunks(&self) -> u32 {
+        self.offsets.len() as u32
+    }
+
+    pub fn offsets(&self) -> &[ChunkOffsets] {
+        &self.offsets
+    }
+
+    /// Bits of `section` over all chunks, i.e. the length of its buffer.
+    pub fn total(&self, section: Section) -> u32 {
+        self.totals.of(section)
+    }
+
+    /// Global range of `chunk`'s `section`; `None` past the last chunk.
+    pub fn extent(&self, chunk: u32, section: Section) -> Option<Range<u32>> {
+        let start = self.offsets.get(chunk as usize)?.of(section);
+        let end = self
+            .offsets
+            .get(chunk as usize + 1)
+            .map_or(self.totals.of(section), |o| o.of(section));
+        Some(start..end)
+    }
+
+    /// Global index of bit `local` of `chunk`'s `section`; `None` when the
+    /// chunk or bit does not exist.
+    pub fn global(&self, chunk: u32, section: Section, local: u32) -> Option<u32> {
+        let extent = self.extent(chunk, section)?;
+        (local < extent.len() as u32).then_some(extent.start + local)
+    }
+
+    /// Chunk and local index of global bit `global` of `section`, the inverse
+    /// of [`global`](Self::global); `None` past the end of the buffer.
+    pub fn locate(&self, section: Section, global: u32) -> Option<(u32, u32)> {
+        if global >= self.totals.of(section) {
+            return None;
+        }
+        // the last chunk starting at or before `global` has bits there
+        let chunk = self.offsets.partition_point(|o| o.of(section) <= global) - 1;
+        Some((chunk as u32, global - self.offsets[chunk].of(section)))
+    }
+}
+
 /// Build a CSR adjacency for inter-chunk links using global bit ids.
 ///
 /// Sources are chunk **outputs**; targets are **inputs** of other chunks.
@@ -235,9 +463,316 @@ pub fn build_link_csr(links: &[Link], chunks: &[MycosChunk]) -> CSR {
     }
 }
 
+/// Global bit ids shared by every chunk of a system: all Inputs first, then
+/// all Internals, then all Outputs, each section in chunk order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalBits {
+    map: GlobalBitMap,
+    inputs: u32,
+    internals: u32,
+}
+
+impl GlobalBits {
+    pub fn new(chunks: &[MycosChunk]) -> Self {
+        let map = GlobalBitMap::new(chunks);
+        Self {
+            inputs: map.total(Section::Input),
+            internals: map.total(Section::Internal),
+            map,
+        }
+    }
+
+    /// Total bits over all sections and chunks.
+    pub fn len(&self) -> u32 {
+        self.inputs + self.internals + self.map.total(Section::Output)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Global id of bit `index` in `section` of `chunk`.
+    pub fn id(&self, chunk: u32, section: Section, index: u32) -> u32 {
+        let base = match section {
+            Section::Input => 0,
+            Section::Internal => self.inputs,
+            Section::Output => self.inputs + self.internals,
+        };
+        base + self.map.offsets[chunk as usize].of(section) + index
+    }
+
+    /// Chunk, section and local index of a global id, the inverse of
+    /// [`id`](Self::id). Returns `None` past the last bit.
+    pub fn locate(&self, id: u32) -> Option<(u32, Section, u32)> {
+        let (section, local) = if id < self.inputs {
+            (Section::Input, id)
+        } else if id < self.inputs + self.internals {
+            (Section::Internal, id - self.inputs)
+        } else if id < self.len() {
+            (Section::Output, id - self.inputs - self.internals)
+        } else {
+            return None;
+        };
+        let (chunk, index) = self.map.locate(section, local)?;
+        Some((chunk, section, index))
+    }
+}
+
+/// Build one CSR over [`GlobalBits`] ids covering both the connections of
+/// every chunk and the inter-chunk links.
+///
+/// Every bit is a source row, so Output rows hold link effects and Input and
+/// Internal rows hold connection effects. `to_bit` and `to_word` are global;
+/// `to_is_internal` marks Internal targets. Within a row effects are ordered
+/// by `(to_word, order_tag)` like [`build_csr`](crate::build_csr), and the
+/// result expands exactly like stitching the per-chunk CSRs and
+/// [`build_link_csr`] by hand.
+pub fn build_global_csr(chunks: &[MycosChunk], links: &[Link]) -> CSR {
+    build_global_csr_gated(chunks, links, &[]).0
+}
+
+/// Connections `connections` (table indices) of chunk `chunk` that only
+/// fire while bit `gate` of the same chunk is set, like the child
+/// connections of an alias-mode embed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionGate {
+    pub chunk: u32,
+    pub gate: (Section, u32),
+    pub connections: Range<usize>,
+}
+
+/// [`build_global_csr`] plus the gate of every effect, in effect order: 0
+/// for effects that always fire, otherwise 1 + the [`GlobalBits`] id of the
+/// gate bit from the first of `gates` covering the connection.
+pub(crate) fn build_global_csr_gated(
+    chunks: &[MycosChunk],
+    links: &[Link],
+    gates: &[ConnectionGate],
+) -> (CSR, Vec<u32>) {
+    let bits = GlobalBits::new(chunks);
+    let total = bits.len() as usize;
+    let effect = |to_section: Section, to: u32, action: Action, order_tag: u32| {
+        let (to_word, mask) = bit_to_word(to);
+        Effect {
+            to_word,
+            mask,
+            action,
+            order_tag,
+            to_is_internal: to_section == Section::Internal,
+            to_bit: to,
+        }
+    };
+
+    let conns: usize = chunks.iter().map(|c| c.connections.len()).sum();
+    let mut entries: Vec<(Trigger, u32, Effect, u32)> = Vec::with_capacity(conns + links.len());
+    for (c, chunk) in chunks.iter().enumerate() {
+        let c = c as u32;
+        for (i, conn) in chunk.connections.iter().enumerate() {
+            let to = bits.id(c, conn.to_section, conn.to_index);
+            let gate = gates
+                .iter()
+                .find(|g| g.chunk == c && g.connections.contains(&i))
+                .map_or(0, |g| 1 + bits.id(c, g.gate.0, g.gate.1));
+            entries.push((
+                conn.trigger,
+                bits.id(c, conn.from_section, conn.from_index),
+                effect(conn.to_section, to, conn.action, conn.order_tag),
+                gate,
+            ));
+        }
+    }
+    for link in links {
+        let to = bits.id(link.to_chunk, Section::Input, link.to_in_idx);
+        entries.push((
+            link.trigger,
+            bits.id(link.from_chunk, Section::Output, link.from_out_idx),
+            effect(Section::Input, to, link.action, link.order_tag),
+            0,
+        ));
+    }
+    // stable, so equal keys keep their table order
+    entries.sort_by_key(|(trigger, from, e, _)| (*trigger as u8, *from, e.to_word, e.order_tag));
+
+    let mut offs = [
+        vec![0u32; total + 1],
+        vec![0u32; total + 1],
+        vec![0u32; total + 1],
+    ];
+    for (trigger, from, _, _) in &entries {
+        offs[*trigger as usize][*from as usize + 1] += 1;
+    }
+    let mut base = 0;
+    for o in &mut offs {
+        for i in 0..total {
+            o[i + 1] += o[i];
+        }
+        let count = o[total];
+        for v in o.iter_mut() {
+            *v += base;
+        }
+        base += count;
+    }
+    let [offs_on, offs_off, offs_tog] = offs;
+
+    let (effects, gates) = entries.into_iter().map(|(_, _, e, g)| (e, g)).unzip();
+    let csr = CSR {
+        offs_on,
+        offs_off,
+        offs_tog,
+        effects,
+    };
+    (csr, gates)
+}
+
+/// Flatten a linked system into one chunk.
+///
+/// Every section is the concatenation of the chunks' sections at their
+/// [`compute_base_offsets`], so each Input and Output keeps its global index.
+/// Connections cannot read Outputs or write Inputs, so every linked Output
+/// and linked Input gets a shadow Internal appended after the chunks'
+/// Internals: writes to the Output land on its shadow, which an `On`/`Off`
+/// pair copies to the Output, and reads of the Input come from its shadow,
+/// which the same pair copies from the Input. Each link becomes a connection
+/// between the two shadows, its order tag shifted past the copy pair.
+///
+/// `links` must pass [`validate_links`]. Delays are dropped and every copy
+/// costs an extra round. Names, notes, ports and the build hash are cleared.
+pub fn merge_chunks(chunks: &[MycosChunk], links: &[Link]) -> MycosChunk {
+    let map = GlobalBitMap::new(chunks);
+    let global = |c: u32, section: Section, index: u32| map.offsets[c as usize].of(section) + index;
+    let bit = |bytes: &[u8], i: u32| bytes[(i / 8) as usize] >> (i % 8) & 1 == 1;
+    let mut shadow_out: Vec<u32> = links
+        .iter()
+        .map(|l| global(l.from_chunk, Section::Output, l.from_out_idx))
+        .collect();
+    let mut shadow_in: Vec<u32> = links
+        .iter()
+        .map(|l| global(l.to_chunk, Section::Input, l.to_in_idx))
+        .collect();
+    for shadows in [&mut shadow_out, &mut shadow_in] {
+        shadows.sort_unstable();
+        shadows.dedup();
+    }
+    let internal_count = map.total(Section::Internal);
+    let out_at = |o: u32| {
+        shadow_out
+            .binary_search(&o)
+            .ok()
+            .map(|i| internal_count + i as u32)
+    };
+    let in_at = |i: u32| {
+        let base = internal_count + shadow_out.len() as u32;
+        shadow_in.binary_search(&i).ok().map(|k| base + k as u32)
+    };
+
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut internals = Vec::new();
+    let mut connections = Vec::new();
+    for (c, chunk) in chunks.iter().enumerate() {
+        let c = c as u32;
+        inputs.extend((0..chunk.input_count).map(|i| bit(&chunk.input_bits, i)));
+        outputs.extend((0..chunk.output_count).map(|i| bit(&chunk.output_bits, i)));
+        internals.extend((0..chunk.internal_count).map(|i| bit(&chunk.internal_bits, i)));
+        for conn in &chunk.connections {
+            let from = global(c, conn.from_section, conn.from_index);
+            let (from_section, from_index) = match conn.from_section {
+                Section::Input => {
+                    in_at(from).map_or((Section::Input, from), |s| (Section::Internal, s))
+                }
+                section => (section, from),
+            };
+            let to = global(c, conn.to_section, conn.to_index);
+            let (to_section, to_index) = match conn.to_section {
+                Section::Output => {
+                    out_at(to).map_or((Section::Output, to), |s| (Section::Internal, s))
+                }
+                section => (section, to),
+            };
+            connections.push(Connection {
+                from_section,
+                to_section,
+                from_index,
+                to_index,
+                ..conn.clone()
+            });
+        }
+    }
+    let copy = |from_section, from_index, to_section, to_index| {
+        [
+            (Trigger::On, Action::Enable),
+            (Trigger::Off, Action::Disable),
+        ]
+        .into_iter()
+        .map(move |(trigger, action)| Connection {
+            from_section,
+            to_section,
+            trigger,
+            action,
+            from_index,
+            to_index,
+            order_tag: trigger as u32,
+        })
+    };
+    for &o in &shadow_out {
+        internals.push(outputs[o as usize]);
+        connections.extend(copy(
+            Section::Internal,
+            out_at(o).unwrap(),
+            Section::Output,
+            o,
+        ));
+    }
+    for &i in &shadow_in {
+        internals.push(inputs[i as usize]);
+        connections.extend(copy(
+            Section::Input,
+            i,
+            Section::Internal,
+            in_at(i).unwrap(),
+        ));
+    }
+    for link in links {
+        let from = global(link.from_chunk, Section::Output, link.from_out_idx);
+        let to = global(link.to_chunk, Section::Input, link.to_in_idx);
+        connections.push(Connection {
+            from_section: Section::Internal,
+            to_section: Section::Internal,
+            trigger: link.trigger,
+            action: link.action,
+            from_index: out_at(from).unwrap(),
+            to_index: in_at(to).unwrap(),
+            order_tag: link.order_tag + 2,
+        });
+    }
+
+    let pack = |bits: &[bool]| {
+        let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+        for (i, _) in bits.iter().enumerate().filter(|(_, &b)| b) {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+        bytes
+    };
+    MycosChunk {
+        input_bits: pack(&inputs),
+        output_bits: pack(&outputs),
+        internal_bits: pack(&internals),
+        input_count: inputs.len() as u32,
+        output_count: outputs.len() as u32,
+        internal_count: internals.len() as u32,
+        connections,
+        name: None,
+        note: None,
+        build_hash: None,
+        ports: Vec::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::builder::fixtures::{relay, wire};
+    use crate::builder::ChunkBuilder;
     use crate::chunk::{parse_chunk, validate_chunk};
     use std::path::PathBuf;
 
@@ -272,6 +807,31 @@ mod tests {
         validate_links(&links, &chunks).unwrap();
     }
 
+    #[test]
+    fn encode_round_trip() {
+        let links = parse_links(&LINKS_BASIC).unwrap();
+        assert_eq!(encode_links(&links), LINKS_BASIC.to_vec());
+
+        let tight = ParseLimits {
+            max_links: 0,
+            ..ParseLimits::default()
+        };
+        assert!(matches!(
+            parse_links_with_limits(&LINKS_BASIC, &tight),
+            Err(LinkError::LimitExceeded {
+                limit: Limit::Links,
+                value: 1,
+                max: 0
+            })
+        ));
+
+        assert_eq!(Link::from_json(&links[0].to_json()).unwrap(), links[0]);
+        let authored = r#"{"from_chunk": 0, "from_out_idx": 0, "trigger": "On",
+            "action": "Enable", "to_chunk": 1, "to_in_idx": 0, "order_tag": 0}"#;
+        assert_eq!(Link::from_json(authored).unwrap().delay, 0);
+        assert!(matches!(Link::from_json("{}"), Err(LinkError::Json(_))));
+    }
+
     #[test]
     fn invalid_to_in_index() {
         let mut data = LINKS_BASIC.to_vec();
@@ -292,6 +852,14 @@ mod tests {
             validate_links(&links, &chunks),
             Err(LinkError::ToInIndexOutOfRange { .. })
         ));
+
+        // the same check runs against genome genes without encoding them
+        let genes: Vec<ChunkGene> = chunks.iter().map(ChunkGene::from_chunk).collect();
+        assert!(matches!(
+            validate_links(&links, &genes),
+            Err(LinkError::ToInIndexOutOfRange { .. })
+        ));
+        validate_links(&parse_links(&LINKS_BASIC).unwrap(), &genes).unwrap();
     }
 
     #[test]
@@ -334,5 +902,181 @@ mod tests {
         // from_chunk (0) output index 0 -> global output bit 0
         assert_eq!(csr.offs_on[0], 0);
         assert_eq!(csr.offs_on[1], 1);
+
+        // a chunk without inputs owns no input bits
+        let sized = |n| ChunkBuilder::new().inputs(n).outputs(1).build().unwrap();
+        let map = GlobalBitMap::new(&[sized(2), sized(0), sized(3)]);
+        assert_eq!(map.extent(1, Section::Input), Some(2..2));
+        assert_eq!(map.extent(2, Section::Input), Some(2..5));
+        assert_eq!(map.extent(3, Section::Input), None);
+        assert_eq!(map.total(Section::Output), 3);
+        assert_eq!(map.global(2, Section::Input, 1), Some(3));
+        assert_eq!(map.global(1, Section::Input, 0), None);
+        assert_eq!(map.locate(Section::Input, 2), Some((2, 0)));
+        assert_eq!(map.locate(Section::Output, 1), Some((1, 0)));
+        assert_eq!(map.locate(Section::Internal, 0), None);
+        for c in 0..map.chunks() {
+            for section in [Section::Input, Section::Internal, Section::Output] {
+                for global in map.extent(c, section).unwrap() {
+                    let (chunk, local) = map.locate(section, global).unwrap();
+                    assert_eq!(
+                        (chunk, map.global(chunk, section, local)),
+                        (c, Some(global))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn links_resolve_by_port_name() {
+        let named = |name: &str, fixture: &str| {
+            let mut chunk = parse_chunk(&std::fs::read(fixtures().join(fixture)).unwrap()).unwrap();
+            chunk.name = Some(name.to_string());
+            chunk.ports = vec![
+                crate::chunk::Port {
+                    section: Section::Input,
+                    index: 0,
+                    name: "in".to_string(),
+                },
+                crate::chunk::Port {
+                    section: Section::Output,
+                    index: 0,
+                    name: "out".to_string(),
+                },
+            ];
+            chunk
+        };
+        let chunks = vec![named("a", "tiny_toggle.myc"), named("b", "noop.myc")];
+        let link = link_by_name(
+            &chunks,
+            ("a", "out"),
+            ("b", "in"),
+            Trigger::On,
+            Action::Enable,
+            0,
+        );
+        assert_eq!(link.unwrap(), parse_links(&LINKS_BASIC).unwrap()[0]);
+
+        let err = |from, to| link_by_name(&chunks, from, to, Trigger::On, Action::Enable, 0);
+        assert!(matches!(
+            err(("c", "out"), ("b", "in")),
+            Err(LinkError::UnknownChunk(_))
+        ));
+        // "in" is an input, so it cannot be a link source
+        assert!(matches!(
+            err(("a", "in"), ("b", "in")),
+            Err(LinkError::UnknownPort { .. })
+        ));
+        let twins = vec![named("a", "noop.myc"), named("a", "noop.myc")];
+        assert!(matches!(
+            resolve_port(&twins, ("a", "in"), Section::Input),
+            Err(LinkError::AmbiguousChunk(_))
+        ));
+    }
+
+    #[test]
+    fn global_csr_matches_stitched_tables() {
+        let chunk_a = parse_chunk(&std::fs::read(fixtures().join("tiny_toggle.myc")).unwrap());
+        let chunk_b = parse_chunk(&std::fs::read(fixtures().join("fanout_1_to_1024.myc")).unwrap());
+        let chunks = vec![chunk_a.unwrap(), chunk_b.unwrap()];
+        let mut links = parse_links(&LINKS_BASIC).unwrap();
+        links.push(Link {
+            trigger: Trigger::Toggle,
+            to_chunk: 0,
+            ..links[0].clone()
+        });
+        validate_links(&links, &chunks).unwrap();
+
+        let bits = GlobalBits::new(&chunks);
+        let global = build_global_csr(&chunks, &links);
+        let conns: usize = chunks.iter().map(|c| c.connections.len()).sum();
+        assert_eq!(global.effects.len(), conns + links.len());
+        assert_eq!(global.offs_on.len(), bits.len() as usize + 1);
+
+        let row = |csr: &CSR, src: usize| -> Vec<Effect> {
+            [&csr.offs_on, &csr.offs_off, &csr.offs_tog]
+                .iter()
+                .flat_map(|offs| csr.effects[offs[src] as usize..offs[src + 1] as usize].to_vec())
+                .collect()
+        };
+        // every per-chunk source row reappears with global targets
+        for (c, chunk) in chunks.iter().enumerate() {
+            let local = crate::csr::build_csr(chunk);
+            for src in 0..(chunk.input_count + chunk.internal_count) {
+                let (section, index) = if src < chunk.input_count {
+                    (Section::Input, src)
+                } else {
+                    (Section::Internal, src - chunk.input_count)
+                };
+                let expected: Vec<u32> = row(&local, src as usize)
+                    .iter()
+                    .map(|e| {
+                        let to = if e.to_is_internal {
+                            Section::Internal
+                        } else {
+                            Section::Output
+                        };
+                        bits.id(c as u32, to, e.to_bit)
+                    })
+                    .collect();
+                let id = bits.id(c as u32, section, index) as usize;
+                let got: Vec<u32> = row(&global, id).iter().map(|e| e.to_bit).collect();
+                assert_eq!(got, expected);
+            }
+        }
+        // the link rows translate build_link_csr's input ids
+        let link_csr = build_link_csr(&links, &chunks);
+        let from = bits.id(0, Section::Output, 0) as usize;
+        let got: Vec<_> = row(&global, from)
+            .iter()
+            .map(|e| bits.locate(e.to_bit).unwrap())
+            .collect();
+        assert_eq!(got, vec![(1, Section::Input, 0), (0, Section::Input, 0)]);
+        assert_eq!(row(&link_csr, 0).len(), 2);
+        assert_eq!(bits.locate(bits.len()), None);
+
+        // gated connections carry 1 + the gate bit's global id
+        let gate = ConnectionGate {
+            chunk: 1,
+            gate: (Section::Internal, 0),
+            connections: 0..1,
+        };
+        let (gated, gates) = build_global_csr_gated(&chunks, &links, &[gate]);
+        assert_eq!(gated.effects, global.effects);
+        let gate_id = bits.id(1, Section::Internal, 0) + 1;
+        assert_eq!(gates.iter().filter(|&&g| g == gate_id).count(), 1);
+        assert_eq!(gates.iter().filter(|&&g| g == 0).count(), gates.len() - 1);
+    }
+
+    #[test]
+    fn merged_relay_matches_system() {
+        let chunks = [1, 0].map(|input| MycosChunk {
+            input_bits: vec![input],
+            name: Some("wire".into()),
+            ..wire()
+        });
+        let links = vec![
+            relay(Trigger::On, Action::Enable, 0),
+            relay(Trigger::Off, Action::Disable, 1),
+        ];
+
+        let merged = merge_chunks(&chunks, &links);
+        validate_chunk(&merged).unwrap();
+        // two chunk internals, then one shadow each for the linked bits
+        assert_eq!(
+            (
+                merged.input_count,
+                merged.output_count,
+                merged.internal_count
+            ),
+            (2, 2, 4)
+        );
+        assert_eq!(merged.name, None);
+        let (_, outputs, _) = crate::cpu_ref::execute(&merged);
+        assert_eq!(outputs, vec![0b11]);
+        let system = crate::cpu_ref::execute_system(&chunks, &links);
+        assert_eq!((system[0].1[0], system[1].1[0], system[1].0[0]), (1, 1, 1));
+        assert_eq!(merge_chunks(&chunks, &[]).internal_count, 2);
     }
 }
@@ -0,0 +1,305 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    crossover, evaluate_batch, gpu_eval::Episode, innovation::InnovationTracker, mutate_with,
+    FitnessResult, Genome, MutationConfig, MutationRegistry, Task, ValidationError,
+};
+
+/// Genome or behaviour property used as a MAP-Elites feature axis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Feature {
+    /// Total connection genes over all chunks.
+    Connections,
+    /// Number of chunks.
+    Chunks,
+    /// Number of inter-chunk links.
+    Links,
+    /// Mean wavefront rounds per episode until quiescence.
+    Rounds,
+}
+
+impl Feature {
+    pub fn value(self, genome: &Genome, result: &FitnessResult) -> f32 {
+        match self {
+            Feature::Connections => {
+                genome.chunks.iter().map(|c| c.conns.len()).sum::<usize>() as f32
+            }
+            Feature::Chunks => genome.chunks.len() as f32,
+            Feature::Links => genome.links.len() as f32,
+            Feature::Rounds => {
+                let n = result.metrics.len().max(1) as f32;
+                result.metrics.iter().map(|m| m.rounds as f32).sum::<f32>() / n
+            }
+        }
+    }
+}
+
+/// One dimension of the grid: `bins` equal-width bins over `[min, max)`.
+/// Values outside the range fall into the first or last bin.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeatureAxis {
+    pub feature: Feature,
+    pub min: f32,
+    pub max: f32,
+    pub bins: usize,
+}
+
+impl FeatureAxis {
+    pub fn bin(&self, value: f32) -> usize {
+        let last = self.bins.saturating_sub(1);
+        if self.max <= self.min || value <= self.min {
+            return 0;
+        }
+        let t = (value - self.min) / (self.max - self.min);
+        ((t * self.bins as f32) as usize).min(last)
+    }
+}
+
+/// Best genome found for one grid cell.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Elite {
+    pub genome: Genome,
+    pub fitness: f32,
+    /// Raw feature values, one per axis.
+    pub features: Vec<f32>,
+}
+
+/// MAP-Elites grid keeping the fittest genome per cell.
+///
+/// Cells are stored row-major with the last axis varying fastest.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EliteArchive {
+    axes: Vec<FeatureAxis>,
+    cells: Vec<Option<Elite>>,
+}
+
+impl EliteArchive {
+    pub fn new(axes: Vec<FeatureAxis>) -> Self {
+        let len = axes.iter().map(|a| a.bins.max(1)).product();
+        Self {
+            axes,
+            cells: vec![None; len],
+        }
+    }
+
+    pub fn axes(&self) -> &[FeatureAxis] {
+        &self.axes
+    }
+
+    /// All cells in row-major order.
+    pub fn cells(&self) -> &[Option<Elite>] {
+        &self.cells
+    }
+
+    /// The elite at a bin coordinate, one index per axis.
+    pub fn get(&self, bins: &[usize]) -> Option<&Elite> {
+        self.index(bins).and_then(|i| self.cells[i].as_ref())
+    }
+
+    /// Occupied cells in row-major order.
+    pub fn elites(&self) -> impl Iterator<Item = &Elite> {
+        self.cells.iter().flatten()
+    }
+
+    /// Fraction of occupied cells.
+    pub fn coverage(&self) -> f32 {
+        self.elites().count() as f32 / self.cells.len() as f32
+    }
+
+    /// Bin coordinate of a feature vector.
+    pub fn bins_of(&self, features: &[f32]) -> Vec<usize> {
+        self.axes
+            .iter()
+            .zip(features)
+            .map(|(axis, &v)| axis.bin(v))
+            .collect()
+    }
+
+    /// Place a genome in its cell if the cell is empty or it beats the
+    /// current elite, returning whether it was kept.
+    pub fn insert(&mut self, genome: &Genome, fitness: f32, features: Vec<f32>) -> bool {
+        let Some(i) = self.index(&self.bins_of(&features)) else {
+            return false;
+        };
+        if self.cells[i].as_ref().is_some_and(|e| e.fitness >= fitness) {
+            return false;
+        }
+        self.cells[i] = Some(Elite {
+            genome: genome.clone(),
+            fitness,
+            features,
+        });
+        true
+    }
+
+    fn index(&self, bins: &[usize]) -> Option<usize> {
+        if bins.len() != self.axes.len() {
+            return None;
+        }
+        let mut index = 0;
+        for (axis, &b) in self.axes.iter().zip(bins) {
+            let n = axis.bins.max(1);
+            if b >= n {
+                return None;
+            }
+            index = index * n + b;
+        }
+        Some(index)
+    }
+}
+
+/// Configuration for [`run_map_elites`].
+pub struct MapElitesConfig {
+    pub task: Task,
+    /// Template for the initial random batch.
+    pub base_genome: Genome,
+    /// Feature axes spanning the grid.
+    pub axes: Vec<FeatureAxis>,
+    /// Mutated copies of `base_genome` evaluated before the main loop.
+    pub initial: usize,
+    /// Offspring evaluated per iteration.
+    pub batch_size: usize,
+    /// Number of batches after initialisation.
+    pub iterations: u32,
+    /// Probability of crossing the chosen elite with a second random elite.
+    pub crossover_rate: f32,
+    pub mutation: MutationConfig,
+    pub operators: MutationRegistry,
+    pub seed: u64,
+}
+
+/// Run MAP-Elites: seed the grid with mutated copies of the base genome,
+/// then repeatedly mutate (and optionally cross) uniformly chosen elites and
+/// place the evaluated offspring in their cells.
+///
+/// Returns an error if crossover produces an invalid genome, which means an
+/// invalid genome entered the archive.
+pub fn run_map_elites(config: &MapElitesConfig) -> Result<EliteArchive, ValidationError> {
+    let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+    let mut archive = EliteArchive::new(config.axes.clone());
+    let mut base = config.base_genome.clone();
+    let mut innovations = InnovationTracker::from_genomes(std::slice::from_ref(&base));
+    innovations.assign(&mut base);
+    let episodes = vec![Episode::default(); config.task.episodes.len()];
+
+    let mut batch: Vec<Genome> = (0..config.initial)
+        .map(|_| {
+            let mut g = base.clone();
+            mutate_offspring(&mut g, config, &mut rng, &mut innovations);
+            g
+        })
+        .collect();
+    for iteration in 0..=config.iterations {
+        let results = evaluate_batch(&batch, &config.task, &episodes);
+        for (genome, res) in batch.iter().zip(&results) {
+            let features = config.axes.iter().map(|a| a.feature.value(genome, res));
+            archive.insert(genome, res.fitness, features.collect());
+        }
+        if iteration == config.iterations {
+            break;
+        }
+
+        innovations.next_generation();
+        let parents: Vec<&Genome> = archive.elites().map(|e| &e.genome).collect();
+        if parents.is_empty() {
+            break;
+        }
+        let mut next = Vec::with_capacity(config.batch_size);
+        for _ in 0..config.batch_size {
+            let a = parents[rng.gen_range(0..parents.len())];
+            let mut child = if parents.len() > 1 && rng.gen::<f32>() < config.crossover_rate {
+                let b = parents[rng.gen_range(0..parents.len())];
+                crossover(a, b, &mut rng)?
+            } else {
+                a.clone()
+            };
+            mutate_offspring(&mut child, config, &mut rng, &mut innovations);
+            next.push(child);
+        }
+        batch = next;
+    }
+    Ok(archive)
+}
+
+fn mutate_offspring(
+    genome: &mut Genome,
+    config: &MapElitesConfig,
+    rng: &mut ChaCha8Rng,
+    innovations: &mut InnovationTracker,
+) {
+    let seed = rng.gen();
+    genome.meta.seed = seed;
+    let mut grng = ChaCha8Rng::seed_from_u64(seed);
+    mutate_with(genome, &mut grng, &config.mutation, &config.operators);
+    innovations.assign(genome);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::fixtures::wire_genome;
+    use crate::tasks::t00_wire_echo;
+
+    #[test]
+    fn cells_keep_the_fittest() {
+        let axis = |feature, bins| FeatureAxis {
+            feature,
+            min: 0.0,
+            max: 8.0,
+            bins,
+        };
+        let mut archive = EliteArchive::new(vec![
+            axis(Feature::Connections, 4),
+            axis(Feature::Chunks, 2),
+        ]);
+        assert_eq!(archive.axes()[0].bin(-1.0), 0);
+        assert_eq!(archive.axes()[0].bin(3.0), 1);
+        assert_eq!(archive.axes()[0].bin(100.0), 3);
+        assert_eq!(archive.cells().len(), 8);
+
+        let g = wire_genome();
+        assert!(archive.insert(&g, 0.5, vec![3.0, 1.0]));
+        assert!(!archive.insert(&g, 0.4, vec![2.0, 0.0]));
+        assert!(archive.insert(&g, 0.9, vec![2.5, 0.0]));
+        assert!(archive.insert(&g, 0.1, vec![7.0, 5.0]));
+        assert_eq!(archive.get(&[1, 0]).unwrap().fitness, 0.9);
+        assert_eq!(archive.get(&[1, 0]).unwrap().features, vec![2.5, 0.0]);
+        assert!(archive.get(&[0, 0]).is_none());
+        assert!(archive.get(&[4, 0]).is_none());
+        assert_eq!(archive.coverage(), 2.0 / 8.0);
+    }
+
+    #[test]
+    fn run_fills_the_grid() {
+        let config = MapElitesConfig {
+            task: t00_wire_echo(),
+            base_genome: wire_genome(),
+            axes: vec![FeatureAxis {
+                feature: Feature::Connections,
+                min: 0.0,
+                max: 8.0,
+                bins: 8,
+            }],
+            initial: 8,
+            batch_size: 8,
+            iterations: 5,
+            crossover_rate: 0.3,
+            mutation: MutationConfig::default(),
+            operators: MutationRegistry::new(),
+            seed: 7,
+        };
+        let archive = run_map_elites(&config).unwrap();
+        assert!(archive.elites().count() > 1);
+        for elite in archive.elites() {
+            let conns: usize = elite.genome.chunks.iter().map(|c| c.conns.len()).sum();
+            assert_eq!(elite.features, vec![conns as f32]);
+        }
+        let again = run_map_elites(&config).unwrap();
+        assert_eq!(
+            archive.elites().map(|e| e.fitness).collect::<Vec<_>>(),
+            again.elites().map(|e| e.fitness).collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,87 +1,370 @@
-use crate::genome::{ChunkGene, ConnGene, Genome, LinkGene};
+use crate::genome::{ChunkGene, ConnGene, EmbedGene, Genome, LinkGene};
+use crate::scc::{feedback_arcs, SystemEdge};
+use bitvec::prelude::*;
 use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-// Probabilities per genome per generation
-const P_ADD_CONN: f64 = 0.20;
-const P_REMOVE_CONN: f64 = 0.15;
-const P_REWIRE: f64 = 0.15;
-const P_FLIP_TRIGGER: f64 = 0.05;
-const P_FLIP_ACTION: f64 = 0.05;
-const P_BUMP_ORDER: f64 = 0.05;
-const P_ADD_BIT: f64 = 0.05;
-const P_REMOVE_BIT: f64 = 0.03;
-const P_ADD_LINK: f64 = 0.10;
-const P_REMOVE_LINK: f64 = 0.07;
-const P_INIT_TWEAK: f64 = 0.05;
-const P_GATE_INSERT: f64 = 0.02; // optional
-
-/// Apply mutation operators with their probabilities.
-/// Each mutation retries up to three times if validation fails.
-pub fn mutate(genome: &mut Genome, rng: &mut dyn RngCore) {
-    if rng.gen::<f64>() < P_ADD_CONN {
-        apply_with_retry(genome, rng, add_connection);
+/// Per-operator probabilities, each rolled once per genome per call to
+/// [`mutate`]. Missing fields deserialize to their default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MutationConfig {
+    pub p_add_conn: f64,
+    pub p_remove_conn: f64,
+    pub p_rewire: f64,
+    pub p_flip_trigger: f64,
+    pub p_flip_action: f64,
+    pub p_bump_order: f64,
+    pub p_add_bit: f64,
+    pub p_remove_bit: f64,
+    pub p_add_link: f64,
+    pub p_remove_link: f64,
+    pub p_init_tweak: f64,
+    /// Optional gate insertion. Off by default: evaluation runs a genome's
+    /// chunks and links only, so embeds do not affect fitness.
+    pub p_gate_insert: f64,
+    pub p_add_chunk: f64,
+    pub p_remove_chunk: f64,
+    pub p_duplicate_chunk: f64,
+}
+
+impl Default for MutationConfig {
+    fn default() -> Self {
+        Self {
+            p_add_conn: 0.20,
+            p_remove_conn: 0.15,
+            p_rewire: 0.15,
+            p_flip_trigger: 0.05,
+            p_flip_action: 0.05,
+            p_bump_order: 0.05,
+            p_add_bit: 0.05,
+            p_remove_bit: 0.03,
+            p_add_link: 0.10,
+            p_remove_link: 0.07,
+            p_init_tweak: 0.05,
+            p_gate_insert: 0.0,
+            p_add_chunk: 0.02,
+            p_remove_chunk: 0.02,
+            p_duplicate_chunk: 0.01,
+        }
     }
-    if rng.gen::<f64>() < P_REMOVE_CONN {
-        apply_with_retry(genome, rng, remove_connection);
+}
+
+/// Outcome of one operator that fired during [`mutate`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutationRecord {
+    /// Operator name, e.g. `"add_connection"`.
+    pub operator: String,
+    /// Chunk the operator acted on, if any.
+    pub chunk: Option<u32>,
+    /// Operator-specific index within the chunk: connection for the
+    /// connection operators, bit for bit and gate operators, link for
+    /// `remove_link`, and the copy's chunk index for `duplicate_chunk`.
+    pub index: Option<u32>,
+    /// Whether the genome was changed and passed validation.
+    pub success: bool,
+    /// Attempts made beyond the first.
+    pub retries: u32,
+}
+
+/// A mutation operator supplied from outside the crate.
+///
+/// `apply` returns `false` when it found nothing to change. Changes that
+/// leave the genome invalid are rolled back and retried like the built-in
+/// operators.
+pub trait MutationOperator: Send + Sync {
+    fn apply(&self, genome: &mut Genome, rng: &mut dyn RngCore) -> bool;
+}
+
+/// Stabilizing operator for a [`MutationRegistry`]: removes one edge of the
+/// approximate feedback arc set from [`feedback_arcs`], choosing edges of
+/// larger loops with proportionally higher probability, so oscillation-prone
+/// feedback is cut first. Finds nothing to change in an acyclic genome.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CutFeedbackArc;
+
+impl MutationOperator for CutFeedbackArc {
+    fn apply(&self, genome: &mut Genome, rng: &mut dyn RngCore) -> bool {
+        let (Ok(chunks), Ok(links)) = (genome.to_chunks(), genome.to_links()) else {
+            return false;
+        };
+        let arcs = feedback_arcs(&chunks, &links);
+        let total: usize = arcs.iter().map(|a| a.component_size).sum();
+        if total == 0 {
+            return false;
+        }
+        let mut pick = rng.gen_range(0..total);
+        let arc = arcs
+            .iter()
+            .find(|a| {
+                if pick < a.component_size {
+                    return true;
+                }
+                pick -= a.component_size;
+                false
+            })
+            .expect("pick is below the total weight");
+        match arc.edge {
+            SystemEdge::Connection { chunk, index } => {
+                let chunk = &mut genome.chunks[chunk as usize];
+                chunk.conns.remove(index as usize);
+                fix_conn_order_tags(chunk);
+            }
+            SystemEdge::Link { index } => {
+                genome.links.remove(index as usize);
+                fix_link_order_tags(genome);
+            }
+        }
+        true
+    }
+}
+
+/// Custom operators rolled by [`mutate_with`] after the built-in ones.
+///
+/// Each weight is the probability that its operator fires on a given call,
+/// just like the fields of [`MutationConfig`].
+#[derive(Clone, Default)]
+pub struct MutationRegistry {
+    ops: Vec<(String, f64, Arc<dyn MutationOperator>)>,
+}
+
+impl MutationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `op` under `name`; the name is reported in
+    /// [`MutationRecord::operator`].
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        weight: f64,
+        op: impl MutationOperator + 'static,
+    ) -> &mut Self {
+        self.ops.push((name.into(), weight, Arc::new(op)));
+        self
     }
-    if rng.gen::<f64>() < P_REWIRE {
-        apply_with_retry(genome, rng, rewire_target);
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+/// Apply the built-in mutation operators with their probabilities.
+/// Each mutation retries up to three times if validation fails.
+/// Returns a record for every operator whose probability roll fired.
+pub fn mutate(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    config: &MutationConfig,
+) -> Vec<MutationRecord> {
+    mutate_with(genome, rng, config, &MutationRegistry::default())
+}
+
+/// Like [`mutate`], then rolls every operator in `registry` in registration
+/// order.
+pub fn mutate_with(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    config: &MutationConfig,
+    registry: &MutationRegistry,
+) -> Vec<MutationRecord> {
+    let ops: [(&str, f64, Mutator); 15] = [
+        ("add_connection", config.p_add_conn, add_connection),
+        ("remove_connection", config.p_remove_conn, remove_connection),
+        ("rewire_target", config.p_rewire, rewire_target),
+        ("flip_trigger", config.p_flip_trigger, flip_trigger),
+        ("flip_action", config.p_flip_action, flip_action),
+        ("bump_order_tag", config.p_bump_order, bump_order_tag),
+        ("add_internal_bit", config.p_add_bit, add_internal_bit),
+        (
+            "remove_internal_bit",
+            config.p_remove_bit,
+            remove_internal_bit,
+        ),
+        ("add_link", config.p_add_link, add_link),
+        ("remove_link", config.p_remove_link, remove_link),
+        ("init_state_tweak", config.p_init_tweak, init_state_tweak),
+        ("gate_insert", config.p_gate_insert, gate_insert),
+        ("add_chunk", config.p_add_chunk, add_chunk),
+        ("remove_chunk", config.p_remove_chunk, remove_chunk),
+        ("duplicate_chunk", config.p_duplicate_chunk, duplicate_chunk),
+    ];
+    let mut records = Vec::new();
+    for (name, p, op) in ops {
+        if rng.gen::<f64>() < p {
+            let (target, success, retries) = apply_with_retry(genome, rng, op);
+            let target = target.unwrap_or_default();
+            records.push(MutationRecord {
+                operator: name.to_string(),
+                chunk: target.chunk,
+                index: target.index,
+                success,
+                retries,
+            });
+        }
     }
-    if rng.gen::<f64>() < P_FLIP_TRIGGER {
-        apply_with_retry(genome, rng, flip_trigger);
+    for (name, p, op) in &registry.ops {
+        if rng.gen::<f64>() < *p {
+            let (success, retries) = apply_custom_with_retry(genome, rng, op.as_ref());
+            records.push(MutationRecord {
+                operator: name.clone(),
+                chunk: None,
+                index: None,
+                success,
+                retries,
+            });
+        }
     }
-    if rng.gen::<f64>() < P_FLIP_ACTION {
-        apply_with_retry(genome, rng, flip_action);
+    records
+}
+
+/// Where an operator acted; `None` from an operator means it found nothing to
+/// change.
+#[derive(Clone, Copy, Default)]
+struct Target {
+    chunk: Option<u32>,
+    index: Option<u32>,
+}
+
+impl Target {
+    fn chunk(chunk: usize) -> Self {
+        Self {
+            chunk: Some(chunk as u32),
+            index: None,
+        }
     }
-    if rng.gen::<f64>() < P_BUMP_ORDER {
-        apply_with_retry(genome, rng, bump_order_tag);
+
+    fn at(chunk: usize, index: usize) -> Self {
+        Self {
+            chunk: Some(chunk as u32),
+            index: Some(index as u32),
+        }
     }
-    if rng.gen::<f64>() < P_ADD_BIT {
-        apply_with_retry(genome, rng, add_internal_bit);
+}
+
+type Mutator = fn(&mut Genome, &mut dyn RngCore, &mut UndoLog) -> Option<Target>;
+
+/// Parts of a genome an operator is about to change, recorded so a failed
+/// attempt can be rolled back without cloning the whole genome.
+#[derive(Default)]
+struct UndoLog {
+    entries: Vec<Undo>,
+}
+
+enum Undo {
+    Chunk(usize, ChunkGene),
+    PushedChunk,
+    RemovedChunk(usize, ChunkGene),
+    Links(Vec<LinkGene>),
+    Embeds(Vec<EmbedGene>),
+}
+
+impl UndoLog {
+    fn save_chunk(&mut self, genome: &Genome, idx: usize) {
+        self.entries
+            .push(Undo::Chunk(idx, genome.chunks[idx].clone()));
     }
-    if rng.gen::<f64>() < P_REMOVE_BIT {
-        apply_with_retry(genome, rng, remove_internal_bit);
+
+    fn save_links(&mut self, genome: &Genome) {
+        self.entries.push(Undo::Links(genome.links.clone()));
     }
-    if rng.gen::<f64>() < P_ADD_LINK {
-        apply_with_retry(genome, rng, add_link);
+
+    fn save_embeds(&mut self, genome: &Genome) {
+        self.entries.push(Undo::Embeds(genome.embeds.clone()));
     }
-    if rng.gen::<f64>() < P_REMOVE_LINK {
-        apply_with_retry(genome, rng, remove_link);
+
+    fn push_chunk(&mut self, genome: &mut Genome, chunk: ChunkGene) {
+        genome.chunks.push(chunk);
+        self.entries.push(Undo::PushedChunk);
     }
-    if rng.gen::<f64>() < P_INIT_TWEAK {
-        apply_with_retry(genome, rng, init_state_tweak);
+
+    fn remove_chunk(&mut self, genome: &mut Genome, idx: usize) {
+        let chunk = genome.chunks.remove(idx);
+        self.entries.push(Undo::RemovedChunk(idx, chunk));
     }
-    if rng.gen::<f64>() < P_GATE_INSERT {
-        apply_with_retry(genome, rng, gate_insert);
+
+    /// Undo every recorded change, newest first.
+    fn rollback(self, genome: &mut Genome) {
+        for entry in self.entries.into_iter().rev() {
+            match entry {
+                Undo::Chunk(idx, chunk) => genome.chunks[idx] = chunk,
+                Undo::PushedChunk => {
+                    genome.chunks.pop();
+                }
+                Undo::RemovedChunk(idx, chunk) => genome.chunks.insert(idx, chunk),
+                Undo::Links(links) => genome.links = links,
+                Undo::Embeds(embeds) => genome.embeds = embeds,
+            }
+        }
     }
 }
 
+/// Returns the target of the last attempt, whether it succeeded and the
+/// number of retries.
 fn apply_with_retry(
     genome: &mut Genome,
     rng: &mut dyn RngCore,
-    mutator: fn(&mut Genome, &mut dyn RngCore),
-) {
+    mutator: Mutator,
+) -> (Option<Target>, bool, u32) {
+    let mut target = None;
+    for attempt in 0..3 {
+        let mut undo = UndoLog::default();
+        target = mutator(genome, rng, &mut undo);
+        if target.is_none() {
+            undo.rollback(genome);
+            return (None, false, attempt);
+        }
+        genome.sort();
+        if genome.validate().is_ok() {
+            return (target, true, attempt);
+        }
+        undo.rollback(genome);
+    }
+    (target, false, 2)
+}
+
+/// Custom operators keep no undo log, so each attempt works from a copy of
+/// the genome.
+fn apply_custom_with_retry(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    op: &dyn MutationOperator,
+) -> (bool, u32) {
     let original = genome.clone();
-    for _ in 0..3 {
-        mutator(genome, rng);
+    for attempt in 0..3 {
+        if !op.apply(genome, rng) {
+            *genome = original;
+            return (false, attempt);
+        }
         genome.sort();
         if genome.validate().is_ok() {
-            return;
+            return (true, attempt);
         }
         *genome = original.clone();
     }
-    *genome = original;
+    (false, 2)
 }
 
-fn add_connection(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn add_connection(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    undo: &mut UndoLog,
+) -> Option<Target> {
     if genome.chunks.is_empty() {
-        return;
+        return None;
     }
     let chunk_idx = rng.next_u32() as usize % genome.chunks.len();
-    let chunk = &mut genome.chunks[chunk_idx];
-    if chunk.nn == 0 && chunk.no == 0 {
-        return;
+    if genome.chunks[chunk_idx].nn == 0 && genome.chunks[chunk_idx].no == 0 {
+        return None;
     }
+    undo.save_chunk(genome, chunk_idx);
+    let chunk = &mut genome.chunks[chunk_idx];
     let edge = rng.next_u32() % 3;
     let (from_section, to_section) = match edge {
         0 => (0, 1),
@@ -124,11 +407,17 @@ fn add_connection(genome: &mut Genome, rng: &mut dyn RngCore) {
         from_index,
         to_index,
         order_tag,
+        innovation: 0,
     });
     fix_conn_order_tags(chunk);
+    Some(Target::chunk(chunk_idx))
 }
 
-fn remove_connection(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn remove_connection(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    undo: &mut UndoLog,
+) -> Option<Target> {
     let indices: Vec<usize> = genome
         .chunks
         .iter()
@@ -137,16 +426,18 @@ fn remove_connection(genome: &mut Genome, rng: &mut dyn RngCore) {
         .map(|(i, _)| i)
         .collect();
     if indices.is_empty() {
-        return;
+        return None;
     }
     let cidx = indices[rng.next_u32() as usize % indices.len()];
+    undo.save_chunk(genome, cidx);
     let chunk = &mut genome.chunks[cidx];
     let conn_idx = rng.next_u32() as usize % chunk.conns.len();
     chunk.conns.remove(conn_idx);
     fix_conn_order_tags(chunk);
+    Some(Target::at(cidx, conn_idx))
 }
 
-fn rewire_target(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn rewire_target(genome: &mut Genome, rng: &mut dyn RngCore, undo: &mut UndoLog) -> Option<Target> {
     let indices: Vec<usize> = genome
         .chunks
         .iter()
@@ -155,9 +446,10 @@ fn rewire_target(genome: &mut Genome, rng: &mut dyn RngCore) {
         .map(|(i, _)| i)
         .collect();
     if indices.is_empty() {
-        return;
+        return None;
     }
     let cidx = indices[rng.next_u32() as usize % indices.len()];
+    undo.save_chunk(genome, cidx);
     let chunk = &mut genome.chunks[cidx];
     let conn_idx = rng.next_u32() as usize % chunk.conns.len();
     let conn = &mut chunk.conns[conn_idx];
@@ -167,7 +459,7 @@ fn rewire_target(genome: &mut Genome, rng: &mut dyn RngCore) {
         _ => 0,
     };
     if range == 0 {
-        return;
+        return None;
     }
     let mut new_idx = rng.next_u32() % range;
     if range > 1 {
@@ -179,9 +471,11 @@ fn rewire_target(genome: &mut Genome, rng: &mut dyn RngCore) {
         }
     }
     conn.to_index = new_idx;
+    conn.innovation = 0;
+    Some(Target::at(cidx, conn_idx))
 }
 
-fn flip_trigger(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn flip_trigger(genome: &mut Genome, rng: &mut dyn RngCore, undo: &mut UndoLog) -> Option<Target> {
     let indices: Vec<usize> = genome
         .chunks
         .iter()
@@ -190,15 +484,17 @@ fn flip_trigger(genome: &mut Genome, rng: &mut dyn RngCore) {
         .map(|(i, _)| i)
         .collect();
     if indices.is_empty() {
-        return;
+        return None;
     }
     let cidx = indices[rng.next_u32() as usize % indices.len()];
+    undo.save_chunk(genome, cidx);
     let chunk = &mut genome.chunks[cidx];
     let conn_idx = rng.next_u32() as usize % chunk.conns.len();
     chunk.conns[conn_idx].trigger = (chunk.conns[conn_idx].trigger + 1) % 3;
+    Some(Target::at(cidx, conn_idx))
 }
 
-fn flip_action(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn flip_action(genome: &mut Genome, rng: &mut dyn RngCore, undo: &mut UndoLog) -> Option<Target> {
     let indices: Vec<usize> = genome
         .chunks
         .iter()
@@ -207,15 +503,21 @@ fn flip_action(genome: &mut Genome, rng: &mut dyn RngCore) {
         .map(|(i, _)| i)
         .collect();
     if indices.is_empty() {
-        return;
+        return None;
     }
     let cidx = indices[rng.next_u32() as usize % indices.len()];
+    undo.save_chunk(genome, cidx);
     let chunk = &mut genome.chunks[cidx];
     let conn_idx = rng.next_u32() as usize % chunk.conns.len();
     chunk.conns[conn_idx].action = (chunk.conns[conn_idx].action + 1) % 3;
+    Some(Target::at(cidx, conn_idx))
 }
 
-fn bump_order_tag(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn bump_order_tag(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    undo: &mut UndoLog,
+) -> Option<Target> {
     let indices: Vec<usize> = genome
         .chunks
         .iter()
@@ -224,28 +526,40 @@ fn bump_order_tag(genome: &mut Genome, rng: &mut dyn RngCore) {
         .map(|(i, _)| i)
         .collect();
     if indices.is_empty() {
-        return;
+        return None;
     }
     let cidx = indices[rng.next_u32() as usize % indices.len()];
+    undo.save_chunk(genome, cidx);
     let chunk = &mut genome.chunks[cidx];
     let conn_idx = rng.next_u32() as usize % chunk.conns.len();
     let bump = rng.next_u32() % 5 + 1;
     chunk.conns[conn_idx].order_tag += bump;
     fix_conn_order_tags(chunk);
+    Some(Target::at(cidx, conn_idx))
 }
 
-fn add_internal_bit(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn add_internal_bit(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    undo: &mut UndoLog,
+) -> Option<Target> {
     if genome.chunks.is_empty() {
-        return;
+        return None;
     }
     let idx = rng.next_u32() as usize % genome.chunks.len();
+    undo.save_chunk(genome, idx);
     let chunk = &mut genome.chunks[idx];
     let add = (rng.next_u32() % 8 + 1) as usize;
     chunk.nn += add as u32;
     chunk.internals_init.resize(chunk.nn as usize, false);
+    Some(Target::at(idx, chunk.nn as usize - add))
 }
 
-fn remove_internal_bit(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn remove_internal_bit(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    undo: &mut UndoLog,
+) -> Option<Target> {
     let candidates: Vec<usize> = genome
         .chunks
         .iter()
@@ -254,22 +568,34 @@ fn remove_internal_bit(genome: &mut Genome, rng: &mut dyn RngCore) {
         .map(|(i, _)| i)
         .collect();
     if candidates.is_empty() {
-        return;
+        return None;
     }
     let cidx = candidates[rng.next_u32() as usize % candidates.len()];
+    let gated: Vec<u32> = genome
+        .embeds
+        .iter()
+        .filter(|e| e.parent_chunk == cidx as u32)
+        .flat_map(|e| {
+            let gate = (e.gate_section == 1).then_some(e.gate_bit);
+            gate.into_iter().chain(e.map_in.iter().map(|&(p, _)| p))
+        })
+        .collect();
     let chunk = &mut genome.chunks[cidx];
     let unused: Vec<u32> = (0..chunk.nn)
         .filter(|i| {
-            !chunk.conns.iter().any(|c| {
-                (c.from_section == 1 && c.from_index == *i)
-                    || (c.to_section == 1 && c.to_index == *i)
-            })
+            !gated.contains(i)
+                && !chunk.conns.iter().any(|c| {
+                    (c.from_section == 1 && c.from_index == *i)
+                        || (c.to_section == 1 && c.to_index == *i)
+                })
         })
         .collect();
     if unused.is_empty() {
-        return;
+        return None;
     }
     let remove = unused[rng.next_u32() as usize % unused.len()] as usize;
+    undo.save_chunk(genome, cidx);
+    let chunk = &mut genome.chunks[cidx];
     chunk.internals_init.remove(remove);
     chunk.nn -= 1;
     for conn in &mut chunk.conns {
@@ -292,18 +618,37 @@ fn remove_internal_bit(genome: &mut Genome, rng: &mut dyn RngCore) {
         .conns
         .retain(|c| c.from_index != u32::MAX && c.to_index != u32::MAX);
     fix_conn_order_tags(chunk);
+    undo.save_embeds(genome);
+    let shift = |bit: &mut u32| {
+        if *bit > remove as u32 {
+            *bit -= 1;
+        }
+    };
+    for embed in genome
+        .embeds
+        .iter_mut()
+        .filter(|e| e.parent_chunk == cidx as u32)
+    {
+        if embed.gate_section == 1 {
+            shift(&mut embed.gate_bit);
+        }
+        for (p, _) in &mut embed.map_in {
+            shift(p);
+        }
+    }
+    Some(Target::at(cidx, remove))
 }
 
-fn add_link(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn add_link(genome: &mut Genome, rng: &mut dyn RngCore, undo: &mut UndoLog) -> Option<Target> {
     if genome.chunks.is_empty() {
-        return;
+        return None;
     }
     let from_chunk_idx = rng.next_u32() as usize % genome.chunks.len();
     let to_chunk_idx = rng.next_u32() as usize % genome.chunks.len();
     let from_chunk = &genome.chunks[from_chunk_idx];
     let to_chunk = &genome.chunks[to_chunk_idx];
     if from_chunk.no == 0 || to_chunk.ni == 0 {
-        return;
+        return None;
     }
     let from_out_idx = rng.next_u32() % from_chunk.no;
     let to_in_idx = rng.next_u32() % to_chunk.ni;
@@ -325,6 +670,7 @@ fn add_link(genome: &mut Genome, rng: &mut dyn RngCore) {
     } else {
         0
     };
+    undo.save_links(genome);
     genome.links.push(LinkGene {
         from_chunk: from_chunk_idx as u32,
         from_out_idx,
@@ -333,20 +679,29 @@ fn add_link(genome: &mut Genome, rng: &mut dyn RngCore) {
         to_chunk: to_chunk_idx as u32,
         to_in_idx,
         order_tag,
+        delay: 0,
+        innovation: 0,
     });
     fix_link_order_tags(genome);
+    Some(Target::chunk(from_chunk_idx))
 }
 
-fn remove_link(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn remove_link(genome: &mut Genome, rng: &mut dyn RngCore, undo: &mut UndoLog) -> Option<Target> {
     if genome.links.is_empty() {
-        return;
+        return None;
     }
     let idx = rng.next_u32() as usize % genome.links.len();
-    genome.links.remove(idx);
+    undo.save_links(genome);
+    let from_chunk = genome.links.remove(idx).from_chunk;
     fix_link_order_tags(genome);
+    Some(Target::at(from_chunk as usize, idx))
 }
 
-fn init_state_tweak(genome: &mut Genome, rng: &mut dyn RngCore) {
+fn init_state_tweak(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    undo: &mut UndoLog,
+) -> Option<Target> {
     let candidates: Vec<usize> = genome
         .chunks
         .iter()
@@ -355,17 +710,212 @@ fn init_state_tweak(genome: &mut Genome, rng: &mut dyn RngCore) {
         .map(|(i, _)| i)
         .collect();
     if candidates.is_empty() {
-        return;
+        return None;
     }
     let cidx = candidates[rng.next_u32() as usize % candidates.len()];
+    undo.save_chunk(genome, cidx);
     let chunk = &mut genome.chunks[cidx];
     let bit = rng.next_u32() as usize % chunk.nn as usize;
     let current = chunk.internals_init[bit];
     chunk.internals_init.set(bit, !current);
+    Some(Target::at(cidx, bit))
+}
+
+/// Embed a random chunk in another behind a fresh parent internal gate bit.
+/// Every child input is fed from a random parent internal bit and every
+/// child output drives a random parent output.
+fn gate_insert(genome: &mut Genome, rng: &mut dyn RngCore, undo: &mut UndoLog) -> Option<Target> {
+    let n = genome.chunks.len();
+    if n < 2 {
+        return None;
+    }
+    let parent = rng.next_u32() as usize % n;
+    let mut child = rng.next_u32() as usize % (n - 1);
+    if child >= parent {
+        child += 1;
+    }
+    let io_mode = (rng.next_u32() % 2) as u8;
+
+    undo.save_chunk(genome, parent);
+    let p = &mut genome.chunks[parent];
+    let gate_bit = p.nn;
+    p.nn += 1;
+    p.internals_init.push(false);
+    let (pnn, pno) = (p.nn, p.no);
+
+    let c = &genome.chunks[child];
+    let map_in = (0..c.ni).map(|k| (rng.next_u32() % pnn, k)).collect();
+    let map_out = if pno == 0 {
+        Vec::new()
+    } else {
+        (0..c.no).map(|k| (k, rng.next_u32() % pno)).collect()
+    };
+    undo.save_embeds(genome);
+    genome.embeds.push(EmbedGene {
+        parent_chunk: parent as u32,
+        child_chunk: child as u32,
+        gate_bit,
+        gate_section: 1,
+        io_mode,
+        map_in,
+        map_out,
+    });
+    Some(Target::at(parent, gate_bit as usize))
+}
+
+/// Append a small chunk that relays one input to one output through an
+/// internal bit, and with p=0.5 link an existing output to its first input.
+fn add_chunk(genome: &mut Genome, rng: &mut dyn RngCore, undo: &mut UndoLog) -> Option<Target> {
+    let ni = rng.next_u32() % 2 + 1;
+    let no = rng.next_u32() % 2 + 1;
+    let nn = rng.next_u32() % 4 + 1;
+    let random_conn =
+        |rng: &mut dyn RngCore, from_section, to_section, from_range, to_range| ConnGene {
+            from_section,
+            to_section,
+            trigger: (rng.next_u32() % 3) as u8,
+            action: (rng.next_u32() % 3) as u8,
+            from_index: rng.next_u32() % from_range,
+            to_index: rng.next_u32() % to_range,
+            order_tag: 0,
+            innovation: 0,
+        };
+    let conns = vec![
+        random_conn(rng, 0, 1, ni, nn),
+        random_conn(rng, 1, 2, nn, no),
+    ];
+    let mut chunk = ChunkGene::new(
+        ni,
+        no,
+        nn,
+        BitVec::repeat(false, ni as usize),
+        BitVec::repeat(false, no as usize),
+        BitVec::repeat(false, nn as usize),
+        conns,
+    );
+    fix_conn_order_tags(&mut chunk);
+    undo.push_chunk(genome, chunk);
+
+    let new_idx = genome.chunks.len() as u32 - 1;
+    let sources: Vec<usize> = (0..new_idx as usize)
+        .filter(|&i| genome.chunks[i].no > 0)
+        .collect();
+    let target = Some(Target::chunk(new_idx as usize));
+    if sources.is_empty() || rng.next_u32().is_multiple_of(2) {
+        return target;
+    }
+    let from_chunk = sources[rng.next_u32() as usize % sources.len()];
+    let from_out_idx = rng.next_u32() % genome.chunks[from_chunk].no;
+    let order_tag = genome
+        .links
+        .iter()
+        .filter(|l| l.from_chunk == from_chunk as u32 && l.from_out_idx == from_out_idx)
+        .map(|l| l.order_tag + 1)
+        .max()
+        .unwrap_or(0);
+    undo.save_links(genome);
+    genome.links.push(LinkGene {
+        from_chunk: from_chunk as u32,
+        from_out_idx,
+        trigger: (rng.next_u32() % 3) as u8,
+        action: (rng.next_u32() % 3) as u8,
+        to_chunk: new_idx,
+        to_in_idx: 0,
+        order_tag,
+        delay: 0,
+        innovation: 0,
+    });
+    fix_link_order_tags(genome);
+    target
+}
+
+/// Remove a chunk that no link or embed touches, shifting higher chunk
+/// indices down by one. The last remaining chunk is never removed.
+fn remove_chunk(genome: &mut Genome, rng: &mut dyn RngCore, undo: &mut UndoLog) -> Option<Target> {
+    if genome.chunks.len() <= 1 {
+        return None;
+    }
+    let unreferenced: Vec<u32> = (0..genome.chunks.len() as u32)
+        .filter(|&i| {
+            !genome
+                .links
+                .iter()
+                .any(|l| l.from_chunk == i || l.to_chunk == i)
+                && !genome
+                    .embeds
+                    .iter()
+                    .any(|e| e.parent_chunk == i || e.child_chunk == i)
+        })
+        .collect();
+    if unreferenced.is_empty() {
+        return None;
+    }
+    let remove = unreferenced[rng.next_u32() as usize % unreferenced.len()];
+    undo.remove_chunk(genome, remove as usize);
+    undo.save_links(genome);
+    undo.save_embeds(genome);
+    for link in &mut genome.links {
+        if link.from_chunk > remove {
+            link.from_chunk -= 1;
+        }
+        if link.to_chunk > remove {
+            link.to_chunk -= 1;
+        }
+    }
+    for embed in &mut genome.embeds {
+        if embed.parent_chunk > remove {
+            embed.parent_chunk -= 1;
+        }
+        if embed.child_chunk > remove {
+            embed.child_chunk -= 1;
+        }
+    }
+    fix_link_order_tags(genome);
+    Some(Target::chunk(remove as usize))
 }
 
-fn gate_insert(_genome: &mut Genome, _rng: &mut dyn RngCore) {
-    // Optional gate insertion not implemented.
+/// Append a copy of a random chunk. Incoming and outgoing links of the
+/// original are each duplicated onto the copy with p=0.5.
+fn duplicate_chunk(
+    genome: &mut Genome,
+    rng: &mut dyn RngCore,
+    undo: &mut UndoLog,
+) -> Option<Target> {
+    if genome.chunks.is_empty() {
+        return None;
+    }
+    let src = rng.next_u32() as usize % genome.chunks.len();
+    let mut dup = genome.chunks[src].clone();
+    for conn in &mut dup.conns {
+        conn.innovation = 0;
+    }
+    undo.push_chunk(genome, dup);
+    let copy = genome.chunks.len() as u32 - 1;
+    let src = src as u32;
+
+    let dup_incoming = rng.next_u32().is_multiple_of(2);
+    let dup_outgoing = rng.next_u32().is_multiple_of(2);
+    let mut new_links = Vec::new();
+    for link in &genome.links {
+        if dup_incoming && link.to_chunk == src {
+            new_links.push(LinkGene {
+                to_chunk: copy,
+                innovation: 0,
+                ..link.clone()
+            });
+        }
+        if dup_outgoing && link.from_chunk == src {
+            new_links.push(LinkGene {
+                from_chunk: copy,
+                innovation: 0,
+                ..link.clone()
+            });
+        }
+    }
+    undo.save_links(genome);
+    genome.links.extend(new_links);
+    fix_link_order_tags(genome);
+    Some(Target::at(src as usize, copy as usize))
 }
 
 fn fix_conn_order_tags(chunk: &mut ChunkGene) {
@@ -420,7 +970,6 @@ fn fix_link_order_tags(genome: &mut Genome) {
 mod tests {
     use super::*;
     use crate::genome::GenomeMeta;
-    use bitvec::prelude::*;
     use rand::rngs::mock::StepRng;
 
     struct SeqRng {
@@ -470,7 +1019,7 @@ mod tests {
     fn test_add_connection() {
         let mut genome = simple_genome();
         let mut rng = StepRng::new(0, 1);
-        add_connection(&mut genome, &mut rng);
+        add_connection(&mut genome, &mut rng, &mut UndoLog::default());
         genome.sort();
         genome.validate().unwrap();
         assert_eq!(genome.chunks[0].conns.len(), 1);
@@ -480,8 +1029,8 @@ mod tests {
     fn test_remove_connection() {
         let mut genome = simple_genome();
         let mut rng = StepRng::new(0, 0);
-        add_connection(&mut genome, &mut rng);
-        remove_connection(&mut genome, &mut rng);
+        add_connection(&mut genome, &mut rng, &mut UndoLog::default());
+        remove_connection(&mut genome, &mut rng, &mut UndoLog::default());
         genome.sort();
         genome.validate().unwrap();
         assert_eq!(genome.chunks[0].conns.len(), 0);
@@ -500,7 +1049,7 @@ mod tests {
         );
         let mut genome = Genome::new(vec![chunk], vec![], GenomeMeta::new(0, "t".into())).unwrap();
         let mut rng = StepRng::new(1, 0);
-        rewire_target(&mut genome, &mut rng);
+        rewire_target(&mut genome, &mut rng, &mut UndoLog::default());
         genome.sort();
         genome.validate().unwrap();
         assert_eq!(genome.chunks[0].conns[0].to_index, 1);
@@ -510,8 +1059,8 @@ mod tests {
     fn test_flip_trigger() {
         let mut genome = simple_genome();
         let mut rng = StepRng::new(0, 0);
-        add_connection(&mut genome, &mut rng);
-        flip_trigger(&mut genome, &mut rng);
+        add_connection(&mut genome, &mut rng, &mut UndoLog::default());
+        flip_trigger(&mut genome, &mut rng, &mut UndoLog::default());
         assert_eq!(genome.chunks[0].conns[0].trigger, 1);
     }
 
@@ -519,8 +1068,8 @@ mod tests {
     fn test_flip_action() {
         let mut genome = simple_genome();
         let mut rng = StepRng::new(0, 0);
-        add_connection(&mut genome, &mut rng);
-        flip_action(&mut genome, &mut rng);
+        add_connection(&mut genome, &mut rng, &mut UndoLog::default());
+        flip_action(&mut genome, &mut rng, &mut UndoLog::default());
         assert_eq!(genome.chunks[0].conns[0].action, 1);
     }
 
@@ -535,13 +1084,13 @@ mod tests {
             bitvec![u8, Lsb0; 0],
             vec![
                 ConnGene::new(1, 2, 0, 0, 0, 0, 0).unwrap(),
-                ConnGene::new(1, 2, 0, 0, 0, 0, 1).unwrap(),
+                ConnGene::new(1, 2, 1, 0, 0, 0, 1).unwrap(),
             ],
         );
         let mut genome = Genome::new(vec![chunk], vec![], GenomeMeta::new(0, "t".into())).unwrap();
         let old = genome.chunks[0].conns[0].order_tag;
         let mut rng = StepRng::new(0, 0);
-        bump_order_tag(&mut genome, &mut rng);
+        bump_order_tag(&mut genome, &mut rng, &mut UndoLog::default());
         assert!(genome.chunks[0].conns[0].order_tag > old);
         genome.sort();
         genome.validate().unwrap();
@@ -551,7 +1100,7 @@ mod tests {
     fn test_add_internal_bit() {
         let mut genome = simple_genome();
         let mut rng = StepRng::new(0, 0);
-        add_internal_bit(&mut genome, &mut rng);
+        add_internal_bit(&mut genome, &mut rng, &mut UndoLog::default());
         assert_eq!(genome.chunks[0].nn, 2);
         assert_eq!(genome.chunks[0].internals_init.len(), 2);
     }
@@ -569,7 +1118,7 @@ mod tests {
         );
         let mut genome = Genome::new(vec![chunk], vec![], GenomeMeta::new(0, "t".into())).unwrap();
         let mut rng = StepRng::new(0, 0);
-        remove_internal_bit(&mut genome, &mut rng);
+        remove_internal_bit(&mut genome, &mut rng, &mut UndoLog::default());
         assert_eq!(genome.chunks[0].nn, 1);
         assert_eq!(genome.chunks[0].internals_init.len(), 1);
     }
@@ -604,7 +1153,7 @@ mod tests {
             values: vec![0, 1, 0, 0, 0, 0],
             idx: 0,
         };
-        add_link(&mut genome, &mut rng);
+        add_link(&mut genome, &mut rng, &mut UndoLog::default());
         genome.sort();
         genome.validate().unwrap();
         assert_eq!(genome.links.len(), 1);
@@ -638,15 +1187,307 @@ mod tests {
         )
         .unwrap();
         let mut rng = StepRng::new(0, 0);
-        remove_link(&mut genome, &mut rng);
+        remove_link(&mut genome, &mut rng, &mut UndoLog::default());
         assert_eq!(genome.links.len(), 0);
     }
 
+    fn zero_config() -> MutationConfig {
+        serde_json::from_str(
+            r#"{"p_add_conn":0,"p_remove_conn":0,"p_rewire":0,"p_flip_trigger":0,
+            "p_flip_action":0,"p_bump_order":0,"p_add_bit":0,"p_remove_bit":0,
+            "p_add_link":0,"p_remove_link":0,"p_init_tweak":0,"p_gate_insert":0,
+            "p_add_chunk":0,"p_remove_chunk":0,"p_duplicate_chunk":0}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_mutation_config() {
+        let zero = zero_config();
+        let mut genome = simple_genome();
+        let mut rng = StepRng::new(0, 0);
+        mutate(&mut genome, &mut rng, &zero);
+        assert_eq!(genome.chunks[0].conns.len(), 0);
+        assert_eq!(genome.chunks[0].nn, 1);
+
+        // partial configs fill in defaults
+        let cfg: MutationConfig = serde_json::from_str(r#"{"p_add_conn":1.0}"#).unwrap();
+        assert_eq!(cfg.p_add_conn, 1.0);
+        assert_eq!(cfg.p_remove_conn, MutationConfig::default().p_remove_conn);
+        let mut rng = StepRng::new(0, 0);
+        let only_add = MutationConfig {
+            p_add_conn: 1.0,
+            ..zero
+        };
+        mutate(&mut genome, &mut rng, &only_add);
+        assert_eq!(genome.chunks[0].conns.len(), 1);
+    }
+
+    #[test]
+    fn test_add_chunk() {
+        let mut genome = simple_genome();
+        // with an odd roll the new chunk is linked from chunk 0
+        let mut rng = SeqRng {
+            values: vec![1, 1, 3, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0],
+            idx: 0,
+        };
+        add_chunk(&mut genome, &mut rng, &mut UndoLog::default());
+        genome.sort();
+        genome.validate().unwrap();
+        assert_eq!(genome.chunks.len(), 2);
+        let added = &genome.chunks[1];
+        assert_eq!((added.ni, added.no, added.nn), (2, 2, 4));
+        assert_eq!(added.conns.len(), 2);
+        assert_eq!(genome.links.len(), 1);
+        assert_eq!(genome.links[0].to_chunk, 1);
+    }
+
+    #[test]
+    fn test_remove_chunk() {
+        let chunk = |ni, no| {
+            ChunkGene::new(
+                ni,
+                no,
+                0,
+                BitVec::repeat(false, ni as usize),
+                BitVec::repeat(false, no as usize),
+                BitVec::new(),
+                vec![],
+            )
+        };
+        // chunk 0 is unreferenced; the link 1 -> 2 must become 0 -> 1
+        let link = LinkGene::new(1, 0, 0, 0, 2, 0, 0).unwrap();
+        let mut genome = Genome::new(
+            vec![chunk(1, 1), chunk(0, 1), chunk(1, 0)],
+            vec![link],
+            GenomeMeta::new(0, "t".into()),
+        )
+        .unwrap();
+        let mut rng = StepRng::new(0, 0);
+        remove_chunk(&mut genome, &mut rng, &mut UndoLog::default());
+        genome.validate().unwrap();
+        assert_eq!(genome.chunks.len(), 2);
+        assert_eq!(
+            (genome.links[0].from_chunk, genome.links[0].to_chunk),
+            (0, 1)
+        );
+
+        // every remaining chunk is referenced, so nothing is removed
+        remove_chunk(&mut genome, &mut rng, &mut UndoLog::default());
+        assert_eq!(genome.chunks.len(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_chunk() {
+        let chunk = |ni, no| {
+            ChunkGene::new(
+                ni,
+                no,
+                0,
+                BitVec::repeat(false, ni as usize),
+                BitVec::repeat(false, no as usize),
+                BitVec::new(),
+                vec![],
+            )
+        };
+        // 0 -> 1 -> 2; duplicate chunk 1 with both link directions
+        let links = vec![
+            LinkGene::new(0, 0, 0, 0, 1, 0, 0).unwrap(),
+            LinkGene::new(1, 0, 0, 0, 2, 0, 0).unwrap(),
+        ];
+        let mut genome = Genome::new(
+            vec![chunk(0, 1), chunk(1, 1), chunk(1, 0)],
+            links,
+            GenomeMeta::new(0, "t".into()),
+        )
+        .unwrap();
+        let mut rng = SeqRng {
+            values: vec![1, 0, 0],
+            idx: 0,
+        };
+        duplicate_chunk(&mut genome, &mut rng, &mut UndoLog::default());
+        genome.sort();
+        genome.validate().unwrap();
+        assert_eq!(genome.chunks.len(), 4);
+        let pairs: Vec<(u32, u32)> = genome
+            .links
+            .iter()
+            .map(|l| (l.from_chunk, l.to_chunk))
+            .collect();
+        assert_eq!(pairs, vec![(0, 1), (0, 3), (1, 2), (3, 2)]);
+        // links sharing a source keep distinct order tags
+        assert_ne!(genome.links[0].order_tag, genome.links[1].order_tag);
+    }
+
+    #[test]
+    fn test_gate_insert() {
+        let child = ChunkGene::new(
+            2,
+            1,
+            0,
+            bitvec![u8, Lsb0; 0, 0],
+            bitvec![u8, Lsb0; 0],
+            BitVec::new(),
+            vec![],
+        );
+        let mut genome = Genome::new(
+            vec![simple_genome().chunks[0].clone(), child],
+            vec![],
+            GenomeMeta::new(0, "t".into()),
+        )
+        .unwrap();
+        let mut rng = StepRng::new(0, 0);
+        apply_with_retry(&mut genome, &mut rng, gate_insert);
+        assert_eq!(genome.embeds.len(), 1);
+        let e = &genome.embeds[0];
+        assert_eq!((e.parent_chunk, e.child_chunk, e.gate_bit), (0, 1, 1));
+        assert_eq!(e.map_in, vec![(0, 0), (0, 1)]);
+        assert_eq!(e.map_out, vec![(0, 0)]);
+        assert_eq!(genome.chunks[0].nn, 2);
+
+        // the child is already embedded and the reverse would be a cycle
+        apply_with_retry(&mut genome, &mut rng, gate_insert);
+        assert_eq!(genome.embeds.len(), 1);
+        genome.validate().unwrap();
+
+        // the gate bit and mapped parent bits are never removed
+        remove_internal_bit(&mut genome, &mut rng, &mut UndoLog::default());
+        assert_eq!(genome.chunks[0].nn, 2);
+    }
+
+    #[test]
+    fn test_mutation_records() {
+        let mut genome = simple_genome();
+        let cfg = MutationConfig {
+            p_add_conn: 1.0,
+            p_remove_link: 1.0,
+            ..zero_config()
+        };
+        let mut rng = StepRng::new(0, 0);
+        let records = mutate(&mut genome, &mut rng, &cfg);
+        assert_eq!(
+            records,
+            vec![
+                MutationRecord {
+                    operator: "add_connection".into(),
+                    chunk: Some(0),
+                    index: None,
+                    success: true,
+                    retries: 0,
+                },
+                MutationRecord {
+                    operator: "remove_link".into(),
+                    chunk: None,
+                    index: None,
+                    success: false,
+                    retries: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn undo_log_restores_genome() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        let ops: [Mutator; 15] = [
+            add_connection,
+            remove_connection,
+            rewire_target,
+            flip_trigger,
+            flip_action,
+            bump_order_tag,
+            add_internal_bit,
+            remove_internal_bit,
+            add_link,
+            remove_link,
+            init_state_tweak,
+            gate_insert,
+            add_chunk,
+            remove_chunk,
+            duplicate_chunk,
+        ];
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut genome = simple_genome();
+        let config = MutationConfig {
+            p_remove_chunk: 0.0,
+            ..MutationConfig::default()
+        };
+        for _ in 0..20 {
+            mutate(&mut genome, &mut rng, &config);
+        }
+        let before = serde_json::to_string(&genome).unwrap();
+        for _ in 0..10 {
+            let mut undo = UndoLog::default();
+            for op in ops {
+                op(&mut genome, &mut rng, &mut undo);
+            }
+            undo.rollback(&mut genome);
+            assert_eq!(serde_json::to_string(&genome).unwrap(), before);
+        }
+    }
+
+    #[test]
+    fn cut_feedback_arc_stabilizes() {
+        // internals 0 and 1 toggle each other; the wire through internal 0
+        // is acyclic and must survive
+        let mut genome = crate::builder::fixtures::wire_genome();
+        genome.resize_chunk_internals(0, 2);
+        genome.chunks[0].conns.extend([
+            ConnGene::new(1, 1, 2, 2, 0, 1, 4).unwrap(),
+            ConnGene::new(1, 1, 2, 2, 1, 0, 4).unwrap(),
+        ]);
+        genome.validate().unwrap();
+        let mut rng = StepRng::new(0, 1);
+        assert!(CutFeedbackArc.apply(&mut genome, &mut rng));
+        genome.validate().unwrap();
+        assert_eq!(genome.chunks[0].conns.len(), 5);
+        assert!(genome.chunks[0].conns.iter().any(|c| c.from_section == 0));
+        assert!(!CutFeedbackArc.apply(&mut genome, &mut rng));
+    }
+
+    #[test]
+    fn registry_operators_are_rolled() {
+        struct AddBit;
+        impl MutationOperator for AddBit {
+            fn apply(&self, genome: &mut Genome, _rng: &mut dyn RngCore) -> bool {
+                let chunk = &mut genome.chunks[0];
+                chunk.nn += 1;
+                chunk.internals_init.push(true);
+                true
+            }
+        }
+        struct Break;
+        impl MutationOperator for Break {
+            fn apply(&self, genome: &mut Genome, _rng: &mut dyn RngCore) -> bool {
+                genome.chunks[0].nn += 1;
+                true
+            }
+        }
+
+        let mut registry = MutationRegistry::new();
+        registry
+            .register("add_bit", 1.0, AddBit)
+            .register("break", 1.0, Break);
+        let mut genome = simple_genome();
+        let mut rng = StepRng::new(0, 0);
+        let records = mutate_with(&mut genome, &mut rng, &zero_config(), &registry);
+        assert_eq!(genome.chunks[0].nn, 2);
+        assert!(genome.validate().is_ok());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].operator, "add_bit");
+        assert!(records[0].success);
+        assert_eq!(records[1].operator, "break");
+        assert!(!records[1].success);
+        assert_eq!(records[1].retries, 2);
+    }
+
     #[test]
     fn test_init_state_tweak() {
         let mut genome = simple_genome();
         let mut rng = StepRng::new(0, 0);
-        init_state_tweak(&mut genome, &mut rng);
+        init_state_tweak(&mut genome, &mut rng, &mut UndoLog::default());
         assert!(genome.chunks[0].internals_init[0]);
     }
 }
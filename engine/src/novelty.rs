@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+
+use crate::FitnessResult;
+
+/// Settings for blending task fitness with behavioural novelty.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoveltyConfig {
+    /// Number of nearest neighbours averaged into the novelty score.
+    pub k: usize,
+    /// Weight of task fitness in the selection score
+    /// `alpha·fitness + (1-alpha)·novelty`; 0 is pure novelty search.
+    pub alpha: f32,
+    /// Behaviours whose novelty exceeds this are added to the archive.
+    pub archive_threshold: f32,
+    /// Maximum archive size; the oldest behaviours are evicted first.
+    pub archive_capacity: usize,
+}
+
+impl Default for NoveltyConfig {
+    fn default() -> Self {
+        Self {
+            k: 15,
+            alpha: 0.5,
+            archive_threshold: 0.1,
+            archive_capacity: 1024,
+        }
+    }
+}
+
+/// Behaviour descriptor of an evaluation: the output words of every tick of
+/// every episode, concatenated in order.
+pub fn behavior(result: &FitnessResult) -> Vec<u32> {
+    result.outputs.concat()
+}
+
+/// Fraction of differing observed bits between two descriptors in `[0, 1]`,
+/// where each tick packs `bits_per_tick` bits (`task.io.observed()`) into
+/// whole words. Padding bits are ignored, and ticks missing from the shorter
+/// descriptor count as fully different.
+pub fn behavior_distance(a: &[u32], b: &[u32], bits_per_tick: usize) -> f32 {
+    let words = bits_per_tick.div_ceil(32);
+    if words == 0 {
+        return 0.0;
+    }
+    let ticks = a.len().max(b.len()).div_ceil(words);
+    if ticks == 0 {
+        return 0.0;
+    }
+    let mut differing = 0;
+    for i in 0..ticks * words {
+        let valid = (bits_per_tick - (i % words) * 32).min(32);
+        let mask = u32::MAX >> (32 - valid);
+        differing += match (a.get(i), b.get(i)) {
+            (Some(x), Some(y)) => ((x ^ y) & mask).count_ones(),
+            (None, None) => 0,
+            _ => valid as u32,
+        };
+    }
+    differing as f32 / (ticks * bits_per_tick) as f32
+}
+
+/// Archive of past behaviours used as the k-NN reference set.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NoveltyArchive {
+    capacity: usize,
+    behaviors: Vec<Vec<u32>>,
+}
+
+impl NoveltyArchive {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            behaviors: Vec::new(),
+        }
+    }
+
+    /// Archived behaviours, oldest first.
+    pub fn behaviors(&self) -> &[Vec<u32>] {
+        &self.behaviors
+    }
+
+    /// Mean distance from `behavior` to its `k` nearest neighbours among the
+    /// archive and `others`, with `bits_per_tick` as in [`behavior_distance`].
+    /// With no neighbours at all it is maximally novel.
+    pub fn novelty<'a>(
+        &self,
+        behavior: &[u32],
+        others: impl IntoIterator<Item = &'a [u32]>,
+        k: usize,
+        bits_per_tick: usize,
+    ) -> f32 {
+        let distance = |b: &[u32]| behavior_distance(behavior, b, bits_per_tick);
+        let mut dists: Vec<f32> = self
+            .behaviors
+            .iter()
+            .map(|b| distance(b))
+            .chain(others.into_iter().map(distance))
+            .collect();
+        if dists.is_empty() || k == 0 {
+            return 1.0;
+        }
+        dists.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let k = k.min(dists.len());
+        dists[..k].iter().sum::<f32>() / k as f32
+    }
+
+    /// Append a behaviour, evicting the oldest once over capacity.
+    pub fn insert(&mut self, behavior: Vec<u32>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.behaviors.len() == self.capacity {
+            self.behaviors.remove(0);
+        }
+        self.behaviors.push(behavior);
+    }
+}
+
+/// Novelty of each behaviour against the archive and the rest of the
+/// population, archiving those above `config.archive_threshold`. Each tick
+/// of a behaviour carries `bits_per_tick` observed bits.
+pub fn score_novelty(
+    behaviors: &[Vec<u32>],
+    archive: &mut NoveltyArchive,
+    config: &NoveltyConfig,
+    bits_per_tick: usize,
+) -> Vec<f32> {
+    let scores: Vec<f32> = behaviors
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            let others = behaviors
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, o)| o.as_slice());
+            archive.novelty(b, others, config.k, bits_per_tick)
+        })
+        .collect();
+    for (b, &s) in behaviors.iter().zip(&scores) {
+        if s > config.archive_threshold {
+            archive.insert(b.clone());
+        }
+    }
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn novelty_is_mean_distance_to_nearest() {
+        assert_eq!(behavior_distance(&[0b1010], &[0b0110], 4), 0.5);
+        // padding above the observed bits is ignored
+        assert_eq!(behavior_distance(&[0b10], &[0b11_0000_0000], 2), 0.5);
+        // a missing tick counts all its observed bits
+        assert_eq!(behavior_distance(&[1], &[1, 0], 1), 0.5);
+        assert_eq!(
+            behavior_distance(&[0, 0, 0, 0], &[0, 1, 0, 0], 33),
+            1.0 / 66.0
+        );
+        assert_eq!(behavior_distance(&[], &[], 8), 0.0);
+
+        let mut archive = NoveltyArchive::new(2);
+        assert_eq!(archive.novelty(&[0], [], 3, 4), 1.0);
+        archive.insert(vec![0]);
+        archive.insert(vec![u32::MAX]);
+        let others: [&[u32]; 1] = [&[0b11]];
+        // nearest two: 0 (distance 0) and 0b11 (2/4)
+        assert_eq!(archive.novelty(&[0], others, 2, 4), 0.25);
+        archive.insert(vec![7]);
+        assert_eq!(archive.behaviors(), &[vec![u32::MAX], vec![7]]);
+
+        let config = NoveltyConfig {
+            k: 1,
+            archive_threshold: 0.4,
+            ..NoveltyConfig::default()
+        };
+        let mut archive = NoveltyArchive::new(8);
+        let scores = score_novelty(
+            &[vec![0], vec![0], vec![u32::MAX]],
+            &mut archive,
+            &config,
+            8,
+        );
+        assert_eq!(scores, vec![0.0, 0.0, 1.0]);
+        assert_eq!(archive.behaviors(), &[vec![u32::MAX]]);
+    }
+}
@@ -1,8 +1,9 @@
 use crate::chunk::Action;
-use serde::Serialize;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
 
 /// Policy applied when guards trigger.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Policy {
     /// Revert to last stable state seen before the cycle.
     FreezeLastStable,
@@ -10,6 +11,69 @@ pub enum Policy {
     ClampCommutative,
     /// Toggle bits once based on cycle parity.
     ParityQuench,
+    /// Return each bit that toggled in the cycle to its last stable value
+    /// with probability `fraction`, drawn from an RNG seeded with `seed`.
+    StochasticQuench { fraction: f32, seed: u64 },
+}
+
+/// Oscillation policy per chunk: `default`, unless the chunk id appears in
+/// `overrides`, where a later entry wins over an earlier one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    pub default: Policy,
+    #[serde(default)]
+    pub overrides: Vec<(u32, Policy)>,
+    /// Cycle detection that decides when a policy applies.
+    #[serde(default)]
+    pub detector: DetectorConfig,
+}
+
+impl PolicyConfig {
+    pub fn for_chunk(&self, chunk_id: u32) -> Policy {
+        self.overrides
+            .iter()
+            .rev()
+            .find(|(id, _)| *id == chunk_id)
+            .map_or(self.default, |&(_, p)| p)
+    }
+}
+
+impl From<Policy> for PolicyConfig {
+    fn from(default: Policy) -> Self {
+        Self {
+            default,
+            overrides: Vec::new(),
+            detector: DetectorConfig::default(),
+        }
+    }
+}
+
+/// How [`CycleDetector`] decides that a state repeats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CycleConfirmation {
+    /// A matching 128-bit hash counts as a repeat.
+    #[default]
+    Hash,
+    /// A hash match is confirmed against a stored copy of the state, so a
+    /// hash collision never triggers a destructive policy.
+    Exact,
+}
+
+/// Window and confirmation mode of a [`CycleDetector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectorConfig {
+    /// States remembered; longer periods go undetected.
+    pub window: usize,
+    pub confirmation: CycleConfirmation,
+}
+
+impl Default for DetectorConfig {
+    fn default() -> Self {
+        Self {
+            window: 8,
+            confirmation: CycleConfirmation::Hash,
+        }
+    }
 }
 
 /// Result of executing with guards and policies applied.
@@ -25,15 +89,31 @@ pub struct ExecutionResult {
 }
 
 /// Ring buffer based cycle detector using 128-bit hashes of the internal state.
+///
+/// With [`CycleConfirmation::Exact`] it also keeps a copy of every state in
+/// the window and only reports a period once the states compare equal.
 pub struct CycleDetector {
     ring: Vec<u128>,
+    /// Stored states, kept in exact mode only.
+    states: Option<Vec<Option<Vec<u32>>>>,
     pos: usize,
 }
 
 impl CycleDetector {
     pub fn new(window: usize) -> Self {
+        Self::with_config(DetectorConfig {
+            window,
+            confirmation: CycleConfirmation::Hash,
+        })
+    }
+
+    pub fn with_config(config: DetectorConfig) -> Self {
         Self {
-            ring: vec![0; window],
+            ring: vec![0; config.window],
+            states: match config.confirmation {
+                CycleConfirmation::Hash => None,
+                CycleConfirmation::Exact => Some(vec![None; config.window]),
+            },
             pos: 0,
         }
     }
@@ -42,17 +122,21 @@ impl CycleDetector {
     /// detected, otherwise `None`.
     pub fn observe(&mut self, state: &[u32]) -> Option<u32> {
         let h = hash_state(state);
-        for i in 0..self.ring.len() {
-            if self.ring[i] == h {
-                let period = (self.ring.len() + self.pos - i) % self.ring.len();
-                self.ring[self.pos] = h;
-                self.pos = (self.pos + 1) % self.ring.len();
-                return Some(period as u32);
-            }
-        }
+        let len = self.ring.len();
+        let found = (0..len).find(|&i| {
+            self.ring[i] == h
+                && self
+                    .states
+                    .as_ref()
+                    .is_none_or(|s| s[i].as_deref() == Some(state))
+        });
+        let period = found.map(|i| ((len + self.pos - i) % len) as u32);
         self.ring[self.pos] = h;
-        self.pos = (self.pos + 1) % self.ring.len();
-        None
+        if let Some(states) = &mut self.states {
+            states[self.pos] = Some(state.to_vec());
+        }
+        self.pos = (self.pos + 1) % len;
+        period
     }
 }
 
@@ -136,6 +220,26 @@ pub fn parity_quench(curr: &mut [u32], period: u32) {
     }
 }
 
+/// Apply the `stochastic_quench` policy: every bit set in `toggled` is
+/// restored from `stable` with probability `fraction`; the rest keep their
+/// current value.
+pub fn stochastic_quench(
+    curr: &mut [u32],
+    stable: &[u32],
+    toggled: &[u32],
+    fraction: f32,
+    rng: &mut dyn RngCore,
+) {
+    for ((c, s), t) in curr.iter_mut().zip(stable).zip(toggled) {
+        for bit in 0..32 {
+            let m = 1u32 << bit;
+            if t & m != 0 && rng.gen::<f32>() < fraction {
+                *c = (*c & !m) | (s & m);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,4 +266,43 @@ mod tests {
         });
         assert_json_snapshot!("freeze_last_stable", res);
     }
+
+    #[test]
+    fn exact_mode_rejects_hash_collisions() {
+        let config = DetectorConfig {
+            window: 4,
+            confirmation: CycleConfirmation::Exact,
+        };
+        let mut exact = CycleDetector::with_config(config);
+        let mut hashed = CycleDetector::new(4);
+        // plant a hash equal to the next state's, as a collision would
+        let h = hash_state(&[7]);
+        for det in [&mut exact, &mut hashed] {
+            det.ring[1] = h;
+        }
+        assert_eq!(hashed.observe(&[7]), Some(3));
+        assert_eq!(exact.observe(&[7]), None);
+        assert_eq!(exact.observe(&[8]), None);
+        assert_eq!(exact.observe(&[7]), Some(2));
+    }
+
+    #[test]
+    fn stochastic_quench_restores_a_fraction_of_toggled_bits() {
+        use rand::SeedableRng;
+        let rng = || rand_chacha::ChaCha8Rng::seed_from_u64(5);
+        let stable = [0u32];
+        let toggled = [0xFFFF_0000];
+        let quench = |fraction| {
+            let mut curr = [u32::MAX];
+            stochastic_quench(&mut curr, &stable, &toggled, fraction, &mut rng());
+            curr[0]
+        };
+        assert_eq!(quench(0.0), u32::MAX);
+        assert_eq!(quench(1.0), 0x0000_FFFF);
+        let half = quench(0.5);
+        // untoggled bits are never touched
+        assert_eq!(half & 0xFFFF, 0xFFFF);
+        assert!((4..=12).contains(&(!half >> 16).count_ones()));
+        assert_eq!(half, quench(0.5));
+    }
 }
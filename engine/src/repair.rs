@@ -0,0 +1,539 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use crate::genome::{ChunkGene, Genome, ValidationError};
+
+/// One change made by [`Genome::repair`], with the violation it fixed.
+///
+/// Connection, link and embed indices are positions before the repair, the
+/// same ones [`Genome::validate_all`] reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// An init bitvec was truncated or zero-padded to its section count.
+    InitResized {
+        chunk: u32,
+        error: ValidationError,
+    },
+    /// An out-of-range connection index was moved to the section's last bit.
+    ConnClamped {
+        chunk: u32,
+        conn: u32,
+        error: ValidationError,
+        clamped: u32,
+    },
+    ConnDropped {
+        chunk: u32,
+        conn: u32,
+        error: ValidationError,
+    },
+    /// A reused order tag was replaced by one past the source's largest tag.
+    ConnRetagged {
+        chunk: u32,
+        conn: u32,
+        error: ValidationError,
+        order_tag: u32,
+    },
+    LinkClamped {
+        link: u32,
+        error: ValidationError,
+        clamped: u32,
+    },
+    LinkDropped {
+        link: u32,
+        error: ValidationError,
+    },
+    LinkRetagged {
+        link: u32,
+        error: ValidationError,
+        order_tag: u32,
+    },
+    /// An out-of-range `map_in` or `map_out` pair was removed.
+    EmbedMapDropped {
+        embed: u32,
+        error: ValidationError,
+    },
+    EmbedDropped {
+        embed: u32,
+        error: ValidationError,
+    },
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Repair::*;
+        match self {
+            InitResized { chunk, error } => write!(f, "chunk {chunk}: {error}; resized"),
+            ConnClamped {
+                chunk,
+                conn,
+                error,
+                clamped,
+            } => write!(
+                f,
+                "chunk {chunk} conn {conn}: {error}; clamped to {clamped}"
+            ),
+            ConnDropped { chunk, conn, error } => {
+                write!(f, "chunk {chunk} conn {conn}: {error}; dropped")
+            }
+            ConnRetagged {
+                chunk,
+                conn,
+                error,
+                order_tag,
+            } => write!(
+                f,
+                "chunk {chunk} conn {conn}: {error}; retagged {order_tag}"
+            ),
+            LinkClamped {
+                link,
+                error,
+                clamped,
+            } => write!(f, "link {link}: {error}; clamped to {clamped}"),
+            LinkDropped { link, error } => write!(f, "link {link}: {error}; dropped"),
+            LinkRetagged {
+                link,
+                error,
+                order_tag,
+            } => write!(f, "link {link}: {error}; retagged {order_tag}"),
+            EmbedMapDropped { embed, error } => {
+                write!(f, "embed {embed}: {error}; pair dropped")
+            }
+            EmbedDropped { embed, error } => write!(f, "embed {embed}: {error}; dropped"),
+        }
+    }
+}
+
+impl Genome {
+    /// Fix what [`validate_all`](Self::validate_all) would report instead of
+    /// rejecting it, for genomes imported from other tools. Section counts
+    /// are taken as authoritative:
+    ///
+    /// * init bitvecs are resized to their counts;
+    /// * out-of-range connection and link indices are clamped to the last bit
+    ///   of their section, or dropped when the section is empty;
+    /// * connections and links with bad codes, edges or chunk references are
+    ///   dropped, as are repeats of a source, target and trigger except the one
+    ///   with the highest order tag;
+    /// * reused order tags move past the largest tag of their source;
+    /// * out-of-range embed map pairs are dropped, then any embed that is still
+    ///   invalid, reuses a child or closes a cycle.
+    ///
+    /// Everything is re-sorted afterwards. Returns the fixes in the order they
+    /// were made; an empty list means the genome was already valid.
+    pub fn repair(&mut self) -> Vec<Repair> {
+        let mut fixes = Vec::new();
+        for (c, chunk) in self.chunks.iter_mut().enumerate() {
+            repair_chunk(c as u32, chunk, &mut fixes);
+        }
+        self.repair_links(&mut fixes);
+        self.repair_embeds(&mut fixes);
+        self.sort();
+        fixes
+    }
+
+    fn repair_links(&mut self, fixes: &mut Vec<Repair>) {
+        let mut keep = vec![true; self.links.len()];
+        for (l, link) in self.links.iter_mut().enumerate() {
+            while let Err(error) = Genome::validate_link(&self.chunks, link) {
+                let (index, count) = match error {
+                    ValidationError::InvalidLinkFromIndex { chunk, .. } => {
+                        (&mut link.from_out_idx, self.chunks[chunk as usize].no)
+                    }
+                    ValidationError::InvalidLinkToIndex { chunk, .. } => {
+                        (&mut link.to_in_idx, self.chunks[chunk as usize].ni)
+                    }
+                    _ => (&mut link.from_out_idx, 0),
+                };
+                if count == 0 {
+                    keep[l] = false;
+                    fixes.push(Repair::LinkDropped {
+                        link: l as u32,
+                        error,
+                    });
+                    break;
+                }
+                *index = count - 1;
+                fixes.push(Repair::LinkClamped {
+                    link: l as u32,
+                    error,
+                    clamped: count - 1,
+                });
+            }
+        }
+        let keys: Vec<_> = self
+            .links
+            .iter()
+            .map(|l| {
+                (
+                    (l.from_chunk, l.from_out_idx),
+                    (l.to_chunk, l.to_in_idx, l.trigger),
+                    l.order_tag,
+                )
+            })
+            .collect();
+        for fix in dedupe(&keys, &mut keep) {
+            match fix {
+                Dedupe::Duplicate {
+                    first,
+                    second,
+                    dropped,
+                } => {
+                    fixes.push(Repair::LinkDropped {
+                        link: dropped as u32,
+                        error: ValidationError::DuplicateLink {
+                            first: first as u32,
+                            second: second as u32,
+                        },
+                    });
+                }
+                Dedupe::Retag { index, order_tag } => {
+                    let link = &mut self.links[index];
+                    fixes.push(Repair::LinkRetagged {
+                        link: index as u32,
+                        error: ValidationError::LinkOrderTagCollision {
+                            chunk: link.from_chunk,
+                            index: link.from_out_idx,
+                            order_tag: link.order_tag,
+                        },
+                        order_tag,
+                    });
+                    link.order_tag = order_tag;
+                }
+            }
+        }
+        let mut keep = keep.into_iter();
+        self.links.retain(|_| keep.next().unwrap());
+    }
+
+    fn repair_embeds(&mut self, fixes: &mut Vec<Repair>) {
+        let chunks = &self.chunks;
+        let mut parent_of = vec![None; chunks.len()];
+        let mut e = 0;
+        self.embeds.retain_mut(|embed| {
+            let index = e as u32;
+            e += 1;
+            if let (Some(parent), Some(child)) = (
+                chunks.get(embed.parent_chunk as usize),
+                chunks.get(embed.child_chunk as usize),
+            ) {
+                embed.map_in.retain(|&(parent_bit, child_bit)| {
+                    let ok = parent_bit < parent.nn && child_bit < child.ni;
+                    if !ok {
+                        fixes.push(Repair::EmbedMapDropped {
+                            embed: index,
+                            error: ValidationError::InvalidEmbedMapIn {
+                                parent_bit,
+                                child_bit,
+                            },
+                        });
+                    }
+                    ok
+                });
+                embed.map_out.retain(|&(child_bit, parent_bit)| {
+                    let ok = child_bit < child.no && parent_bit < parent.no;
+                    if !ok {
+                        fixes.push(Repair::EmbedMapDropped {
+                            embed: index,
+                            error: ValidationError::InvalidEmbedMapOut {
+                                child_bit,
+                                parent_bit,
+                            },
+                        });
+                    }
+                    ok
+                });
+            }
+            let error = embed.validate(chunks).err().or_else(|| {
+                let child = embed.child_chunk as usize;
+                if parent_of[child].is_some() {
+                    return Some(ValidationError::InvalidEmbedChild(embed.child_chunk));
+                }
+                // Walking up from the new parent must not reach the child.
+                let mut cur = embed.parent_chunk as usize;
+                for _ in 0..chunks.len() {
+                    if cur == child {
+                        return Some(ValidationError::EmbedCycle(embed.child_chunk));
+                    }
+                    match parent_of[cur] {
+                        Some(p) => cur = p,
+                        None => break,
+                    }
+                }
+                parent_of[child] = Some(embed.parent_chunk as usize);
+                None
+            });
+            match error {
+                Some(error) => {
+                    fixes.push(Repair::EmbedDropped {
+                        embed: index,
+                        error,
+                    });
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+}
+
+fn repair_chunk(c: u32, chunk: &mut ChunkGene, fixes: &mut Vec<Repair>) {
+    for error in chunk.validate_all() {
+        let (init, count) = match error {
+            ValidationError::InputsLenMismatch { expected, .. } => {
+                (&mut chunk.inputs_init, expected)
+            }
+            ValidationError::OutputsLenMismatch { expected, .. } => {
+                (&mut chunk.outputs_init, expected)
+            }
+            ValidationError::InternalsLenMismatch { expected, .. } => {
+                (&mut chunk.internals_init, expected)
+            }
+            _ => continue,
+        };
+        init.resize(count as usize, false);
+        fixes.push(Repair::InitResized { chunk: c, error });
+    }
+
+    let mut keep = vec![true; chunk.conns.len()];
+    for (i, kept) in keep.iter_mut().enumerate() {
+        while let Err(error) = chunk.validate_conn(&chunk.conns[i]) {
+            let conn = &mut chunk.conns[i];
+            let (index, section) = match error {
+                ValidationError::FromIndexOutOfRange { section, .. } => {
+                    (&mut conn.from_index, section)
+                }
+                ValidationError::ToIndexOutOfRange { section, .. } => (&mut conn.to_index, section),
+                _ => (&mut conn.from_index, u8::MAX),
+            };
+            let count = match section {
+                0 => chunk.ni,
+                1 => chunk.nn,
+                2 => chunk.no,
+                _ => 0,
+            };
+            if count == 0 {
+                *kept = false;
+                fixes.push(Repair::ConnDropped {
+                    chunk: c,
+                    conn: i as u32,
+                    error,
+                });
+                break;
+            }
+            *index = count - 1;
+            fixes.push(Repair::ConnClamped {
+                chunk: c,
+                conn: i as u32,
+                error,
+                clamped: count - 1,
+            });
+        }
+    }
+
+    let keys: Vec<_> = chunk
+        .conns
+        .iter()
+        .map(|c| {
+            (
+                (c.from_section, c.from_index),
+                (c.to_section, c.to_index, c.trigger),
+                c.order_tag,
+            )
+        })
+        .collect();
+    for fix in dedupe(&keys, &mut keep) {
+        match fix {
+            Dedupe::Duplicate {
+                first,
+                second,
+                dropped,
+            } => {
+                fixes.push(Repair::ConnDropped {
+                    chunk: c,
+                    conn: dropped as u32,
+                    error: ValidationError::DuplicateConn {
+                        first: first as u32,
+                        second: second as u32,
+                    },
+                });
+            }
+            Dedupe::Retag { index, order_tag } => {
+                let conn = &mut chunk.conns[index];
+                fixes.push(Repair::ConnRetagged {
+                    chunk: c,
+                    conn: index as u32,
+                    error: ValidationError::ConnOrderTagCollision {
+                        section: conn.from_section,
+                        index: conn.from_index,
+                        order_tag: conn.order_tag,
+                    },
+                    order_tag,
+                });
+                conn.order_tag = order_tag;
+            }
+        }
+    }
+    let mut keep = keep.into_iter();
+    chunk.conns.retain(|_| keep.next().unwrap());
+}
+
+enum Dedupe {
+    /// `first` and `second` share an edge; `dropped`, the one with the lower
+    /// order tag (the later one on a tie), has been unmarked in `keep`.
+    Duplicate {
+        first: usize,
+        second: usize,
+        dropped: usize,
+    },
+    /// `index` reuses a tag of its source and should take `order_tag`.
+    Retag { index: usize, order_tag: u32 },
+}
+
+/// Resolve repeated `(source, target)` keys and reused `(source, tag)` pairs
+/// among the entries still marked in `keep`.
+fn dedupe<S, T>(keys: &[(S, T, u32)], keep: &mut [bool]) -> Vec<Dedupe>
+where
+    S: Copy + Eq + Hash,
+    T: Copy + Eq + Hash,
+{
+    let mut out = Vec::new();
+    let mut edges: HashMap<(S, T), usize> = HashMap::new();
+    for (i, &(from, to, tag)) in keys.iter().enumerate() {
+        if !keep[i] {
+            continue;
+        }
+        match edges.get_mut(&(from, to)) {
+            None => {
+                edges.insert((from, to), i);
+            }
+            Some(kept) => {
+                let first = *kept;
+                let dropped = if tag > keys[first].2 {
+                    *kept = i;
+                    first
+                } else {
+                    i
+                };
+                keep[dropped] = false;
+                out.push(Dedupe::Duplicate {
+                    first,
+                    second: i,
+                    dropped,
+                });
+            }
+        }
+    }
+    let mut next: HashMap<S, u32> = HashMap::new();
+    for (i, &(from, _, tag)) in keys.iter().enumerate() {
+        if keep[i] {
+            let n = next.entry(from).or_default();
+            *n = (*n).max(tag.saturating_add(1));
+        }
+    }
+    let mut used = HashSet::new();
+    for (i, &(from, _, tag)) in keys.iter().enumerate() {
+        if keep[i] && !used.insert((from, tag)) {
+            let n = next.get_mut(&from).unwrap();
+            out.push(Dedupe::Retag {
+                index: i,
+                order_tag: *n,
+            });
+            used.insert((from, *n));
+            *n = n.saturating_add(1);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::{ConnGene, EmbedGene, GenomeMeta, LinkGene};
+    use bitvec::prelude::*;
+
+    fn conn(from: (u8, u32), to: (u8, u32), trigger: u8, order_tag: u32) -> ConnGene {
+        ConnGene {
+            from_section: from.0,
+            to_section: to.0,
+            trigger,
+            action: 0,
+            from_index: from.1,
+            to_index: to.1,
+            order_tag,
+            innovation: 0,
+        }
+    }
+
+    #[test]
+    fn repair_makes_genome_valid() {
+        let chunk = ChunkGene::new(
+            1,
+            1,
+            2,
+            bitvec![u8, Lsb0; 1, 0, 1],
+            bitvec![u8, Lsb0; 0],
+            bitvec![u8, Lsb0; 0, 1],
+            vec![
+                conn((0, 0), (1, 7), 0, 0), // clamped to internal 1
+                conn((0, 0), (1, 1), 0, 3), // duplicate with a higher tag
+                conn((1, 0), (0, 0), 0, 0), // bad edge
+                conn((1, 0), (2, 0), 9, 0), // bad trigger
+                conn((1, 0), (2, 0), 0, 0),
+                conn((1, 0), (1, 1), 0, 0), // reused tag
+            ],
+        );
+        let mut link = LinkGene::new(0, 5, 0, 0, 1, 0, 0).unwrap();
+        let mut genome = Genome {
+            chunks: vec![chunk.clone(), chunk],
+            links: vec![link.clone(), {
+                link.to_chunk = 4;
+                link
+            }],
+            embeds: vec![EmbedGene {
+                parent_chunk: 0,
+                child_chunk: 1,
+                gate_bit: 0,
+                gate_section: 1,
+                io_mode: 0,
+                map_in: vec![(0, 0), (9, 0)],
+                map_out: Vec::new(),
+            }],
+            meta: GenomeMeta::new(0, String::new()),
+        };
+        assert!(!genome.validate_all().is_empty());
+
+        let fixes = genome.repair();
+        assert!(genome.validate_all().is_empty());
+        assert_eq!(fixes.len(), 2 * 6 + 3);
+        assert_eq!(
+            fixes[1].to_string(),
+            "chunk 0 conn 0: to index 7 out of range for section 1; clamped to 1"
+        );
+        assert_eq!(
+            fixes[4],
+            Repair::ConnDropped {
+                chunk: 0,
+                conn: 0,
+                error: ValidationError::DuplicateConn {
+                    first: 0,
+                    second: 1
+                },
+            }
+        );
+        assert!(matches!(
+            fixes[5],
+            Repair::ConnRetagged {
+                conn: 5,
+                order_tag: 1,
+                ..
+            }
+        ));
+        assert!(matches!(fixes[13], Repair::LinkDropped { link: 1, .. }));
+        assert_eq!(genome.chunks[0].inputs_init.len(), 1);
+        assert_eq!(genome.chunks[0].conns.len(), 3);
+        assert_eq!(genome.links[0].from_out_idx, 0);
+        assert_eq!(genome.embeds[0].map_in, vec![(0, 0)]);
+        assert!(genome.repair().is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::chunk::MycosChunk;
+use crate::cpu_ref::System;
+use crate::genome::{Genome, ValidationError};
+use crate::link::Link;
+use crate::tasks::{EpisodeSpec, IoMap, Task};
+
+/// Round cap per tick before the settle loop gives up.
+pub const MAX_ROUNDS: u32 = 1024;
+
+/// Work performed while settling a single tick.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TickStats {
+    /// Number of wavefront rounds executed.
+    pub rounds: u32,
+    /// Number of effects applied.
+    pub effects: u32,
+    /// Whether the tick hit a guard before quiescence.
+    pub oscillator: bool,
+    /// Detected oscillation period when `oscillator` is true.
+    pub period: u32,
+}
+
+/// Captured outputs and per-tick stats for one episode.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EpisodeTrace {
+    /// Observed words per tick, packed in `IoMap::outputs` then
+    /// `IoMap::probes` order.
+    pub outputs: Vec<Vec<u32>>,
+    /// Stats for each tick.
+    pub ticks: Vec<TickStats>,
+}
+
+/// Runs a task's episodes tick by tick against a set of linked chunks.
+///
+/// Each episode starts from the chunks' initial bits without firing them.
+/// Every tick writes the stimulus through `IoMap::inputs` (bit `k` of the
+/// stimulus words drives `inputs[k]`), settles to quiescence and reads
+/// `IoMap::outputs` and then `IoMap::probes` back in the same packing.
+/// Mapped bits that fall outside a chunk are ignored on input and read as 0.
+/// Effects of delayed links land at the start of their tick, before the
+/// stimulus is written.
+pub struct EpisodeRunner<'a> {
+    task: &'a Task,
+    chunks: Vec<MycosChunk>,
+    links: Vec<Link>,
+}
+
+impl<'a> EpisodeRunner<'a> {
+    pub fn new(task: &'a Task, chunks: Vec<MycosChunk>, links: Vec<Link>) -> Self {
+        Self {
+            task,
+            chunks,
+            links,
+        }
+    }
+
+    /// Build a runner from a genome's chunks and links. Its embeds are not
+    /// run, so they do not affect the traces.
+    pub fn from_genome(task: &'a Task, genome: &Genome) -> Result<Self, ValidationError> {
+        Ok(Self::new(task, genome.to_chunks()?, genome.to_links()?))
+    }
+
+    /// Run a single episode from the initial state.
+    pub fn run_episode(&self, spec: &EpisodeSpec) -> EpisodeTrace {
+        self.run_episode_from(spec, &[])
+    }
+
+    /// Run a single episode after presetting mapped inputs from
+    /// `initial_inputs` (same packing as a stimulus). The preset is part of
+    /// the initial state and fires no edges.
+    pub fn run_episode_from(&self, spec: &EpisodeSpec, initial_inputs: &[u32]) -> EpisodeTrace {
+        let io = &self.task.io;
+        let mut system = System::new(&self.chunks, &self.links);
+        if !initial_inputs.is_empty() {
+            for (k, port) in io.inputs.iter().enumerate() {
+                system.preset_input(port.chunk_id, port.bit_idx, word_bit(initial_inputs, k));
+            }
+        }
+        let mut trace = EpisodeTrace::default();
+        for (tick, stimulus) in spec.stimulus.iter().enumerate() {
+            if tick > 0 {
+                system.advance_tick();
+            }
+            for (k, port) in io.inputs.iter().enumerate() {
+                system.write_input(port.chunk_id, port.bit_idx, word_bit(stimulus, k));
+            }
+            let stats = system.settle(MAX_ROUNDS);
+            trace.ticks.push(TickStats {
+                rounds: stats.rounds,
+                effects: stats.effects,
+                oscillator: stats.oscillator,
+                period: stats.period,
+            });
+            trace.outputs.push(read_outputs(&system, io));
+        }
+        trace
+    }
+
+    /// Run every episode of the task in order.
+    pub fn run(&self) -> Vec<EpisodeTrace> {
+        self.task
+            .episodes
+            .iter()
+            .map(|spec| self.run_episode(spec))
+            .collect()
+    }
+
+    /// Run every episode and return outputs in the shape `scoring::score`
+    /// expects.
+    pub fn outputs(&self) -> Vec<Vec<Vec<u32>>> {
+        self.run().into_iter().map(|t| t.outputs).collect()
+    }
+}
+
+pub(crate) fn word_bit(words: &[u32], k: usize) -> bool {
+    words.get(k / 32).is_some_and(|w| (w >> (k % 32)) & 1 != 0)
+}
+
+fn read_outputs(system: &System, io: &IoMap) -> Vec<u32> {
+    let mut words = vec![0u32; io.observed().div_ceil(32)];
+    let outputs = io
+        .outputs
+        .iter()
+        .map(|port| system.read_output(port.chunk_id, port.bit_idx));
+    let probes = io
+        .probes
+        .iter()
+        .map(|port| system.read_internal(port.chunk_id, port.bit_idx));
+    for (k, high) in outputs.chain(probes).enumerate() {
+        if high {
+            words[k / 32] |= 1 << (k % 32);
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::fixtures::{relay, wire};
+    use crate::chunk::{Action, Trigger};
+    use crate::scoring::score;
+    use crate::tasks::{t00_wire_echo, t04_cross_chunk_relay};
+
+    #[test]
+    fn wire_echo_scores_perfectly() {
+        let task = t00_wire_echo();
+        let runner = EpisodeRunner::new(&task, vec![wire()], Vec::new());
+        let traces = runner.run();
+        assert_eq!(traces[0].outputs, vec![vec![1]]);
+        assert_eq!(traces[0].ticks[0].rounds, 3);
+        assert_eq!(traces[0].ticks[0].effects, 2);
+        assert_eq!(traces[1].ticks[0], TickStats::default());
+        assert_eq!(score(&task, &runner.outputs(), &[]).unwrap().score, 1.0);
+    }
+
+    #[test]
+    fn relay_follows_link() {
+        let task = t04_cross_chunk_relay();
+        let link = relay(Trigger::On, Action::Enable, 0);
+        let runner = EpisodeRunner::new(&task, vec![wire(), wire()], vec![link]);
+        // the link only enables, so chunk 1 latches high from the first tick
+        assert_eq!(runner.outputs(), vec![vec![vec![1], vec![1]]]);
+
+        let unlinked = EpisodeRunner::new(&task, vec![wire(), wire()], Vec::new());
+        assert_eq!(unlinked.outputs(), vec![vec![vec![0], vec![0]]]);
+    }
+
+    #[test]
+    fn preset_inputs_fire_no_edges() {
+        let task = t00_wire_echo();
+        let runner = EpisodeRunner::new(&task, vec![wire()], Vec::new());
+        // input already high, so the tick's stimulus of 1 changes nothing
+        let trace = runner.run_episode_from(&task.episodes[0], &[1]);
+        assert_eq!(trace.outputs, vec![vec![0]]);
+        assert_eq!(trace.ticks[0], TickStats::default());
+    }
+
+    #[test]
+    fn probes_observe_internal_bits() {
+        let mut task = t00_wire_echo();
+        task.io.probes.push(crate::tasks::Io {
+            chunk_id: 0,
+            bit_idx: 0,
+        });
+        task.io.probes.push(crate::tasks::Io {
+            chunk_id: 0,
+            bit_idx: 5,
+        });
+        for episode in &mut task.episodes {
+            let high = episode.expected[0][0];
+            // output and internal bit agree; the out-of-range probe reads 0
+            episode.expected[0][0] = high | high << 1;
+        }
+        let runner = EpisodeRunner::new(&task, vec![wire()], Vec::new());
+        assert_eq!(runner.outputs(), vec![vec![vec![0b011]], vec![vec![0]]]);
+        assert_eq!(score(&task, &runner.outputs(), &[]).unwrap().score, 1.0);
+    }
+
+    #[test]
+    fn out_of_range_io_is_ignored() {
+        let task = t04_cross_chunk_relay();
+        let runner = EpisodeRunner::new(&task, vec![wire()], Vec::new());
+        assert_eq!(runner.outputs(), vec![vec![vec![0], vec![0]]]);
+    }
+}
@@ -1,9 +1,19 @@
 use crate::chunk::{MycosChunk, Section};
+use crate::link::Link;
 use petgraph::algo::kosaraju_scc;
 use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::visit::EdgeRef;
+use std::cmp::Reverse;
 use std::collections::{HashSet, VecDeque};
 
+/// A bit of one chunk in a multi-chunk system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemBit {
+    pub chunk: u32,
+    pub section: Section,
+    pub index: u32,
+}
+
 pub fn build_internal_graph(chunk: &MycosChunk) -> DiGraph<(), ()> {
     let mut graph = DiGraph::<(), ()>::new();
     let nodes: Vec<NodeIndex> = (0..chunk.internal_count)
@@ -23,9 +33,213 @@ pub fn build_internal_graph(chunk: &MycosChunk) -> DiGraph<(), ()> {
     graph
 }
 
+/// Graph over every bit of every chunk: connections within a chunk and
+/// links from an output of one chunk to an input of another.
+///
+/// Nodes are numbered chunk by chunk, each chunk's inputs, then internals,
+/// then outputs, and carry the [`SystemBit`] they stand for. Links must be
+/// in range, see [`validate_links`](crate::link::validate_links).
+pub fn build_system_graph(chunks: &[MycosChunk], links: &[Link]) -> DiGraph<SystemBit, ()> {
+    let mut graph = DiGraph::<SystemBit, ()>::new();
+    let mut bases = Vec::with_capacity(chunks.len());
+    for (c, chunk) in chunks.iter().enumerate() {
+        bases.push(graph.node_count());
+        for (section, count) in [
+            (Section::Input, chunk.input_count),
+            (Section::Internal, chunk.internal_count),
+            (Section::Output, chunk.output_count),
+        ] {
+            for index in 0..count {
+                graph.add_node(SystemBit {
+                    chunk: c as u32,
+                    section,
+                    index,
+                });
+            }
+        }
+    }
+
+    let node = |c: usize, section: Section, index: u32| {
+        let chunk = &chunks[c];
+        let offset = match section {
+            Section::Input => 0,
+            Section::Internal => chunk.input_count,
+            Section::Output => chunk.input_count + chunk.internal_count,
+        };
+        NodeIndex::new(bases[c] + (offset + index) as usize)
+    };
+    for (c, chunk) in chunks.iter().enumerate() {
+        for conn in &chunk.connections {
+            graph.add_edge(
+                node(c, conn.from_section, conn.from_index),
+                node(c, conn.to_section, conn.to_index),
+                (),
+            );
+        }
+    }
+    for link in links {
+        graph.add_edge(
+            node(link.from_chunk as usize, Section::Output, link.from_out_idx),
+            node(link.to_chunk as usize, Section::Input, link.to_in_idx),
+            (),
+        );
+    }
+
+    graph
+}
+
 pub fn scc_ids_and_topo_levels(chunk: &MycosChunk) -> (Vec<usize>, Vec<usize>) {
-    let graph = build_internal_graph(chunk);
-    let sccs = kosaraju_scc(&graph);
+    components_and_levels(&build_internal_graph(chunk))
+}
+
+/// [`scc_ids_and_topo_levels`] over [`build_system_graph`]: component ids
+/// per system node and the topological level of each component.
+pub fn system_scc_ids_and_topo_levels(
+    chunks: &[MycosChunk],
+    links: &[Link],
+) -> (Vec<usize>, Vec<usize>) {
+    components_and_levels(&build_system_graph(chunks, links))
+}
+
+/// Feedback loops that pass through more than one chunk, one list of bits
+/// per strongly connected component. Such loops only exist once links are
+/// taken into account, so per-chunk analysis cannot see them.
+pub fn cross_chunk_loops(chunks: &[MycosChunk], links: &[Link]) -> Vec<Vec<SystemBit>> {
+    let graph = build_system_graph(chunks, links);
+    kosaraju_scc(&graph)
+        .into_iter()
+        .map(|component| {
+            let mut bits: Vec<SystemBit> = component.iter().map(|&n| graph[n]).collect();
+            bits.sort_by_key(|b| (b.chunk, b.section as u8, b.index));
+            bits
+        })
+        .filter(|bits| bits.iter().any(|b| b.chunk != bits[0].chunk))
+        .collect()
+}
+
+/// A connection or link of a multi-chunk system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemEdge {
+    /// `chunks[chunk].connections[index]`.
+    Connection { chunk: u32, index: u32 },
+    /// `links[index]`.
+    Link { index: u32 },
+}
+
+/// An edge whose removal helps break a feedback loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeedbackArc {
+    pub edge: SystemEdge,
+    /// Bits in the strongly connected component the edge closes.
+    pub component_size: usize,
+}
+
+/// Approximate minimum feedback arc set of the system graph, largest
+/// components first.
+///
+/// Each cyclic component (more than one bit, or a bit feeding itself) is
+/// ordered with the Eades–Lin–Smyth heuristic; the edges pointing backwards
+/// in that order are reported. Removing all of them leaves the graph
+/// acyclic, and cutting the ones of the largest components first breaks the
+/// most oscillation-prone loops. Embeds are not part of the graph.
+pub fn feedback_arcs(chunks: &[MycosChunk], links: &[Link]) -> Vec<FeedbackArc> {
+    let graph = build_system_graph(chunks, links);
+    let mut edges = Vec::with_capacity(graph.edge_count());
+    for (c, chunk) in chunks.iter().enumerate() {
+        edges.extend(
+            (0..chunk.connections.len()).map(|i| SystemEdge::Connection {
+                chunk: c as u32,
+                index: i as u32,
+            }),
+        );
+    }
+    edges.extend((0..links.len()).map(|i| SystemEdge::Link { index: i as u32 }));
+
+    let mut arcs = Vec::new();
+    for component in kosaraju_scc(&graph) {
+        let mut position = vec![usize::MAX; graph.node_count()];
+        for (i, &n) in component.iter().enumerate() {
+            position[n.index()] = i;
+        }
+        // edges inside the component as local (from, to, edge index) triples
+        let inner: Vec<(usize, usize, usize)> = component
+            .iter()
+            .flat_map(|&n| graph.edges(n))
+            .filter(|e| position[e.target().index()] != usize::MAX)
+            .map(|e| {
+                (
+                    position[e.source().index()],
+                    position[e.target().index()],
+                    e.id().index(),
+                )
+            })
+            .collect();
+        if component.len() == 1 && inner.is_empty() {
+            continue;
+        }
+        let order = eades_order(component.len(), &inner);
+        let mut backward: Vec<usize> = inner
+            .iter()
+            .filter(|&&(from, to, _)| order[to] <= order[from])
+            .map(|&(_, _, e)| e)
+            .collect();
+        backward.sort_unstable();
+        arcs.extend(backward.into_iter().map(|e| FeedbackArc {
+            edge: edges[e],
+            component_size: component.len(),
+        }));
+    }
+    arcs.sort_by_key(|a| Reverse(a.component_size));
+    arcs
+}
+
+/// Rank of each of `n` nodes in a vertex order with few backward edges:
+/// sinks are peeled to the back, sources to the front, and otherwise the
+/// node with the largest out-degree minus in-degree goes next.
+fn eades_order(n: usize, edges: &[(usize, usize, usize)]) -> Vec<usize> {
+    let mut outs = vec![Vec::new(); n];
+    let mut ins = vec![Vec::new(); n];
+    let (mut out_deg, mut in_deg) = (vec![0isize; n], vec![0isize; n]);
+    for &(from, to, _) in edges {
+        if from != to {
+            outs[from].push(to);
+            ins[to].push(from);
+            out_deg[from] += 1;
+            in_deg[to] += 1;
+        }
+    }
+    let mut removed = vec![false; n];
+    let (mut front, mut back) = (Vec::with_capacity(n), Vec::new());
+    for _ in 0..n {
+        let live = || (0..n).filter(|&v| !removed[v]);
+        let next = if let Some(v) = live().find(|&v| out_deg[v] == 0) {
+            back.push(v);
+            v
+        } else {
+            let v = live()
+                .find(|&v| in_deg[v] == 0)
+                .or_else(|| live().max_by_key(|&v| (out_deg[v] - in_deg[v], Reverse(v))))
+                .expect("a node is left");
+            front.push(v);
+            v
+        };
+        removed[next] = true;
+        for &u in &ins[next] {
+            out_deg[u] -= 1;
+        }
+        for &w in &outs[next] {
+            in_deg[w] -= 1;
+        }
+    }
+    let mut rank = vec![0; n];
+    for (i, v) in front.into_iter().chain(back.into_iter().rev()).enumerate() {
+        rank[v] = i;
+    }
+    rank
+}
+
+fn components_and_levels<N>(graph: &DiGraph<N, ()>) -> (Vec<usize>, Vec<usize>) {
+    let sccs = kosaraju_scc(graph);
 
     let mut scc_ids = vec![0usize; graph.node_count()];
     for (id, component) in sccs.iter().enumerate() {
@@ -77,7 +291,7 @@ pub fn scc_ids_and_topo_levels(chunk: &MycosChunk) -> (Vec<usize>, Vec<usize>) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::chunk::{parse_chunk, validate_chunk};
+    use crate::chunk::{parse_chunk, validate_chunk, Action, Connection, Trigger};
     use std::fs;
     use std::path::PathBuf;
 
@@ -121,4 +335,117 @@ mod tests {
         assert_eq!(scc_ids, vec![0, 0]);
         assert_eq!(levels, vec![0]);
     }
+
+    #[test]
+    fn links_close_cross_chunk_loops() {
+        // each chunk wires its input to its output; linking the two chunks
+        // both ways forms a loop no single chunk contains
+        let wire = || MycosChunk {
+            input_bits: vec![0],
+            output_bits: vec![0],
+            internal_bits: vec![0],
+            input_count: 1,
+            output_count: 1,
+            internal_count: 1,
+            connections: [
+                (Section::Input, Section::Internal),
+                (Section::Internal, Section::Output),
+            ]
+            .into_iter()
+            .map(|(from_section, to_section)| Connection {
+                from_section,
+                to_section,
+                trigger: Trigger::On,
+                action: Action::Enable,
+                from_index: 0,
+                to_index: 0,
+                order_tag: 0,
+            })
+            .collect(),
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        };
+        let link = |from_chunk, to_chunk| Link {
+            from_chunk,
+            from_out_idx: 0,
+            trigger: Trigger::On,
+            action: Action::Enable,
+            to_chunk,
+            to_in_idx: 0,
+            order_tag: 0,
+            delay: 0,
+        };
+        let chunks = [wire(), wire()];
+        let graph = build_system_graph(&chunks, &[link(0, 1)]);
+        assert_eq!((graph.node_count(), graph.edge_count()), (6, 5));
+        assert_eq!(
+            graph[NodeIndex::new(5)],
+            SystemBit {
+                chunk: 1,
+                section: Section::Output,
+                index: 0
+            }
+        );
+        assert!(cross_chunk_loops(&chunks, &[link(0, 1)]).is_empty());
+        let (_, levels) = system_scc_ids_and_topo_levels(&chunks, &[link(0, 1)]);
+        assert_eq!(levels.iter().max(), Some(&5));
+
+        let links = [link(0, 1), link(1, 0)];
+        let loops = cross_chunk_loops(&chunks, &links);
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].len(), 6);
+        let (ids, levels) = system_scc_ids_and_topo_levels(&chunks, &links);
+        assert!(ids.iter().all(|&id| id == ids[0]));
+        assert_eq!(levels, vec![0]);
+    }
+
+    #[test]
+    fn feedback_arcs_break_every_loop() {
+        // a 3-ring over internals 0..3, a 2-loop over 3 and 4, a self-loop on
+        // 5 and an acyclic edge from 0 to 5
+        let conn = |from_index, to_index| Connection {
+            from_section: Section::Internal,
+            to_section: Section::Internal,
+            trigger: Trigger::Toggle,
+            action: Action::Toggle,
+            from_index,
+            to_index,
+            order_tag: 0,
+        };
+        let edges = [(0, 1), (1, 2), (2, 0), (3, 4), (4, 3), (5, 5), (0, 5)];
+        let mut chunk = MycosChunk {
+            input_bits: vec![],
+            output_bits: vec![],
+            internal_bits: vec![0],
+            input_count: 0,
+            output_count: 0,
+            internal_count: 6,
+            connections: edges.iter().map(|&(f, t)| conn(f, t)).collect(),
+            name: None,
+            note: None,
+            build_hash: None,
+            ports: Vec::new(),
+        };
+        let arcs = feedback_arcs(std::slice::from_ref(&chunk), &[]);
+        let sizes: Vec<usize> = arcs.iter().map(|a| a.component_size).collect();
+        assert_eq!(sizes, vec![3, 2, 1]);
+        assert_eq!(arcs[2].edge, SystemEdge::Connection { chunk: 0, index: 5 });
+
+        let mut cut: Vec<usize> = arcs
+            .iter()
+            .map(|a| match a.edge {
+                SystemEdge::Connection { index, .. } => index as usize,
+                SystemEdge::Link { .. } => unreachable!(),
+            })
+            .collect();
+        cut.sort_unstable();
+        for i in cut.into_iter().rev() {
+            chunk.connections.remove(i);
+        }
+        assert!(feedback_arcs(std::slice::from_ref(&chunk), &[]).is_empty());
+        let (ids, _) = scc_ids_and_topo_levels(&chunk);
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 6);
+    }
 }
@@ -1,48 +1,327 @@
+use serde::{Deserialize, Serialize};
+
+use crate::gpu_eval::{EpisodeMetrics, FitnessResult};
 use crate::tasks::{EpisodeSpec, Task};
+use crate::Genome;
 
 /// Scoring strategies supported by the engine.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ScoringSpec {
     /// Measure Hamming similarity of outputs versus expected targets.
     /// The score is `1.0 - H(outputs XOR targets) / M`, where `M` is the
     /// number of observed output bits.
     Hamming,
+    /// Hamming similarity with per-bit weights and per-tick don't-care
+    /// masks. The score is `1.0 - Σ w·d / Σ w` over the scored bits, where
+    /// `d` is 1 for a wrong bit, or 1.0 when no bit is scored.
+    WeightedHamming {
+        /// Weight of each output bit; bits past the end weigh 1.0.
+        weights: Vec<f32>,
+        /// Per tick, the output bits that are scored, packed like the
+        /// outputs. Ticks past the end score every bit.
+        care: Vec<Vec<u32>>,
+    },
+    /// Hamming similarity scaled by `exp(-alpha · rounds / ticks)`, so of
+    /// two equally correct genomes the one settling in fewer wavefront
+    /// rounds per tick scores higher. Rounds come from the episode's
+    /// [`EpisodeMetrics`].
+    LatencyWeighted { alpha: f32 },
+}
+
+/// Energy penalty blended into every episode score: the score drops by
+/// `effects` per effect applied per tick and by `oscillation` when the
+/// episode oscillated. Both default to zero, and an episode without metrics
+/// is not penalized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivityPenalty {
+    pub effects: f32,
+    pub oscillation: f32,
+}
+
+impl ActivityPenalty {
+    /// Amount subtracted from the score of a `ticks`-tick episode.
+    pub fn penalty(&self, metrics: &EpisodeMetrics, ticks: usize) -> f32 {
+        let effects = metrics.effects as f32 / ticks.max(1) as f32;
+        self.effects * effects
+            + if metrics.oscillator {
+                self.oscillation
+            } else {
+                0.0
+            }
+    }
 }
 
 /// Compute a fitness score for a task given the captured outputs for each
-/// episode. `outputs` must have the same shape as `task.episodes`: a vector of
-/// episodes, each containing per-tick output words.
-pub fn score(task: &Task, outputs: &[Vec<Vec<u32>>]) -> f32 {
-    assert_eq!(task.episodes.len(), outputs.len());
-    match task.scoring {
-        ScoringSpec::Hamming => {
-            let mut total_score = 0.0f32;
-            for (spec, actual) in task.episodes.iter().zip(outputs.iter()) {
-                total_score += hamming_episode(spec, actual, task.io.outputs.len());
+/// episode, with its per-episode and per-tick components. `outputs` must have
+/// the same shape as the expected outputs of `task.episodes`: a vector of
+/// episodes, each containing per-tick output words; otherwise a
+/// [`ScoreError`] describes the first mismatch. `metrics` holds the matching
+/// execution metrics; an episode without them counts as having taken zero
+/// rounds. A task without episodes scores 0.
+pub fn score(
+    task: &Task,
+    outputs: &[Vec<Vec<u32>>],
+    metrics: &[EpisodeMetrics],
+) -> Result<ScoreBreakdown, ScoreError> {
+    if outputs.len() != task.episodes.len() {
+        return Err(ScoreError::EpisodeCount {
+            expected: task.episodes.len(),
+            found: outputs.len(),
+        });
+    }
+    for (episode, (spec, actual)) in task.episodes.iter().zip(outputs).enumerate() {
+        if actual.len() != spec.expected.len() {
+            return Err(ScoreError::TickCount {
+                episode,
+                expected: spec.expected.len(),
+                found: actual.len(),
+            });
+        }
+        for (tick, (e, a)) in spec.expected.iter().zip(actual).enumerate() {
+            if a.len() != e.len() {
+                return Err(ScoreError::WordCount {
+                    episode,
+                    tick,
+                    expected: e.len(),
+                    found: a.len(),
+                });
+            }
+        }
+    }
+    Ok(score_lenient(task, outputs, metrics))
+}
+
+/// [`score`] for outputs that may be truncated, e.g. by an evaluator that
+/// stopped early. Missing episodes, ticks and words count as wrong on every
+/// scored bit and are flagged in [`EpisodeScore::missing_ticks`]; extra ones
+/// are ignored. Equals `score` on well-shaped outputs.
+pub fn score_lenient(
+    task: &Task,
+    outputs: &[Vec<Vec<u32>>],
+    metrics: &[EpisodeMetrics],
+) -> ScoreBreakdown {
+    let output_bits = task.io.observed();
+    let (weights, care): (&[f32], &[Vec<u32>]) = match &task.scoring {
+        ScoringSpec::WeightedHamming { weights, care } => (weights, care),
+        _ => (&[], &[]),
+    };
+    let episodes: Vec<EpisodeScore> = task
+        .episodes
+        .iter()
+        .enumerate()
+        .map(|(i, spec)| {
+            let actual = outputs.get(i).map_or(&[][..], |o| &o[..]);
+            let (accuracy, ticks, missing_ticks) =
+                accuracy(spec, actual, output_bits, weights, care);
+            let metrics = metrics.get(i);
+            let latency = match task.scoring {
+                ScoringSpec::LatencyWeighted { alpha } => {
+                    let rounds = metrics.map_or(0, |m| m.rounds) as f32;
+                    (-alpha * rounds / spec.expected.len().max(1) as f32).exp()
+                }
+                _ => 1.0,
+            };
+            let penalty = metrics.map_or(0.0, |m| task.activity.penalty(m, spec.expected.len()));
+            EpisodeScore {
+                score: accuracy * latency - penalty,
+                accuracy,
+                ticks,
+                latency,
+                penalty,
+                missing_ticks,
+            }
+        })
+        .collect();
+    let score = if episodes.is_empty() {
+        0.0
+    } else {
+        episodes.iter().map(|e| e.score).sum::<f32>() / episodes.len() as f32
+    };
+    ScoreBreakdown { score, episodes }
+}
+
+/// Per-episode scores whose mean is [`score`]. Lexicase selection uses these
+/// as its test cases.
+pub fn score_episodes(
+    task: &Task,
+    outputs: &[Vec<Vec<u32>>],
+    metrics: &[EpisodeMetrics],
+) -> Result<Vec<f32>, ScoreError> {
+    let breakdown = score(task, outputs, metrics)?;
+    Ok(breakdown.episodes.iter().map(|e| e.score).collect())
+}
+
+/// Outputs whose shape differs from a task's expected outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScoreError {
+    EpisodeCount {
+        expected: usize,
+        found: usize,
+    },
+    TickCount {
+        episode: usize,
+        expected: usize,
+        found: usize,
+    },
+    WordCount {
+        episode: usize,
+        tick: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl std::fmt::Display for ScoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScoreError::EpisodeCount { expected, found } => {
+                write!(f, "expected outputs for {expected} episodes, got {found}")
             }
-            total_score / task.episodes.len() as f32
+            ScoreError::TickCount {
+                episode,
+                expected,
+                found,
+            } => write!(
+                f,
+                "episode {episode}: expected {expected} ticks, got {found}"
+            ),
+            ScoreError::WordCount {
+                episode,
+                tick,
+                expected,
+                found,
+            } => write!(
+                f,
+                "episode {episode} tick {tick}: expected {expected} output words, got {found}"
+            ),
         }
     }
 }
 
-fn hamming_episode(spec: &EpisodeSpec, actual: &[Vec<u32>], output_bits: usize) -> f32 {
-    assert_eq!(spec.expected.len(), actual.len());
-    let mut total_bits = 0u32;
-    let mut diff_bits = 0u32;
-    for (expected_tick, actual_tick) in spec.expected.iter().zip(actual.iter()) {
-        assert_eq!(expected_tick.len(), actual_tick.len());
-        for (e, a) in expected_tick.iter().zip(actual_tick.iter()) {
-            diff_bits += (e ^ a).count_ones();
+impl std::error::Error for ScoreError {}
+
+/// Components of a task score, for reports and debugging.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    /// Mean of the episode scores.
+    pub score: f32,
+    pub episodes: Vec<EpisodeScore>,
+}
+
+/// How one episode scored: `score = accuracy · latency - penalty`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EpisodeScore {
+    pub score: f32,
+    /// Output similarity over the whole episode, weighted and masked for
+    /// `WeightedHamming`.
+    pub accuracy: f32,
+    /// Output similarity of each tick on its own; 1.0 for a tick with no
+    /// scored bits.
+    pub ticks: Vec<f32>,
+    /// Factor from `LatencyWeighted`; 1.0 for other specs.
+    pub latency: f32,
+    /// Subtracted [`ActivityPenalty`].
+    pub penalty: f32,
+    /// Ticks whose outputs were absent or short, scored as wrong; only
+    /// [`score_lenient`] produces these.
+    pub missing_ticks: usize,
+}
+
+impl std::fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "score {:.4}", self.score)?;
+        for (i, e) in self.episodes.iter().enumerate() {
+            write!(
+                f,
+                "\n  episode {i}: {:.4} = accuracy {:.4} x latency {:.4} - penalty {:.4}; ticks [",
+                e.score, e.accuracy, e.latency, e.penalty
+            )?;
+            for (t, s) in e.ticks.iter().enumerate() {
+                let sep = if t == 0 { "" } else { " " };
+                write!(f, "{sep}{s:.2}")?;
+            }
+            write!(f, "]")?;
+            if e.missing_ticks > 0 {
+                write!(f, " ({} missing)", e.missing_ticks)?;
+            }
         }
-        total_bits += output_bits as u32;
+        Ok(())
     }
-    if total_bits == 0 {
-        1.0
-    } else {
-        1.0 - diff_bits as f32 / total_bits as f32
+}
+
+/// Parsimony pressure: per-unit fitness penalties on genome size and
+/// activity. All coefficients default to zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Parsimony {
+    /// Per connection gene, over all chunks.
+    pub conns: f32,
+    /// Per inter-chunk link.
+    pub links: f32,
+    /// Per chunk.
+    pub chunks: f32,
+    /// Per effect applied, averaged over episodes.
+    pub effects: f32,
+}
+
+impl Parsimony {
+    /// Amount subtracted from the fitness of `genome` evaluated as `result`.
+    pub fn penalty(&self, genome: &Genome, result: &FitnessResult) -> f32 {
+        let conns: usize = genome.chunks.iter().map(|c| c.conns.len()).sum();
+        let effects = result.metrics.iter().map(|m| m.effects as f32).sum::<f32>()
+            / result.metrics.len().max(1) as f32;
+        self.conns * conns as f32
+            + self.links * genome.links.len() as f32
+            + self.chunks * genome.chunks.len() as f32
+            + self.effects * effects
     }
 }
 
+/// Weighted share of correct scored bits over the episode and per tick,
+/// plus the number of ticks with absent or short outputs, whose missing bits
+/// count as wrong. Without weights or a mask this is plain Hamming
+/// similarity.
+fn accuracy(
+    spec: &EpisodeSpec,
+    actual: &[Vec<u32>],
+    output_bits: usize,
+    weights: &[f32],
+    care: &[Vec<u32>],
+) -> (f32, Vec<f32>, usize) {
+    let bit = |words: &[u32], k: usize| words.get(k / 32).map(|w| (w >> (k % 32)) & 1);
+    let similarity = |wrong: f32, total: f32| {
+        if total == 0.0 {
+            1.0
+        } else {
+            1.0 - wrong / total
+        }
+    };
+    let (mut total, mut wrong) = (0.0f32, 0.0f32);
+    let mut ticks = Vec::with_capacity(spec.expected.len());
+    let mut missing = 0;
+    for (t, expected_tick) in spec.expected.iter().enumerate() {
+        let actual_tick = actual.get(t).map_or(&[][..], |a| &a[..]);
+        if actual_tick.len() < expected_tick.len() {
+            missing += 1;
+        }
+        let (mut tick_total, mut tick_wrong) = (0.0f32, 0.0f32);
+        for k in 0..output_bits {
+            if care.get(t).is_some_and(|mask| bit(mask, k) != Some(1)) {
+                continue;
+            }
+            let w = weights.get(k).copied().unwrap_or(1.0);
+            tick_total += w;
+            let got = bit(actual_tick, k);
+            if got.is_none() || got != Some(bit(expected_tick, k).unwrap_or(0)) {
+                tick_wrong += w;
+            }
+        }
+        ticks.push(similarity(tick_wrong, tick_total));
+        total += tick_total;
+        wrong += tick_wrong;
+    }
+    (similarity(wrong, total), ticks, missing)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,8 +348,164 @@ mod tests {
         let task = t00_wire_echo();
         let good = perfect_outputs(&task);
         let bad = flipped_outputs(&task);
-        assert_eq!(score(&task, &good), 1.0);
-        assert!(score(&task, &bad) < 1.0);
+        assert_eq!(score(&task, &good, &[]).unwrap().score, 1.0);
+        assert!(score(&task, &bad, &[]).unwrap().score < 1.0);
+        let per_episode = score_episodes(&task, &bad, &[]).unwrap();
+        assert!(per_episode[0] < 1.0);
+        assert!(per_episode[1..].iter().all(|&s| s == 1.0));
+    }
+
+    #[test]
+    fn breakdown_explains_the_score() {
+        let task = t02_sr_latch();
+        let breakdown = score(&task, &flipped_outputs(&task), &[]).unwrap();
+        let first = &breakdown.episodes[0];
+        assert_eq!(first.ticks, vec![0.0, 1.0]);
+        assert_eq!(
+            (first.accuracy, first.latency, first.penalty),
+            (0.5, 1.0, 0.0)
+        );
+        assert_eq!(first.score, 0.5);
+        let n = breakdown.episodes.len() as f32;
+        assert_eq!(breakdown.score, (n - 0.5) / n);
+        let text = breakdown.to_string();
+        assert!(text.contains("episode 0: 0.5000 = accuracy 0.5000"));
+        assert!(text.contains("ticks [0.00 1.00]"));
+    }
+
+    #[test]
+    fn shape_mismatches_error_or_count_as_wrong() {
+        let task = t02_sr_latch();
+        let mut outputs = perfect_outputs(&task);
+        outputs[1].pop();
+        assert_eq!(
+            score(&task, &outputs, &[]).unwrap_err(),
+            ScoreError::TickCount {
+                episode: 1,
+                expected: 2,
+                found: 1
+            }
+        );
+        outputs[0][0].clear();
+        assert!(matches!(
+            score(&task, &outputs, &[]),
+            Err(ScoreError::WordCount { episode: 0, .. })
+        ));
+        let short = &outputs[..1];
+        assert!(matches!(
+            score_episodes(&task, short, &[]),
+            Err(ScoreError::EpisodeCount { .. })
+        ));
+
+        let lenient = score_lenient(&task, short, &[]);
+        // episode 0 lost its first tick's word, episode 1 everything
+        assert_eq!(lenient.episodes[0].ticks, vec![0.0, 1.0]);
+        assert_eq!(lenient.episodes[0].missing_ticks, 1);
+        assert_eq!(lenient.episodes[1].score, 0.0);
+        assert_eq!(lenient.episodes[1].missing_ticks, 2);
+        assert!(lenient.to_string().contains("(2 missing)"));
+        let good = perfect_outputs(&task);
+        assert_eq!(
+            score_lenient(&task, &good, &[]),
+            score(&task, &good, &[]).unwrap()
+        );
+
+        let mut empty = task.clone();
+        empty.episodes.clear();
+        assert_eq!(score(&empty, &[], &[]).unwrap().score, 0.0);
+    }
+
+    #[test]
+    fn weighted_hamming_skips_dont_care_bits() {
+        let mut task = t02_sr_latch();
+        // a second output that is expected and observed as 0 throughout
+        task.io.outputs.push(task.io.outputs[0].clone());
+        let outputs = flipped_outputs(&task);
+        // the flipped bit is output 0 on tick 0 of episode 0
+        task.scoring = ScoringSpec::WeightedHamming {
+            weights: vec![],
+            care: vec![vec![0b10]],
+        };
+        assert_eq!(score_episodes(&task, &outputs, &[]).unwrap()[0], 1.0);
+        task.scoring = ScoringSpec::WeightedHamming {
+            weights: vec![3.0, 1.0],
+            care: vec![],
+        };
+        let ticks = task.episodes[0].expected.len() as f32;
+        let weighted = score_episodes(&task, &outputs, &[]).unwrap()[0];
+        assert!((weighted - (1.0 - 3.0 / (4.0 * ticks))).abs() < 1e-6);
+        task.scoring = ScoringSpec::WeightedHamming {
+            weights: vec![1.0; 2],
+            care: vec![],
+        };
+        let plain = score_episodes(&task, &outputs, &[]).unwrap();
+        task.scoring = ScoringSpec::Hamming;
+        assert_eq!(plain, score_episodes(&task, &outputs, &[]).unwrap());
+    }
+
+    #[test]
+    fn latency_weighting_prefers_fewer_rounds() {
+        let mut task = t01_xor_2();
+        task.scoring = ScoringSpec::LatencyWeighted { alpha: 0.5 };
+        let good = perfect_outputs(&task);
+        let rounds = |rounds| {
+            vec![
+                EpisodeMetrics {
+                    rounds,
+                    ..EpisodeMetrics::default()
+                };
+                task.episodes.len()
+            ]
+        };
+        assert_eq!(score(&task, &good, &[]).unwrap().score, 1.0);
+        let fast = score(&task, &good, &rounds(1)).unwrap().score;
+        let slow = score(&task, &good, &rounds(10)).unwrap().score;
+        assert!(1.0 > fast && fast > slow && slow > 0.0);
+        let ticks = task.episodes[0].expected.len() as f32;
+        assert!((fast - (-0.5 / ticks).exp()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn activity_penalty_lowers_busy_episodes() {
+        let mut task = t00_wire_echo();
+        task.activity = ActivityPenalty {
+            effects: 0.1,
+            oscillation: 0.5,
+        };
+        let good = perfect_outputs(&task);
+        let mut metrics = vec![EpisodeMetrics::default(); task.episodes.len()];
+        let ticks = task.episodes[0].expected.len();
+        metrics[0].effects = 2 * ticks as u32;
+        metrics[1].oscillator = true;
+        let scores = score_episodes(&task, &good, &metrics).unwrap();
+        assert!((scores[0] - 0.8).abs() < 1e-6);
+        assert_eq!(scores[1], 0.5);
+        assert!(scores[2..].iter().all(|&s| s == 1.0));
+        assert_eq!(score(&task, &good, &[]).unwrap().score, 1.0);
+    }
+
+    #[test]
+    fn parsimony_scales_with_size() {
+        use crate::builder::fixtures::wire_genome;
+        use crate::gpu_eval::EpisodeMetrics;
+
+        let genome = wire_genome();
+        let metrics = |effects| EpisodeMetrics {
+            effects,
+            ..EpisodeMetrics::default()
+        };
+        let result = FitnessResult {
+            metrics: vec![metrics(2), metrics(4)],
+            ..FitnessResult::default()
+        };
+        assert_eq!(Parsimony::default().penalty(&genome, &result), 0.0);
+        let p = Parsimony {
+            conns: 0.5,
+            links: 10.0,
+            chunks: 2.0,
+            effects: 0.25,
+        };
+        assert_eq!(p.penalty(&genome, &result), 2.0 + 0.0 + 2.0 + 0.75);
     }
 
     #[test]
@@ -78,8 +513,8 @@ mod tests {
         let task = t01_xor_2();
         let good = perfect_outputs(&task);
         let bad = flipped_outputs(&task);
-        assert_eq!(score(&task, &good), 1.0);
-        assert!(score(&task, &bad) < 1.0);
+        assert_eq!(score(&task, &good, &[]).unwrap().score, 1.0);
+        assert!(score(&task, &bad, &[]).unwrap().score < 1.0);
     }
 
     #[test]
@@ -87,8 +522,8 @@ mod tests {
         let task = t02_sr_latch();
         let good = perfect_outputs(&task);
         let bad = flipped_outputs(&task);
-        assert_eq!(score(&task, &good), 1.0);
-        assert!(score(&task, &bad) < 1.0);
+        assert_eq!(score(&task, &good, &[]).unwrap().score, 1.0);
+        assert!(score(&task, &bad, &[]).unwrap().score < 1.0);
     }
 
     #[test]
@@ -96,8 +531,8 @@ mod tests {
         let task = t03_pulse_counter();
         let good = perfect_outputs(&task);
         let bad = flipped_outputs(&task);
-        assert_eq!(score(&task, &good), 1.0);
-        assert!(score(&task, &bad) < 1.0);
+        assert_eq!(score(&task, &good, &[]).unwrap().score, 1.0);
+        assert!(score(&task, &bad, &[]).unwrap().score < 1.0);
     }
 
     #[test]
@@ -105,7 +540,7 @@ mod tests {
         let task = t04_cross_chunk_relay();
         let good = perfect_outputs(&task);
         let bad = flipped_outputs(&task);
-        assert_eq!(score(&task, &good), 1.0);
-        assert!(score(&task, &bad) < 1.0);
+        assert_eq!(score(&task, &good, &[]).unwrap().score, 1.0);
+        assert!(score(&task, &bad, &[]).unwrap().score < 1.0);
     }
 }
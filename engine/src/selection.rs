@@ -0,0 +1,158 @@
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// Parent selection scheme used within each species.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Selection {
+    /// Best of `size` uniformly drawn candidates.
+    Tournament { size: usize },
+    /// Probability proportional to rank, the worst ranking 1.
+    Rank,
+    /// Probability proportional to score above the minimum; uniform when
+    /// all scores are equal.
+    Roulette,
+    /// Uniform among the best `fraction` of candidates, at least one.
+    Truncation { fraction: f32 },
+    /// Filter by per-episode cases in random order, keeping the best on each,
+    /// and pick uniformly among the survivors.
+    Lexicase,
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Selection::Tournament { size: 3 }
+    }
+}
+
+impl Selection {
+    /// Index of the chosen candidate. `cases` holds the per-episode scores of
+    /// each candidate and is only read by `Lexicase`.
+    ///
+    /// Panics if `scores` is empty.
+    pub fn select(&self, scores: &[f32], cases: &[&[f32]], rng: &mut dyn RngCore) -> usize {
+        match *self {
+            Selection::Tournament { size } => tournament(scores, size, rng),
+            Selection::Rank => {
+                let order = ascending(scores);
+                let n = order.len();
+                let mut pick = rng.gen_range(0..n * (n + 1) / 2);
+                for (rank, &i) in order.iter().enumerate() {
+                    if pick <= rank {
+                        return i;
+                    }
+                    pick -= rank + 1;
+                }
+                order[n - 1]
+            }
+            Selection::Roulette => {
+                let min = scores.iter().copied().fold(f32::INFINITY, f32::min);
+                let total: f32 = scores.iter().map(|s| s - min).sum();
+                if total <= 0.0 {
+                    return rng.gen_range(0..scores.len());
+                }
+                let mut pick = rng.gen::<f32>() * total;
+                for (i, s) in scores.iter().enumerate() {
+                    pick -= s - min;
+                    if pick < 0.0 {
+                        return i;
+                    }
+                }
+                scores.len() - 1
+            }
+            Selection::Truncation { fraction } => {
+                let order = ascending(scores);
+                let keep = ((order.len() as f32 * fraction).ceil() as usize).clamp(1, order.len());
+                order[order.len() - 1 - rng.gen_range(0..keep)]
+            }
+            Selection::Lexicase => lexicase(cases, rng),
+        }
+    }
+}
+
+/// Candidate indices sorted by ascending score.
+fn ascending(scores: &[f32]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[a].partial_cmp(&scores[b]).unwrap());
+    order
+}
+
+fn tournament(scores: &[f32], k: usize, rng: &mut dyn RngCore) -> usize {
+    let mut best_idx = rng.gen_range(0..scores.len());
+    let mut best_fit = scores[best_idx];
+    for _ in 1..k {
+        let idx = rng.gen_range(0..scores.len());
+        if scores[idx] > best_fit {
+            best_fit = scores[idx];
+            best_idx = idx;
+        }
+    }
+    best_idx
+}
+
+fn lexicase(cases: &[&[f32]], rng: &mut dyn RngCore) -> usize {
+    let mut pool: Vec<usize> = (0..cases.len()).collect();
+    let n_cases = cases.iter().map(|c| c.len()).min().unwrap_or(0);
+    let mut order: Vec<usize> = (0..n_cases).collect();
+    order.shuffle(rng);
+    for case in order {
+        if pool.len() == 1 {
+            break;
+        }
+        let best = pool
+            .iter()
+            .map(|&i| cases[i][case])
+            .fold(f32::NEG_INFINITY, f32::max);
+        pool.retain(|&i| cases[i][case] == best);
+    }
+    pool[rng.gen_range(0..pool.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn counts(selection: Selection, scores: &[f32], cases: &[&[f32]]) -> Vec<usize> {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut picked = vec![0; scores.len()];
+        for _ in 0..2000 {
+            picked[selection.select(scores, cases, &mut rng)] += 1;
+        }
+        picked
+    }
+
+    #[test]
+    fn schemes_favour_higher_scores() {
+        let scores = [0.1, 0.4, 0.2, 0.3];
+        let rank = counts(Selection::Rank, &scores, &[]);
+        // weights 1:4:2:3 out of 10
+        assert!(rank[0] < rank[2] && rank[2] < rank[3] && rank[3] < rank[1]);
+        let roulette = counts(Selection::Roulette, &scores, &[]);
+        assert_eq!(roulette[0], 0);
+        assert!(roulette[2] < roulette[3] && roulette[3] < roulette[1]);
+        let truncation = counts(Selection::Truncation { fraction: 0.5 }, &scores, &[]);
+        assert_eq!((truncation[0], truncation[2]), (0, 0));
+        let tournament = counts(Selection::Tournament { size: 4 }, &scores, &[]);
+        assert!(tournament[1] > tournament[3] && tournament[3] > tournament[0]);
+        let even = counts(Selection::Roulette, &[0.5, 0.5], &[]);
+        assert!(even[0] > 0 && even[1] > 0);
+    }
+
+    #[test]
+    fn lexicase_filters_case_by_case() {
+        // every case has a sole best specialist, so the generalist with the
+        // best mean is never picked
+        let cases: [&[f32]; 4] = [
+            &[1.0, 0.0, 0.0],
+            &[0.0, 1.0, 0.0],
+            &[0.9, 0.9, 0.9],
+            &[0.5, 0.5, 1.0],
+        ];
+        let picked = counts(Selection::Lexicase, &[0.0; 4], &cases);
+        assert_eq!(picked[2], 0);
+        assert!(picked[0] > 0 && picked[1] > 0 && picked[3] > 0);
+        let dominated: [&[f32]; 2] = [&[0.5, 0.5], &[1.0, 0.5]];
+        assert_eq!(counts(Selection::Lexicase, &[0.0; 2], &dominated)[0], 0);
+    }
+}
@@ -1,21 +1,41 @@
-use crate::scoring::ScoringSpec;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::{ActivityPenalty, ScoringSpec};
 
 /// Mapping of task-controlled inputs and observed outputs.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Io {
     pub chunk_id: u32,
     pub bit_idx: u32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct IoMap {
     pub inputs: Vec<Io>,
     pub outputs: Vec<Io>,
+    /// Internal bits observed like outputs, so tasks can score hidden
+    /// state. They are packed after `outputs`: observed bit
+    /// `outputs.len() + k` reads `probes[k]`, with `bit_idx` indexing the
+    /// chunk's internal section.
+    #[serde(default)]
+    pub probes: Vec<Io>,
+}
+
+impl IoMap {
+    /// Bits read back per tick: outputs, then probes.
+    pub fn observed(&self) -> usize {
+        self.outputs.len() + self.probes.len()
+    }
 }
 
 /// Specification of a single episode: initial state and stimuli per tick with
 /// expected outputs used for scoring.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EpisodeSpec {
     /// Input bit vectors per tick.
     pub stimulus: Vec<Vec<u32>>,
@@ -24,19 +44,124 @@ pub struct EpisodeSpec {
 }
 
 /// Complete task description.
-#[derive(Clone, Debug)]
+///
+/// Tasks can be authored as JSON or TOML files; `activity` may be left out.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Task {
-    pub name: &'static str,
+    pub name: String,
     pub io: IoMap,
     pub episodes: Vec<EpisodeSpec>,
     pub tick_budget: u32,
     pub scoring: ScoringSpec,
+    /// Per-episode penalty on activity, subtracted from the score.
+    #[serde(default)]
+    pub activity: ActivityPenalty,
+}
+
+impl Task {
+    pub fn from_json(json: &str) -> Result<Self, TaskError> {
+        serde_json::from_str(json).map_err(TaskError::Json)
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self, TaskError> {
+        toml::from_str(toml).map_err(TaskError::Toml)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("task serializes to JSON")
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("task serializes to TOML")
+    }
+
+    /// Read a task file, parsed as TOML when the extension is `.toml` and as
+    /// JSON otherwise.
+    pub fn load(path: &Path) -> Result<Self, TaskError> {
+        let text = std::fs::read_to_string(path).map_err(TaskError::Io)?;
+        if path.extension().is_some_and(|e| e == "toml") {
+            Self::from_toml(&text)
+        } else {
+            Self::from_json(&text)
+        }
+    }
+
+    /// FNV-1a digest of the whole definition (via its `Debug` form), so it
+    /// changes when a task keeps its name but its episodes or scoring do not.
+    pub fn digest(&self) -> u64 {
+        crate::genome::fnv1a(format!("{self:?}").as_bytes())
+    }
+
+    /// A combinational task with one single-tick episode per row of `table`,
+    /// which holds the packed outputs for every input word `0..2^inputs`.
+    /// Io and naming follow [`TaskGenerator`] tasks; rename as needed.
+    ///
+    /// Panics unless `table` has `2^inputs` rows that each fit in `outputs`
+    /// bits.
+    pub fn from_truth_table(inputs: u32, outputs: u32, table: &[u32]) -> Task {
+        assert!(
+            inputs < 32 && table.len() as u64 == 1 << inputs,
+            "truth table needs 2^inputs rows"
+        );
+        assert!(
+            outputs >= 32 || table.iter().all(|&y| y >> outputs == 0),
+            "truth table row wider than its outputs"
+        );
+        combinational(
+            format!("truth-table-{inputs}x{outputs}"),
+            inputs,
+            outputs,
+            |x| table[x as usize],
+        )
+    }
+
+    /// Like [`from_truth_table`](Self::from_truth_table) for input spaces too
+    /// wide to enumerate: `count` distinct input words drawn uniformly, each
+    /// expecting `table(x)`. All rows are used when `count` covers them.
+    ///
+    /// Panics unless `1 <= inputs <= 32`.
+    pub fn sample_truth_table(
+        inputs: u32,
+        outputs: u32,
+        table: impl Fn(u32) -> u32,
+        count: usize,
+        rng: &mut dyn RngCore,
+    ) -> Task {
+        assert!(
+            (1..=32).contains(&inputs),
+            "truth table needs 1..=32 inputs"
+        );
+        let rows = 1u64 << inputs;
+        let words: Vec<u32> = if count as u64 >= rows {
+            (0..rows).map(|x| x as u32).collect()
+        } else {
+            let mut drawn = BTreeSet::new();
+            while drawn.len() < count {
+                drawn.insert(rng.gen_range(0..rows) as u32);
+            }
+            drawn.into_iter().collect()
+        };
+        let episodes = words
+            .into_iter()
+            .map(|x| EpisodeSpec {
+                stimulus: vec![vec![x]],
+                expected: vec![vec![table(x)]],
+            })
+            .collect();
+        generated(
+            format!("truth-table-{inputs}x{outputs}"),
+            inputs,
+            outputs,
+            episodes,
+            1,
+        )
+    }
 }
 
 /// T-00 Wire-Echo: output mirrors input on the same tick.
 pub fn t00_wire_echo() -> Task {
     Task {
-        name: "T-00 Wire-Echo",
+        name: "T-00 Wire-Echo".into(),
         io: IoMap {
             inputs: vec![Io {
                 chunk_id: 0,
@@ -46,6 +171,7 @@ pub fn t00_wire_echo() -> Task {
                 chunk_id: 0,
                 bit_idx: 0,
             }],
+            probes: Vec::new(),
         },
         episodes: vec![
             EpisodeSpec {
@@ -59,13 +185,14 @@ pub fn t00_wire_echo() -> Task {
         ],
         tick_budget: 1,
         scoring: ScoringSpec::Hamming,
+        activity: ActivityPenalty::default(),
     }
 }
 
 /// T-01 XOR-2: outputs XOR of two inputs.
 pub fn t01_xor_2() -> Task {
     Task {
-        name: "T-01 XOR-2",
+        name: "T-01 XOR-2".into(),
         io: IoMap {
             inputs: vec![
                 Io {
@@ -81,6 +208,7 @@ pub fn t01_xor_2() -> Task {
                 chunk_id: 0,
                 bit_idx: 2,
             }],
+            probes: Vec::new(),
         },
         episodes: vec![
             EpisodeSpec {
@@ -102,13 +230,14 @@ pub fn t01_xor_2() -> Task {
         ],
         tick_budget: 1,
         scoring: ScoringSpec::Hamming,
+        activity: ActivityPenalty::default(),
     }
 }
 
 /// T-02 SR-Latch: implements a basic set-reset latch.
 pub fn t02_sr_latch() -> Task {
     Task {
-        name: "T-02 SR-Latch",
+        name: "T-02 SR-Latch".into(),
         io: IoMap {
             inputs: vec![
                 Io {
@@ -124,6 +253,7 @@ pub fn t02_sr_latch() -> Task {
                 chunk_id: 0,
                 bit_idx: 2,
             }], // Q
+            probes: Vec::new(),
         },
         episodes: vec![
             // Set then hold
@@ -139,13 +269,14 @@ pub fn t02_sr_latch() -> Task {
         ],
         tick_budget: 2,
         scoring: ScoringSpec::Hamming,
+        activity: ActivityPenalty::default(),
     }
 }
 
 /// T-03 Pulse-Counter: counts incoming pulses modulo 4 using two output bits.
 pub fn t03_pulse_counter() -> Task {
     Task {
-        name: "T-03 Pulse-Counter",
+        name: "T-03 Pulse-Counter".into(),
         io: IoMap {
             inputs: vec![Io {
                 chunk_id: 0,
@@ -161,6 +292,7 @@ pub fn t03_pulse_counter() -> Task {
                     bit_idx: 2,
                 },
             ],
+            probes: Vec::new(),
         },
         episodes: vec![EpisodeSpec {
             stimulus: vec![vec![1], vec![1], vec![1]],
@@ -168,13 +300,14 @@ pub fn t03_pulse_counter() -> Task {
         }],
         tick_budget: 3,
         scoring: ScoringSpec::Hamming,
+        activity: ActivityPenalty::default(),
     }
 }
 
 /// T-04 Cross-Chunk Relay: relays an input from chunk 0 to an output on chunk 1 with one tick delay.
 pub fn t04_cross_chunk_relay() -> Task {
     Task {
-        name: "T-04 Cross-Chunk Relay",
+        name: "T-04 Cross-Chunk Relay".into(),
         io: IoMap {
             inputs: vec![Io {
                 chunk_id: 0,
@@ -184,6 +317,7 @@ pub fn t04_cross_chunk_relay() -> Task {
                 chunk_id: 1,
                 bit_idx: 0,
             }],
+            probes: Vec::new(),
         },
         episodes: vec![EpisodeSpec {
             stimulus: vec![vec![1], vec![0]],
@@ -191,5 +325,667 @@ pub fn t04_cross_chunk_relay() -> Task {
         }],
         tick_budget: 2,
         scoring: ScoringSpec::Hamming,
+        activity: ActivityPenalty::default(),
+    }
+}
+
+/// T-05 Shift-Register: a 3-bit shift register. Input 0 shifts in each tick;
+/// output `k` holds the input from `k` ticks earlier (0 before the episode).
+pub fn t05_shift_register() -> Task {
+    let episodes = simulated(
+        &[&[1, 0, 0, 0], &[1, 1, 0, 1, 0, 0], &[0, 1, 1, 0, 1, 0, 0]],
+        |reg: &mut u32, d| {
+            // bit k is the input from k ticks ago
+            *reg = ((*reg << 1) | d) & 0b111;
+            *reg
+        },
+    );
+    builtin("T-05 Shift-Register", 1, 3, episodes)
+}
+
+/// T-06 Sequence-Detector: the output is high on each tick that completes
+/// the input sequence 1, 0, 1; matches may overlap.
+pub fn t06_sequence_detector() -> Task {
+    let episodes = simulated(
+        &[
+            &[1, 0, 1],
+            &[1, 0, 1, 0, 1],
+            &[1, 1, 0, 0, 1, 1],
+            &[0, 1, 1, 0, 1, 0, 0, 1],
+        ],
+        |last: &mut u32, x| {
+            *last = ((*last << 1) | x) & 0b111;
+            u32::from(*last == 0b101)
+        },
+    );
+    builtin("T-06 Sequence-Detector", 1, 1, episodes)
+}
+
+/// T-07 Memory-Cell: two addressable 1-bit cells. Inputs are address (0),
+/// data (1) and write enable (2); the output reads the addressed cell, after
+/// any write on the same tick. Cells start at 0.
+pub fn t07_memory_cell() -> Task {
+    const A: u32 = 0b001;
+    const D: u32 = 0b010;
+    const W: u32 = 0b100;
+    let episodes = simulated(
+        &[
+            &[W | D, 0, A, W | A | D, 0, A],
+            &[W | A | D, 0, A, W | A, A, 0],
+            &[D, A | D, W | D, A, 0, W, 0],
+        ],
+        |cells: &mut u32, x| {
+            let addr = x & A;
+            if x & W != 0 {
+                let bit = 1 << addr;
+                *cells = if x & D != 0 {
+                    *cells | bit
+                } else {
+                    *cells & !bit
+                };
+            }
+            (*cells >> addr) & 1
+        },
+    );
+    builtin("T-07 Memory-Cell", 3, 1, episodes)
+}
+
+/// T-08 Gated-D-Latch: while enable (input 1) is high the output follows
+/// data (input 0); otherwise it holds. The latch starts at 0.
+pub fn t08_gated_d_latch() -> Task {
+    const D: u32 = 0b01;
+    const EN: u32 = 0b10;
+    let episodes = simulated(
+        &[
+            &[EN | D, 0, D, 0, EN, D],
+            &[D, EN, EN | D, D, 0, EN],
+            &[EN | D, EN, EN | D, 0, D, 0],
+        ],
+        |q: &mut u32, x| {
+            if x & EN != 0 {
+                *q = x & D;
+            }
+            *q
+        },
+    );
+    builtin("T-08 Gated-D-Latch", 2, 1, episodes)
+}
+
+/// Episodes whose expected outputs come from running `step` over each input
+/// sequence, starting from the default state.
+fn simulated<S: Default>(
+    sequences: &[&[u32]],
+    step: impl Fn(&mut S, u32) -> u32,
+) -> Vec<EpisodeSpec> {
+    sequences
+        .iter()
+        .map(|seq| {
+            let mut state = S::default();
+            EpisodeSpec {
+                stimulus: seq.iter().map(|&x| vec![x]).collect(),
+                expected: seq.iter().map(|&x| vec![step(&mut state, x)]).collect(),
+            }
+        })
+        .collect()
+}
+
+/// A built-in task whose tick budget is its longest episode.
+fn builtin(name: &str, inputs: u32, outputs: u32, episodes: Vec<EpisodeSpec>) -> Task {
+    let ticks = episodes.iter().map(|e| e.stimulus.len()).max().unwrap_or(0);
+    generated(name.into(), inputs, outputs, episodes, ticks as u32)
+}
+
+/// Parametric task family; [`generate`](Self::generate) builds the episode
+/// table. Generated tasks use chunk 0, with inputs and outputs on its input
+/// and output bits from index 0 up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskGenerator {
+    /// See [`xor_n`].
+    Xor { inputs: u32 },
+    /// See [`adder`].
+    Adder { bits: u32 },
+    /// See [`parity`].
+    Parity { bits: u32 },
+    /// See [`counter`].
+    Counter { modulus: u32 },
+}
+
+impl TaskGenerator {
+    /// Parse a generated task's name, `xor-3` or `xor3`, returning `None`
+    /// for unknown families and out-of-range sizes.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let split = name.find(|c: char| c.is_ascii_digit())?;
+        let (family, n) = name.split_at(split);
+        let n: u32 = n.parse().ok()?;
+        let generator = match family.strip_suffix('-').unwrap_or(family) {
+            "xor" => TaskGenerator::Xor { inputs: n },
+            "adder" => TaskGenerator::Adder { bits: n },
+            "parity" => TaskGenerator::Parity { bits: n },
+            "counter" => TaskGenerator::Counter { modulus: n },
+            _ => return None,
+        };
+        generator.in_range().then_some(generator)
+    }
+
+    /// Whether the size is one [`generate`](Self::generate) and
+    /// [`sample`](Self::sample) support. Deserializing does not check it.
+    pub fn in_range(&self) -> bool {
+        match *self {
+            TaskGenerator::Xor { inputs } => (1..=16).contains(&inputs),
+            TaskGenerator::Adder { bits } => (1..=8).contains(&bits),
+            TaskGenerator::Parity { bits } => (1..=12).contains(&bits),
+            TaskGenerator::Counter { modulus } => (2..=256).contains(&modulus),
+        }
+    }
+
+    pub fn generate(&self) -> Task {
+        match *self {
+            TaskGenerator::Xor { inputs } => xor_n(inputs),
+            TaskGenerator::Adder { bits } => adder(bits),
+            TaskGenerator::Parity { bits } => parity(bits),
+            TaskGenerator::Counter { modulus } => counter(modulus),
+        }
+    }
+
+    /// A task with the io of [`generate`](Self::generate) but `count`
+    /// random `ticks`-tick episodes instead of the exhaustive table. Each
+    /// tick draws a uniform input word; combinational families score every
+    /// tick on its own input, sequential ones on the inputs so far.
+    pub fn sample(&self, count: usize, ticks: u32, rng: &mut dyn RngCore) -> Task {
+        let (name, inputs, outputs) = match *self {
+            TaskGenerator::Xor { inputs } => (format!("xor-{inputs}"), inputs, 1),
+            TaskGenerator::Adder { bits } => (format!("adder-{bits}"), 2 * bits, bits + 1),
+            TaskGenerator::Parity { bits } => (format!("parity-{bits}"), 1, 1),
+            TaskGenerator::Counter { modulus } => (
+                format!("counter-{modulus}"),
+                1,
+                (u32::BITS - (modulus - 1).leading_zeros()).max(1),
+            ),
+        };
+        let episodes = (0..count)
+            .map(|_| {
+                let mut state = 0;
+                let mut spec = EpisodeSpec {
+                    stimulus: Vec::new(),
+                    expected: Vec::new(),
+                };
+                for _ in 0..ticks {
+                    let x = rng.gen_range(0..1u32 << inputs);
+                    spec.stimulus.push(vec![x]);
+                    spec.expected.push(vec![self.step(&mut state, x)]);
+                }
+                spec
+            })
+            .collect();
+        generated(name, inputs, outputs, episodes, ticks)
+    }
+
+    /// The family's model: expected outputs for input word `x`, with
+    /// `state` carried across the ticks of an episode.
+    fn step(&self, state: &mut u32, x: u32) -> u32 {
+        match *self {
+            TaskGenerator::Xor { .. } => x.count_ones() & 1,
+            TaskGenerator::Adder { bits } => (x & ((1 << bits) - 1)) + (x >> bits),
+            TaskGenerator::Parity { .. } => {
+                *state ^= x;
+                *state
+            }
+            TaskGenerator::Counter { modulus } => {
+                *state = (*state + x) % modulus;
+                *state
+            }
+        }
+    }
+}
+
+/// `xor-N`: one output, the XOR of `n` inputs, with one single-tick episode
+/// per input combination. `xor_n(2)` behaves like T-01.
+///
+/// Panics unless `1 <= n <= 16`.
+pub fn xor_n(n: u32) -> Task {
+    assert!((1..=16).contains(&n), "xor_n needs 1..=16 inputs");
+    combinational(format!("xor-{n}"), n, 1, |x| x.count_ones() & 1)
+}
+
+/// `adder-N`: adds two `bits`-bit operands, the first on inputs
+/// `0..bits` and the second on `bits..2 * bits`, LSB first, into a
+/// `bits + 1`-bit sum. One episode per operand pair.
+///
+/// Panics unless `1 <= bits <= 8`.
+pub fn adder(bits: u32) -> Task {
+    assert!((1..=8).contains(&bits), "adder needs 1..=8 bits");
+    let mask = (1 << bits) - 1;
+    combinational(format!("adder-{bits}"), 2 * bits, bits + 1, |x| {
+        (x & mask) + (x >> bits)
+    })
+}
+
+/// `parity-N`: serial parity. One input bit arrives per tick and the single
+/// output holds the XOR of all bits seen so far in the episode. One
+/// `bits`-tick episode per input sequence.
+///
+/// Panics unless `1 <= bits <= 12`.
+pub fn parity(bits: u32) -> Task {
+    assert!((1..=12).contains(&bits), "parity needs 1..=12 bits");
+    let episodes = (0..1u32 << bits)
+        .map(|seq| {
+            let stimulus: Vec<Vec<u32>> = (0..bits).map(|t| vec![(seq >> t) & 1]).collect();
+            let expected = (0..bits)
+                .map(|t| vec![(seq & ((2 << t) - 1)).count_ones() & 1])
+                .collect();
+            EpisodeSpec { stimulus, expected }
+        })
+        .collect();
+    generated(format!("parity-{bits}"), 1, 1, episodes, bits)
+}
+
+/// `counter-M`: counts ticks with input 0 high, modulo `modulus`, on
+/// `ceil(log2(modulus))` outputs (at least one). Episodes pulse every tick
+/// and every other tick, each long enough to wrap around.
+///
+/// Panics unless `2 <= modulus <= 256`.
+pub fn counter(modulus: u32) -> Task {
+    assert!(
+        (2..=256).contains(&modulus),
+        "counter needs a modulus in 2..=256"
+    );
+    let width = (u32::BITS - (modulus - 1).leading_zeros()).max(1);
+    let episode = |pulse: &dyn Fn(u32) -> bool, ticks: u32| {
+        let mut count = 0;
+        let mut spec = EpisodeSpec {
+            stimulus: Vec::new(),
+            expected: Vec::new(),
+        };
+        for t in 0..ticks {
+            let high = pulse(t);
+            count = (count + u32::from(high)) % modulus;
+            spec.stimulus.push(vec![u32::from(high)]);
+            spec.expected.push(vec![count]);
+        }
+        spec
+    };
+    let episodes = vec![
+        episode(&|_| true, modulus + 1),
+        episode(&|t| t % 2 == 0, 2 * modulus + 1),
+    ];
+    generated(
+        format!("counter-{modulus}"),
+        1,
+        width,
+        episodes,
+        2 * modulus + 1,
+    )
+}
+
+/// One single-tick episode per assignment of `inputs` bits.
+fn combinational(name: String, inputs: u32, outputs: u32, f: impl Fn(u32) -> u32) -> Task {
+    let episodes = (0..1u32 << inputs)
+        .map(|x| EpisodeSpec {
+            stimulus: vec![vec![x]],
+            expected: vec![vec![f(x)]],
+        })
+        .collect();
+    generated(name, inputs, outputs, episodes, 1)
+}
+
+fn generated(
+    name: String,
+    inputs: u32,
+    outputs: u32,
+    episodes: Vec<EpisodeSpec>,
+    tick_budget: u32,
+) -> Task {
+    let ports = |n: u32| {
+        (0..n)
+            .map(|bit_idx| Io {
+                chunk_id: 0,
+                bit_idx,
+            })
+            .collect()
+    };
+    Task {
+        name,
+        io: IoMap {
+            inputs: ports(inputs),
+            outputs: ports(outputs),
+            probes: Vec::new(),
+        },
+        episodes,
+        tick_budget,
+        scoring: ScoringSpec::Hamming,
+        activity: ActivityPenalty::default(),
+    }
+}
+
+const BUILTINS: [fn() -> Task; 9] = [
+    t00_wire_echo,
+    t01_xor_2,
+    t02_sr_latch,
+    t03_pulse_counter,
+    t04_cross_chunk_relay,
+    t05_shift_register,
+    t06_sequence_detector,
+    t07_memory_cell,
+    t08_gated_d_latch,
+];
+
+/// The built-in tasks, in order.
+pub fn builtin_tasks() -> Vec<Task> {
+    BUILTINS.iter().map(|f| f()).collect()
+}
+
+/// Look up a task in [`TaskRegistry::builtin`]: a built-in task by its full
+/// name (`"T-01 XOR-2"`) or its code (`"T-01"`), or a generated one such as
+/// `"xor-3"`.
+pub fn task_by_name(name: &str) -> Option<Task> {
+    TaskRegistry::builtin().get(name)
+}
+
+type TaskConstructor = Arc<dyn Fn() -> Task + Send + Sync>;
+
+/// Task constructors by name, so configs and tools can refer to tasks
+/// declaratively.
+///
+/// A registry from [`builtin`](Self::builtin) also resolves the names of
+/// [`TaskGenerator`] tasks, `xor-N`, `adder-N`, `parity-N` and `counter-M`,
+/// with or without the dash (`"xor3"`). Registered names take precedence.
+#[derive(Clone, Default)]
+pub struct TaskRegistry {
+    tasks: BTreeMap<String, TaskConstructor>,
+    generators: bool,
+}
+
+impl TaskRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in tasks under their full names and codes, plus the
+    /// generated families.
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            generators: true,
+            ..Self::default()
+        };
+        for ctor in BUILTINS {
+            let name = ctor().name;
+            if let Some(code) = name.split(' ').next() {
+                registry.register(code, ctor);
+            }
+            registry.register(name, ctor);
+        }
+        registry
+    }
+
+    /// Register `ctor` under `name`, replacing any earlier entry.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        ctor: impl Fn() -> Task + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.tasks.insert(name.into(), Arc::new(ctor));
+        self
+    }
+
+    /// Register a copy of `task`, e.g. one read with [`Task::load`], under
+    /// its name.
+    pub fn insert(&mut self, task: Task) -> &mut Self {
+        self.register(task.name.clone(), move || task.clone())
+    }
+
+    /// Build the task registered as `name`.
+    pub fn get(&self, name: &str) -> Option<Task> {
+        match self.tasks.get(name) {
+            Some(ctor) => Some(ctor()),
+            None if self.generators => TaskGenerator::from_name(name).map(|g| g.generate()),
+            None => None,
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tasks.contains_key(name)
+            || (self.generators && TaskGenerator::from_name(name).is_some())
+    }
+
+    /// Registered names in sorted order, without the generated families.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tasks.keys().map(String::as_str)
+    }
+}
+
+impl std::fmt::Debug for TaskRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskRegistry")
+            .field("tasks", &self.tasks.keys().collect::<Vec<_>>())
+            .field("generators", &self.generators)
+            .finish()
+    }
+}
+
+/// Write every built-in task to `dir` as `<code>.json`, e.g. `T-01.json`,
+/// returning the paths written. [`Task::load`] reads them back unchanged.
+pub fn write_builtin_tasks(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    builtin_tasks()
+        .iter()
+        .map(|task| {
+            let code = task.name.split(' ').next().unwrap_or(&task.name);
+            let path = dir.join(format!("{code}.json"));
+            std::fs::write(&path, task.to_json())?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Error loading a [`Task`] file.
+#[derive(Debug)]
+pub enum TaskError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+}
+
+impl std::fmt::Display for TaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskError::Io(e) => write!(f, "reading task: {e}"),
+            TaskError::Json(e) => write!(f, "invalid JSON task: {e}"),
+            TaskError::Toml(e) => write!(f, "invalid TOML task: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+/// Serde adapter storing a [`Task`] as its name. Names are read back through
+/// [`TaskRegistry::builtin`]; a full task definition is accepted in place of
+/// a name.
+pub mod by_name {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::{task_by_name, Task};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NameOrTask {
+        Name(String),
+        Task(Task),
+    }
+
+    pub fn serialize<S: Serializer>(task: &Task, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&task.name)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Task, D::Error> {
+        match NameOrTask::deserialize(d)? {
+            NameOrTask::Name(name) => task_by_name(&name)
+                .ok_or_else(|| D::Error::custom(format!("unknown task {name:?}"))),
+            NameOrTask::Task(task) => Ok(task),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn truth_tables_expand_into_episodes() {
+        // 2-input AND, NAND on bits 0 and 1
+        let table = [0b10, 0b10, 0b10, 0b01];
+        let task = Task::from_truth_table(2, 2, &table);
+        assert_eq!((task.io.inputs.len(), task.io.outputs.len()), (2, 2));
+        assert_eq!(task.episodes.len(), 4);
+        assert_eq!(task.episodes[3].stimulus, vec![vec![3]]);
+        assert_eq!(task.episodes[3].expected, vec![vec![0b01]]);
+        let xor = Task::from_truth_table(2, 1, &[0, 1, 1, 0]);
+        assert_eq!(xor.episodes.len(), t01_xor_2().episodes.len());
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(2);
+        let wide = Task::sample_truth_table(24, 1, |x| x.count_ones() & 1, 50, &mut rng);
+        assert_eq!(wide.episodes.len(), 50);
+        let mut words: Vec<u32> = wide.episodes.iter().map(|e| e.stimulus[0][0]).collect();
+        words.dedup();
+        assert_eq!(words.len(), 50);
+        for e in &wide.episodes {
+            assert_eq!(e.expected[0][0], e.stimulus[0][0].count_ones() & 1);
+        }
+        let all = Task::sample_truth_table(3, 1, |x| x & 1, 100, &mut rng);
+        assert_eq!(all.episodes.len(), 8);
+    }
+
+    #[test]
+    fn registry_resolves_builtin_generated_and_custom_names() {
+        let builtin = TaskRegistry::builtin();
+        assert_eq!(builtin.get("T-01").unwrap().name, "T-01 XOR-2");
+        assert_eq!(
+            builtin.get("T-08 Gated-D-Latch").unwrap().name,
+            "T-08 Gated-D-Latch"
+        );
+        assert_eq!(builtin.get("xor3").unwrap().name, "xor-3");
+        assert_eq!(builtin.get("counter-5").unwrap().io.outputs.len(), 3);
+        assert!(builtin.contains("parity-12") && !builtin.contains("parity-13"));
+        assert!(builtin.get("T-99").is_none() && builtin.get("nand2").is_none());
+        assert_eq!(builtin.names().count(), 2 * builtin_tasks().len());
+
+        let mut custom = TaskRegistry::new();
+        assert!(custom.get("xor-3").is_none());
+        let mut task = t00_wire_echo();
+        task.name = "echo".into();
+        let digest = task.digest();
+        custom.insert(task).register("T-01", t02_sr_latch);
+        assert_eq!(custom.get("echo").unwrap().digest(), digest);
+        assert_eq!(custom.get("T-01").unwrap().name, "T-02 SR-Latch");
+        assert_eq!(custom.names().collect::<Vec<_>>(), ["T-01", "echo"]);
+    }
+
+    #[test]
+    fn builtin_tasks_round_trip_through_files() {
+        let dir = std::env::temp_dir().join("mycos_task_files_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths = write_builtin_tasks(&dir).unwrap();
+        assert_eq!(paths[1], dir.join("T-01.json"));
+        for (task, path) in builtin_tasks().iter().zip(&paths) {
+            assert_eq!(Task::load(path).unwrap().digest(), task.digest());
+            assert_eq!(
+                Task::from_toml(&task.to_toml()).unwrap().digest(),
+                task.digest()
+            );
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut weighted = t01_xor_2();
+        weighted.scoring = ScoringSpec::WeightedHamming {
+            weights: vec![2.0],
+            care: vec![vec![1]],
+        };
+        let toml = weighted.to_toml();
+        assert_eq!(Task::from_toml(&toml).unwrap().scoring, weighted.scoring);
+
+        let authored = r#"
+            name = "buffer"
+            tick_budget = 1
+            scoring = "Hamming"
+            io = { inputs = [{ chunk_id = 0, bit_idx = 0 }], outputs = [{ chunk_id = 0, bit_idx = 0 }] }
+            episodes = [{ stimulus = [[1]], expected = [[1]] }]
+        "#;
+        let task = Task::from_toml(authored).unwrap();
+        assert_eq!(task.name, "buffer");
+        assert_eq!(task.activity, ActivityPenalty::default());
+        assert!(Task::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn sequential_tasks_follow_their_models() {
+        let outputs = |task: &Task, episode: usize| -> Vec<u32> {
+            task.episodes[episode]
+                .expected
+                .iter()
+                .map(|t| t[0])
+                .collect()
+        };
+        let shift = t05_shift_register();
+        assert_eq!(outputs(&shift, 0), vec![0b001, 0b010, 0b100, 0b000]);
+        let detector = t06_sequence_detector();
+        assert_eq!(outputs(&detector, 1), vec![0, 0, 1, 0, 1]);
+        assert_eq!(outputs(&detector, 2), vec![0; 6]);
+        let memory = t07_memory_cell();
+        assert_eq!(outputs(&memory, 0), vec![1, 1, 0, 1, 1, 1]);
+        let latch = t08_gated_d_latch();
+        assert_eq!(outputs(&latch, 0), vec![1, 1, 1, 1, 0, 0]);
+        for task in [shift, detector, memory, latch] {
+            assert!(task.episodes.len() > 1);
+            let longest = task.episodes.iter().map(|e| e.expected.len()).max();
+            assert_eq!(longest, Some(task.tick_budget as usize));
+        }
+        assert_eq!(task_by_name("T-07").unwrap().name, "T-07 Memory-Cell");
+    }
+
+    #[test]
+    fn generators_build_episode_tables() {
+        let xor = TaskGenerator::Xor { inputs: 2 }.generate();
+        let t01 = t01_xor_2();
+        let table = |t: &Task| {
+            t.episodes
+                .iter()
+                .map(|e| (e.stimulus.clone(), e.expected.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(table(&xor), table(&t01));
+        assert_eq!(xor.name, "xor-2");
+
+        let add = adder(2);
+        assert_eq!((add.io.inputs.len(), add.io.outputs.len()), (4, 3));
+        assert_eq!(add.episodes.len(), 16);
+        // 3 + 2 = 5
+        assert_eq!(add.episodes[0b10_11].expected, vec![vec![5]]);
+
+        let par = parity(3);
+        assert_eq!(par.episodes.len(), 8);
+        assert_eq!(
+            par.episodes[0b101].expected,
+            vec![vec![1], vec![1], vec![0]]
+        );
+
+        let count = counter(3);
+        assert_eq!(count.io.outputs.len(), 2);
+        let wrap: Vec<u32> = count.episodes[0].expected.iter().map(|t| t[0]).collect();
+        assert_eq!(wrap, vec![1, 2, 0, 1]);
+        assert_eq!(counter(2).io.outputs.len(), 1);
+        assert_eq!(counter(4).io.outputs.len(), 2);
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(4);
+        let sampled = TaskGenerator::Parity { bits: 3 }.sample(5, 6, &mut rng);
+        assert_eq!((sampled.episodes.len(), sampled.tick_budget), (5, 6));
+        for e in &sampled.episodes {
+            let mut parity = 0;
+            for (x, y) in e.stimulus.iter().zip(&e.expected) {
+                parity ^= x[0];
+                assert_eq!(y[0], parity);
+            }
+        }
+        assert_eq!(counter(5).io.outputs.len(), 3);
     }
 }
@@ -0,0 +1,175 @@
+use crate::chunk::MycosChunk;
+use crate::cpu_ref::System;
+use crate::runner::MAX_ROUNDS;
+
+/// Largest Input count [`truth_table`] accepts; the state check settles
+/// every ordered pair of combinations, so cost grows as `4^Ni`.
+pub const DEFAULT_MAX_INPUTS: u32 = 8;
+
+/// Outputs of a chunk for every Input combination.
+///
+/// Combination `x` drives Input `i` with bit `i` of `x`. Rows are settled
+/// from the chunk's initial state, like the first tick of an episode, and
+/// pack the Outputs into `u32` words the same way as episode outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTable {
+    pub inputs: u32,
+    pub outputs: u32,
+    pub rows: Vec<Vec<u32>>,
+    /// Combinations whose outputs change when another combination was
+    /// applied and settled first, i.e. where the chunk holds state.
+    pub state_dependent: Vec<u32>,
+    /// Combinations where settling hit the round limit, in either check.
+    pub oscillating: Vec<u32>,
+}
+
+impl TruthTable {
+    /// Whether every row is a pure function of the current inputs.
+    pub fn is_combinational(&self) -> bool {
+        self.state_dependent.is_empty() && self.oscillating.is_empty()
+    }
+
+    /// Output `bit` for Input combination `inputs`.
+    pub fn output(&self, inputs: u32, bit: u32) -> bool {
+        self.rows[inputs as usize][(bit / 32) as usize] >> (bit % 32) & 1 == 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruthTableError {
+    TooManyInputs { inputs: u32, max: u32 },
+}
+
+impl std::fmt::Display for TruthTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TruthTableError::TooManyInputs { inputs, max } => {
+                write!(f, "{inputs} inputs exceed the truth table limit of {max}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TruthTableError {}
+
+/// [`truth_table_with_limit`] with [`DEFAULT_MAX_INPUTS`].
+pub fn truth_table(chunk: &MycosChunk) -> Result<TruthTable, TruthTableError> {
+    truth_table_with_limit(chunk, DEFAULT_MAX_INPUTS)
+}
+
+/// Simulate every Input combination on the CPU executor. Each row is also
+/// re-settled after every other combination, one tick earlier, to detect
+/// state dependence. Fails when the chunk has more than `max_inputs` Inputs
+/// (capped at 31).
+pub fn truth_table_with_limit(
+    chunk: &MycosChunk,
+    max_inputs: u32,
+) -> Result<TruthTable, TruthTableError> {
+    if chunk.input_count > max_inputs.min(31) {
+        return Err(TruthTableError::TooManyInputs {
+            inputs: chunk.input_count,
+            max: max_inputs.min(31),
+        });
+    }
+    let chunks = std::slice::from_ref(chunk);
+    let combos = 1u32 << chunk.input_count;
+    let apply = |system: &mut System, x: u32| {
+        for i in 0..chunk.input_count {
+            system.write_input(0, i, x >> i & 1 == 1);
+        }
+        system.settle(MAX_ROUNDS).oscillator
+    };
+    let read = |system: &System| {
+        let mut words = vec![0u32; chunk.output_count.div_ceil(32) as usize];
+        for o in 0..chunk.output_count {
+            if system.read_output(0, o) {
+                words[(o / 32) as usize] |= 1 << (o % 32);
+            }
+        }
+        words
+    };
+
+    let mut table = TruthTable {
+        inputs: chunk.input_count,
+        outputs: chunk.output_count,
+        rows: Vec::with_capacity(combos as usize),
+        state_dependent: Vec::new(),
+        oscillating: Vec::new(),
+    };
+    for x in 0..combos {
+        let mut system = System::new(chunks, &[]);
+        let mut oscillated = apply(&mut system, x);
+        let row = read(&system);
+        let mut dependent = false;
+        for y in (0..combos).filter(|&y| y != x) {
+            let mut system = System::new(chunks, &[]);
+            oscillated |= apply(&mut system, y);
+            system.advance_tick();
+            oscillated |= apply(&mut system, x);
+            dependent |= read(&system) != row;
+        }
+        if dependent {
+            table.state_dependent.push(x);
+        }
+        if oscillated {
+            table.oscillating.push(x);
+        }
+        table.rows.push(row);
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ChunkBuilder;
+    use crate::chunk::{Action, Section::*, Trigger};
+
+    #[test]
+    fn xor_is_combinational_and_latch_is_not() {
+        // each input edge flips Internal 0, which the output follows; input
+        // 1 goes through Internal 1 because two Toggles of one bit in the
+        // same round apply once (spec §4.2)
+        let xor = ChunkBuilder::new()
+            .inputs(2)
+            .internal(2)
+            .outputs(1)
+            .conn((Input, 0), (Internal, 0), Trigger::Toggle, Action::Toggle)
+            .conn((Input, 1), (Internal, 1), Trigger::Toggle, Action::Toggle)
+            .conn(
+                (Internal, 1),
+                (Internal, 0),
+                Trigger::Toggle,
+                Action::Toggle,
+            )
+            .conn((Internal, 0), (Output, 0), Trigger::On, Action::Enable)
+            .conn((Internal, 0), (Output, 0), Trigger::Off, Action::Disable)
+            .build()
+            .unwrap();
+        let table = truth_table(&xor).unwrap();
+        let column: Vec<bool> = (0..4).map(|x| table.output(x, 0)).collect();
+        assert_eq!(column, vec![false, true, true, false]);
+        assert!(table.is_combinational());
+
+        // set by input 0, reset by input 1, holds otherwise
+        let latch = ChunkBuilder::new()
+            .inputs(2)
+            .internal(1)
+            .outputs(1)
+            .conn((Input, 0), (Internal, 0), Trigger::On, Action::Enable)
+            .conn((Input, 1), (Internal, 0), Trigger::On, Action::Disable)
+            .conn((Internal, 0), (Output, 0), Trigger::On, Action::Enable)
+            .conn((Internal, 0), (Output, 0), Trigger::Off, Action::Disable)
+            .build()
+            .unwrap();
+        let table = truth_table(&latch).unwrap();
+        assert_eq!(table.rows[0b01], vec![1]);
+        assert!(table.state_dependent.contains(&0b00));
+        assert!(!table.is_combinational());
+
+        assert_eq!(
+            truth_table_with_limit(&latch, 1),
+            Err(TruthTableError::TooManyInputs { inputs: 2, max: 1 })
+        );
+    }
+}